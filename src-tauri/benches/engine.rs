@@ -0,0 +1,58 @@
+//! Benchmarks for move generation, evaluation and search.
+//!
+//! Run with `cargo bench`; criterion keeps the previous results and reports changes against them.
+
+use chess_engine_lib::{perft, search, ChessGame, EvalPerspective, Evaluator, SearchLimits};
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+
+/// Opening, middlegame and endgame positions from the usual perft suites
+const POSITIONS: [(&str, &str); 3] = [
+    ("startpos", "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1"),
+    ("kiwipete", "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1"),
+    ("endgame", "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1"),
+];
+
+fn game(fen: &str) -> ChessGame {
+    ChessGame::from_fen(fen).expect("benchmark FEN should parse")
+}
+
+fn bench_perft(c: &mut Criterion) {
+    let mut group = c.benchmark_group("perft");
+    group.sample_size(10);
+    for (name, fen) in POSITIONS {
+        let game = game(fen);
+        group.bench_function(BenchmarkId::new(name, 3), |b| {
+            b.iter(|| perft(black_box(game.get_board_state()), 3))
+        });
+    }
+    group.finish();
+}
+
+fn bench_evaluation(c: &mut Criterion) {
+    let games: Vec<ChessGame> = POSITIONS.iter().map(|(_, fen)| game(fen)).collect();
+
+    // A single-entry cache, so alternating positions always get a full evaluation
+    Evaluator::set_cache_megabytes(0);
+    c.bench_function("evaluate", |b| {
+        b.iter(|| {
+            for game in &games {
+                black_box(Evaluator::evaluate_from(game.get_board_state(), EvalPerspective::White));
+            }
+        })
+    });
+}
+
+fn bench_search(c: &mut Criterion) {
+    let mut group = c.benchmark_group("search");
+    group.sample_size(10);
+    for (name, fen) in POSITIONS {
+        let game = game(fen);
+        group.bench_function(BenchmarkId::new(name, 4), |b| {
+            b.iter(|| search(black_box(game.get_board_state()), SearchLimits::depth(4)))
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_perft, bench_evaluation, bench_search);
+criterion_main!(benches);
@@ -0,0 +1,362 @@
+use crate::chess_engine::{ChessError, ChessGame, Position, Variant};
+use rusqlite::types::Type;
+use rusqlite::{params, Connection, OptionalExtension, Row};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+use thiserror::Error;
+
+const ARCHIVE_FILE: &str = "games.sqlite";
+
+/// Version of the position index, kept in the database's `user_version`. Raise it when
+/// the way positions are hashed changes, and the index is rebuilt when the archive opens.
+const INDEX_VERSION: i64 = 1;
+
+const SCHEMA: &str = "
+    CREATE TABLE IF NOT EXISTS games (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        tags TEXT NOT NULL,
+        moves TEXT NOT NULL,
+        result TEXT NOT NULL,
+        variant TEXT NOT NULL,
+        finished INTEGER NOT NULL,
+        game TEXT NOT NULL,
+        created_at INTEGER NOT NULL,
+        updated_at INTEGER NOT NULL
+    );
+    CREATE TABLE IF NOT EXISTS positions (
+        game_id INTEGER NOT NULL REFERENCES games (id) ON DELETE CASCADE,
+        ply INTEGER NOT NULL,
+        hash INTEGER NOT NULL,
+        PRIMARY KEY (game_id, ply)
+    );
+    CREATE INDEX IF NOT EXISTS positions_by_hash ON positions (hash);
+    PRAGMA foreign_keys = ON;
+";
+
+/// Columns `read_game` expects, in order
+const GAME_COLUMNS: &str = "games.id, games.tags, games.moves, games.result, games.variant, games.finished,
+    games.created_at, games.updated_at";
+
+#[derive(Debug, Error)]
+pub enum ArchiveError {
+    #[error("game archive: {0}")]
+    Database(#[from] rusqlite::Error),
+
+    #[error("game archive: {0}")]
+    Io(#[from] io::Error),
+
+    #[error("no archived game with ID {0}")]
+    NotFound(i64),
+
+    #[error(transparent)]
+    Game(#[from] ChessError),
+}
+
+/// A stored game as listed, without the full game document
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ArchivedGame {
+    pub id: i64,
+    /// PGN tag pairs given when the game was stored ("White", "Event", ...)
+    pub tags: Vec<(String, String)>,
+    /// SAN moves of the line that was being played
+    pub moves: Vec<String>,
+    /// "1-0", "0-1", "1/2-1/2", or "*" for a game still in progress
+    pub result: String,
+    pub variant: Variant,
+    pub finished: bool,
+    /// Milliseconds since the Unix epoch when the game was first stored and last updated
+    pub created_at: u64,
+    pub updated_at: u64,
+}
+
+/// A stored game in which a position occurs
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PositionMatch {
+    pub game: ArchivedGame,
+    /// Half-moves played before the position first occurs (0 = the starting position)
+    pub ply: usize,
+    /// The move the game went on with from there, in SAN
+    pub next_move: Option<String>,
+}
+
+/// Finished and in-progress games kept in a SQLite database in the app data directory
+pub struct GameArchive {
+    connection: Connection,
+}
+
+impl GameArchive {
+    /// Opens the archive in the given app data directory, creating it on first use
+    pub fn in_dir(dir: PathBuf) -> Result<Self, ArchiveError> {
+        fs::create_dir_all(&dir)?;
+        let connection = Connection::open(dir.join(ARCHIVE_FILE))?;
+        connection.execute_batch(SCHEMA)?;
+        let archive = GameArchive { connection };
+        archive.drop_outdated_index()?;
+        archive.index_unindexed_games()?;
+        Ok(archive)
+    }
+
+    /// Empties a position index built by an older version, to be indexed again
+    fn drop_outdated_index(&self) -> Result<(), ArchiveError> {
+        let version: i64 = self.connection.query_row("PRAGMA user_version", [], |row| row.get(0))?;
+        if version != INDEX_VERSION {
+            let transaction = self.connection.unchecked_transaction()?;
+            transaction.execute("DELETE FROM positions", [])?;
+            transaction.pragma_update(None, "user_version", INDEX_VERSION)?;
+            transaction.commit()?;
+        }
+        Ok(())
+    }
+
+    /// Indexes the positions of games stored before the archive kept a position index
+    fn index_unindexed_games(&self) -> Result<(), ArchiveError> {
+        let ids: Vec<i64> = self
+            .connection
+            .prepare("SELECT id FROM games WHERE id NOT IN (SELECT game_id FROM positions)")?
+            .query_map([], |row| row.get(0))?
+            .collect::<rusqlite::Result<_>>()?;
+        // A game that no longer loads stays listed, just not found by position
+        for id in ids {
+            if let Ok(game) = self.load(id) {
+                let transaction = self.connection.unchecked_transaction()?;
+                index_positions(&transaction, id, &game)?;
+                transaction.commit()?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Stores the game and returns its ID. With an `id` the stored game is replaced,
+    /// keeping its tags unless new ones are given, so a game can be saved as it goes.
+    pub fn store(&self, game: &ChessGame, id: Option<i64>, tags: Option<&[(String, String)]>) -> Result<i64, ArchiveError> {
+        let tags = tags.map(serde_json::to_string).transpose().map_err(io::Error::from)?;
+        let moves = game.san_history().join(" ");
+        let result = game.result().result;
+        // Named as the settings and commands name it ("threeCheck")
+        let variant = serde_json::to_value(game.get_board_state().variant).map_err(io::Error::from)?;
+        let finished = game.get_status().is_over();
+        let document = game.to_json()?;
+        let now = now_ms();
+
+        // One transaction, so the game and its index rows are written together
+        let transaction = self.connection.unchecked_transaction()?;
+        let id = match id {
+            Some(id) => {
+                let updated = transaction.execute(
+                    "UPDATE games SET tags = COALESCE(?1, tags), moves = ?2, result = ?3, variant = ?4,
+                         finished = ?5, game = ?6, updated_at = ?7
+                     WHERE id = ?8",
+                    params![tags, moves, result, variant.as_str(), finished, document, now, id],
+                )?;
+                if updated == 0 {
+                    return Err(ArchiveError::NotFound(id));
+                }
+                id
+            }
+            None => {
+                transaction.execute(
+                    "INSERT INTO games (tags, moves, result, variant, finished, game, created_at, updated_at)
+                     VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?7)",
+                    params![tags.unwrap_or_else(|| "[]".to_string()), moves, result, variant.as_str(), finished, document, now],
+                )?;
+                transaction.last_insert_rowid()
+            }
+        };
+        index_positions(&transaction, id, game)?;
+        transaction.commit()?;
+        Ok(id)
+    }
+
+    /// Every stored game, most recently updated first
+    pub fn list(&self) -> Result<Vec<ArchivedGame>, ArchiveError> {
+        let mut statement = self
+            .connection
+            .prepare(&format!("SELECT {} FROM games ORDER BY updated_at DESC, id DESC", GAME_COLUMNS))?;
+        let games = statement.query_map([], read_game)?.collect::<rusqlite::Result<_>>()?;
+        Ok(games)
+    }
+
+    /// The stored games that reach `position`, most recently updated first, each with
+    /// the ply it first occurs at and the move played from it: an opening explorer over
+    /// the player's own games
+    pub fn find_position(&self, position: &Position) -> Result<Vec<PositionMatch>, ArchiveError> {
+        let mut statement = self.connection.prepare(&format!(
+            "SELECT {}, MIN(positions.ply) FROM positions JOIN games ON games.id = positions.game_id
+             WHERE positions.hash = ?1
+             GROUP BY games.id ORDER BY games.updated_at DESC, games.id DESC",
+            GAME_COLUMNS
+        ))?;
+        let matches = statement
+            .query_map([position.position_key() as i64], |row| {
+                let game = read_game(row)?;
+                let ply = row.get::<_, i64>(8)? as usize;
+                let next_move = game.moves.get(ply).cloned();
+                Ok(PositionMatch { game, ply, next_move })
+            })?
+            .collect::<rusqlite::Result<_>>()?;
+        Ok(matches)
+    }
+
+    /// The stored game, with its history and variations, ready to be played on
+    pub fn load(&self, id: i64) -> Result<ChessGame, ArchiveError> {
+        let document: Option<String> = self
+            .connection
+            .query_row("SELECT game FROM games WHERE id = ?1", [id], |row| row.get(0))
+            .optional()?;
+        let document = document.ok_or(ArchiveError::NotFound(id))?;
+        Ok(ChessGame::from_json(&document)?)
+    }
+
+    pub fn delete(&self, id: i64) -> Result<(), ArchiveError> {
+        match self.connection.execute("DELETE FROM games WHERE id = ?1", [id])? {
+            0 => Err(ArchiveError::NotFound(id)),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Replaces the game's rows in the position index with the positions of its current line
+fn index_positions(connection: &Connection, id: i64, game: &ChessGame) -> Result<(), ArchiveError> {
+    connection.execute("DELETE FROM positions WHERE game_id = ?1", [id])?;
+    let mut insert = connection.prepare("INSERT OR IGNORE INTO positions (game_id, ply, hash) VALUES (?1, ?2, ?3)")?;
+    for (ply, hash) in game.position_hashes().into_iter().enumerate() {
+        // SQLite integers are signed; the bits are all that matter
+        insert.execute(params![id, ply as i64, hash as i64])?;
+    }
+    Ok(())
+}
+
+/// An `ArchivedGame` from a row that starts with `GAME_COLUMNS`
+fn read_game(row: &Row) -> rusqlite::Result<ArchivedGame> {
+    let tags: String = row.get(1)?;
+    let variant: String = row.get(4)?;
+    Ok(ArchivedGame {
+        id: row.get(0)?,
+        tags: serde_json::from_str(&tags).map_err(|e| conversion_error(1, e))?,
+        moves: row.get::<_, String>(2)?.split_whitespace().map(str::to_string).collect(),
+        result: row.get(3)?,
+        variant: serde_json::from_value(variant.into()).map_err(|e| conversion_error(4, e))?,
+        finished: row.get(5)?,
+        created_at: row.get::<_, i64>(6)? as u64,
+        updated_at: row.get::<_, i64>(7)? as u64,
+    })
+}
+
+fn conversion_error(column: usize, error: serde_json::Error) -> rusqlite::Error {
+    rusqlite::Error::FromSqlConversionFailure(column, Type::Text, Box::new(error))
+}
+
+fn now_ms() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_millis() as i64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_store_list_load_delete() {
+        let dir = std::env::temp_dir().join(format!("chess-archive-{}", std::process::id()));
+        let archive = GameArchive::in_dir(dir.clone()).unwrap();
+        assert!(archive.list().unwrap().is_empty());
+
+        let mut game = ChessGame::new();
+        game.make_uci_moves(&["e2e4", "e7e5"]).unwrap();
+        let tags = [("White".to_string(), "Ann".to_string())];
+        let id = archive.store(&game, None, Some(&tags)).unwrap();
+
+        // Saving again as the game goes on updates the same entry and keeps its tags
+        game.make_uci_moves(&["d1h5", "b8c6", "f1c4", "g8f6", "h5f7"]).unwrap();
+        assert_eq!(archive.store(&game, Some(id), None).unwrap(), id);
+        let crazyhouse = ChessGame::new_variant(Variant::Crazyhouse);
+        let other = archive.store(&crazyhouse, None, None).unwrap();
+
+        let games = archive.list().unwrap();
+        assert_eq!(games.iter().map(|game| game.id).collect::<Vec<_>>(), [other, id]);
+        assert_eq!(games[1].tags, tags);
+        assert_eq!(games[1].moves.len(), 7);
+        assert_eq!(games[1].result, "1-0");
+        assert!(games[1].finished);
+        assert!(games[1].created_at <= games[1].updated_at);
+        assert_eq!(games[0].variant, Variant::Crazyhouse);
+        assert!(!games[0].finished);
+
+        // Reopened, the archive still has both games
+        drop(archive);
+        let archive = GameArchive::in_dir(dir.clone()).unwrap();
+        let loaded = archive.load(id).unwrap();
+        assert_eq!(loaded.to_fen(), game.to_fen());
+        assert_eq!(loaded.san_history(), game.san_history());
+
+        archive.delete(id).unwrap();
+        assert!(matches!(archive.load(id), Err(ArchiveError::NotFound(_))));
+        assert!(matches!(archive.delete(id), Err(ArchiveError::NotFound(_))));
+        assert!(matches!(archive.store(&game, Some(id), None), Err(ArchiveError::NotFound(_))));
+        assert_eq!(archive.list().unwrap().len(), 1);
+
+        drop(archive);
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_find_games_with_position() {
+        let dir = std::env::temp_dir().join(format!("chess-archive-positions-{}", std::process::id()));
+        let archive = GameArchive::in_dir(dir.clone()).unwrap();
+
+        // Two move orders into the same position
+        let mut first = ChessGame::new();
+        first.make_uci_moves(&["e2e4", "e7e5", "g1f3", "b8c6", "f1b5"]).unwrap();
+        let first_id = archive.store(&first, None, None).unwrap();
+        let mut second = ChessGame::new();
+        second.make_uci_moves(&["g1f3", "e7e5", "e2e4", "b8c6"]).unwrap();
+        let second_id = archive.store(&second, None, None).unwrap();
+
+        let fen = "r1bqkbnr/pppp1ppp/2n5/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R w KQkq - 2 3";
+        let position = ChessGame::from_fen(fen).unwrap().get_board_state().clone();
+        let found = |archive: &GameArchive| -> Vec<(i64, usize, Option<String>)> {
+            archive
+                .find_position(&position)
+                .unwrap()
+                .into_iter()
+                .map(|found| (found.game.id, found.ply, found.next_move))
+                .collect()
+        };
+        assert_eq!(found(&archive), [(second_id, 4, None), (first_id, 4, Some("Bb5".to_string()))]);
+
+        let start = ChessGame::new().get_board_state().clone();
+        assert_eq!(archive.find_position(&start).unwrap()[1].next_move.as_deref(), Some("e4"));
+        let elsewhere = ChessGame::from_fen("4k3/8/8/8/8/8/8/4K3 w - - 0 1").unwrap();
+        assert!(archive.find_position(elsewhere.get_board_state()).unwrap().is_empty());
+
+        // Reached by a double push, the position still matches one given without an
+        // en passant square when no en passant capture is possible
+        let after_e4 = ChessGame::from_fen("rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq - 0 1").unwrap();
+        assert_eq!(archive.find_position(after_e4.get_board_state()).unwrap().len(), 1);
+        let after_e4 = ChessGame::from_fen("rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1").unwrap();
+        assert_eq!(archive.find_position(after_e4.get_board_state()).unwrap().len(), 1);
+
+        // Games stored before the index existed are indexed when the archive opens
+        archive.connection.execute("DELETE FROM positions", []).unwrap();
+        drop(archive);
+        let archive = GameArchive::in_dir(dir.clone()).unwrap();
+        assert_eq!(found(&archive).len(), 2);
+
+        // So are games indexed by an older version of the index
+        archive.connection.execute("UPDATE positions SET hash = 0", []).unwrap();
+        archive.connection.pragma_update(None, "user_version", 0).unwrap();
+        drop(archive);
+        let archive = GameArchive::in_dir(dir.clone()).unwrap();
+        assert_eq!(found(&archive).len(), 2);
+
+        archive.delete(first_id).unwrap();
+        assert_eq!(found(&archive), [(second_id, 4, None)]);
+
+        drop(archive);
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,70 @@
+use crate::chess_engine::ChessGame;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+
+const AUTOSAVE_FILE: &str = "autosave.json";
+
+/// Keeps the active game on disk so it can be restored when the app restarts
+pub struct Autosave {
+    path: PathBuf,
+}
+
+impl Autosave {
+    /// Autosave stored in the given app data directory
+    pub fn in_dir(dir: PathBuf) -> Self {
+        Autosave {
+            path: dir.join(AUTOSAVE_FILE),
+        }
+    }
+
+    /// The saved game, if there is one and it can still be read
+    pub fn load(&self) -> Option<ChessGame> {
+        let json = fs::read_to_string(&self.path).ok()?;
+        ChessGame::from_json(&json).ok()
+    }
+
+    /// Writes the game next to the autosave and then renames it into place, so a
+    /// crash mid-write never leaves a truncated save behind
+    pub fn save(&self, game: &ChessGame) -> io::Result<()> {
+        let json = game
+            .to_json()
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e.to_string()))?;
+
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let temporary = self.path.with_extension("json.tmp");
+        fs::write(&temporary, json)?;
+        fs::rename(&temporary, &self.path)
+    }
+
+    pub fn discard(&self) -> io::Result<()> {
+        match fs::remove_file(&self.path) {
+            Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e),
+            _ => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_save_load_discard() {
+        let dir = std::env::temp_dir().join(format!("chess-autosave-{}", std::process::id()));
+        let autosave = Autosave::in_dir(dir.clone());
+        assert!(autosave.load().is_none());
+
+        let mut game = ChessGame::new();
+        game.make_san_move("e4").unwrap();
+        autosave.save(&game).unwrap();
+        assert_eq!(autosave.load().unwrap().to_fen(), game.to_fen());
+
+        autosave.discard().unwrap();
+        assert!(autosave.load().is_none());
+        autosave.discard().unwrap();
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,7 @@
+// Command-line UCI engine for chess GUIs and engine matches (e.g. cutechess-cli, Arena)
+
+use std::io;
+
+fn main() -> io::Result<()> {
+    chess_engine_lib::uci::run(io::stdin().lock(), io::stdout())
+}
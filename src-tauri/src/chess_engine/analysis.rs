@@ -1,5 +1,8 @@
+use rayon::prelude::*;
 use serde::{Deserialize, Serialize};
-use crate::chess_engine::{Move, Piece, Position};
+use std::cmp::Reverse;
+use crate::chess_engine::{Color, Move, Piece, Position};
+use crate::chess_engine::tactics::{move_motifs, Motif};
 
 /// Category of chess move based on its characteristics
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
@@ -35,16 +38,21 @@ pub struct MoveAnalysis {
 
     /// Change in material balance (in centipawns)
     pub material_change: i32,
+
+    /// Tactical motifs the move creates
+    pub motifs: Vec<Motif>,
 }
 
 impl MoveAnalysis {
     /// Analyze a move in the context of a position
     pub fn analyze(chess_move: &Move, position: &Position) -> Self {
-        use crate::chess_engine::validation::{apply_move_for_validation, is_in_check};
+        use crate::chess_engine::validation::is_in_check;
 
         // Determine if this is a capture
         let captured_piece = if chess_move.is_en_passant {
             Some(Piece::Pawn)
+        } else if chess_move.is_castling {
+            None
         } else {
             position.board.get(chess_move.to).map(|(piece, _)| piece)
         };
@@ -60,7 +68,7 @@ impl MoveAnalysis {
 
         // Apply the move to check if it results in check
         let mut test_position = position.clone();
-        apply_move_for_validation(&mut test_position, chess_move);
+        test_position.board.apply_move_for(chess_move, position.side_to_move);
 
         // Check if opponent king is in check after this move
         let opponent_color = position.side_to_move.opposite();
@@ -69,6 +77,8 @@ impl MoveAnalysis {
         // Categorize the move
         let category = categorize_move(chess_move, is_capture, is_check);
 
+        let motifs = move_motifs(position, &test_position, chess_move);
+
         MoveAnalysis {
             move_data: chess_move.clone(),
             is_capture,
@@ -76,6 +86,7 @@ impl MoveAnalysis {
             captured_piece,
             category,
             material_change,
+            motifs,
         }
     }
 }
@@ -109,7 +120,7 @@ fn categorize_move(chess_move: &Move, is_capture: bool, is_check: bool) -> MoveC
 }
 
 /// Get the material value of a piece in centipawns (100 = 1 pawn)
-pub fn piece_value(piece: Piece) -> i32 {
+pub const fn piece_value(piece: Piece) -> i32 {
     match piece {
         Piece::Pawn => 100,
         Piece::Knight => 320,
@@ -120,14 +131,64 @@ pub fn piece_value(piece: Piece) -> i32 {
     }
 }
 
-/// Analyze all legal moves for a position
+/// How many of each piece type one side has on the board
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MaterialCount {
+    pub pawns: u8,
+    pub knights: u8,
+    pub bishops: u8,
+    pub rooks: u8,
+    pub queens: u8,
+    /// The pieces' plain values added up, in centipawns
+    pub value: i32,
+}
+
+impl MaterialCount {
+    pub fn count(position: &Position, color: Color) -> Self {
+        let mut counts = MaterialCount::default();
+        for (_, piece) in position.board.pieces_of_color(color) {
+            match piece {
+                Piece::Pawn => counts.pawns += 1,
+                Piece::Knight => counts.knights += 1,
+                Piece::Bishop => counts.bishops += 1,
+                Piece::Rook => counts.rooks += 1,
+                Piece::Queen => counts.queens += 1,
+                Piece::King => {}
+            }
+            counts.value += piece_value(piece);
+        }
+        counts
+    }
+}
+
+/// Material on the board for both sides, counted with plain piece values and
+/// independent of the full evaluation
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MaterialBalance {
+    pub white: MaterialCount,
+    pub black: MaterialCount,
+    /// White's material minus Black's, in centipawns
+    pub difference: i32,
+}
+
+pub fn material_balance(position: &Position) -> MaterialBalance {
+    let white = MaterialCount::count(position, Color::White);
+    let black = MaterialCount::count(position, Color::Black);
+    MaterialBalance { white, black, difference: white.value - black.value }
+}
+
+/// Analyze all legal moves for a position, in parallel. The biggest material gains
+/// come first; moves that gain the same keep their generation order.
 pub fn analyze_all_moves(position: &Position) -> Vec<MoveAnalysis> {
     use crate::chess_engine::validation::generate_legal_moves;
 
     let legal_moves = generate_legal_moves(position);
-    legal_moves.iter()
+    let mut analyses: Vec<MoveAnalysis> = legal_moves
+        .par_iter()
         .map(|m| MoveAnalysis::analyze(m, position))
-        .collect()
+        .collect();
+    analyses.sort_by_key(|analysis| Reverse(analysis.material_change));
+    analyses
 }
 
 #[cfg(test)]
@@ -144,6 +205,29 @@ mod tests {
         assert_eq!(piece_value(Piece::Queen), 900);
     }
 
+    #[test]
+    fn test_material_balance() {
+        let balance = material_balance(&Position::new());
+        assert_eq!((balance.white, balance.difference), (balance.black, 0));
+        assert_eq!((balance.white.pawns, balance.white.knights, balance.white.queens), (8, 2, 1));
+
+        // White is a knight up for a pawn
+        let position = crate::chess_engine::fen::parse_fen("4k3/pp6/8/8/8/8/P7/4KN2 w - - 0 1").unwrap();
+        let balance = material_balance(&position);
+        assert_eq!((balance.white.value, balance.black.value), (420, 200));
+        assert_eq!(balance.difference, 220);
+    }
+
+    #[test]
+    fn test_analyze_all_moves_puts_biggest_gains_first() {
+        let position = crate::chess_engine::fen::parse_fen("4k3/8/8/3q4/8/2p5/3R4/4K3 w - - 0 1").unwrap();
+        let analyses = analyze_all_moves(&position);
+        assert_eq!(analyses.len(), crate::chess_engine::validation::generate_legal_moves(&position).len());
+        assert_eq!(analyses[0].move_data.to_uci(), "d2d5");
+        assert_eq!(analyses[0].material_change, 900);
+        assert!(analyses.windows(2).all(|pair| pair[0].material_change >= pair[1].material_change));
+    }
+
     #[test]
     fn test_quiet_move_categorization() {
         let chess_move = Move {
@@ -152,6 +236,9 @@ mod tests {
             promotion: None,
             is_castling: false,
             is_en_passant: false,
+            is_null: false,
+            drop: None,
+            castling_rook: None,
         };
 
         let category = categorize_move(&chess_move, false, false);
@@ -166,6 +253,9 @@ mod tests {
             promotion: None,
             is_castling: false,
             is_en_passant: false,
+            is_null: false,
+            drop: None,
+            castling_rook: None,
         };
 
         let category = categorize_move(&chess_move, true, false);
@@ -180,6 +270,9 @@ mod tests {
             promotion: None,
             is_castling: true,
             is_en_passant: false,
+            is_null: false,
+            drop: None,
+            castling_rook: None,
         };
 
         let category = categorize_move(&chess_move, false, false);
@@ -1,11 +1,45 @@
-use crate::chess_engine::types::{Color, Piece, Square};
+use crate::chess_engine::magic::{bishop_attacks, queen_attacks, rook_attacks};
+use crate::chess_engine::types::{Color, Piece, Square, Move};
+use once_cell::sync::OnceCell;
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+/// Squares attacked by each side, pins ignored
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AttackMap {
+    white: u64,
+    black: u64,
+}
+
+impl AttackMap {
+    pub fn by(&self, color: Color) -> u64 {
+        match color {
+            Color::White => self.white,
+            Color::Black => self.black,
+        }
+    }
+}
+
+/// An 8x8 board as an array of 64 squares plus one occupancy bitboard per color, with
+/// index and bit 0 = a1 to 63 = h8. Move generation, hashing and the opening book all
+/// rely on this layout.
+#[derive(Debug, Clone)]
 pub struct Board {
     squares: [Option<(Piece, Color)>; 64],
+    /// Squares holding a white piece and a black piece, kept in step with `squares`
+    white: u64,
+    black: u64,
+    /// Worked out on first use and cleared by every change to the board
+    attacks: OnceCell<AttackMap>,
+}
+
+impl PartialEq for Board {
+    fn eq(&self, other: &Self) -> bool {
+        self.squares == other.squares
+    }
 }
 
+impl Eq for Board {}
+
 impl Serialize for Board {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
     where
@@ -27,9 +61,11 @@ impl<'de> Deserialize<'de> for Board {
                 squares_vec.len()
             )));
         }
-        let mut squares = [None; 64];
-        squares.copy_from_slice(&squares_vec);
-        Ok(Board { squares })
+        let mut board = Board::new();
+        for (index, piece) in squares_vec.into_iter().enumerate() {
+            board.set(Square::new(index as u8).unwrap(), piece);
+        }
+        Ok(board)
     }
 }
 
@@ -37,6 +73,9 @@ impl Board {
     pub fn new() -> Self {
         Board {
             squares: [None; 64],
+            white: 0,
+            black: 0,
+            attacks: OnceCell::new(),
         }
     }
 
@@ -82,10 +121,66 @@ impl Board {
 
     pub fn set(&mut self, square: Square, piece: Option<(Piece, Color)>) {
         self.squares[square.index() as usize] = piece;
+        self.attacks = OnceCell::new();
+        let bit = 1u64 << square.index();
+        self.white &= !bit;
+        self.black &= !bit;
+        match piece {
+            Some((_, Color::White)) => self.white |= bit,
+            Some((_, Color::Black)) => self.black |= bit,
+            None => {}
+        }
+    }
+
+    /// Moves the pieces for `mv`: the rook too when castling, the pawn taken en passant,
+    /// and the chosen piece in place of a promoting pawn. Rights and clocks are left to
+    /// the position.
+    pub fn apply_move(&mut self, mv: &Move) {
+        if mv.is_null {
+            return;
+        }
+
+        // The pawn taken en passant stands beside the capturing pawn
+        if mv.is_en_passant {
+            if let Some(captured_square) = Square::from_rank_file(mv.from.rank(), mv.to.file()) {
+                self.set(captured_square, None);
+            }
+        }
+
+        if let Some((rook_from, rook_to)) = mv.castling_rook_squares() {
+            let king = self.get(mv.from);
+            let rook = self.get(rook_from);
+            debug_assert!(
+                matches!(rook, Some((Piece::Rook, c)) if Some(c) == king.map(|(_, c)| c)),
+                "Rook not found or wrong color at castling position"
+            );
+            // Both are lifted before either lands: in Chess960 each may land where the other stood
+            self.set(mv.from, None);
+            self.set(rook_from, None);
+            self.set(rook_to, rook);
+            self.set(mv.to, king);
+            return;
+        }
+
+        let piece = self.get(mv.from);
+        self.set(mv.from, None);
+        match (mv.promotion, piece) {
+            (Some(promotion), Some((_, color))) => self.set(mv.to, Some((promotion, color))),
+            _ => self.set(mv.to, piece),
+        }
+    }
+
+    /// [`apply_move`](Self::apply_move) for a move by `color`, which a drop needs to
+    /// know whose piece it places
+    pub fn apply_move_for(&mut self, mv: &Move, color: Color) {
+        match mv.drop {
+            Some(piece) => self.set(mv.to, Some((piece, color))),
+            None => self.apply_move(mv),
+        }
     }
 
     pub fn is_empty(&self, square: Square) -> bool {
-        self.squares[square.index() as usize].is_none()
+        self.occupied() & (1u64 << square.index()) == 0
     }
 
     pub fn find_king(&self, color: Color) -> Option<Square> {
@@ -99,16 +194,10 @@ impl Board {
         None
     }
 
-    pub fn pieces_of_color(&self, color: Color) -> Vec<(Square, Piece)> {
-        let mut pieces = Vec::new();
-        for i in 0..64 {
-            if let Some((piece, c)) = self.squares[i] {
-                if c == color {
-                    pieces.push((Square::new(i as u8).unwrap(), piece));
-                }
-            }
-        }
-        pieces
+    /// Pieces of one color with their squares, from a1 to h8, walking the occupied squares
+    pub fn pieces_of_color(&self, color: Color) -> impl Iterator<Item = (Square, Piece)> + '_ {
+        squares_in(self.occupancy(color))
+            .filter_map(move |square| self.get(square).map(|(piece, _)| (square, piece)))
     }
 
     pub fn is_attacked_by(
@@ -139,11 +228,6 @@ impl Board {
         }
 
         // Check for knight attacks
-        const KNIGHT_OFFSETS: [(i8, i8); 8] = [
-            (-2, -1), (-2, 1), (-1, -2), (-1, 2),
-            (1, -2), (1, 2), (2, -1), (2, 1),
-        ];
-
         for (rank_offset, file_offset) in KNIGHT_OFFSETS {
             let knight_rank = (target_rank as i8) + rank_offset;
             let knight_file = (target_file as i8) + file_offset;
@@ -160,12 +244,6 @@ impl Board {
         }
 
         // Check for king attacks
-        const KING_OFFSETS: [(i8, i8); 8] = [
-            (-1, -1), (-1, 0), (-1, 1),
-            (0, -1),           (0, 1),
-            (1, -1),  (1, 0),  (1, 1),
-        ];
-
         for (rank_offset, file_offset) in KING_OFFSETS {
             let king_rank = (target_rank as i8) + rank_offset;
             let king_file = (target_file as i8) + file_offset;
@@ -181,59 +259,164 @@ impl Board {
             }
         }
 
-        // Check for sliding piece attacks (bishop, rook, queen)
-        const BISHOP_DIRECTIONS: [(i8, i8); 4] = [(-1, -1), (-1, 1), (1, -1), (1, 1)];
-        const ROOK_DIRECTIONS: [(i8, i8); 4] = [(-1, 0), (1, 0), (0, -1), (0, 1)];
+        // Check for sliding piece attacks: only the first piece along each line can attack
+        let attackers = self.occupancy(attacker_color);
+        let diagonal_blockers = bishop_attacks(square, self.occupied()) & attackers;
+        let straight_blockers = rook_attacks(square, self.occupied()) & attackers;
+        self.holds_any(diagonal_blockers, attacker_color, &[Piece::Bishop, Piece::Queen])
+            || self.holds_any(straight_blockers, attacker_color, &[Piece::Rook, Piece::Queen])
+    }
 
-        for (rank_dir, file_dir) in BISHOP_DIRECTIONS {
-            if self.is_attacked_along_ray(square, attacker_color, rank_dir, file_dir, &[Piece::Bishop, Piece::Queen]) {
-                return true;
+    /// Bitmask of every square `color` attacks, from the cached attack map
+    pub fn attacked_by(&self, color: Color) -> u64 {
+        self.attack_map().by(color)
+    }
+
+    /// Squares attacked by each side, worked out once per placement of the pieces
+    pub fn attack_map(&self) -> AttackMap {
+        *self.attacks.get_or_init(|| {
+            let side = |color| {
+                self.pieces_of_color(color)
+                    .fold(0, |mask, (square, piece)| mask | self.attacks_from(square, piece, color))
+            };
+            AttackMap { white: side(Color::White), black: side(Color::Black) }
+        })
+    }
+
+    /// Bitmask of occupied squares, either color
+    pub fn occupied(&self) -> u64 {
+        self.white | self.black
+    }
+
+    /// Bitmask of occupied squares for one color (bit index = square index)
+    pub fn occupancy(&self, color: Color) -> u64 {
+        match color {
+            Color::White => self.white,
+            Color::Black => self.black,
+        }
+    }
+
+    /// Bitmask of squares a piece standing on `square` attacks (pseudo-legal:
+    /// pins are ignored, and squares occupied by either color are included)
+    pub fn attacks_from(&self, square: Square, piece: Piece, color: Color) -> u64 {
+        match piece {
+            Piece::Pawn => {
+                let direction = if color == Color::White { 1 } else { -1 };
+                self.offset_attacks(square, &[(direction, -1), (direction, 1)])
+            }
+            Piece::Knight => self.offset_attacks(square, &KNIGHT_OFFSETS),
+            Piece::King => self.offset_attacks(square, &KING_OFFSETS),
+            Piece::Bishop => bishop_attacks(square, self.occupied()),
+            Piece::Rook => rook_attacks(square, self.occupied()),
+            Piece::Queen => queen_attacks(square, self.occupied()),
+        }
+    }
+
+    /// Bitmask of every square attacked by the pawns of one color
+    pub fn pawn_attacks(&self, color: Color) -> u64 {
+        self.pieces_of_color(color)
+            .filter(|(_, piece)| *piece == Piece::Pawn)
+            .fold(0, |mask, (square, _)| mask | self.attacks_from(square, Piece::Pawn, color))
+    }
+
+    fn offset_attacks(&self, square: Square, offsets: &[(i8, i8)]) -> u64 {
+        let mut mask = 0u64;
+        for (rank_offset, file_offset) in offsets {
+            let rank = (square.rank() as i8) + rank_offset;
+            let file = (square.file() as i8) + file_offset;
+            if is_valid_square(rank, file) {
+                mask |= 1u64 << (rank * 8 + file);
             }
         }
+        mask
+    }
 
-        for (rank_dir, file_dir) in ROOK_DIRECTIONS {
-            if self.is_attacked_along_ray(square, attacker_color, rank_dir, file_dir, &[Piece::Rook, Piece::Queen]) {
+    /// Whether any of `squares` holds one of `pieces` of `color`
+    fn holds_any(&self, mut squares: u64, color: Color, pieces: &[Piece]) -> bool {
+        while squares != 0 {
+            let index = squares.trailing_zeros() as usize;
+            if matches!(self.squares[index], Some((piece, c)) if c == color && pieces.contains(&piece)) {
                 return true;
             }
+            squares &= squares - 1;
         }
-
         false
     }
+}
 
-    fn is_attacked_along_ray(
-        &self,
-        square: Square,
-        attacker_color: Color,
-        rank_dir: i8,
-        file_dir: i8,
-        piece_types: &[Piece],
-    ) -> bool {
-        let mut rank = square.rank() as i8;
-        let mut file = square.file() as i8;
+const KNIGHT_OFFSETS: [(i8, i8); 8] = [
+    (-2, -1), (-2, 1), (-1, -2), (-1, 2),
+    (1, -2), (1, 2), (2, -1), (2, 1),
+];
 
-        loop {
-            rank += rank_dir;
-            file += file_dir;
+const KING_OFFSETS: [(i8, i8); 8] = [
+    (-1, -1), (-1, 0), (-1, 1),
+    (0, -1),           (0, 1),
+    (1, -1),  (1, 0),  (1, 1),
+];
 
-            if !is_valid_square(rank, file) {
-                break;
-            }
+/// The squares of a bitmask, lowest first
+pub fn squares_in(mask: u64) -> SquareIter {
+    SquareIter(mask)
+}
 
-            if let Some(sq) = Square::from_rank_file(rank as u8, file as u8) {
-                if let Some((piece, color)) = self.get(sq) {
-                    if color == attacker_color && piece_types.contains(&piece) {
-                        return true;
-                    }
-                    // Blocked by any piece
-                    break;
-                }
-            }
-        }
+pub struct SquareIter(u64);
 
-        false
+impl Iterator for SquareIter {
+    type Item = Square;
+
+    fn next(&mut self) -> Option<Square> {
+        if self.0 == 0 {
+            return None;
+        }
+        let index = self.0.trailing_zeros() as u8;
+        self.0 &= self.0 - 1;
+        Square::new(index)
     }
 }
 
 pub fn is_valid_square(rank: i8, file: i8) -> bool {
     rank >= 0 && rank < 8 && file >= 0 && file < 8
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_attack_map_follows_changes() {
+        let mut board = Board::initial_position();
+        let e4 = 1u64 << Square::from_algebraic("e4").unwrap().index();
+        assert_eq!(board.attacked_by(Color::White) & e4, 0);
+        assert_eq!(board.attack_map().by(Color::White).count_ones(), 22);
+
+        // Moving the e-pawn to d3 brings e4 under attack
+        board.set(Square::from_algebraic("e2").unwrap(), None);
+        board.set(Square::from_algebraic("d3").unwrap(), Some((Piece::Pawn, Color::White)));
+        assert_ne!(board.attacked_by(Color::White) & e4, 0);
+
+        // Whether the map has been worked out does not affect equality
+        let mut fresh = Board::new();
+        for (index, piece) in board.squares.iter().enumerate() {
+            fresh.set(Square::new(index as u8).unwrap(), *piece);
+        }
+        assert_eq!(fresh, board);
+    }
+
+    #[test]
+    fn test_occupancy_follows_changes() {
+        let mut board = Board::initial_position();
+        assert_eq!(board.occupancy(Color::White), 0xFFFF);
+        assert_eq!(board.occupancy(Color::Black), 0xFFFF << 48);
+
+        // A capture swaps the square's color
+        let d7 = Square::from_algebraic("d7").unwrap();
+        board.set(d7, Some((Piece::Queen, Color::White)));
+        assert_ne!(board.occupancy(Color::White) & (1u64 << d7.index()), 0);
+        assert_eq!(board.occupancy(Color::Black) & (1u64 << d7.index()), 0);
+
+        board.set(d7, None);
+        assert!(board.is_empty(d7));
+        assert_eq!(board.occupied().count_ones(), 31);
+    }
+}
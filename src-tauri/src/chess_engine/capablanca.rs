@@ -0,0 +1,670 @@
+use crate::chess_engine::error::{ChessError, Result};
+use crate::chess_engine::types::Color;
+use serde::{Deserialize, Serialize};
+
+/// Files of the Capablanca board, a to j
+pub const FILES: u8 = 10;
+/// Ranks of the Capablanca board, 1 to 8
+pub const RANKS: u8 = 8;
+const SQUARES: usize = (FILES * RANKS) as usize;
+
+pub const CAPABLANCA_START_FEN: &str = "rnabqkbcnr/pppppppppp/10/10/10/10/PPPPPPPPPP/RNABQKBCNR w KQkq - 0 1";
+
+/// File the king starts on and castles from
+const KING_FILE: u8 = 5;
+
+/// Knight jumps as (file, rank) steps, shared by the archbishop and chancellor
+const KNIGHT_STEPS: [(i8, i8); 8] = [(1, 2), (2, 1), (2, -1), (1, -2), (-1, -2), (-2, -1), (-2, 1), (-1, 2)];
+const KING_STEPS: [(i8, i8); 8] = [(1, 0), (1, 1), (0, 1), (-1, 1), (-1, 0), (-1, -1), (0, -1), (1, -1)];
+const DIAGONALS: [(i8, i8); 4] = [(1, 1), (-1, 1), (-1, -1), (1, -1)];
+const ORTHOGONALS: [(i8, i8); 4] = [(1, 0), (0, 1), (-1, 0), (0, -1)];
+
+/// The pieces of Capablanca chess: the standard six and two compounds
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CapablancaPiece {
+    Pawn,
+    Knight,
+    Bishop,
+    Rook,
+    Queen,
+    King,
+    /// Moves as a bishop or a knight
+    Archbishop,
+    /// Moves as a rook or a knight
+    Chancellor,
+}
+
+impl CapablancaPiece {
+    /// What a pawn reaching the last rank can become
+    pub const PROMOTIONS: [CapablancaPiece; 6] = [
+        CapablancaPiece::Queen,
+        CapablancaPiece::Chancellor,
+        CapablancaPiece::Archbishop,
+        CapablancaPiece::Rook,
+        CapablancaPiece::Bishop,
+        CapablancaPiece::Knight,
+    ];
+
+    /// Uppercase FEN letter
+    pub fn letter(self) -> char {
+        match self {
+            CapablancaPiece::Pawn => 'P',
+            CapablancaPiece::Knight => 'N',
+            CapablancaPiece::Bishop => 'B',
+            CapablancaPiece::Rook => 'R',
+            CapablancaPiece::Queen => 'Q',
+            CapablancaPiece::King => 'K',
+            CapablancaPiece::Archbishop => 'A',
+            CapablancaPiece::Chancellor => 'C',
+        }
+    }
+
+    fn from_letter(letter: char) -> Option<Self> {
+        Some(match letter.to_ascii_uppercase() {
+            'P' => CapablancaPiece::Pawn,
+            'N' => CapablancaPiece::Knight,
+            'B' => CapablancaPiece::Bishop,
+            'R' => CapablancaPiece::Rook,
+            'Q' => CapablancaPiece::Queen,
+            'K' => CapablancaPiece::King,
+            'A' => CapablancaPiece::Archbishop,
+            'C' => CapablancaPiece::Chancellor,
+            _ => return None,
+        })
+    }
+
+    fn leaps_like_knight(self) -> bool {
+        matches!(self, CapablancaPiece::Knight | CapablancaPiece::Archbishop | CapablancaPiece::Chancellor)
+    }
+
+    fn slides_diagonally(self) -> bool {
+        matches!(self, CapablancaPiece::Bishop | CapablancaPiece::Queen | CapablancaPiece::Archbishop)
+    }
+
+    fn slides_orthogonally(self) -> bool {
+        matches!(self, CapablancaPiece::Rook | CapablancaPiece::Queen | CapablancaPiece::Chancellor)
+    }
+}
+
+/// A square of the 10x8 board, index 0 = a1 to 79 = j8
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CapablancaSquare {
+    index: u8,
+}
+
+impl CapablancaSquare {
+    pub fn new(index: u8) -> Option<Self> {
+        (usize::from(index) < SQUARES).then_some(CapablancaSquare { index })
+    }
+
+    pub fn from_rank_file(rank: u8, file: u8) -> Option<Self> {
+        (rank < RANKS && file < FILES).then_some(CapablancaSquare { index: rank * FILES + file })
+    }
+
+    pub fn from_algebraic(s: &str) -> Result<Self> {
+        let invalid = || ChessError::InvalidSquare { square: s.to_string() };
+        let mut chars = s.chars();
+        let (Some(file), Some(rank), None) = (chars.next(), chars.next(), chars.next()) else {
+            return Err(invalid());
+        };
+        if !('a'..='j').contains(&file) || !('1'..='8').contains(&rank) {
+            return Err(invalid());
+        }
+        Self::from_rank_file(rank as u8 - b'1', file as u8 - b'a').ok_or_else(invalid)
+    }
+
+    pub fn to_algebraic(self) -> String {
+        format!("{}{}", (b'a' + self.file()) as char, (b'1' + self.rank()) as char)
+    }
+
+    pub fn rank(self) -> u8 {
+        self.index / FILES
+    }
+
+    pub fn file(self) -> u8 {
+        self.index % FILES
+    }
+
+    pub fn index(self) -> u8 {
+        self.index
+    }
+
+    /// The square `files` and `ranks` away, if it is on the board
+    fn offset(self, (files, ranks): (i8, i8)) -> Option<Self> {
+        let file = self.file() as i8 + files;
+        let rank = self.rank() as i8 + ranks;
+        if (0..FILES as i8).contains(&file) && (0..RANKS as i8).contains(&rank) {
+            Self::from_rank_file(rank as u8, file as u8)
+        } else {
+            None
+        }
+    }
+}
+
+/// A move on the 10x8 board. Castling is written as the king's own move (f1i1, f1c1).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CapablancaMove {
+    pub from: CapablancaSquare,
+    pub to: CapablancaSquare,
+    pub promotion: Option<CapablancaPiece>,
+    pub is_castling: bool,
+    pub is_en_passant: bool,
+}
+
+impl CapablancaMove {
+    fn new(from: CapablancaSquare, to: CapablancaSquare) -> Self {
+        CapablancaMove { from, to, promotion: None, is_castling: false, is_en_passant: false }
+    }
+
+    pub fn to_uci(&self) -> String {
+        let mut uci = format!("{}{}", self.from.to_algebraic(), self.to.to_algebraic());
+        if let Some(piece) = self.promotion {
+            uci.push(piece.letter().to_ascii_lowercase());
+        }
+        uci
+    }
+}
+
+/// An 80-square board, kept apart from the 8x8 `Board` whose bitboards fill a `u64`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CapablancaBoard {
+    squares: [Option<(CapablancaPiece, Color)>; SQUARES],
+}
+
+impl CapablancaBoard {
+    pub fn empty() -> Self {
+        CapablancaBoard { squares: [None; SQUARES] }
+    }
+
+    pub fn get(&self, square: CapablancaSquare) -> Option<(CapablancaPiece, Color)> {
+        self.squares[usize::from(square.index)]
+    }
+
+    pub fn set(&mut self, square: CapablancaSquare, piece: Option<(CapablancaPiece, Color)>) {
+        self.squares[usize::from(square.index)] = piece;
+    }
+
+    pub fn find_king(&self, color: Color) -> Option<CapablancaSquare> {
+        (0..SQUARES as u8)
+            .filter_map(CapablancaSquare::new)
+            .find(|&square| self.get(square) == Some((CapablancaPiece::King, color)))
+    }
+
+    /// Whether a piece of `by` attacks `square`
+    pub fn is_attacked(&self, square: CapablancaSquare, by: Color) -> bool {
+        let holds = |target: Option<CapablancaSquare>, test: fn(CapablancaPiece) -> bool| {
+            target.and_then(|target| self.get(target)).is_some_and(|(piece, color)| color == by && test(piece))
+        };
+
+        let pawn_rank = match by {
+            Color::White => -1,
+            Color::Black => 1,
+        };
+        if [-1, 1]
+            .into_iter()
+            .any(|file| holds(square.offset((file, pawn_rank)), |piece| piece == CapablancaPiece::Pawn))
+        {
+            return true;
+        }
+        if KNIGHT_STEPS.iter().any(|&step| holds(square.offset(step), CapablancaPiece::leaps_like_knight)) {
+            return true;
+        }
+        if KING_STEPS.iter().any(|&step| holds(square.offset(step), |piece| piece == CapablancaPiece::King)) {
+            return true;
+        }
+        DIAGONALS.iter().any(|&step| holds(self.first_piece(square, step), CapablancaPiece::slides_diagonally))
+            || ORTHOGONALS
+                .iter()
+                .any(|&step| holds(self.first_piece(square, step), CapablancaPiece::slides_orthogonally))
+    }
+
+    /// The first occupied square from `square` in the direction `step`
+    fn first_piece(&self, square: CapablancaSquare, step: (i8, i8)) -> Option<CapablancaSquare> {
+        let mut current = square.offset(step)?;
+        while self.get(current).is_none() {
+            current = current.offset(step)?;
+        }
+        Some(current)
+    }
+}
+
+/// Castling rights in FEN order: White kingside, White queenside, Black kingside, Black queenside
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CapablancaCastling(pub [bool; 4]);
+
+impl CapablancaCastling {
+    fn index(color: Color, kingside: bool) -> usize {
+        let base = match color {
+            Color::White => 0,
+            Color::Black => 2,
+        };
+        base + usize::from(!kingside)
+    }
+
+    pub fn allows(&self, color: Color, kingside: bool) -> bool {
+        self.0[Self::index(color, kingside)]
+    }
+
+    fn revoke(&mut self, color: Color, kingside: bool) {
+        self.0[Self::index(color, kingside)] = false;
+    }
+}
+
+/// A position of Capablanca chess: king on the f-file between the bishops, archbishop
+/// beside the queenside bishop and chancellor beside the kingside one. Castling puts the
+/// king on the c- or i-file with the rook beside it on the d- or h-file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CapablancaPosition {
+    pub board: CapablancaBoard,
+    pub side_to_move: Color,
+    pub castling: CapablancaCastling,
+    pub en_passant_target: Option<CapablancaSquare>,
+    pub halfmove_clock: u32,
+    pub fullmove_number: u32,
+}
+
+impl CapablancaPosition {
+    pub fn new() -> Self {
+        Self::from_fen(CAPABLANCA_START_FEN).expect("the starting FEN is valid")
+    }
+
+    /// Reads a 10x8 FEN; ranks may use "10" for an empty rank
+    pub fn from_fen(fen: &str) -> Result<Self> {
+        let invalid = |reason: String| ChessError::InvalidFen { reason };
+        let fields: Vec<&str> = fen.split_whitespace().collect();
+        if fields.len() < 4 {
+            return Err(invalid(format!("expected at least 4 fields, found {}", fields.len())));
+        }
+
+        let rows: Vec<&str> = fields[0].split('/').collect();
+        if rows.len() != RANKS as usize {
+            return Err(invalid(format!("expected {} ranks, found {}", RANKS, rows.len())));
+        }
+        let mut board = CapablancaBoard::empty();
+        for (row, text) in rows.iter().enumerate() {
+            let rank = RANKS - 1 - row as u8;
+            let mut file = 0u8;
+            let mut chars = text.chars().peekable();
+            while let Some(c) = chars.next() {
+                if let Some(digit) = c.to_digit(10) {
+                    let mut empty = digit;
+                    // Two digits only make "10"
+                    if let Some(next) = chars.peek().and_then(|next| next.to_digit(10)) {
+                        empty = empty * 10 + next;
+                        chars.next();
+                    }
+                    file = file.saturating_add(empty as u8);
+                } else {
+                    let piece =
+                        CapablancaPiece::from_letter(c).ok_or_else(|| invalid(format!("unknown piece '{}'", c)))?;
+                    let color = if c.is_ascii_uppercase() { Color::White } else { Color::Black };
+                    let square = CapablancaSquare::from_rank_file(rank, file)
+                        .ok_or_else(|| invalid(format!("rank {} is longer than {} squares", rank + 1, FILES)))?;
+                    board.set(square, Some((piece, color)));
+                    file += 1;
+                }
+            }
+            if file != FILES {
+                return Err(invalid(format!("rank {} has {} squares, not {}", rank + 1, file, FILES)));
+            }
+        }
+        for color in [Color::White, Color::Black] {
+            if board.find_king(color).is_none() {
+                return Err(invalid(format!("{:?} has no king", color)));
+            }
+        }
+
+        let side_to_move = match fields[1] {
+            "w" => Color::White,
+            "b" => Color::Black,
+            other => return Err(invalid(format!("unknown side to move '{}'", other))),
+        };
+
+        let mut castling = CapablancaCastling([false; 4]);
+        if fields[2] != "-" {
+            for c in fields[2].chars() {
+                let index = "KQkq".find(c).ok_or_else(|| invalid(format!("unknown castling right '{}'", c)))?;
+                castling.0[index] = true;
+            }
+        }
+
+        let en_passant_target = match fields[3] {
+            "-" => None,
+            square => Some(
+                CapablancaSquare::from_algebraic(square)
+                    .map_err(|_| invalid(format!("bad en passant square '{}'", square)))?,
+            ),
+        };
+        let number = |index: usize, default: u32| -> Result<u32> {
+            fields
+                .get(index)
+                .map_or(Ok(default), |text| text.parse().map_err(|_| invalid(format!("bad move number '{}'", text))))
+        };
+
+        Ok(CapablancaPosition {
+            board,
+            side_to_move,
+            castling,
+            en_passant_target,
+            halfmove_clock: number(4, 0)?,
+            fullmove_number: number(5, 1)?,
+        })
+    }
+
+    pub fn to_fen(&self) -> String {
+        let mut placement = String::new();
+        for rank in (0..RANKS).rev() {
+            let mut empty = 0;
+            for file in 0..FILES {
+                let square = CapablancaSquare::from_rank_file(rank, file).expect("on the board");
+                match self.board.get(square) {
+                    Some((piece, color)) => {
+                        if empty > 0 {
+                            placement.push_str(&empty.to_string());
+                            empty = 0;
+                        }
+                        placement.push(match color {
+                            Color::White => piece.letter(),
+                            Color::Black => piece.letter().to_ascii_lowercase(),
+                        });
+                    }
+                    None => empty += 1,
+                }
+            }
+            if empty > 0 {
+                placement.push_str(&empty.to_string());
+            }
+            if rank > 0 {
+                placement.push('/');
+            }
+        }
+
+        let castling: String =
+            "KQkq".chars().zip(self.castling.0).filter_map(|(c, allowed)| allowed.then_some(c)).collect();
+        format!(
+            "{} {} {} {} {} {}",
+            placement,
+            if self.side_to_move == Color::White { "w" } else { "b" },
+            if castling.is_empty() { "-".to_string() } else { castling },
+            self.en_passant_target.map_or("-".to_string(), CapablancaSquare::to_algebraic),
+            self.halfmove_clock,
+            self.fullmove_number
+        )
+    }
+
+    pub fn is_in_check(&self, color: Color) -> bool {
+        self.board.find_king(color).is_some_and(|king| self.board.is_attacked(king, color.opposite()))
+    }
+
+    pub fn legal_moves(&self) -> Vec<CapablancaMove> {
+        self.pseudo_legal_moves().into_iter().filter(|mv| !self.play(mv).is_in_check(self.side_to_move)).collect()
+    }
+
+    /// The legal move written in UCI, castling as the king's own move
+    pub fn parse_uci(&self, uci: &str) -> Result<CapablancaMove> {
+        self.legal_moves()
+            .into_iter()
+            .find(|mv| mv.to_uci() == uci)
+            .ok_or_else(|| ChessError::InvalidMove { reason: format!("{} is not legal here", uci) })
+    }
+
+    /// The position after `mv`, which must be one of `legal_moves` or its pseudo-legal superset
+    pub fn play(&self, mv: &CapablancaMove) -> Self {
+        let mut next = self.clone();
+        let us = self.side_to_move;
+        let (piece, _) = self.board.get(mv.from).expect("a move starts on a piece");
+        let captured = self.board.get(mv.to);
+
+        next.board.set(mv.from, None);
+        next.board.set(mv.to, Some((mv.promotion.unwrap_or(piece), us)));
+        if mv.is_en_passant {
+            let taken = CapablancaSquare::from_rank_file(mv.from.rank(), mv.to.file()).expect("beside the pawn");
+            next.board.set(taken, None);
+        }
+        if mv.is_castling {
+            let kingside = mv.to.file() > mv.from.file();
+            let (rook_from, rook_to) = if kingside { (FILES - 1, mv.to.file() - 1) } else { (0, mv.to.file() + 1) };
+            let rank = mv.from.rank();
+            next.board.set(CapablancaSquare::from_rank_file(rank, rook_from).expect("on the board"), None);
+            next.board.set(
+                CapablancaSquare::from_rank_file(rank, rook_to).expect("on the board"),
+                Some((CapablancaPiece::Rook, us)),
+            );
+        }
+
+        // Rights go with a king or rook that moves, and with a rook taken on its corner
+        if piece == CapablancaPiece::King {
+            next.castling.revoke(us, true);
+            next.castling.revoke(us, false);
+        }
+        for square in [mv.from, mv.to] {
+            for color in [Color::White, Color::Black] {
+                let home_rank = match color {
+                    Color::White => 0,
+                    Color::Black => RANKS - 1,
+                };
+                if square.rank() == home_rank && square.file() == FILES - 1 {
+                    next.castling.revoke(color, true);
+                } else if square.rank() == home_rank && square.file() == 0 {
+                    next.castling.revoke(color, false);
+                }
+            }
+        }
+
+        let double_step = piece == CapablancaPiece::Pawn && mv.from.rank().abs_diff(mv.to.rank()) == 2;
+        next.en_passant_target = double_step
+            .then(|| CapablancaSquare::from_rank_file((mv.from.rank() + mv.to.rank()) / 2, mv.from.file()))
+            .flatten();
+        next.halfmove_clock =
+            if piece == CapablancaPiece::Pawn || captured.is_some() { 0 } else { self.halfmove_clock + 1 };
+        if us == Color::Black {
+            next.fullmove_number += 1;
+        }
+        next.side_to_move = us.opposite();
+        next
+    }
+
+    fn pseudo_legal_moves(&self) -> Vec<CapablancaMove> {
+        let us = self.side_to_move;
+        let mut moves = Vec::new();
+        for index in 0..SQUARES as u8 {
+            let from = CapablancaSquare { index };
+            let Some((piece, color)) = self.board.get(from) else {
+                continue;
+            };
+            if color != us {
+                continue;
+            }
+            if piece == CapablancaPiece::Pawn {
+                self.pawn_moves(from, &mut moves);
+                continue;
+            }
+
+            let mut add_to = |to: CapablancaSquare| {
+                if self.board.get(to).is_none_or(|(_, color)| color != us) {
+                    moves.push(CapablancaMove::new(from, to));
+                }
+            };
+            if piece.leaps_like_knight() {
+                KNIGHT_STEPS.iter().filter_map(|&step| from.offset(step)).for_each(&mut add_to);
+            }
+            if piece == CapablancaPiece::King {
+                KING_STEPS.iter().filter_map(|&step| from.offset(step)).for_each(&mut add_to);
+            }
+            let mut directions = Vec::new();
+            if piece.slides_diagonally() {
+                directions.extend(DIAGONALS);
+            }
+            if piece.slides_orthogonally() {
+                directions.extend(ORTHOGONALS);
+            }
+            for step in directions {
+                let mut current = from.offset(step);
+                while let Some(to) = current {
+                    add_to(to);
+                    if self.board.get(to).is_some() {
+                        break;
+                    }
+                    current = to.offset(step);
+                }
+            }
+        }
+        self.castling_moves(&mut moves);
+        moves
+    }
+
+    fn pawn_moves(&self, from: CapablancaSquare, moves: &mut Vec<CapablancaMove>) {
+        let us = self.side_to_move;
+        let (forward, start_rank, last_rank) = match us {
+            Color::White => (1, 1, RANKS - 1),
+            Color::Black => (-1, RANKS - 2, 0),
+        };
+        let mut push = |to: CapablancaSquare, is_en_passant: bool| {
+            if to.rank() == last_rank {
+                for piece in CapablancaPiece::PROMOTIONS {
+                    moves.push(CapablancaMove { promotion: Some(piece), ..CapablancaMove::new(from, to) });
+                }
+            } else {
+                moves.push(CapablancaMove { is_en_passant, ..CapablancaMove::new(from, to) });
+            }
+        };
+
+        if let Some(one) = from.offset((0, forward)).filter(|&to| self.board.get(to).is_none()) {
+            push(one, false);
+            if from.rank() == start_rank {
+                if let Some(two) = one.offset((0, forward)).filter(|&to| self.board.get(to).is_none()) {
+                    push(two, false);
+                }
+            }
+        }
+        for file in [-1, 1] {
+            let Some(to) = from.offset((file, forward)) else {
+                continue;
+            };
+            match self.board.get(to) {
+                Some((_, color)) if color != us => push(to, false),
+                None if self.en_passant_target == Some(to) => push(to, true),
+                _ => {}
+            }
+        }
+    }
+
+    fn castling_moves(&self, moves: &mut Vec<CapablancaMove>) {
+        let us = self.side_to_move;
+        let rank = match us {
+            Color::White => 0,
+            Color::Black => RANKS - 1,
+        };
+        let square = |file: u8| CapablancaSquare::from_rank_file(rank, file).expect("on the back rank");
+        let king = square(KING_FILE);
+        if self.board.get(king) != Some((CapablancaPiece::King, us)) || self.board.is_attacked(king, us.opposite()) {
+            return;
+        }
+
+        for (kingside, rook_file, king_to) in [(true, FILES - 1, FILES - 2), (false, 0, 2)] {
+            if !self.castling.allows(us, kingside)
+                || self.board.get(square(rook_file)) != Some((CapablancaPiece::Rook, us))
+            {
+                continue;
+            }
+            let mut between = if kingside { KING_FILE + 1..rook_file } else { rook_file + 1..KING_FILE };
+            if between.any(|file| self.board.get(square(file)).is_some()) {
+                continue;
+            }
+            // The king may not pass through or land on an attacked square
+            let mut crossed = if kingside { KING_FILE + 1..=king_to } else { king_to..=KING_FILE - 1 };
+            if crossed.any(|file| self.board.is_attacked(square(file), us.opposite())) {
+                continue;
+            }
+            moves.push(CapablancaMove { is_castling: true, ..CapablancaMove::new(king, square(king_to)) });
+        }
+    }
+}
+
+impl Default for CapablancaPosition {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Counts the leaf nodes of the legal move tree `depth` plies deep
+pub fn capablanca_perft(position: &CapablancaPosition, depth: u8) -> u64 {
+    if depth == 0 {
+        return 1;
+    }
+    let moves = position.legal_moves();
+    if depth == 1 {
+        return moves.len() as u64;
+    }
+    moves.iter().map(|mv| capablanca_perft(&position.play(mv), depth - 1)).sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fen_round_trip() {
+        let position = CapablancaPosition::new();
+        assert_eq!(position.to_fen(), CAPABLANCA_START_FEN);
+        let e4 = position.play(&position.parse_uci("e2e4").unwrap());
+        assert_eq!(e4.to_fen(), "rnabqkbcnr/pppppppppp/10/10/4P5/10/PPPP1PPPPP/RNABQKBCNR b KQkq e3 0 1");
+        assert_eq!(CapablancaPosition::from_fen(&e4.to_fen()).unwrap(), e4);
+
+        assert!(CapablancaPosition::from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1").is_err());
+        assert!(CapablancaSquare::from_algebraic("k1").is_err());
+        assert_eq!(CapablancaSquare::from_algebraic("j8").unwrap().index(), 79);
+    }
+
+    #[test]
+    fn test_start_perft() {
+        let position = CapablancaPosition::new();
+        assert_eq!(capablanca_perft(&position, 1), 28);
+        assert_eq!(capablanca_perft(&position, 2), 784);
+        assert_eq!(capablanca_perft(&position, 3), 25228);
+    }
+
+    #[test]
+    fn test_compound_pieces() {
+        // An archbishop on e4 reaches 14 bishop squares and 8 knight squares
+        let position = CapablancaPosition::from_fen("9k/10/10/10/4A5/10/10/K9 w - - 0 1").unwrap();
+        let archbishop = CapablancaSquare::from_algebraic("e4").unwrap();
+        let count =
+            |position: &CapablancaPosition, from| position.legal_moves().iter().filter(|mv| mv.from == from).count();
+        assert_eq!(count(&position, archbishop), 14 + 8);
+
+        // A chancellor gives check like a rook and like a knight
+        let position = CapablancaPosition::from_fen("k9/10/1C8/10/10/10/10/9K b - - 0 1").unwrap();
+        assert!(position.is_in_check(Color::Black));
+        let position = CapablancaPosition::from_fen("k9/10/10/10/10/10/10/C8K b - - 0 1").unwrap();
+        assert!(position.is_in_check(Color::Black));
+        let position = CapablancaPosition::from_fen("k9/10/2C7/10/10/10/10/9K b - - 0 1").unwrap();
+        assert!(!position.is_in_check(Color::Black));
+
+        // Promotion offers the compounds too
+        let position = CapablancaPosition::from_fen("k9/4P5/10/10/10/10/10/9K w - - 0 1").unwrap();
+        let promotions: Vec<String> =
+            position.legal_moves().iter().filter(|mv| mv.promotion.is_some()).map(CapablancaMove::to_uci).collect();
+        assert_eq!(promotions, ["e7e8q", "e7e8c", "e7e8a", "e7e8r", "e7e8b", "e7e8n"]);
+    }
+
+    #[test]
+    fn test_castling() {
+        let fen = "r4k3r/10/10/10/10/10/10/R4K3R w KQkq - 0 1";
+        let position = CapablancaPosition::from_fen(fen).unwrap();
+        let castles: Vec<String> =
+            position.legal_moves().iter().filter(|mv| mv.is_castling).map(CapablancaMove::to_uci).collect();
+        assert_eq!(castles, ["f1i1", "f1c1"]);
+
+        let kingside = position.play(&position.parse_uci("f1i1").unwrap());
+        assert_eq!(kingside.to_fen(), "r4k3r/10/10/10/10/10/10/R6RK1 b kq - 1 1");
+        let queenside = position.play(&position.parse_uci("f1c1").unwrap());
+        assert_eq!(queenside.to_fen(), "r4k3r/10/10/10/10/10/10/2KR5R b kq - 1 1");
+
+        // A black rook on the i-file keeps the king from castling onto i1
+        let position = CapablancaPosition::from_fen("r4k2rr/10/10/10/10/10/10/R4K3R w KQ - 0 1").unwrap();
+        let castles: Vec<String> =
+            position.legal_moves().iter().filter(|mv| mv.is_castling).map(CapablancaMove::to_uci).collect();
+        assert_eq!(castles, ["f1c1"]);
+    }
+}
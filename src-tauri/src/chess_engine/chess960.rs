@@ -0,0 +1,157 @@
+use crate::chess_engine::error::{ChessError, Result};
+use crate::chess_engine::types::Piece;
+use serde::{Deserialize, Serialize};
+use std::collections::hash_map::RandomState;
+use std::hash::BuildHasher;
+
+/// How many back-rank arrangements Chess960 has; number 518 is the standard one
+pub const CHESS960_POSITIONS: u16 = 960;
+
+/// Where the two knights go among the five squares left once the bishops and queen
+/// are placed, for each of the ten knight arrangements
+const KNIGHT_PLACEMENTS: [(usize, usize); 10] = [(0, 1), (0, 2), (0, 3), (0, 4), (1, 2), (1, 3), (1, 4), (2, 3), (2, 4), (3, 4)];
+
+/// A Chess960 starting position, with the numbers of both sides' back-rank arrangements
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Chess960Start {
+    pub fen: String,
+    pub white: u16,
+    /// The same as `white` except in Double Fischer Random Chess
+    pub black: u16,
+}
+
+impl Chess960Start {
+    /// The position where White's back rank is arrangement `white` and Black's is `black`,
+    /// castling with the outer rooks on both sides
+    pub fn new(white: u16, black: u16) -> Result<Self> {
+        let rank = |number, color_case: fn(char) -> char| -> Result<String> {
+            Ok(back_rank(number)?
+                .into_iter()
+                .map(|piece| color_case(piece_char(piece)))
+                .collect())
+        };
+        let fen = format!(
+            "{}/pppppppp/8/8/8/8/PPPPPPPP/{} w KQkq - 0 1",
+            rank(black, |c| c)?,
+            rank(white, |c| c.to_ascii_uppercase())?
+        );
+        Ok(Chess960Start { fen, white, black })
+    }
+
+    /// Arrangement `number`, or a random one, and for Double Fischer Random Chess
+    /// (`double`) Black's own arrangement, `black_number` or random
+    pub fn pick(number: Option<u16>, double: bool, black_number: Option<u16>) -> Result<Self> {
+        let random = Self::random(double);
+        let white = number.unwrap_or(random.white);
+        let black = match (double, black_number) {
+            (false, _) => white,
+            (true, Some(black)) => black,
+            (true, None) => random.black,
+        };
+        Self::new(white, black)
+    }
+
+    /// A random arrangement, or for Double Fischer Random Chess one for each side
+    pub fn random(double: bool) -> Self {
+        let white = random_number();
+        let black = if double { random_number() } else { white };
+        Self::new(white, black).expect("random Chess960 numbers are in range")
+    }
+}
+
+/// The back rank, from the a-file to the h-file, of Chess960 arrangement `number`
+/// (0-959) in Scharnagl's numbering
+pub fn back_rank(number: u16) -> Result<[Piece; 8]> {
+    if number >= CHESS960_POSITIONS {
+        return Err(ChessError::InvalidOption {
+            name: "number".to_string(),
+            reason: format!("Chess960 positions are numbered 0 to 959, not {}", number),
+        });
+    }
+
+    let mut rank: [Option<Piece>; 8] = [None; 8];
+    let mut n = usize::from(number);
+
+    // One bishop on a light square (b, d, f, h), the other on a dark one (a, c, e, g)
+    rank[n % 4 * 2 + 1] = Some(Piece::Bishop);
+    n /= 4;
+    rank[n % 4 * 2] = Some(Piece::Bishop);
+    n /= 4;
+
+    // The queen, then the knights, on the squares still empty
+    let queen = n % 6;
+    n /= 6;
+    place_on_empty(&mut rank, queen, Piece::Queen);
+    let (first, second) = KNIGHT_PLACEMENTS[n];
+    place_on_empty(&mut rank, second, Piece::Knight);
+    place_on_empty(&mut rank, first, Piece::Knight);
+
+    // The king goes between the rooks on the three squares left
+    for piece in [Piece::Rook, Piece::King, Piece::Rook] {
+        place_on_empty(&mut rank, 0, piece);
+    }
+
+    Ok(rank.map(|piece| piece.expect("every square of the back rank is filled")))
+}
+
+/// Puts `piece` on the `index`-th empty square from the a-file
+fn place_on_empty(rank: &mut [Option<Piece>; 8], index: usize, piece: Piece) {
+    if let Some(square) = rank.iter_mut().filter(|square| square.is_none()).nth(index) {
+        *square = Some(piece);
+    }
+}
+
+fn piece_char(piece: Piece) -> char {
+    match piece {
+        Piece::Pawn => 'p',
+        Piece::Knight => 'n',
+        Piece::Bishop => 'b',
+        Piece::Rook => 'r',
+        Piece::Queen => 'q',
+        Piece::King => 'k',
+    }
+}
+
+/// A number drawn from 0-959, seeded afresh by the standard library's hasher keys
+fn random_number() -> u16 {
+    (RandomState::new().hash_one(()) % u64::from(CHESS960_POSITIONS)) as u16
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chess_engine::fen::{parse_fen, parse_fen_for, position_to_fen, STARTING_FEN};
+    use crate::chess_engine::rules::Variant;
+
+    #[test]
+    fn test_numbered_positions() {
+        assert_eq!(Chess960Start::new(518, 518).unwrap().fen, STARTING_FEN);
+        assert_eq!(Chess960Start::new(0, 0).unwrap().fen, "bbqnnrkr/pppppppp/8/8/8/8/PPPPPPPP/BBQNNRKR w KQkq - 0 1");
+        assert_eq!(
+            Chess960Start::new(959, 0).unwrap().fen,
+            "bbqnnrkr/pppppppp/8/8/8/8/PPPPPPPP/RKRNNQBB w KQkq - 0 1"
+        );
+        assert!(Chess960Start::new(960, 0).is_err());
+
+        // Every arrangement is different and plays as Chess960, and any reads back by itself
+        let mut ranks: Vec<[Piece; 8]> = (0..CHESS960_POSITIONS).map(|number| back_rank(number).unwrap()).collect();
+        ranks.sort_by_key(|rank| rank.map(piece_char));
+        ranks.dedup();
+        assert_eq!(ranks.len(), 960);
+        for number in (0..CHESS960_POSITIONS).step_by(7) {
+            let fen = Chess960Start::new(number, 959 - number).unwrap().fen;
+            assert_eq!(position_to_fen(&parse_fen_for(&fen, Variant::Chess960).unwrap()), fen);
+            assert!(parse_fen(&fen).is_ok());
+        }
+    }
+
+    #[test]
+    fn test_random_positions() {
+        let single = Chess960Start::random(false);
+        assert_eq!(single.white, single.black);
+        assert!(single.white < CHESS960_POSITIONS);
+
+        let double = Chess960Start::random(true);
+        assert_eq!(double, Chess960Start::new(double.white, double.black).unwrap());
+    }
+}
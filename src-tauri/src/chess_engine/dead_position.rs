@@ -0,0 +1,131 @@
+use crate::chess_engine::board::Board;
+use crate::chess_engine::position::Position;
+use crate::chess_engine::types::{Color, Piece, Square};
+
+/// Whether no sequence of legal moves can end in checkmate for either side (FIDE 5.2.2),
+/// beyond the material combinations [`Position::has_insufficient_material`] covers.
+///
+/// Recognizes kings with any number of bishops that all stand on squares of one color,
+/// and kings behind a wall of pawns that are blocked head-on, can never capture, and
+/// can never be reached by the enemy king. Positions it cannot prove dead are not dead.
+pub fn is_dead_position(position: &Position) -> bool {
+    let rules = position.variant.rules();
+    if !position.pockets.is_empty()
+        || rules.counts_checks()
+        || rules.king_capture()
+        || rules.king_goal()
+        || !rules.has_king(Color::White)
+        || !rules.has_king(Color::Black)
+    {
+        return false;
+    }
+    let board = &position.board;
+    let mut bishop_square_colors = [false; 2];
+    let mut has_pawns = false;
+
+    for color in [Color::White, Color::Black] {
+        for (square, piece) in board.pieces_of_color(color) {
+            match piece {
+                Piece::King => {}
+                Piece::Pawn => has_pawns = true,
+                Piece::Bishop => bishop_square_colors[((square.rank() + square.file()) % 2) as usize] = true,
+                _ => return false,
+            }
+        }
+    }
+
+    if !has_pawns {
+        // A king checked by a bishop always has a flight square of the other color that
+        // nothing can attack or occupy
+        return !(bishop_square_colors[0] && bishop_square_colors[1]);
+    }
+    bishop_square_colors == [false, false] && is_locked_pawn_wall(position)
+}
+
+/// Only kings and pawns, where no pawn can ever move or capture because every pawn is
+/// blocked by an enemy pawn and neither king can get next to an enemy pawn
+fn is_locked_pawn_wall(position: &Position) -> bool {
+    let board = &position.board;
+    let (white_pawns, black_pawns) = (pawns(board, Color::White), pawns(board, Color::Black));
+
+    // Each white pawn faces a black pawn directly ahead, and the other way round
+    if white_pawns << 8 != black_pawns {
+        return false;
+    }
+    for color in [Color::White, Color::Black] {
+        if board.pawn_attacks(color) & board.occupancy(color.opposite()) != 0 {
+            return false;
+        }
+    }
+    if let Some(target) = position.en_passant_target {
+        if board.pawn_attacks(position.side_to_move) & (1u64 << target.index()) != 0 {
+            return false;
+        }
+    }
+
+    [Color::White, Color::Black].into_iter().all(|color| {
+        board
+            .find_king(color)
+            .is_some_and(|king| !king_reaches_enemy_pawn(board, king, color, white_pawns | black_pawns))
+    })
+}
+
+/// Walks every square the king can reach without stepping onto a pawn or a square an
+/// enemy pawn attacks, and reports whether it ever stands next to an enemy pawn
+fn king_reaches_enemy_pawn(board: &Board, king: Square, color: Color, all_pawns: u64) -> bool {
+    let enemy_pawns = pawns(board, color.opposite());
+    let off_limits = all_pawns | board.pawn_attacks(color.opposite());
+
+    let mut reached = 0u64;
+    let mut frontier = 1u64 << king.index();
+    while frontier != 0 {
+        reached |= frontier;
+        let neighbours = (0..64u8)
+            .filter(|index| frontier & (1u64 << index) != 0)
+            .filter_map(Square::new)
+            .fold(0, |mask, square| mask | board.attacks_from(square, Piece::King, color));
+        if neighbours & enemy_pawns != 0 {
+            return true;
+        }
+        frontier = neighbours & !off_limits & !reached;
+    }
+    false
+}
+
+fn pawns(board: &Board, color: Color) -> u64 {
+    board
+        .pieces_of_color(color)
+        .filter(|(_, piece)| *piece == Piece::Pawn)
+        .fold(0, |mask, (square, _)| mask | (1u64 << square.index()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chess_engine::fen::parse_fen;
+
+    fn dead(fen: &str) -> bool {
+        is_dead_position(&parse_fen(fen).unwrap())
+    }
+
+    #[test]
+    fn test_bishops_on_one_color() {
+        // Three dark-squared bishops between the two sides
+        assert!(dead("4k3/8/3b4/8/8/2B5/1B6/4K3 w - - 0 1"));
+        // Opposite-colored bishops can still mate
+        assert!(!dead("4k3/8/4b3/8/8/2B5/8/4K3 w - - 0 1"));
+        assert!(!dead("4k3/8/8/8/8/2N5/8/4K3 w - - 0 1"));
+    }
+
+    #[test]
+    fn test_locked_pawn_wall() {
+        // A closed chain across the board with each king on its own side
+        assert!(dead("8/4k3/8/1p1p1p1p/pPpPpPpP/P1P1P1P1/8/4K3 w - - 0 1"));
+        // A gap on the queenside lets the white king through to attack c4
+        assert!(!dead("8/4k3/8/3p1p1p/2pPpPpP/2P1P1P1/8/4K3 w - - 0 1"));
+        // Pawns that could capture each other
+        assert!(!dead("4k3/8/8/3p4/4P3/8/8/4K3 w - - 0 1"));
+        // A blocked pair the kings can reach
+        assert!(!dead("4k3/8/8/4p3/4P3/8/8/4K3 w - - 0 1"));
+    }
+}
@@ -0,0 +1,143 @@
+use crate::chess_engine::position::Position;
+use crate::chess_engine::types::{Color, Piece, Square};
+use serde::{Deserialize, Serialize};
+
+/// A piece standing on a square
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PlacedPiece {
+    pub square: Square,
+    pub piece: Piece,
+    pub color: Color,
+}
+
+/// A piece that is on a different square in the second position
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PieceTransfer {
+    pub from: Square,
+    pub to: Square,
+    pub piece: Piece,
+    pub color: Color,
+}
+
+/// How the pieces of one position have to change to become another. A piece that
+/// disappears from one square and reappears on another is reported as moved, pairing
+/// the closest squares first; everything else appears or disappears in place.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PositionDiff {
+    pub added: Vec<PlacedPiece>,
+    pub removed: Vec<PlacedPiece>,
+    pub moved: Vec<PieceTransfer>,
+}
+
+/// The piece placement changes from `from` to `to`; side to move, castling rights
+/// and clocks are ignored
+pub fn diff_positions(from: &Position, to: &Position) -> PositionDiff {
+    let mut removed = Vec::new();
+    let mut added = Vec::new();
+    for square in (0..64).filter_map(Square::new) {
+        let (before, after) = (from.board.get(square), to.board.get(square));
+        if before == after {
+            continue;
+        }
+        if let Some((piece, color)) = before {
+            removed.push(PlacedPiece { square, piece, color });
+        }
+        if let Some((piece, color)) = after {
+            added.push(PlacedPiece { square, piece, color });
+        }
+    }
+
+    let mut pairs: Vec<(u8, usize, usize)> = Vec::new();
+    for (r, gone) in removed.iter().enumerate() {
+        for (a, new) in added.iter().enumerate() {
+            if gone.piece == new.piece && gone.color == new.color {
+                pairs.push((distance(gone.square, new.square), r, a));
+            }
+        }
+    }
+    pairs.sort_unstable();
+
+    let mut removed_used = vec![false; removed.len()];
+    let mut added_used = vec![false; added.len()];
+    let mut moved = Vec::new();
+    for (_, r, a) in pairs {
+        if removed_used[r] || added_used[a] {
+            continue;
+        }
+        removed_used[r] = true;
+        added_used[a] = true;
+        moved.push(PieceTransfer {
+            from: removed[r].square,
+            to: added[a].square,
+            piece: removed[r].piece,
+            color: removed[r].color,
+        });
+    }
+    moved.sort_by_key(|transfer| transfer.from.index());
+
+    PositionDiff {
+        added: unused(added, &added_used),
+        removed: unused(removed, &removed_used),
+        moved,
+    }
+}
+
+fn unused(pieces: Vec<PlacedPiece>, used: &[bool]) -> Vec<PlacedPiece> {
+    pieces
+        .into_iter()
+        .zip(used)
+        .filter(|(_, used)| !**used)
+        .map(|(piece, _)| piece)
+        .collect()
+}
+
+/// Number of king steps between two squares
+fn distance(a: Square, b: Square) -> u8 {
+    a.rank().abs_diff(b.rank()).max(a.file().abs_diff(b.file()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chess_engine::fen::parse_fen;
+
+    fn square(name: &str) -> Square {
+        Square::from_algebraic(name).unwrap()
+    }
+
+    fn diff(from: &str, to: &str) -> PositionDiff {
+        diff_positions(&parse_fen(from).unwrap(), &parse_fen(to).unwrap())
+    }
+
+    #[test]
+    fn test_identical_positions() {
+        assert_eq!(diff_positions(&Position::new(), &Position::new()), PositionDiff::default());
+    }
+
+    #[test]
+    fn test_capture_and_castling() {
+        // Rook takes on a8 (removing the knight), then both sides of a castle
+        let capture = diff("n3k3/8/8/8/8/8/8/R3K3 w - - 0 1", "R3k3/8/8/8/8/8/8/4K3 b - - 0 1");
+        assert_eq!(capture.moved.len(), 1);
+        assert_eq!((capture.moved[0].from, capture.moved[0].to), (square("a1"), square("a8")));
+        assert_eq!(capture.removed, vec![PlacedPiece { square: square("a8"), piece: Piece::Knight, color: Color::Black }]);
+        assert!(capture.added.is_empty());
+
+        let castle = diff("4k3/8/8/8/8/8/8/4K2R w K - 0 1", "4k3/8/8/8/8/8/8/5RK1 b - - 1 1");
+        let moves: Vec<(Square, Square)> = castle.moved.iter().map(|t| (t.from, t.to)).collect();
+        assert_eq!(moves, vec![(square("e1"), square("g1")), (square("h1"), square("f1"))]);
+    }
+
+    #[test]
+    fn test_promotion_and_unrelated_positions() {
+        let promotion = diff("4k3/P7/8/8/8/8/8/4K3 w - - 0 1", "Q3k3/8/8/8/8/8/8/4K3 b - - 0 1");
+        assert!(promotion.moved.is_empty());
+        assert_eq!(promotion.removed[0].piece, Piece::Pawn);
+        assert_eq!(promotion.added[0].piece, Piece::Queen);
+
+        // Of two white knights the nearer one is paired with the new square
+        let jump = diff("4k3/8/8/8/8/8/8/N3K2N w - - 0 1", "4k3/8/8/8/8/8/6N1/4K3 w - - 0 1");
+        assert_eq!((jump.moved[0].from, jump.moved[0].to), (square("h1"), square("g2")));
+        assert_eq!(jump.removed[0].square, square("a1"));
+    }
+}
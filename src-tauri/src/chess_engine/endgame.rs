@@ -0,0 +1,521 @@
+use crate::chess_engine::analysis::piece_value;
+use crate::chess_engine::{Color, Piece, Position, Square};
+use once_cell::sync::Lazy;
+
+/// Bonus added on top of material when the stronger side has a known forced win
+pub const KNOWN_WIN_BONUS: i32 = 1000;
+
+/// Score a position from built-in endgame knowledge, from White's perspective.
+/// Returns `None` when the material configuration is not a recognized ending.
+pub fn probe(position: &Position) -> Option<i32> {
+    let white = non_king_pieces(position, Color::White);
+    let black = non_king_pieces(position, Color::Black);
+
+    if is_trivial_draw(&white, &black) {
+        return Some(0);
+    }
+
+    // All remaining recognized endings have a lone king on one side
+    let (strong, strong_pieces) = if black.is_empty() {
+        (Color::White, &white)
+    } else if white.is_empty() {
+        (Color::Black, &black)
+    } else {
+        return None;
+    };
+
+    let strong_king = position.board.find_king(strong)?;
+    let weak_king = position.board.find_king(strong.opposite())?;
+
+    let score = match strong_pieces.as_slice() {
+        [(pawn, Piece::Pawn)] => {
+            evaluate_kpk(strong_king, *pawn, weak_king, strong, position.side_to_move == strong)
+        }
+        [(_, Piece::Bishop), (_, Piece::Knight)] | [(_, Piece::Knight), (_, Piece::Bishop)] => {
+            let bishop = strong_pieces
+                .iter()
+                .find(|(_, piece)| *piece == Piece::Bishop)
+                .map(|(square, _)| *square)?;
+            evaluate_kbnk(strong_king, bishop, weak_king)
+        }
+        pieces if has_mating_force(pieces) => {
+            let material: i32 = pieces.iter().map(|(_, piece)| piece_value(*piece)).sum();
+            KNOWN_WIN_BONUS
+                + material
+                + push_to_edge(weak_king)
+                + push_close(strong_king, weak_king)
+        }
+        _ => return None,
+    };
+
+    Some(match strong {
+        Color::White => score,
+        Color::Black => -score,
+    })
+}
+
+/// Non-king pieces of one color
+fn non_king_pieces(position: &Position, color: Color) -> Vec<(Square, Piece)> {
+    position
+        .board
+        .pieces_of_color(color)
+        .filter(|(_, piece)| *piece != Piece::King)
+        .collect()
+}
+
+/// Neither side can force mate: bare kings, a single minor piece,
+/// two knights, or same-colored bishops against each other
+fn is_trivial_draw(white: &[(Square, Piece)], black: &[(Square, Piece)]) -> bool {
+    let (strong, weak) = if black.is_empty() { (white, black) } else { (black, white) };
+
+    if weak.is_empty() {
+        return matches!(
+            strong,
+            [] | [(_, Piece::Knight)] | [(_, Piece::Bishop)] | [(_, Piece::Knight), (_, Piece::Knight)]
+        );
+    }
+
+    match (white, black) {
+        ([(white_sq, Piece::Bishop)], [(black_sq, Piece::Bishop)]) => {
+            square_color(*white_sq) == square_color(*black_sq)
+        }
+        _ => false,
+    }
+}
+
+/// Whether the pieces can force mate against a lone king
+fn has_mating_force(pieces: &[(Square, Piece)]) -> bool {
+    if pieces.iter().any(|(_, piece)| matches!(piece, Piece::Queen | Piece::Rook)) {
+        return true;
+    }
+
+    let bishops: Vec<Square> = pieces
+        .iter()
+        .filter(|(_, piece)| *piece == Piece::Bishop)
+        .map(|(square, _)| *square)
+        .collect();
+    let has_light = bishops.iter().any(|sq| square_color(*sq) == 1);
+    let has_dark = bishops.iter().any(|sq| square_color(*sq) == 0);
+
+    has_light && has_dark
+}
+
+/// KPK: exact result from the bitbase, scored by pawn advancement when winning
+fn evaluate_kpk(strong_king: Square, pawn: Square, weak_king: Square, strong: Color, strong_to_move: bool) -> i32 {
+    if !probe_kpk(strong_king, pawn, weak_king, strong, strong_to_move) {
+        return 0;
+    }
+
+    let advancement = match strong {
+        Color::White => pawn.rank() as i32,
+        Color::Black => 7 - pawn.rank() as i32,
+    };
+
+    KNOWN_WIN_BONUS + piece_value(Piece::Pawn) + advancement * KPK_ADVANCEMENT_BONUS
+}
+
+/// KBN vs K: drive the lone king to a corner the bishop can cover
+fn evaluate_kbnk(strong_king: Square, bishop: Square, weak_king: Square) -> i32 {
+    // a1 is a dark square, so dark-squared bishops mate on a1/h8
+    let corners: [(u8, u8); 2] = if square_color(bishop) == 0 {
+        [(0, 0), (7, 7)]
+    } else {
+        [(0, 7), (7, 0)]
+    };
+
+    let corner_distance = corners
+        .iter()
+        .map(|(rank, file)| weak_king.rank().abs_diff(*rank).max(weak_king.file().abs_diff(*file)))
+        .min()
+        .unwrap_or(7) as i32;
+
+    KNOWN_WIN_BONUS
+        + piece_value(Piece::Bishop)
+        + piece_value(Piece::Knight)
+        + (7 - corner_distance) * CORNER_PUSH_WEIGHT
+        + push_close(strong_king, weak_king)
+}
+
+/// Bonus for the lone king being near the edge of the board
+fn push_to_edge(square: Square) -> i32 {
+    let rank_from_edge = square.rank().min(7 - square.rank()) as i32;
+    let file_from_edge = square.file().min(7 - square.file()) as i32;
+    (6 - rank_from_edge - file_from_edge) * EDGE_PUSH_WEIGHT
+}
+
+/// Bonus for the attacking king approaching the lone king
+fn push_close(a: Square, b: Square) -> i32 {
+    (7 - king_distance(a, b) as i32) * KING_PROXIMITY_WEIGHT
+}
+
+/// Chebyshev (king-move) distance between two squares
+pub fn king_distance(a: Square, b: Square) -> u8 {
+    a.rank().abs_diff(b.rank()).max(a.file().abs_diff(b.file()))
+}
+
+/// 0 for dark squares, 1 for light squares
+fn square_color(square: Square) -> u8 {
+    (square.rank() + square.file()) % 2
+}
+
+// Guidance weights (centipawns)
+const KPK_ADVANCEMENT_BONUS: i32 = 20;
+const EDGE_PUSH_WEIGHT: i32 = 20;
+const CORNER_PUSH_WEIGHT: i32 = 30;
+const KING_PROXIMITY_WEIGHT: i32 = 10;
+
+/// Scale factor representing "no scaling" (the evaluation is used as is)
+pub const SCALE_NORMAL: i32 = 64;
+
+/// How much of the evaluation the stronger side can expect to convert, out of
+/// `SCALE_NORMAL`. Drawish material configurations return smaller factors so
+/// the score is pulled toward zero.
+pub fn scale_factor(position: &Position, strong: Color) -> i32 {
+    let weak = strong.opposite();
+    let strong_pieces = non_king_pieces(position, strong);
+    let weak_pieces = non_king_pieces(position, weak);
+
+    let count = |pieces: &[(Square, Piece)], kind: Piece| pieces.iter().filter(|(_, p)| *p == kind).count();
+    let non_pawn_material = |pieces: &[(Square, Piece)]| -> i32 {
+        pieces
+            .iter()
+            .filter(|(_, p)| *p != Piece::Pawn)
+            .map(|(_, p)| piece_value(*p))
+            .sum()
+    };
+
+    let strong_pawns = count(&strong_pieces, Piece::Pawn);
+    let weak_pawns = count(&weak_pieces, Piece::Pawn);
+    let strong_npm = non_pawn_material(&strong_pieces);
+    let weak_npm = non_pawn_material(&weak_pieces);
+
+    // Without pawns, an extra minor piece (or less) is not enough to win
+    if strong_pawns == 0 && strong_npm - weak_npm <= piece_value(Piece::Bishop) {
+        return if strong_npm < piece_value(Piece::Rook) {
+            0
+        } else if weak_npm <= piece_value(Piece::Bishop) {
+            4
+        } else {
+            14
+        };
+    }
+
+    // Opposite-colored bishops
+    let strong_bishops: Vec<Square> = bishops(&strong_pieces);
+    let weak_bishops: Vec<Square> = bishops(&weak_pieces);
+    if let ([strong_bishop], [weak_bishop]) = (strong_bishops.as_slice(), weak_bishops.as_slice()) {
+        if square_color(*strong_bishop) != square_color(*weak_bishop) {
+            let only_bishops = strong_npm == piece_value(Piece::Bishop) && weak_npm == piece_value(Piece::Bishop);
+            return if only_bishops {
+                (OCB_BASE_SCALE + OCB_PAWN_SCALE * strong_pawns as i32).min(SCALE_NORMAL)
+            } else {
+                OCB_WITH_PIECES_SCALE
+            };
+        }
+    }
+
+    // Rook and pawn vs rook
+    let only_rooks = strong_npm == piece_value(Piece::Rook)
+        && weak_npm == piece_value(Piece::Rook)
+        && count(&strong_pieces, Piece::Rook) == 1
+        && count(&weak_pieces, Piece::Rook) == 1;
+    if only_rooks && strong_pawns == 1 && weak_pawns == 0 {
+        let pawn = strong_pieces.iter().find(|(_, p)| *p == Piece::Pawn).map(|(sq, _)| *sq);
+        let weak_king = position.board.find_king(weak);
+
+        // Defending king directly in front of the pawn is the textbook draw
+        if let (Some(pawn), Some(king)) = (pawn, weak_king) {
+            let in_front = match strong {
+                Color::White => king.rank() > pawn.rank(),
+                Color::Black => king.rank() < pawn.rank(),
+            };
+            if in_front && king.file().abs_diff(pawn.file()) <= 1 {
+                return KRPKR_BLOCKED_SCALE;
+            }
+        }
+        return KRPKR_SCALE;
+    }
+
+    SCALE_NORMAL
+}
+
+fn bishops(pieces: &[(Square, Piece)]) -> Vec<Square> {
+    pieces
+        .iter()
+        .filter(|(_, p)| *p == Piece::Bishop)
+        .map(|(sq, _)| *sq)
+        .collect()
+}
+
+// Draw-tendency scale factors (out of SCALE_NORMAL)
+const OCB_BASE_SCALE: i32 = 18;
+const OCB_PAWN_SCALE: i32 = 4;
+const OCB_WITH_PIECES_SCALE: i32 = 46;
+const KRPKR_SCALE: i32 = 32;
+const KRPKR_BLOCKED_SCALE: i32 = 10;
+
+/// Whether the side with the pawn wins a KPK ending with best play.
+/// Squares are given for the actual position; the pawn may be of either color.
+pub fn probe_kpk(strong_king: Square, pawn: Square, weak_king: Square, strong: Color, strong_to_move: bool) -> bool {
+    // Normalize so the pawn is White's and on files a-d
+    let normalize = |square: Square| -> u8 {
+        let rank = if strong == Color::White { square.rank() } else { 7 - square.rank() };
+        let file = if pawn.file() > 3 { 7 - square.file() } else { square.file() };
+        rank * 8 + file
+    };
+
+    let index = kpk_index(
+        normalize(strong_king),
+        normalize(pawn),
+        normalize(weak_king),
+        strong_to_move,
+    );
+    KPK_BITBASE[index / 64] & (1u64 << (index % 64)) != 0
+}
+
+// KPK bitbase, generated at first use by retrograde iteration.
+// Indexed by pawn (files a-d, ranks 2-7), both kings, and side to move.
+
+const KPK_PAWN_SQUARES: usize = 24;
+const KPK_SIZE: usize = KPK_PAWN_SQUARES * 64 * 64 * 2;
+
+const KPK_INVALID: u8 = 0;
+const KPK_UNKNOWN: u8 = 1;
+const KPK_DRAW: u8 = 2;
+const KPK_WIN: u8 = 4;
+
+fn kpk_index(white_king: u8, pawn: u8, black_king: u8, white_to_move: bool) -> usize {
+    let pawn_index = ((pawn / 8 - 1) * 4 + pawn % 8) as usize;
+    ((pawn_index * 64 + white_king as usize) * 64 + black_king as usize) * 2 + white_to_move as usize
+}
+
+static KPK_BITBASE: Lazy<Vec<u64>> = Lazy::new(|| {
+    let mut results = vec![KPK_INVALID; KPK_SIZE];
+    let mut entries = Vec::with_capacity(KPK_SIZE);
+
+    for rank in 1..7u8 {
+        for file in 0..4u8 {
+            let pawn = rank * 8 + file;
+            for white_king in 0..64u8 {
+                for black_king in 0..64u8 {
+                    for white_to_move in [false, true] {
+                        let index = kpk_index(white_king, pawn, black_king, white_to_move);
+                        results[index] = kpk_initial(white_king, pawn, black_king, white_to_move);
+                        if results[index] == KPK_UNKNOWN {
+                            entries.push((index, white_king, pawn, black_king, white_to_move));
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    // Resolve unknown positions from their successors until nothing changes
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for &(index, white_king, pawn, black_king, white_to_move) in &entries {
+            if results[index] == KPK_UNKNOWN {
+                let result = kpk_classify(&results, white_king, pawn, black_king, white_to_move);
+                if result != KPK_UNKNOWN {
+                    results[index] = result;
+                    changed = true;
+                }
+            }
+        }
+    }
+
+    let mut bits = vec![0u64; KPK_SIZE.div_ceil(64)];
+    for (index, result) in results.iter().enumerate() {
+        if *result == KPK_WIN {
+            bits[index / 64] |= 1u64 << (index % 64);
+        }
+    }
+    bits
+});
+
+fn kpk_initial(white_king: u8, pawn: u8, black_king: u8, white_to_move: bool) -> u8 {
+    let push = pawn + 8;
+
+    if distance(white_king, black_king) <= 1
+        || white_king == pawn
+        || black_king == pawn
+        || (white_to_move && kpk_pawn_attacks(pawn) & bit(black_king) != 0)
+    {
+        return KPK_INVALID;
+    }
+
+    // Promotes without the new queen being captured
+    if white_to_move
+        && pawn / 8 == 6
+        && white_king != push
+        && black_king != push
+        && (distance(black_king, push) > 1 || distance(white_king, push) == 1)
+    {
+        return KPK_WIN;
+    }
+
+    if !white_to_move {
+        let black_moves = king_attacks(black_king);
+        let guarded = king_attacks(white_king) | kpk_pawn_attacks(pawn);
+
+        // Stalemate, or the pawn can be taken
+        if black_moves & !guarded == 0 || black_moves & bit(pawn) & !king_attacks(white_king) != 0 {
+            return KPK_DRAW;
+        }
+    }
+
+    KPK_UNKNOWN
+}
+
+fn kpk_classify(results: &[u8], white_king: u8, pawn: u8, black_king: u8, white_to_move: bool) -> u8 {
+    let mut successors = 0u8;
+
+    if white_to_move {
+        let mut moves = king_attacks(white_king);
+        while moves != 0 {
+            let to = moves.trailing_zeros() as u8;
+            successors |= results[kpk_index(to, pawn, black_king, false)];
+            moves &= moves - 1;
+        }
+
+        if pawn / 8 < 6 {
+            successors |= results[kpk_index(white_king, pawn + 8, black_king, false)];
+        }
+
+        if pawn / 8 == 1 && pawn + 8 != white_king && pawn + 8 != black_king {
+            successors |= results[kpk_index(white_king, pawn + 16, black_king, false)];
+        }
+
+        if successors & KPK_WIN != 0 {
+            KPK_WIN
+        } else if successors & KPK_UNKNOWN != 0 {
+            KPK_UNKNOWN
+        } else {
+            KPK_DRAW
+        }
+    } else {
+        let mut moves = king_attacks(black_king);
+        while moves != 0 {
+            let to = moves.trailing_zeros() as u8;
+            successors |= results[kpk_index(white_king, pawn, to, true)];
+            moves &= moves - 1;
+        }
+
+        if successors & KPK_DRAW != 0 {
+            KPK_DRAW
+        } else if successors & KPK_UNKNOWN != 0 {
+            KPK_UNKNOWN
+        } else {
+            KPK_WIN
+        }
+    }
+}
+
+fn bit(square: u8) -> u64 {
+    1u64 << square
+}
+
+fn distance(a: u8, b: u8) -> u8 {
+    (a / 8).abs_diff(b / 8).max((a % 8).abs_diff(b % 8))
+}
+
+fn king_attacks(square: u8) -> u64 {
+    let (rank, file) = ((square / 8) as i8, (square % 8) as i8);
+    let mut mask = 0u64;
+    for rank_offset in -1..=1 {
+        for file_offset in -1..=1 {
+            let (r, f) = (rank + rank_offset, file + file_offset);
+            if (rank_offset, file_offset) != (0, 0) && (0..8).contains(&r) && (0..8).contains(&f) {
+                mask |= bit((r * 8 + f) as u8);
+            }
+        }
+    }
+    mask
+}
+
+/// Squares attacked by a White pawn
+fn kpk_pawn_attacks(pawn: u8) -> u64 {
+    let file = pawn % 8;
+    let mut mask = 0u64;
+    if file > 0 {
+        mask |= bit(pawn + 7);
+    }
+    if file < 7 {
+        mask |= bit(pawn + 9);
+    }
+    mask
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chess_engine::fen::parse_fen;
+
+    fn sq(s: &str) -> Square {
+        Square::from_algebraic(s).unwrap()
+    }
+
+    #[test]
+    fn test_kpk_opposition() {
+        // Kd5 Pd4 vs Kd7: with Black to move White has the opposition and wins,
+        // with White to move Black holds the draw
+        assert!(probe_kpk(sq("d5"), sq("d4"), sq("d7"), Color::White, false));
+        assert!(!probe_kpk(sq("d5"), sq("d4"), sq("d7"), Color::White, true));
+
+        // King on the sixth rank in front of the pawn wins regardless of the move
+        assert!(probe_kpk(sq("e6"), sq("e5"), sq("e8"), Color::White, true));
+    }
+
+    #[test]
+    fn test_kpk_rook_pawn_and_black_pawn() {
+        // Rook pawn with the defending king in the corner is a draw
+        assert!(!probe_kpk(sq("b6"), sq("a5"), sq("a8"), Color::White, true));
+
+        // Mirrored opposition position with a Black pawn on the kingside
+        assert!(probe_kpk(sq("e4"), sq("e5"), sq("e2"), Color::Black, false));
+        assert!(!probe_kpk(sq("e4"), sq("e5"), sq("e2"), Color::Black, true));
+    }
+
+    #[test]
+    fn test_trivial_draws_and_basic_mates() {
+        let knight_vs_king = parse_fen("4k3/8/8/8/8/8/8/3NK3 w - - 0 1").unwrap();
+        assert_eq!(probe(&knight_vs_king), Some(0));
+
+        let two_knights = parse_fen("4k3/8/8/8/8/8/8/2NNK3 b - - 0 1").unwrap();
+        assert_eq!(probe(&two_knights), Some(0));
+
+        // Lone Black king closer to the edge scores better for White in KQK
+        let edge = parse_fen("7k/8/8/8/8/8/8/Q3K3 w - - 0 1").unwrap();
+        let center = parse_fen("8/8/8/3k4/8/8/8/Q3K3 w - - 0 1").unwrap();
+        assert!(probe(&edge).unwrap() > probe(&center).unwrap());
+        assert!(probe(&center).unwrap() > KNOWN_WIN_BONUS);
+    }
+
+    #[test]
+    fn test_kbnk_drives_to_bishop_corner() {
+        // Dark-squared bishop on c1: the lone king belongs on a1 or h8, not a8
+        let right_corner = parse_fen("7k/8/8/8/8/8/8/2B1KN2 w - - 0 1").unwrap();
+        let wrong_corner = parse_fen("k7/8/8/8/8/8/8/2B1KN2 w - - 0 1").unwrap();
+        assert!(probe(&right_corner).unwrap() > probe(&wrong_corner).unwrap());
+    }
+
+    #[test]
+    fn test_draw_scale_factors() {
+        // Rook vs bishop without pawns cannot normally be won
+        let rook_vs_bishop = parse_fen("4k3/8/8/8/8/8/3b4/R3K3 w - - 0 1").unwrap();
+        assert_eq!(scale_factor(&rook_vs_bishop, Color::White), 4);
+
+        // Opposite-colored bishops with an extra pawn
+        let ocb = parse_fen("4k3/5p2/8/8/8/2B5/4PP2/3bK3 w - - 0 1").unwrap();
+        assert_eq!(scale_factor(&ocb, Color::White), OCB_BASE_SCALE + 2 * OCB_PAWN_SCALE);
+
+        // Rook and pawn vs rook with the defending king in front of the pawn
+        let krpkr = parse_fen("3k4/8/8/3P4/8/8/r7/3RK3 w - - 0 1").unwrap();
+        assert_eq!(scale_factor(&krpkr, Color::White), KRPKR_BLOCKED_SCALE);
+
+        // Ordinary middlegame material is left alone
+        let start = Position::new();
+        assert_eq!(scale_factor(&start, Color::White), SCALE_NORMAL);
+    }
+}
@@ -0,0 +1,233 @@
+use crate::chess_engine::error::{ChessError, Result};
+use crate::chess_engine::eval_cache::{EVAL_CACHE_MEGABYTES, PAWN_CACHE_MEGABYTES};
+use crate::chess_engine::evaluator::Evaluator;
+use crate::chess_engine::search::{set_transposition_table_megabytes, SearchLimits, MAX_DEPTH};
+use crate::chess_engine::transposition::TRANSPOSITION_TABLE_MEGABYTES;
+use serde::{Deserialize, Serialize};
+
+pub const ENGINE_NAME: &str = "Chess Engine";
+pub const ENGINE_AUTHOR: &str = "Zuhaad Rathore";
+
+const MAX_HASH_MEGABYTES: u32 = 1024;
+const MAX_THREADS: u32 = 64;
+pub const MAX_SKILL_LEVEL: u8 = 20;
+const MAX_CONTEMPT: i32 = 500;
+
+/// Settings for the built-in engine, respected by every search and analysis command
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct EngineOptions {
+    /// Size of the search's transposition table in megabytes
+    pub hash_mb: u32,
+    /// Size of the evaluation cache in megabytes
+    pub eval_hash_mb: u32,
+    /// Size of the pawn structure cache in megabytes
+    pub pawn_hash_mb: u32,
+    /// Threads for analysing several positions at once (a single search uses one)
+    pub threads: u32,
+    /// 0 (weakest) to 20 (full strength); lower levels search less deeply
+    pub skill_level: u8,
+    /// Centipawns the engine gives up to avoid a draw; negative values make it seek one
+    pub contempt: i32,
+    /// Play moves from the opening book at `book_path` while it has any
+    pub own_book: bool,
+    pub book_path: Option<String>,
+}
+
+impl Default for EngineOptions {
+    fn default() -> Self {
+        EngineOptions {
+            hash_mb: TRANSPOSITION_TABLE_MEGABYTES as u32,
+            eval_hash_mb: EVAL_CACHE_MEGABYTES as u32,
+            pawn_hash_mb: PAWN_CACHE_MEGABYTES as u32,
+            threads: 1,
+            skill_level: MAX_SKILL_LEVEL,
+            contempt: 0,
+            own_book: false,
+            book_path: None,
+        }
+    }
+}
+
+/// One engine option with its new value
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "name", content = "value")]
+pub enum EngineOption {
+    Hash(u32),
+    EvalHash(u32),
+    PawnHash(u32),
+    Threads(u32),
+    SkillLevel(u8),
+    Contempt(i32),
+    OwnBook(bool),
+    BookPath(Option<String>),
+}
+
+impl EngineOptions {
+    /// Changes one option, rejecting values out of its range
+    pub fn set(&mut self, option: EngineOption) -> Result<()> {
+        match option {
+            EngineOption::Hash(mb) => self.hash_mb = in_range("Hash", mb, 1, MAX_HASH_MEGABYTES)?,
+            EngineOption::EvalHash(mb) => self.eval_hash_mb = in_range("EvalHash", mb, 1, MAX_HASH_MEGABYTES)?,
+            EngineOption::PawnHash(mb) => self.pawn_hash_mb = in_range("PawnHash", mb, 1, MAX_HASH_MEGABYTES)?,
+            EngineOption::Threads(threads) => self.threads = in_range("Threads", threads, 1, MAX_THREADS)?,
+            EngineOption::SkillLevel(level) => self.skill_level = in_range("SkillLevel", level, 0, MAX_SKILL_LEVEL)?,
+            EngineOption::Contempt(cp) => self.contempt = in_range("Contempt", cp, -MAX_CONTEMPT, MAX_CONTEMPT)?,
+            EngineOption::OwnBook(on) => self.own_book = on,
+            EngineOption::BookPath(path) => self.book_path = path,
+        }
+        Ok(())
+    }
+
+    /// Checks every option is in its range, for options replaced all at once
+    pub fn validate(&self) -> Result<()> {
+        in_range("Hash", self.hash_mb, 1, MAX_HASH_MEGABYTES)?;
+        in_range("EvalHash", self.eval_hash_mb, 1, MAX_HASH_MEGABYTES)?;
+        in_range("PawnHash", self.pawn_hash_mb, 1, MAX_HASH_MEGABYTES)?;
+        in_range("Threads", self.threads, 1, MAX_THREADS)?;
+        in_range("SkillLevel", self.skill_level, 0, MAX_SKILL_LEVEL)?;
+        in_range("Contempt", self.contempt, -MAX_CONTEMPT, MAX_CONTEMPT)?;
+        Ok(())
+    }
+
+    /// The requested limits with contempt applied and the depth capped by the skill level
+    pub fn apply(&self, limits: SearchLimits) -> SearchLimits {
+        SearchLimits {
+            depth: limits.depth.min(self.max_depth()),
+            contempt: self.contempt,
+            ..limits
+        }
+    }
+
+    /// Resizes, and so empties, every table whose size differs from `previous`
+    /// (all of them when there is no previous)
+    pub fn resize_tables(&self, previous: Option<&EngineOptions>) {
+        let changed = |size: fn(&EngineOptions) -> u32| previous.is_none_or(|previous| size(previous) != size(self));
+        if changed(|options| options.hash_mb) {
+            set_transposition_table_megabytes(self.hash_mb as usize);
+        }
+        if changed(|options| options.eval_hash_mb) {
+            Evaluator::set_cache_megabytes(self.eval_hash_mb as usize);
+        }
+        if changed(|options| options.pawn_hash_mb) {
+            Evaluator::set_pawn_cache_megabytes(self.pawn_hash_mb as usize);
+        }
+    }
+
+    /// The book to play from, when the book is on and has a file
+    pub fn book(&self) -> Option<&str> {
+        self.book_path.as_deref().filter(|_| self.own_book)
+    }
+
+    /// Skill 20 searches as deep as asked; every four levels below it lose one ply
+    fn max_depth(&self) -> u8 {
+        match self.skill_level {
+            MAX_SKILL_LEVEL => MAX_DEPTH,
+            level => 1 + level / 4,
+        }
+    }
+}
+
+/// The range an option accepts
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum OptionKind {
+    Spin { default: i64, min: i64, max: i64 },
+    Check { default: bool },
+    Path,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OptionSpec {
+    pub name: String,
+    pub kind: OptionKind,
+}
+
+/// Name, version and options of the built-in engine
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EngineInfo {
+    pub name: String,
+    pub version: String,
+    pub author: String,
+    pub options: Vec<OptionSpec>,
+    pub current: EngineOptions,
+}
+
+impl EngineInfo {
+    pub fn new(current: EngineOptions) -> Self {
+        let defaults = EngineOptions::default();
+        let spin = |name: &str, default: i64, min: i64, max: i64| OptionSpec {
+            name: name.to_string(),
+            kind: OptionKind::Spin { default, min, max },
+        };
+
+        EngineInfo {
+            name: ENGINE_NAME.to_string(),
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            author: ENGINE_AUTHOR.to_string(),
+            options: vec![
+                spin("Hash", defaults.hash_mb.into(), 1, MAX_HASH_MEGABYTES.into()),
+                spin("EvalHash", defaults.eval_hash_mb.into(), 1, MAX_HASH_MEGABYTES.into()),
+                spin("PawnHash", defaults.pawn_hash_mb.into(), 1, MAX_HASH_MEGABYTES.into()),
+                spin("Threads", defaults.threads.into(), 1, MAX_THREADS.into()),
+                spin("SkillLevel", defaults.skill_level.into(), 0, MAX_SKILL_LEVEL.into()),
+                spin("Contempt", defaults.contempt.into(), (-MAX_CONTEMPT).into(), MAX_CONTEMPT.into()),
+                OptionSpec { name: "OwnBook".to_string(), kind: OptionKind::Check { default: defaults.own_book } },
+                OptionSpec { name: "BookPath".to_string(), kind: OptionKind::Path },
+            ],
+            current,
+        }
+    }
+}
+
+fn in_range<T: PartialOrd + std::fmt::Display>(name: &str, value: T, min: T, max: T) -> Result<T> {
+    if value < min || value > max {
+        return Err(ChessError::InvalidOption {
+            name: name.to_string(),
+            reason: format!("{} is outside {}..={}", value, min, max),
+        });
+    }
+    Ok(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_set_checks_ranges() {
+        let mut options = EngineOptions::default();
+        options.set(EngineOption::SkillLevel(8)).unwrap();
+        options.set(EngineOption::Contempt(-20)).unwrap();
+        assert_eq!((options.skill_level, options.contempt), (8, -20));
+
+        assert!(options.set(EngineOption::SkillLevel(21)).is_err());
+        assert!(options.set(EngineOption::Threads(0)).is_err());
+        assert!(options.set(EngineOption::PawnHash(0)).is_err());
+        assert_eq!(options.skill_level, 8);
+        assert!(options.validate().is_ok());
+
+        options.threads = 0;
+        assert!(options.validate().is_err());
+    }
+
+    #[test]
+    fn test_apply_to_limits() {
+        let mut options = EngineOptions::default();
+        assert_eq!(options.apply(SearchLimits::depth(12)), SearchLimits::depth(12));
+
+        options.set(EngineOption::SkillLevel(8)).unwrap();
+        options.set(EngineOption::Contempt(30)).unwrap();
+        let limits = options.apply(SearchLimits::depth(12));
+        assert_eq!((limits.depth, limits.contempt), (3, 30));
+    }
+
+    #[test]
+    fn test_book_needs_flag_and_path() {
+        let mut options = EngineOptions::default();
+        options.set(EngineOption::BookPath(Some("book.bin".to_string()))).unwrap();
+        assert_eq!(options.book(), None);
+        options.set(EngineOption::OwnBook(true)).unwrap();
+        assert_eq!(options.book(), Some("book.bin"));
+    }
+}
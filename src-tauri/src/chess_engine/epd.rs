@@ -0,0 +1,261 @@
+use crate::chess_engine::error::{ChessError, Result};
+use crate::chess_engine::evaluator::{EvalPerspective, Evaluator};
+use crate::chess_engine::fen::{parse_fen, position_to_fen};
+use crate::chess_engine::position::Position;
+use crate::chess_engine::search::{search, SearchLimits};
+use crate::chess_engine::types::Move;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// One EPD record: the first four FEN fields followed by `opcode operands;` operations
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EpdRecord {
+    /// Piece placement, side to move, castling rights and en passant square
+    pub fields: [String; 4],
+    pub operations: Vec<(String, Vec<String>)>,
+}
+
+/// Result of evaluating and searching one EPD record
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EpdAnalysis {
+    pub id: Option<String>,
+    pub fen: String,
+    /// Static evaluation, side to move's point of view (like `ce`)
+    pub static_eval: i32,
+    /// Search score, side to move's point of view
+    pub search_score: i32,
+    pub search_depth: u8,
+    pub best_move_san: Option<String>,
+    pub best_move_uci: Option<String>,
+    /// The record's `ce` operand, if any
+    pub expected_eval: Option<i32>,
+    /// Whether the engine's move satisfies `bm` / `am`; `None` when neither is given
+    pub solved: Option<bool>,
+}
+
+impl EpdRecord {
+    pub fn parse(line: &str) -> Result<EpdRecord> {
+        let line = line.trim();
+        let mut parts = line.splitn(5, char::is_whitespace);
+        let mut fields: [String; 4] = Default::default();
+        for field in fields.iter_mut() {
+            *field = parts
+                .next()
+                .filter(|part| !part.is_empty())
+                .ok_or_else(|| ChessError::InvalidFen {
+                    reason: format!("EPD needs four position fields: {}", line),
+                })?
+                .to_string();
+        }
+
+        let operations = split_operations(parts.next().unwrap_or(""))?
+            .into_iter()
+            .filter_map(|tokens| {
+                let (opcode, operands) = tokens.split_first()?;
+                Some((opcode.clone(), operands.to_vec()))
+            })
+            .collect();
+
+        let record = EpdRecord { fields, operations };
+        record.position()?;
+        Ok(record)
+    }
+
+    /// Record for a position, carrying its clocks in `hmvc` and `fmvn`
+    pub fn from_position(position: &Position) -> EpdRecord {
+        let fen = position_to_fen(position);
+        let mut fields: [String; 4] = Default::default();
+        for (field, value) in fields.iter_mut().zip(fen.split_whitespace()) {
+            *field = value.to_string();
+        }
+
+        EpdRecord {
+            fields,
+            operations: vec![
+                ("hmvc".to_string(), vec![position.halfmove_clock.to_string()]),
+                ("fmvn".to_string(), vec![position.fullmove_number.to_string()]),
+            ],
+        }
+    }
+
+    /// Operands of the first operation with this opcode
+    pub fn operands(&self, opcode: &str) -> Option<&[String]> {
+        self.operations
+            .iter()
+            .find(|(name, _)| name == opcode)
+            .map(|(_, operands)| operands.as_slice())
+    }
+
+    pub fn id(&self) -> Option<&str> {
+        self.operands("id")?.first().map(String::as_str)
+    }
+
+    /// Centipawn evaluation (`ce`) from the side to move's point of view
+    pub fn centipawn_eval(&self) -> Option<i32> {
+        self.operands("ce")?.first()?.parse().ok()
+    }
+
+    /// The position, using `hmvc` and `fmvn` for the clocks when present
+    pub fn position(&self) -> Result<Position> {
+        let clock = |opcode: &str, default: &str| {
+            self.operands(opcode)
+                .and_then(|operands| operands.first().cloned())
+                .unwrap_or_else(|| default.to_string())
+        };
+        parse_fen(&format!("{} {} {}", self.fields.join(" "), clock("hmvc", "0"), clock("fmvn", "1")))
+    }
+
+    /// Best moves (`bm`) resolved against the position
+    pub fn best_moves(&self, position: &Position) -> Result<Vec<Move>> {
+        self.san_operands("bm", position)
+    }
+
+    /// Moves to avoid (`am`) resolved against the position
+    pub fn avoid_moves(&self, position: &Position) -> Result<Vec<Move>> {
+        self.san_operands("am", position)
+    }
+
+    fn san_operands(&self, opcode: &str, position: &Position) -> Result<Vec<Move>> {
+        self.operands(opcode)
+            .unwrap_or_default()
+            .iter()
+            .map(|san| Move::from_san(san, position))
+            .collect()
+    }
+
+    /// Static evaluation plus a search of the position, checked against `bm`/`am`
+    pub fn analyze(&self, limits: SearchLimits) -> Result<EpdAnalysis> {
+        let position = self.position()?;
+        let best_moves = self.best_moves(&position)?;
+        let avoid_moves = self.avoid_moves(&position)?;
+        let result = search(&position, limits);
+
+        let solved = match result.best_move {
+            _ if best_moves.is_empty() && avoid_moves.is_empty() => None,
+            Some(mv) => Some(
+                (best_moves.is_empty() || best_moves.contains(&mv)) && !avoid_moves.contains(&mv),
+            ),
+            None => Some(false),
+        };
+
+        Ok(EpdAnalysis {
+            id: self.id().map(str::to_string),
+            fen: position_to_fen(&position),
+            static_eval: Evaluator::evaluate_from(&position, EvalPerspective::SideToMove),
+            search_score: result.score,
+            search_depth: result.depth,
+            best_move_san: result.best_move.map(|mv| mv.to_san(&position)),
+            best_move_uci: result.best_move.map(|mv| mv.to_uci()),
+            expected_eval: self.centipawn_eval(),
+            solved,
+        })
+    }
+}
+
+impl fmt::Display for EpdRecord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.fields.join(" "))?;
+        for (opcode, operands) in &self.operations {
+            write!(f, " {}", opcode)?;
+            for operand in operands {
+                if operand.is_empty() || operand.contains(char::is_whitespace) || operand.contains(';') {
+                    write!(f, " \"{}\"", operand)?;
+                } else {
+                    write!(f, " {}", operand)?;
+                }
+            }
+            write!(f, ";")?;
+        }
+        Ok(())
+    }
+}
+
+/// Split `op a b; op "quoted; text";` into token lists, honouring quotes
+fn split_operations(text: &str) -> Result<Vec<Vec<String>>> {
+    let mut operations = Vec::new();
+    let mut tokens: Vec<String> = Vec::new();
+    let mut chars = text.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            ';' => operations.push(std::mem::take(&mut tokens)),
+            '"' => {
+                let mut quoted = String::new();
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some(c) => quoted.push(c),
+                        None => {
+                            return Err(ChessError::ParseError {
+                                input: format!("EPD unterminated string: {}", text),
+                            })
+                        }
+                    }
+                }
+                tokens.push(quoted);
+            }
+            c if c.is_whitespace() => {}
+            c => {
+                let mut token = c.to_string();
+                while let Some(c) = chars.next_if(|c| !c.is_whitespace() && *c != ';' && *c != '"') {
+                    token.push(c);
+                }
+                tokens.push(token);
+            }
+        }
+    }
+
+    if !tokens.is_empty() {
+        operations.push(tokens);
+    }
+    Ok(operations)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_opcodes() {
+        let record = EpdRecord::parse(
+            r#"r1bqkbnr/pppp1ppp/2n5/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R w KQkq - bm Bb5 Bc4; am Ng5; ce 35; id "Open game; test";"#,
+        )
+        .unwrap();
+
+        assert_eq!(record.id(), Some("Open game; test"));
+        assert_eq!(record.centipawn_eval(), Some(35));
+
+        let position = record.position().unwrap();
+        let best: Vec<String> = record.best_moves(&position).unwrap().iter().map(|mv| mv.to_uci()).collect();
+        assert_eq!(best, vec!["f1b5", "f1c4"]);
+        assert_eq!(record.avoid_moves(&position).unwrap()[0].to_uci(), "f3g5");
+    }
+
+    #[test]
+    fn test_emit_round_trip() {
+        let line = r#"4k3/8/8/8/8/8/8/R3K3 w Q - bm Ra8+; id "rook check";"#;
+        let record = EpdRecord::parse(line).unwrap();
+        assert_eq!(record.to_string(), line);
+
+        let emitted = EpdRecord::from_position(&Position::new()).to_string();
+        assert_eq!(emitted, "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - hmvc 0; fmvn 1;");
+        assert_eq!(EpdRecord::parse(&emitted).unwrap().position().unwrap().fullmove_number, 1);
+    }
+
+    #[test]
+    fn test_invalid_records() {
+        assert!(EpdRecord::parse("8/8/8/8 w").is_err());
+        assert!(EpdRecord::parse("4k3/8/8/8/8/8/8/4K3 w - - id \"open").is_err());
+        assert!(EpdRecord::parse("4k3/8/8/8/8/8/8/4K3 w - - bm Qh5;").unwrap().analyze(SearchLimits::depth(1)).is_err());
+    }
+
+    #[test]
+    fn test_analyze_solves_mate() {
+        let record = EpdRecord::parse(r#"6k1/5ppp/8/8/8/8/8/R5K1 w - - bm Ra8#; id "back rank";"#).unwrap();
+        let analysis = record.analyze(SearchLimits::depth(2)).unwrap();
+
+        assert_eq!(analysis.id.as_deref(), Some("back rank"));
+        assert_eq!(analysis.best_move_san.as_deref(), Some("Ra8#"));
+        assert_eq!(analysis.solved, Some(true));
+    }
+}
@@ -16,6 +16,12 @@ pub enum ChessError {
 
     #[error("Parse error: {input}")]
     ParseError { input: String },
+
+    #[error("I/O error: {reason}")]
+    Io { reason: String },
+
+    #[error("Invalid value for option {name}: {reason}")]
+    InvalidOption { name: String, reason: String },
 }
 
 pub type Result<T> = std::result::Result<T, ChessError>;
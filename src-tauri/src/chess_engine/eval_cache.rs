@@ -0,0 +1,95 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// Size of the shared evaluation cache until the engine's EvalHash option changes it
+pub const EVAL_CACHE_MEGABYTES: usize = 1;
+
+/// Size of the shared pawn structure cache until the engine's PawnHash option changes it
+pub const PAWN_CACHE_MEGABYTES: usize = 1;
+
+/// Bytes taken by one entry: two 64-bit atomics
+const ENTRY_BYTES: usize = 16;
+
+/// Fixed-size, always-replace cache of evaluation scores keyed by Zobrist hash.
+///
+/// Entries are stored as `(key ^ data, data)` pairs of atomics so concurrent
+/// readers never see a torn entry: a mismatched pair simply fails the key check.
+pub struct EvalCache {
+    entries: Vec<(AtomicU64, AtomicU64)>,
+}
+
+impl EvalCache {
+    pub fn new(size: usize) -> Self {
+        let size = size.next_power_of_two();
+        EvalCache {
+            entries: (0..size).map(|_| (AtomicU64::new(0), AtomicU64::new(0))).collect(),
+        }
+    }
+
+    /// The largest power-of-two cache that fits in `megabytes`
+    pub fn with_megabytes(megabytes: usize) -> Self {
+        let entries = (megabytes * 1024 * 1024 / ENTRY_BYTES).max(1);
+        Self::new(1 << entries.ilog2())
+    }
+
+    /// Look up a cached score for the given position hash
+    pub fn probe(&self, key: u64) -> Option<i32> {
+        let (checked_key, data) = &self.entries[self.slot(key)];
+        let data = data.load(Ordering::Relaxed);
+        let checked_key = checked_key.load(Ordering::Relaxed);
+
+        // Empty slots hold (0, 0), which only matches the (practically impossible) key 0
+        if checked_key ^ data == key && key != 0 {
+            Some(data as u32 as i32)
+        } else {
+            None
+        }
+    }
+
+    /// Store a score, replacing whatever occupied the slot
+    pub fn store(&self, key: u64, score: i32) {
+        let (checked_key, data) = &self.entries[self.slot(key)];
+        let value = score as u32 as u64;
+        checked_key.store(key ^ value, Ordering::Relaxed);
+        data.store(value, Ordering::Relaxed);
+    }
+
+    /// Remove every entry (needed when evaluation parameters change)
+    pub fn clear(&self) {
+        for (checked_key, data) in &self.entries {
+            checked_key.store(0, Ordering::Relaxed);
+            data.store(0, Ordering::Relaxed);
+        }
+    }
+
+    fn slot(&self, key: u64) -> usize {
+        (key as usize) & (self.entries.len() - 1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_store_and_probe() {
+        let cache = EvalCache::new(16);
+        assert_eq!(cache.probe(0x1234_5678_9abc_def0), None);
+
+        cache.store(0x1234_5678_9abc_def0, -250);
+        assert_eq!(cache.probe(0x1234_5678_9abc_def0), Some(-250));
+
+        // A different key mapping to the same slot replaces the entry
+        cache.store(0x1234_5678_9abc_def0 + 16, 75);
+        assert_eq!(cache.probe(0x1234_5678_9abc_def0), None);
+        assert_eq!(cache.probe(0x1234_5678_9abc_def0 + 16), Some(75));
+
+        cache.clear();
+        assert_eq!(cache.probe(0x1234_5678_9abc_def0 + 16), None);
+    }
+
+    #[test]
+    fn test_sized_in_megabytes() {
+        assert_eq!(EvalCache::with_megabytes(1).entries.len(), 1 << 16);
+        assert_eq!(EvalCache::with_megabytes(3).entries.len(), 1 << 17);
+    }
+}
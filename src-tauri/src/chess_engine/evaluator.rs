@@ -1,5 +1,243 @@
-use crate::chess_engine::{Color, Piece, Position};
+use crate::chess_engine::{Color, Piece, Position, Square};
 use crate::chess_engine::analysis::piece_value;
+use crate::chess_engine::endgame;
+use crate::chess_engine::eval_cache::{EvalCache, EVAL_CACHE_MEGABYTES, PAWN_CACHE_MEGABYTES};
+use crate::chess_engine::position::Pockets;
+use crate::chess_engine::rules::Variant;
+use once_cell::sync::Lazy;
+use std::sync::{PoisonError, RwLock};
+use serde::{Deserialize, Serialize};
+
+/// Win/draw/loss probabilities for White derived from a centipawn score
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct WinProbability {
+    pub win: f64,
+    pub draw: f64,
+    pub loss: f64,
+    /// Expected game score for White (win = 1, draw = 0.5)
+    pub expected_score: f64,
+}
+
+impl WinProbability {
+    /// Convert a White-perspective centipawn score using a logistic model:
+    /// separate sigmoids for winning and losing, with the remainder as draws
+    pub fn from_centipawns(centipawns: i32) -> Self {
+        let cp = centipawns as f64;
+        let win = 1.0 / (1.0 + ((WDL_DRAW_MARGIN - cp) / WDL_SCALE).exp());
+        let loss = 1.0 / (1.0 + ((WDL_DRAW_MARGIN + cp) / WDL_SCALE).exp());
+        let draw = (1.0 - win - loss).max(0.0);
+
+        WinProbability {
+            win,
+            draw,
+            loss,
+            expected_score: win + draw / 2.0,
+        }
+    }
+}
+
+/// Centipawn advantage at which a win becomes as likely as not
+const WDL_DRAW_MARGIN: f64 = 150.0;
+
+/// Steepness of the win/loss sigmoids (centipawns per logistic unit)
+const WDL_SCALE: f64 = 90.0;
+
+/// Built-in piece-square table sets giving the engine different playing styles
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum EvalPersonality {
+    /// The default balanced tables
+    Standard,
+    /// Rewards pieces and pawns pushed into the opponent's half
+    Aggressive,
+    /// Rewards central control and well-placed minor pieces
+    Positional,
+}
+
+/// Positional bonus tables (centipawns) used by the evaluator, one per piece type.
+/// Tables are from White's perspective (row 0 = White's back rank) and are
+/// mirrored for Black.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PieceSquareTables {
+    pub pawn: [[i32; 8]; 8],
+    pub knight: [[i32; 8]; 8],
+    pub bishop: [[i32; 8]; 8],
+    pub rook: [[i32; 8]; 8],
+    pub queen: [[i32; 8]; 8],
+    pub king: [[i32; 8]; 8],
+}
+
+impl PieceSquareTables {
+    /// The default tables
+    pub fn standard() -> Self {
+        PieceSquareTables {
+            pawn: PAWN_TABLE,
+            knight: KNIGHT_TABLE,
+            bishop: BISHOP_TABLE,
+            rook: ROOK_TABLE,
+            queen: QUEEN_TABLE,
+            // Use different tables for middlegame vs endgame
+            // For now, use middlegame table (endgame logic can be added later)
+            king: KING_MIDDLEGAME_TABLE,
+        }
+    }
+
+    /// Tables for one of the built-in personalities, derived from the standard set
+    pub fn for_personality(personality: EvalPersonality) -> Self {
+        let mut tables = Self::standard();
+
+        match personality {
+            EvalPersonality::Standard => {}
+            EvalPersonality::Aggressive => {
+                for table in [&mut tables.pawn, &mut tables.knight, &mut tables.bishop, &mut tables.rook, &mut tables.queen] {
+                    for (rank, row) in table.iter_mut().enumerate().skip(4) {
+                        for value in row.iter_mut() {
+                            *value += AGGRESSIVE_ADVANCE_BONUS * (rank as i32 - 3);
+                        }
+                    }
+                }
+            }
+            EvalPersonality::Positional => {
+                for table in [&mut tables.pawn, &mut tables.knight, &mut tables.bishop] {
+                    for (rank, row) in table.iter_mut().enumerate() {
+                        for (file, value) in row.iter_mut().enumerate() {
+                            let rank_from_edge = rank.min(7 - rank) as i32;
+                            let file_from_edge = file.min(7 - file) as i32;
+                            *value += POSITIONAL_CENTER_BONUS * rank_from_edge.min(file_from_edge);
+                        }
+                    }
+                }
+            }
+        }
+
+        tables
+    }
+
+    fn table(&self, piece: Piece) -> &[[i32; 8]; 8] {
+        match piece {
+            Piece::Pawn => &self.pawn,
+            Piece::Knight => &self.knight,
+            Piece::Bishop => &self.bishop,
+            Piece::Rook => &self.rook,
+            Piece::Queen => &self.queen,
+            Piece::King => &self.king,
+        }
+    }
+}
+
+impl Default for PieceSquareTables {
+    fn default() -> Self {
+        Self::standard()
+    }
+}
+
+/// Extra bonus per rank beyond the 4th in the aggressive personality
+const AGGRESSIVE_ADVANCE_BONUS: i32 = 5;
+
+/// Extra bonus per ring toward the center in the positional personality
+const POSITIONAL_CENTER_BONUS: i32 = 5;
+
+/// Evaluation and search weights for one variant. The standard weights misjudge most
+/// variants: in Crazyhouse a captured piece comes back as a drop, so material counts
+/// for less and an exposed king for far more; in three-check every check is progress.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct VariantParams {
+    /// Pawn, knight, bishop, rook and queen values, on the board or in hand
+    pub piece_values: [i32; 5],
+    /// Percentage applied to the king tropism term
+    pub king_attack_percent: i32,
+    /// Bonus (White minus Black) for having given no, one or two checks
+    pub checks_given_bonus: [i32; 3],
+    /// Whether the standard endgame knowledge applies: recognized endings and the
+    /// scaling of drawish ones assume both sides have a king and no pieces in hand
+    pub endgame_knowledge: bool,
+    /// Whether quiescence also searches moves that give check
+    pub quiescence_checks: bool,
+}
+
+impl VariantParams {
+    /// The parameter set the evaluator and search use for `variant`
+    pub fn for_variant(variant: Variant) -> &'static VariantParams {
+        match variant {
+            Variant::Standard | Variant::Chess960 => &STANDARD_PARAMS,
+            Variant::Crazyhouse => &CRAZYHOUSE_PARAMS,
+            Variant::ThreeCheck => &THREE_CHECK_PARAMS,
+            Variant::Horde | Variant::FogOfWar | Variant::KingOfTheHill | Variant::RacingKings => {
+                &NO_ENDGAME_PARAMS
+            }
+        }
+    }
+
+    /// Value of `piece` under these parameters; a king has none
+    pub fn piece_value(&self, piece: Piece) -> i32 {
+        match piece {
+            Piece::Pawn => self.piece_values[0],
+            Piece::Knight => self.piece_values[1],
+            Piece::Bishop => self.piece_values[2],
+            Piece::Rook => self.piece_values[3],
+            Piece::Queen => self.piece_values[4],
+            Piece::King => 0,
+        }
+    }
+}
+
+const STANDARD_PARAMS: VariantParams = VariantParams {
+    piece_values: [
+        piece_value(Piece::Pawn),
+        piece_value(Piece::Knight),
+        piece_value(Piece::Bishop),
+        piece_value(Piece::Rook),
+        piece_value(Piece::Queen),
+    ],
+    king_attack_percent: 100,
+    checks_given_bonus: [0, 0, 0],
+    endgame_knowledge: true,
+    quiescence_checks: false,
+};
+
+/// Pieces keep their value after capture, so the spread between them shrinks, while
+/// drops make every weakness near the king an immediate mating threat
+const CRAZYHOUSE_PARAMS: VariantParams = VariantParams {
+    piece_values: [150, 260, 280, 350, 550],
+    king_attack_percent: 300,
+    endgame_knowledge: false,
+    ..STANDARD_PARAMS
+};
+
+/// A third check ends the game, so pieces trained on the king count double
+const THREE_CHECK_PARAMS: VariantParams = VariantParams {
+    king_attack_percent: 200,
+    checks_given_bonus: [0, 250, 700],
+    endgame_knowledge: false,
+    quiescence_checks: true,
+    ..STANDARD_PARAMS
+};
+
+/// The recognized endings and drawish scaling assume each side has a king that only
+/// mate can beat; not so in horde, fog of war or the king races
+const NO_ENDGAME_PARAMS: VariantParams = VariantParams {
+    endgame_knowledge: false,
+    ..STANDARD_PARAMS
+};
+
+/// Piece-square tables used by `Evaluator::evaluate`, configurable at runtime
+static PIECE_SQUARE_TABLES: Lazy<RwLock<PieceSquareTables>> =
+    Lazy::new(|| RwLock::new(PieceSquareTables::standard()));
+
+/// Evaluation results shared across calls, keyed by Zobrist hash
+static EVAL_CACHE: Lazy<RwLock<EvalCache>> = Lazy::new(|| RwLock::new(EvalCache::with_megabytes(EVAL_CACHE_MEGABYTES)));
+
+/// Pawn-structure terms keyed by the pawns' hash, which changes far less often than the position's
+static PAWN_CACHE: Lazy<RwLock<EvalCache>> = Lazy::new(|| RwLock::new(EvalCache::with_megabytes(PAWN_CACHE_MEGABYTES)));
+
+/// Point of view an evaluation score is expressed from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum EvalPerspective {
+    /// Positive = White is better (the default)
+    #[default]
+    White,
+    /// Positive = the side to move is better (negamax convention)
+    SideToMove,
+}
 
 /// Chess position evaluator using static evaluation techniques
 pub struct Evaluator;
@@ -9,44 +247,191 @@ impl Evaluator {
     /// Returns score in centipawns (100 = 1 pawn advantage for White)
     /// Positive = White is better, Negative = Black is better
     pub fn evaluate(position: &Position) -> i32 {
+        let key = position.compute_zobrist_hash();
+        let cache = || EVAL_CACHE.read().unwrap_or_else(PoisonError::into_inner);
+        if let Some(score) = cache().probe(key) {
+            return score;
+        }
+
+        let score = Self::evaluate_uncached(position);
+        cache().store(key, score);
+        score
+    }
+
+    /// Full static evaluation, bypassing the evaluation cache
+    fn evaluate_uncached(position: &Position) -> i32 {
+        let params = VariantParams::for_variant(position.variant);
+
+        // Recognized endings (KPK, basic mates, dead draws) have exact or guided scores
+        if params.endgame_knowledge {
+            if let Some(score) = endgame::probe(position) {
+                return score;
+            }
+        }
+
         let mut score = 0;
 
         // Material balance (most important factor)
         score += Self::material_balance(position);
 
         // Piece-square tables (positional value)
-        score += Self::piece_square_value(position);
+        let tables = PIECE_SQUARE_TABLES.read().unwrap_or_else(PoisonError::into_inner);
+        score += Self::piece_square_value(position, &tables);
 
-        // Mobility (number of legal moves available)
+        // Rook activity (open files, 7th rank, connected rooks)
+        let pawn_files = PawnFiles::from_position(position);
+        score += Self::rook_placement(position, &pawn_files);
+
+        // Minor piece outposts and holes in the king's pawn shelter
+        score += Self::outposts_and_holes(position);
+
+        // Space behind the pawn chain in the center
+        score += Self::space(position);
+
+        // Threats against loose or out-gunned pieces
+        let white_attacks = AttackInfo::for_color(position, Color::White);
+        let black_attacks = AttackInfo::for_color(position, Color::Black);
+        score += Self::side_threat_penalty(position, Color::Black, &black_attacks, &white_attacks)
+            - Self::side_threat_penalty(position, Color::White, &white_attacks, &black_attacks);
+
+        // Mobility (safe squares reachable by each piece)
         score += Self::mobility_bonus(position);
 
-        score
+        // King tropism (pieces closing in on the enemy king)
+        score += Self::king_tropism(position);
+
+        // Checks already given, in three-check
+        score += Self::checks_given_bonus(position, params);
+
+        // Tempo (the side to move gets a small initiative bonus)
+        score += match position.side_to_move {
+            Color::White => TEMPO_BONUS,
+            Color::Black => -TEMPO_BONUS,
+        };
+
+        // Pull the score toward zero in drawish endgames
+        if !params.endgame_knowledge {
+            return score;
+        }
+        let strong = if score >= 0 { Color::White } else { Color::Black };
+        score * endgame::scale_factor(position, strong) / endgame::SCALE_NORMAL
+    }
+
+    /// Evaluate a position from the requested perspective
+    pub fn evaluate_from(position: &Position, perspective: EvalPerspective) -> i32 {
+        let score = Self::evaluate(position);
+        match (perspective, position.side_to_move) {
+            (EvalPerspective::SideToMove, Color::Black) => -score,
+            _ => score,
+        }
+    }
+
+    /// The piece-square tables currently used by `evaluate`
+    pub fn piece_square_tables() -> PieceSquareTables {
+        PIECE_SQUARE_TABLES.read().unwrap_or_else(PoisonError::into_inner).clone()
     }
 
-    /// Calculate material balance in centipawns
+    /// Replace the piece-square tables used by `evaluate`
+    pub fn set_piece_square_tables(tables: PieceSquareTables) {
+        *PIECE_SQUARE_TABLES.write().unwrap_or_else(PoisonError::into_inner) = tables;
+
+        // Cached scores were computed with the old tables
+        EVAL_CACHE.read().unwrap_or_else(PoisonError::into_inner).clear();
+    }
+
+    /// Replace the evaluation cache with an empty one of about `megabytes` in size
+    pub fn set_cache_megabytes(megabytes: usize) {
+        *EVAL_CACHE.write().unwrap_or_else(PoisonError::into_inner) = EvalCache::with_megabytes(megabytes);
+    }
+
+    /// Replace the pawn structure cache with an empty one of about `megabytes` in size
+    pub fn set_pawn_cache_megabytes(megabytes: usize) {
+        *PAWN_CACHE.write().unwrap_or_else(PoisonError::into_inner) = EvalCache::with_megabytes(megabytes);
+    }
+
+    /// Empty the evaluation and pawn structure caches
+    pub fn clear_caches() {
+        EVAL_CACHE.read().unwrap_or_else(PoisonError::into_inner).clear();
+        PAWN_CACHE.read().unwrap_or_else(PoisonError::into_inner).clear();
+    }
+
+    /// Calculate material balance in centipawns, including imbalance terms and the
+    /// pieces each side holds in hand
     fn material_balance(position: &Position) -> i32 {
-        use crate::chess_engine::types::Square;
+        let params = VariantParams::for_variant(position.variant);
+        let white = PieceCounts::count(position, Color::White);
+        let black = PieceCounts::count(position, Color::Black);
 
-        let mut white_material = 0;
-        let mut black_material = 0;
+        let in_hand: i32 = Pockets::PIECES
+            .into_iter()
+            .map(|piece| {
+                let count = |color| i32::from(position.pockets.count(color, piece));
+                (count(Color::White) - count(Color::Black)) * params.piece_value(piece)
+            })
+            .sum();
 
-        for square_idx in 0..64 {
-            if let Some(square) = Square::new(square_idx) {
-                if let Some((piece, color)) = position.board.get(square) {
-                    let value = piece_value(piece);
-                    match color {
-                        Color::White => white_material += value,
-                        Color::Black => black_material += value,
-                    }
-                }
+        Self::side_material(&white, params) - Self::side_material(&black, params)
+            + Self::material_imbalance(&white, &black)
+            + in_hand
+    }
+
+    /// Second-order correction for trades of unlike material (White minus Black),
+    /// where the naive point count misjudges the resulting balance
+    fn material_imbalance(white: &PieceCounts, black: &PieceCounts) -> i32 {
+        let minors = (white.knights + white.bishops) - (black.knights + black.bishops);
+        let rooks = white.rooks - black.rooks;
+        let queens = white.queens - black.queens;
+
+        let mut score = 0;
+        for &((minor_diff, rook_diff, queen_diff), bonus) in IMBALANCE_TABLE {
+            if (minors, rooks, queens) == (minor_diff, rook_diff, queen_diff) {
+                score += bonus;
+            } else if (minors, rooks, queens) == (-minor_diff, -rook_diff, -queen_diff) {
+                score -= bonus;
+            }
+        }
+
+        // Rook vs minor and pawns: the rook gains strength as the board empties
+        if white.queens + black.queens == 0 {
+            if (minors, rooks) == (-1, 1) {
+                score += EXCHANGE_ENDGAME_BONUS;
+            } else if (minors, rooks) == (1, -1) {
+                score -= EXCHANGE_ENDGAME_BONUS;
             }
         }
 
-        white_material - black_material
+        score
+    }
+
+    /// Material value of one side's pieces plus its imbalance adjustments
+    fn side_material(counts: &PieceCounts, params: &VariantParams) -> i32 {
+        let mut material = counts.pawns * params.piece_value(Piece::Pawn)
+            + counts.knights * params.piece_value(Piece::Knight)
+            + counts.bishops * params.piece_value(Piece::Bishop)
+            + counts.rooks * params.piece_value(Piece::Rook)
+            + counts.queens * params.piece_value(Piece::Queen);
+
+        // Two bishops cover both square colors and work well together
+        if counts.bishops >= 2 {
+            material += BISHOP_PAIR_BONUS;
+        }
+
+        // Knights gain value in closed positions with many pawns, lose it as pawns come off
+        material += counts.knights * (counts.pawns - 5) * KNIGHT_PAWN_ADJUSTMENT;
+
+        // Major pieces of the same type overlap in function
+        if counts.rooks >= 2 {
+            material -= ROOK_REDUNDANCY_PENALTY;
+        }
+        if counts.queens >= 2 {
+            material -= QUEEN_REDUNDANCY_PENALTY;
+        }
+
+        material
     }
 
     /// Evaluate piece positioning using piece-square tables
-    fn piece_square_value(position: &Position) -> i32 {
+    fn piece_square_value(position: &Position, tables: &PieceSquareTables) -> i32 {
         use crate::chess_engine::types::Square;
 
         let mut score = 0;
@@ -54,7 +439,7 @@ impl Evaluator {
         for square_idx in 0..64 {
             if let Some(square) = Square::new(square_idx) {
                 if let Some((piece, color)) = position.board.get(square) {
-                    let value = Self::get_piece_square_value(piece, color, square_idx);
+                    let value = Self::get_piece_square_value(piece, color, square_idx, tables);
                     score += value;
                 }
             }
@@ -64,7 +449,7 @@ impl Evaluator {
     }
 
     /// Get positional value for a piece on a specific square
-    fn get_piece_square_value(piece: Piece, color: Color, square_idx: u8) -> i32 {
+    fn get_piece_square_value(piece: Piece, color: Color, square_idx: u8, tables: &PieceSquareTables) -> i32 {
         let rank = (square_idx / 8) as usize;
         let file = (square_idx % 8) as usize;
 
@@ -74,18 +459,7 @@ impl Evaluator {
             Color::Black => 7 - rank,
         };
 
-        let bonus = match piece {
-            Piece::Pawn => PAWN_TABLE[table_rank][file],
-            Piece::Knight => KNIGHT_TABLE[table_rank][file],
-            Piece::Bishop => BISHOP_TABLE[table_rank][file],
-            Piece::Rook => ROOK_TABLE[table_rank][file],
-            Piece::Queen => QUEEN_TABLE[table_rank][file],
-            Piece::King => {
-                // Use different tables for middlegame vs endgame
-                // For now, use middlegame table (endgame logic can be added later)
-                KING_MIDDLEGAME_TABLE[table_rank][file]
-            }
-        };
+        let bonus = tables.table(piece)[table_rank][file];
 
         match color {
             Color::White => bonus,
@@ -93,23 +467,517 @@ impl Evaluator {
         }
     }
 
-    /// Calculate mobility bonus (simplified - just counts legal moves)
+    /// Evaluate rook placement for both sides (White minus Black)
+    fn rook_placement(position: &Position, pawn_files: &PawnFiles) -> i32 {
+        Self::side_rook_placement(position, pawn_files, Color::White)
+            - Self::side_rook_placement(position, pawn_files, Color::Black)
+    }
+
+    /// Rook bonuses for one side
+    fn side_rook_placement(position: &Position, pawn_files: &PawnFiles, color: Color) -> i32 {
+        let rooks: Vec<Square> = position
+            .board
+            .pieces_of_color(color)
+            .filter(|(_, piece)| *piece == Piece::Rook)
+            .map(|(square, _)| square)
+            .collect();
+
+        let opponent = color.opposite();
+        let seventh_rank = if color == Color::White { 6 } else { 1 };
+        let eighth_rank = if color == Color::White { 7 } else { 0 };
+
+        let mut score = 0;
+
+        for rook in &rooks {
+            let file = rook.file();
+
+            if pawn_files.is_open(file) {
+                score += ROOK_OPEN_FILE_BONUS;
+            } else if pawn_files.is_semi_open(color, file) {
+                score += ROOK_SEMI_OPEN_FILE_BONUS;
+            }
+
+            // A rook on the 7th only matters if it attacks pawns there or confines the king
+            if rook.rank() == seventh_rank {
+                let enemy_king_on_back_rank = position
+                    .board
+                    .find_king(opponent)
+                    .is_some_and(|king| king.rank() == eighth_rank);
+                let enemy_pawns_on_seventh = (0..8).any(|f| {
+                    Square::from_rank_file(seventh_rank, f)
+                        .is_some_and(|sq| position.board.get(sq) == Some((Piece::Pawn, opponent)))
+                });
+
+                if enemy_king_on_back_rank || enemy_pawns_on_seventh {
+                    score += ROOK_ON_SEVENTH_BONUS;
+                }
+            }
+        }
+
+        for (i, first) in rooks.iter().enumerate() {
+            for second in &rooks[i + 1..] {
+                if Self::rooks_connected(position, *first, *second) {
+                    score += CONNECTED_ROOKS_BONUS;
+                }
+            }
+        }
+
+        score
+    }
+
+    /// Whether two rooks share a rank or file with no pieces between them
+    fn rooks_connected(position: &Position, a: Square, b: Square) -> bool {
+        if a.rank() == b.rank() {
+            let (low, high) = (a.file().min(b.file()), a.file().max(b.file()));
+            (low + 1..high).all(|file| {
+                Square::from_rank_file(a.rank(), file).is_some_and(|sq| position.board.is_empty(sq))
+            })
+        } else if a.file() == b.file() {
+            let (low, high) = (a.rank().min(b.rank()), a.rank().max(b.rank()));
+            (low + 1..high).all(|rank| {
+                Square::from_rank_file(rank, a.file()).is_some_and(|sq| position.board.is_empty(sq))
+            })
+        } else {
+            false
+        }
+    }
+
+    /// Outpost bonuses minus king-shelter hole penalties (White minus Black)
+    fn outposts_and_holes(position: &Position) -> i32 {
+        Self::side_outposts_and_holes(position, Color::White)
+            - Self::side_outposts_and_holes(position, Color::Black)
+    }
+
+    fn side_outposts_and_holes(position: &Position, color: Color) -> i32 {
+        let opponent = color.opposite();
+        let pawn_defended = position.board.pawn_attacks(color);
+        let mut score = 0;
+
+        for (square, piece) in position.board.pieces_of_color(color) {
+            let bonus = match piece {
+                Piece::Knight => KNIGHT_OUTPOST_BONUS,
+                Piece::Bishop => BISHOP_OUTPOST_BONUS,
+                _ => continue,
+            };
+
+            // Outposts are only meaningful in the opponent's half of the board
+            let relative_rank = relative_rank(square, color);
+            if !(3..=5).contains(&relative_rank) {
+                continue;
+            }
+
+            let defended = pawn_defended & (1u64 << square.index()) != 0;
+            if defended && !Self::pawn_can_ever_attack(position, square, opponent) {
+                score += bonus;
+            }
+        }
+
+        // Holes on the third rank in front of a king still sheltering on its first two ranks
+        if let Some(king) = position.board.find_king(color) {
+            if relative_rank(king, color) <= 1 {
+                let shelter_rank = if color == Color::White { 2 } else { 5 };
+                let low_file = king.file().saturating_sub(1);
+                let high_file = (king.file() + 1).min(7);
+
+                for file in low_file..=high_file {
+                    if let Some(square) = Square::from_rank_file(shelter_rank, file) {
+                        if !Self::pawn_can_ever_attack(position, square, color) {
+                            score -= KING_SHELTER_HOLE_PENALTY;
+                        }
+                    }
+                }
+            }
+        }
+
+        score
+    }
+
+    /// Whether any pawn of `color` on an adjacent file could still advance to attack `square`
+    fn pawn_can_ever_attack(position: &Position, square: Square, color: Color) -> bool {
+        position
+            .board
+            .pieces_of_color(color)
+            .any(|(pawn_square, piece)| {
+                piece == Piece::Pawn
+                    && pawn_square.file().abs_diff(square.file()) == 1
+                    && match color {
+                        Color::White => pawn_square.rank() < square.rank(),
+                        Color::Black => pawn_square.rank() > square.rank(),
+                    }
+            })
+    }
+
+    /// Space advantage (White minus Black), scaled by how many pieces remain
+    /// since extra room matters most when there are pieces to use it
+    fn space(position: &Position) -> i32 {
+        let pieces_on_board = [Color::White, Color::Black]
+            .into_iter()
+            .flat_map(|color| position.board.pieces_of_color(color))
+            .filter(|(_, piece)| !matches!(piece, Piece::Pawn | Piece::King))
+            .count() as i32;
+
+        Self::space_area_difference(position) * pieces_on_board * SPACE_WEIGHT / 16
+    }
+
+    /// White's space area minus Black's; it depends only on the pawns, so it is cached by pawn hash
+    fn space_area_difference(position: &Position) -> i32 {
+        let key = position.compute_pawn_hash();
+        let cache = || PAWN_CACHE.read().unwrap_or_else(PoisonError::into_inner);
+        if let Some(area) = cache().probe(key) {
+            return area;
+        }
+
+        let area = Self::side_space_area(position, Color::White) - Self::side_space_area(position, Color::Black);
+        cache().store(key, area);
+        area
+    }
+
+    /// Safe central squares (files c-f, own ranks 2-4) not blocked by own pawns or
+    /// covered by enemy pawns; squares behind an own pawn count twice
+    fn side_space_area(position: &Position, color: Color) -> i32 {
+        let enemy_pawn_attacks = position.board.pawn_attacks(color.opposite());
+        let own_pawns: Vec<Square> = position
+            .board
+            .pieces_of_color(color)
+            .filter(|(_, piece)| *piece == Piece::Pawn)
+            .map(|(square, _)| square)
+            .collect();
+
+        let mut area = 0;
+
+        for relative in 1..=3u8 {
+            let rank = if color == Color::White { relative } else { 7 - relative };
+            for file in 2..=5u8 {
+                let Some(square) = Square::from_rank_file(rank, file) else { continue };
+
+                if own_pawns.contains(&square) || enemy_pawn_attacks & (1u64 << square.index()) != 0 {
+                    continue;
+                }
+
+                area += 1;
+
+                let behind_own_pawn = own_pawns.iter().any(|pawn| {
+                    pawn.file() == file && relative_rank(*pawn, color) > relative
+                });
+                if behind_own_pawn {
+                    area += 1;
+                }
+            }
+        }
+
+        area
+    }
+
+    /// Penalty for one side's pieces that are attacked by cheaper pieces or
+    /// attacked and not defended at all
+    fn side_threat_penalty(
+        position: &Position,
+        color: Color,
+        own: &AttackInfo,
+        enemy: &AttackInfo,
+    ) -> i32 {
+        let mut penalty = 0;
+
+        for (square, piece) in position.board.pieces_of_color(color) {
+            if piece == Piece::King || !enemy.attacks(square) {
+                continue;
+            }
+
+            if enemy.cheapest_attacker(square) < piece_value(piece) {
+                penalty += THREAT_BY_LESSER_PENALTY;
+            } else if !own.attacks(square) {
+                penalty += HANGING_PIECE_PENALTY;
+            }
+        }
+
+        penalty
+    }
+
+    /// Per-piece mobility for both sides (White minus Black)
     fn mobility_bonus(position: &Position) -> i32 {
-        use crate::chess_engine::validation::generate_legal_moves;
+        Self::side_mobility(position, Color::White) - Self::side_mobility(position, Color::Black)
+    }
+
+    /// Mobility of one side's minor and major pieces, counting pseudo-legal
+    /// target squares that are not occupied by friendly pieces or covered by enemy pawns
+    fn side_mobility(position: &Position, color: Color) -> i32 {
+        let own_pieces = position.board.occupancy(color);
+        let enemy_pawn_attacks = position.board.pawn_attacks(color.opposite());
+        let safe_squares = !own_pieces & !enemy_pawn_attacks;
 
-        let moves = generate_legal_moves(position);
-        let mobility = moves.len() as i32;
+        let mut score = 0;
 
-        // Small bonus for having more moves available (capped to avoid overvaluing)
-        let bonus = (mobility - 20).clamp(-20, 20);
+        for (square, piece) in position.board.pieces_of_color(color) {
+            let (weight, baseline) = match piece {
+                Piece::Knight => KNIGHT_MOBILITY,
+                Piece::Bishop => BISHOP_MOBILITY,
+                Piece::Rook => ROOK_MOBILITY,
+                Piece::Queen => QUEEN_MOBILITY,
+                Piece::Pawn | Piece::King => continue,
+            };
 
-        match position.side_to_move {
-            Color::White => bonus,
-            Color::Black => -bonus,
+            let attacks = position.board.attacks_from(square, piece, color);
+            let count = (attacks & safe_squares).count_ones() as i32;
+            score += weight * (count - baseline);
+        }
+
+        score
+    }
+
+    /// Distance-weighted credit for pieces near the enemy king (White minus Black)
+    fn king_tropism(position: &Position) -> i32 {
+        let tropism = Self::side_king_tropism(position, Color::White) - Self::side_king_tropism(position, Color::Black);
+        tropism * VariantParams::for_variant(position.variant).king_attack_percent / 100
+    }
+
+    /// Credit for checks already given (White minus Black), in variants that count them
+    fn checks_given_bonus(position: &Position, params: &VariantParams) -> i32 {
+        let bonus = |color| {
+            let checks = usize::from(position.checks_given(color)).min(params.checks_given_bonus.len() - 1);
+            params.checks_given_bonus[checks]
+        };
+        bonus(Color::White) - bonus(Color::Black)
+    }
+
+    /// Sum over one side's pieces of (7 - distance to the enemy king) times the piece weight
+    fn side_king_tropism(position: &Position, color: Color) -> i32 {
+        let Some(enemy_king) = position.board.find_king(color.opposite()) else {
+            return 0;
+        };
+
+        position
+            .board
+            .pieces_of_color(color)
+            .map(|(square, piece)| {
+                let weight = match piece {
+                    Piece::Knight => KNIGHT_TROPISM,
+                    Piece::Bishop => BISHOP_TROPISM,
+                    Piece::Rook => ROOK_TROPISM,
+                    Piece::Queen => QUEEN_TROPISM,
+                    Piece::Pawn | Piece::King => return 0,
+                };
+                weight * (7 - endgame::king_distance(square, enemy_king) as i32)
+            })
+            .sum()
+    }
+}
+
+/// Rank counted from the given color's own back rank (0 = back rank)
+fn relative_rank(square: Square, color: Color) -> u8 {
+    match color {
+        Color::White => square.rank(),
+        Color::Black => 7 - square.rank(),
+    }
+}
+
+/// Squares attacked by one side, with the value of its cheapest attacker per square
+struct AttackInfo {
+    attacked: u64,
+    cheapest_attacker: [i32; 64],
+}
+
+impl AttackInfo {
+    fn for_color(position: &Position, color: Color) -> Self {
+        let mut info = AttackInfo {
+            attacked: position.board.attacked_by(color),
+            cheapest_attacker: [i32::MAX; 64],
+        };
+
+        for (square, piece) in position.board.pieces_of_color(color) {
+            // The king can attack, but never profitably trades itself
+            let value = match piece {
+                Piece::King => KING_ATTACKER_VALUE,
+                _ => piece_value(piece),
+            };
+
+            let mut attacks = position.board.attacks_from(square, piece, color);
+
+            while attacks != 0 {
+                let target = attacks.trailing_zeros() as usize;
+                info.cheapest_attacker[target] = info.cheapest_attacker[target].min(value);
+                attacks &= attacks - 1;
+            }
         }
+
+        info
     }
+
+    fn attacks(&self, square: Square) -> bool {
+        self.attacked & (1u64 << square.index()) != 0
+    }
+
+    fn cheapest_attacker(&self, square: Square) -> i32 {
+        self.cheapest_attacker[square.index() as usize]
+    }
+}
+
+/// Pawn counts per file for each side, used by file-based evaluation terms
+struct PawnFiles {
+    white: [u8; 8],
+    black: [u8; 8],
 }
 
+impl PawnFiles {
+    fn from_position(position: &Position) -> Self {
+        let mut files = PawnFiles {
+            white: [0; 8],
+            black: [0; 8],
+        };
+
+        for color in [Color::White, Color::Black] {
+            for (square, piece) in position.board.pieces_of_color(color) {
+                if piece == Piece::Pawn {
+                    match color {
+                        Color::White => files.white[square.file() as usize] += 1,
+                        Color::Black => files.black[square.file() as usize] += 1,
+                    }
+                }
+            }
+        }
+
+        files
+    }
+
+    fn count(&self, color: Color, file: u8) -> u8 {
+        match color {
+            Color::White => self.white[file as usize],
+            Color::Black => self.black[file as usize],
+        }
+    }
+
+    /// No pawns of either color on the file
+    fn is_open(&self, file: u8) -> bool {
+        self.count(Color::White, file) == 0 && self.count(Color::Black, file) == 0
+    }
+
+    /// No friendly pawns on the file, but at least one enemy pawn
+    fn is_semi_open(&self, color: Color, file: u8) -> bool {
+        self.count(color, file) == 0 && self.count(color.opposite(), file) > 0
+    }
+}
+
+/// Number of each non-king piece type one side has on the board
+struct PieceCounts {
+    pawns: i32,
+    knights: i32,
+    bishops: i32,
+    rooks: i32,
+    queens: i32,
+}
+
+impl PieceCounts {
+    fn count(position: &Position, color: Color) -> Self {
+        let mut counts = PieceCounts {
+            pawns: 0,
+            knights: 0,
+            bishops: 0,
+            rooks: 0,
+            queens: 0,
+        };
+
+        for (_, piece) in position.board.pieces_of_color(color) {
+            match piece {
+                Piece::Pawn => counts.pawns += 1,
+                Piece::Knight => counts.knights += 1,
+                Piece::Bishop => counts.bishops += 1,
+                Piece::Rook => counts.rooks += 1,
+                Piece::Queen => counts.queens += 1,
+                Piece::King => {}
+            }
+        }
+
+        counts
+    }
+}
+
+// Material imbalance terms (centipawns)
+
+/// Bonus for owning both bishops
+const BISHOP_PAIR_BONUS: i32 = 50;
+
+/// Knight value change per own pawn above (or below) five
+const KNIGHT_PAWN_ADJUSTMENT: i32 = 6;
+
+/// Penalty for owning two or more rooks
+const ROOK_REDUNDANCY_PENALTY: i32 = 20;
+
+/// Penalty for owning two or more queens
+const QUEEN_REDUNDANCY_PENALTY: i32 = 40;
+
+/// Known material imbalances: ((minor, rook, queen) count difference, bonus for the
+/// side with the extra minor pieces/rooks). Mirrored differences score the negation.
+const IMBALANCE_TABLE: &[((i32, i32, i32), i32)] = &[
+    // Two minor pieces coordinate better than a rook
+    ((2, -1, 0), 40),
+    // Three minor pieces outweigh a queen
+    ((3, 0, -1), 50),
+    // Two rooks vs queen: the rooks defend each other and double on files
+    ((0, 2, -1), 25),
+    // Rook and minor piece vs queen
+    ((1, 1, -1), 25),
+];
+
+/// Bonus for a rook facing a minor piece (plus pawns) once the queens are off
+const EXCHANGE_ENDGAME_BONUS: i32 = 30;
+
+/// Bonus for having the move (centipawns)
+const TEMPO_BONUS: i32 = 10;
+
+// Outposts and weak squares (centipawns)
+
+/// Knight on a pawn-defended square no enemy pawn can attack
+const KNIGHT_OUTPOST_BONUS: i32 = 25;
+
+/// Bishop on a pawn-defended square no enemy pawn can attack
+const BISHOP_OUTPOST_BONUS: i32 = 12;
+
+/// Square in front of the king that no friendly pawn can defend anymore
+const KING_SHELTER_HOLE_PENALTY: i32 = 10;
+
+/// Space bonus per safe central square with a full set of pieces (scaled by pieces / 16)
+const SPACE_WEIGHT: i32 = 2;
+
+// Threat terms (centipawns)
+
+/// Piece attacked by a lower-valued enemy piece
+const THREAT_BY_LESSER_PENALTY: i32 = 30;
+
+/// Piece attacked and not defended
+const HANGING_PIECE_PENALTY: i32 = 40;
+
+/// Attacker value used for the king so it never counts as a "lesser" attacker
+const KING_ATTACKER_VALUE: i32 = 10_000;
+
+// Mobility terms: (centipawns per safe square, typical square count)
+// A piece reaching its typical number of squares scores zero
+
+const KNIGHT_MOBILITY: (i32, i32) = (4, 4);
+const BISHOP_MOBILITY: (i32, i32) = (5, 6);
+const ROOK_MOBILITY: (i32, i32) = (3, 7);
+const QUEEN_MOBILITY: (i32, i32) = (1, 13);
+
+// King tropism terms: centipawns per step of closeness to the enemy king
+// (a piece adjacent to the king earns 6 steps, one on the far side of the board none)
+
+const KNIGHT_TROPISM: i32 = 3;
+const BISHOP_TROPISM: i32 = 2;
+const ROOK_TROPISM: i32 = 2;
+const QUEEN_TROPISM: i32 = 4;
+
+// Rook placement terms (centipawns)
+
+/// Rook on a file with no pawns
+const ROOK_OPEN_FILE_BONUS: i32 = 25;
+
+/// Rook on a file with only enemy pawns
+const ROOK_SEMI_OPEN_FILE_BONUS: i32 = 12;
+
+/// Rook on the 7th rank attacking pawns or confining the king
+const ROOK_ON_SEVENTH_BONUS: i32 = 20;
+
+/// Two rooks defending each other along a rank or file
+const CONNECTED_ROOKS_BONUS: i32 = 15;
+
 // Piece-Square Tables
 // Values are in centipawns, represent positional bonuses for each square
 // Tables are from White's perspective (rank 0 = White's back rank)
@@ -203,6 +1071,7 @@ const KING_ENDGAME_TABLE: [[i32; 8]; 8] = [
 mod tests {
     use super::*;
     use crate::chess_engine::{Position, ChessGame};
+    use crate::chess_engine::fen::parse_fen;
 
     #[test]
     fn test_starting_position_is_balanced() {
@@ -227,9 +1096,300 @@ mod tests {
     #[test]
     fn test_piece_square_values() {
         // Knight on edge vs center
-        let edge_value = Evaluator::get_piece_square_value(Piece::Knight, Color::White, 0); // a1
-        let center_value = Evaluator::get_piece_square_value(Piece::Knight, Color::White, 27); // d4
+        let tables = PieceSquareTables::standard();
+        let edge_value = Evaluator::get_piece_square_value(Piece::Knight, Color::White, 0, &tables); // a1
+        let center_value = Evaluator::get_piece_square_value(Piece::Knight, Color::White, 27, &tables); // d4
 
         assert!(center_value > edge_value, "Center knight should be better than edge knight");
     }
+
+    #[test]
+    fn test_bishop_pair_bonus() {
+        // Two bishops vs bishop and knight (a lone knight with no pawns is also devalued)
+        let pair = ChessGame::from_fen("4k3/8/8/8/8/8/8/2B1KB2 w - - 0 1").unwrap();
+        let no_pair = ChessGame::from_fen("4k3/8/8/8/8/8/8/2B1KN2 w - - 0 1").unwrap();
+
+        let pair_material = Evaluator::material_balance(pair.get_board_state());
+        let no_pair_material = Evaluator::material_balance(no_pair.get_board_state());
+
+        assert_eq!(
+            pair_material - no_pair_material,
+            piece_value(Piece::Bishop) - piece_value(Piece::Knight)
+                + BISHOP_PAIR_BONUS
+                + 5 * KNIGHT_PAWN_ADJUSTMENT
+        );
+    }
+
+    #[test]
+    fn test_knight_value_depends_on_pawn_count() {
+        // Knight with eight pawns is worth more than knight with no pawns
+        let closed = ChessGame::from_fen("4k3/pppppppp/8/8/8/8/PPPPPPPP/4KN2 w - - 0 1").unwrap();
+        let open = ChessGame::from_fen("4k3/8/8/8/8/8/8/4KN2 w - - 0 1").unwrap();
+
+        let closed_material = Evaluator::material_balance(closed.get_board_state());
+        let open_material = Evaluator::material_balance(open.get_board_state());
+
+        assert_eq!(closed_material, piece_value(Piece::Knight) + 3 * KNIGHT_PAWN_ADJUSTMENT);
+        assert_eq!(open_material, piece_value(Piece::Knight) - 5 * KNIGHT_PAWN_ADJUSTMENT);
+    }
+
+    #[test]
+    fn test_rook_open_file_bonus() {
+        // Rook on the open a-file vs rook behind its own pawn on the f-file
+        let open = ChessGame::from_fen("4k3/5p2/8/8/8/8/5P2/R3K3 w - - 0 1").unwrap();
+        let closed = ChessGame::from_fen("4k3/5p2/8/8/8/8/5P2/4KR2 w - - 0 1").unwrap();
+        let open_files = PawnFiles::from_position(open.get_board_state());
+        let closed_files = PawnFiles::from_position(closed.get_board_state());
+
+        assert!(open_files.is_open(0));
+        assert!(!closed_files.is_open(5));
+        assert_eq!(Evaluator::rook_placement(open.get_board_state(), &open_files), ROOK_OPEN_FILE_BONUS);
+        assert_eq!(Evaluator::rook_placement(closed.get_board_state(), &closed_files), 0);
+    }
+
+    #[test]
+    fn test_connected_rooks_on_seventh() {
+        // White rooks on a7 and h7 with nothing between them, Black king on g8
+        let position = parse_fen("6k1/R6R/8/8/8/8/8/4K3 w - - 0 1").unwrap();
+        let pawn_files = PawnFiles::from_position(&position);
+
+        assert_eq!(
+            Evaluator::rook_placement(&position, &pawn_files),
+            2 * ROOK_OPEN_FILE_BONUS + 2 * ROOK_ON_SEVENTH_BONUS + CONNECTED_ROOKS_BONUS
+        );
+    }
+
+    #[test]
+    fn test_mobility_excludes_pawn_attacked_squares() {
+        // Knight on d4: e6 and c6 are covered by the Black pawn on d7
+        let free = parse_fen("4k3/8/8/8/3N4/8/8/4K3 w - - 0 1").unwrap();
+        let covered = parse_fen("4k3/3p4/8/8/3N4/8/8/4K3 w - - 0 1").unwrap();
+
+        let free_mobility = Evaluator::side_mobility(&free, Color::White);
+        let covered_mobility = Evaluator::side_mobility(&covered, Color::White);
+
+        assert_eq!(free_mobility, KNIGHT_MOBILITY.0 * (8 - KNIGHT_MOBILITY.1));
+        assert_eq!(free_mobility - covered_mobility, 2 * KNIGHT_MOBILITY.0);
+    }
+
+    #[test]
+    fn test_relative_evaluation_follows_side_to_move() {
+        let white_to_move = parse_fen("4k3/pppp4/8/8/8/8/PPPP4/R3K3 w - - 0 1").unwrap();
+        let black_to_move = parse_fen("4k3/pppp4/8/8/8/8/PPPP4/R3K3 b - - 0 1").unwrap();
+
+        // White's perspective differs only by the tempo bonus swapping sides
+        assert_eq!(
+            Evaluator::evaluate(&white_to_move) - Evaluator::evaluate(&black_to_move),
+            2 * TEMPO_BONUS
+        );
+
+        assert!(Evaluator::evaluate_from(&white_to_move, EvalPerspective::SideToMove) > 400);
+        assert!(Evaluator::evaluate_from(&black_to_move, EvalPerspective::SideToMove) < -400);
+        assert_eq!(
+            Evaluator::evaluate_from(&black_to_move, EvalPerspective::White),
+            Evaluator::evaluate(&black_to_move)
+        );
+    }
+
+    #[test]
+    fn test_knight_outpost() {
+        // Knight on e5 supported by d4; the only Black pawn is on the e-file and can never attack it
+        let outpost = parse_fen("4k3/4p3/8/4N3/3P4/8/8/4K3 w - - 0 1").unwrap();
+        // Same knight, but the Black f-pawn can still chase it away with ...f6
+        let contested = parse_fen("4k3/4pp2/8/4N3/3P4/8/8/4K3 w - - 0 1").unwrap();
+
+        let outpost_score = Evaluator::side_outposts_and_holes(&outpost, Color::White);
+        let contested_score = Evaluator::side_outposts_and_holes(&contested, Color::White);
+
+        assert_eq!(outpost_score - contested_score, KNIGHT_OUTPOST_BONUS);
+    }
+
+    #[test]
+    fn test_king_shelter_holes() {
+        // Castled king with intact f2/g2/h2 has no holes; after g2-g4 the f3 and h3 squares are holes
+        let intact = parse_fen("4k3/8/8/8/8/8/5PPP/6K1 w - - 0 1").unwrap();
+        let weakened = parse_fen("4k3/8/8/8/6P1/8/5P1P/6K1 w - - 0 1").unwrap();
+
+        assert_eq!(Evaluator::side_outposts_and_holes(&intact, Color::White), 0);
+        assert_eq!(
+            Evaluator::side_outposts_and_holes(&weakened, Color::White),
+            -2 * KING_SHELTER_HOLE_PENALTY
+        );
+    }
+
+    #[test]
+    fn test_threat_penalties() {
+        // White knight on d5 attacked by the c6 pawn; White rook on h5 undefended, attacked by the h8 rook
+        let position = parse_fen("4k2r/8/2p5/3N3R/8/8/8/4K3 w - - 0 1").unwrap();
+        let white = AttackInfo::for_color(&position, Color::White);
+        let black = AttackInfo::for_color(&position, Color::Black);
+
+        assert_eq!(
+            Evaluator::side_threat_penalty(&position, Color::White, &white, &black),
+            THREAT_BY_LESSER_PENALTY + HANGING_PIECE_PENALTY
+        );
+    }
+
+    #[test]
+    fn test_king_tropism() {
+        // Knight on f7 is one step from the g8 king; on a1 it is seven steps away
+        let near = parse_fen("6k1/5N2/8/8/8/8/8/4K3 w - - 0 1").unwrap();
+        let far = parse_fen("6k1/8/8/8/8/8/8/N3K3 w - - 0 1").unwrap();
+
+        assert_eq!(Evaluator::side_king_tropism(&near, Color::White), 6 * KNIGHT_TROPISM);
+        assert_eq!(Evaluator::side_king_tropism(&far, Color::White), 0);
+        assert_eq!(Evaluator::king_tropism(&near), 6 * KNIGHT_TROPISM);
+    }
+
+    #[test]
+    fn test_variant_parameters() {
+        // Pieces in hand count as material, at Crazyhouse values
+        let in_hand = parse_fen("4k3/8/8/8/8/8/8/4K3[Pn] w - - 0 1").unwrap();
+        let crazyhouse = VariantParams::for_variant(Variant::Crazyhouse);
+        assert_eq!(
+            Evaluator::material_balance(&in_hand),
+            crazyhouse.piece_value(Piece::Pawn) - crazyhouse.piece_value(Piece::Knight)
+        );
+
+        // An attack on the king weighs three times as much in Crazyhouse
+        let near = parse_fen("6k1/5N2/8/8/8/8/8/4K3[] w - - 0 1").unwrap();
+        assert_eq!(Evaluator::king_tropism(&near), 18 * KNIGHT_TROPISM);
+
+        // Each check given is worth more than the last in three-check
+        let checks = |fen| Evaluator::checks_given_bonus(&parse_fen(fen).unwrap(), &THREE_CHECK_PARAMS);
+        assert_eq!(checks("4k3/8/8/8/8/8/8/4K3 w - - 0 1 +0+0"), 0);
+        assert_eq!(checks("4k3/8/8/8/8/8/8/4K3 w - - 0 1 +2+1"), 700 - 250);
+        assert_eq!(VariantParams::for_variant(Variant::Chess960), &STANDARD_PARAMS);
+    }
+
+    #[test]
+    fn test_cached_evaluation_matches_uncached() {
+        let position = parse_fen("r1bqkbnr/pppp1ppp/2n5/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R w KQkq - 2 3").unwrap();
+
+        let first = Evaluator::evaluate(&position);
+        let second = Evaluator::evaluate(&position);
+
+        assert_eq!(first, Evaluator::evaluate_uncached(&position));
+        assert_eq!(first, second);
+    }
+
+    #[test]
+    fn test_win_probability_model() {
+        let equal = WinProbability::from_centipawns(0);
+        assert!((equal.win - equal.loss).abs() < 1e-9);
+        assert!((equal.expected_score - 0.5).abs() < 1e-9);
+        assert!((equal.win + equal.draw + equal.loss - 1.0).abs() < 1e-9);
+
+        let better = WinProbability::from_centipawns(300);
+        let winning = WinProbability::from_centipawns(900);
+        assert!(better.win > equal.win && winning.win > better.win);
+        assert!(winning.win > 0.99);
+
+        let mirrored = WinProbability::from_centipawns(-300);
+        assert!((mirrored.loss - better.win).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_personality_tables() {
+        let standard = PieceSquareTables::standard();
+        let aggressive = PieceSquareTables::for_personality(EvalPersonality::Aggressive);
+        let positional = PieceSquareTables::for_personality(EvalPersonality::Positional);
+
+        assert_eq!(PieceSquareTables::for_personality(EvalPersonality::Standard), standard);
+
+        // Knight on e6 (deep in Black's half) is worth more to the aggressive engine
+        let e6 = 44;
+        assert!(
+            Evaluator::get_piece_square_value(Piece::Knight, Color::White, e6, &aggressive)
+                > Evaluator::get_piece_square_value(Piece::Knight, Color::White, e6, &standard)
+        );
+
+        // Positional tables leave the rim alone and boost d4
+        let (a1, d4) = (0, 27);
+        assert_eq!(positional.knight[0][0], standard.knight[0][0]);
+        assert!(
+            Evaluator::get_piece_square_value(Piece::Knight, Color::White, d4, &positional)
+                - Evaluator::get_piece_square_value(Piece::Knight, Color::White, a1, &positional)
+                > Evaluator::get_piece_square_value(Piece::Knight, Color::White, d4, &standard)
+                    - Evaluator::get_piece_square_value(Piece::Knight, Color::White, a1, &standard)
+        );
+    }
+
+    #[test]
+    fn test_space_after_central_pawn_advance() {
+        let start = Position::new();
+        assert_eq!(Evaluator::space(&start), 0);
+
+        // 1.e4 frees e2 and gains e2/e3 behind the pawn, at the cost of the e4 square itself
+        let after_e4 = parse_fen("rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1").unwrap();
+        assert_eq!(
+            Evaluator::side_space_area(&after_e4, Color::White) - Evaluator::side_space_area(&start, Color::White),
+            2
+        );
+        assert!(Evaluator::space(&after_e4) > 0);
+    }
+
+    /// Flip the board vertically and swap piece colors and the side to move
+    fn color_flipped(position: &Position) -> Position {
+        let mut flipped = Position::empty();
+        for index in 0..64u8 {
+            let square = Square::new(index).unwrap();
+            if let Some((piece, color)) = position.board.get(square) {
+                let mirror = Square::from_rank_file(7 - square.rank(), square.file()).unwrap();
+                flipped.board.set(mirror, Some((piece, color.opposite())));
+            }
+        }
+        flipped.side_to_move = position.side_to_move.opposite();
+        flipped
+    }
+
+    #[test]
+    fn test_evaluation_is_color_symmetric() {
+        let fens = [
+            "r1bqkbnr/pppp1ppp/2n5/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R w KQkq - 2 3",
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+            "4k3/8/8/3p4/8/8/8/4K3 b - - 0 1",
+        ];
+
+        for fen in fens {
+            let position = parse_fen(fen).unwrap();
+            let flipped = color_flipped(&position);
+
+            // Mobility in particular must not depend on whose turn it is
+            assert_eq!(
+                Evaluator::mobility_bonus(&position),
+                -Evaluator::mobility_bonus(&flipped),
+                "mobility asymmetric for {}",
+                fen
+            );
+            assert_eq!(
+                Evaluator::evaluate_uncached(&position),
+                -Evaluator::evaluate_uncached(&flipped),
+                "evaluation asymmetric for {}",
+                fen
+            );
+        }
+    }
+
+    #[test]
+    fn test_material_imbalance_table() {
+        // White: three minors vs Black's queen
+        let minors_vs_queen = parse_fen("3qk3/pppppppp/8/8/8/8/PPPPPPPP/1NB1KB2 w - - 0 1").unwrap();
+        let white = PieceCounts::count(&minors_vs_queen, Color::White);
+        let black = PieceCounts::count(&minors_vs_queen, Color::Black);
+        assert_eq!(Evaluator::material_imbalance(&white, &black), 50);
+        assert_eq!(Evaluator::material_imbalance(&black, &white), -50);
+
+        // Rook vs bishop and two pawns without queens favors the rook
+        let exchange = parse_fen("4k3/pppp4/8/8/8/8/PP6/R3K2b w - - 0 1").unwrap();
+        let white = PieceCounts::count(&exchange, Color::White);
+        let black = PieceCounts::count(&exchange, Color::Black);
+        assert_eq!(Evaluator::material_imbalance(&white, &black), EXCHANGE_ENDGAME_BONUS);
+
+        // Equal material has no imbalance
+        let start = Position::new();
+        let counts = PieceCounts::count(&start, Color::White);
+        assert_eq!(Evaluator::material_imbalance(&counts, &counts), 0);
+    }
 }
@@ -0,0 +1,289 @@
+use crate::chess_engine::error::{ChessError, Result};
+use crate::chess_engine::fen::position_to_fen;
+use crate::chess_engine::position::Position;
+use crate::chess_engine::rules::Variant;
+use crate::chess_engine::search::{SearchLimits, SearchResult, MATE_SCORE};
+use crate::chess_engine::types::Move;
+use crate::chess_engine::validation::position_after_move;
+use std::io::{BufRead, BufReader, Write};
+use std::process::{Child, ChildStdin, Command, Stdio};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::time::{Duration, Instant};
+
+/// How long an engine may take to answer `uci` and `isready`
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Grace period on top of `movetime`, and after `stop`, before a silent engine is given up on
+const SEARCH_TIMEOUT_MARGIN: Duration = Duration::from_secs(5);
+
+/// Longest a search without a `movetime` may run before the engine is told to stop,
+/// for engines that treat a large depth as infinite
+const UNTIMED_SEARCH_LIMIT: Duration = Duration::from_secs(60);
+
+/// An external UCI engine (e.g. Stockfish) running as a child process
+pub struct ExternalEngine {
+    name: Option<String>,
+    /// Rules the engine was last told to play by
+    variant: Variant,
+    child: Child,
+    stdin: ChildStdin,
+    lines: Receiver<String>,
+}
+
+impl ExternalEngine {
+    /// Start the engine executable and complete the `uci` / `isready` handshake
+    pub fn spawn(path: &str) -> Result<ExternalEngine> {
+        let mut child = Command::new(path)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()
+            .map_err(|e| io_error(&format!("cannot start {}: {}", path, e)))?;
+
+        let (stdin, stdout) = match (child.stdin.take(), child.stdout.take()) {
+            (Some(stdin), Some(stdout)) => (stdin, stdout),
+            _ => return Err(io_error("engine pipes unavailable")),
+        };
+
+        // Read on a separate thread so an unresponsive engine cannot block forever
+        let (sender, lines) = mpsc::channel();
+        std::thread::spawn(move || {
+            for line in BufReader::new(stdout).lines().map_while(|line| line.ok()) {
+                if sender.send(line).is_err() {
+                    break;
+                }
+            }
+        });
+
+        let mut engine = ExternalEngine {
+            name: None,
+            variant: Variant::Standard,
+            child,
+            stdin,
+            lines,
+        };
+
+        engine.send("uci")?;
+        loop {
+            let line = engine.next_line(HANDSHAKE_TIMEOUT)?;
+            if let Some(name) = line.strip_prefix("id name ") {
+                engine.name = Some(name.trim().to_string());
+            } else if line.trim() == "uciok" {
+                break;
+            }
+        }
+        engine.wait_ready()?;
+
+        Ok(engine)
+    }
+
+    /// The name the engine reported with `id name`
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    /// Search a position, translating the engine's `info` and `bestmove` output
+    /// into the same result the built-in search returns. A search that outlasts its
+    /// time is stopped, and the engine's best move so far is the result.
+    pub fn search(&mut self, position: &Position, limits: SearchLimits) -> Result<SearchResult> {
+        if position.variant != self.variant {
+            self.set_variant(position.variant)?;
+        }
+        self.send("ucinewgame")?;
+        self.wait_ready()?;
+        self.send(&format!("position fen {}", position_to_fen(position)))?;
+
+        let mut go = format!("go depth {}", limits.depth.max(1));
+        if let Some(nodes) = limits.nodes {
+            go.push_str(&format!(" nodes {}", nodes));
+        }
+        if let Some(movetime) = limits.movetime_ms {
+            go.push_str(&format!(" movetime {}", movetime));
+        }
+        self.send(&go)?;
+
+        let mut deadline = Instant::now()
+            + limits
+                .movetime_ms
+                .map_or(UNTIMED_SEARCH_LIMIT, |ms| Duration::from_millis(ms) + SEARCH_TIMEOUT_MARGIN);
+        let mut stopped = false;
+        let mut result = SearchResult {
+            best_move: None,
+            score: 0,
+            depth: 0,
+            nodes: 0,
+            pv: Vec::new(),
+        };
+
+        loop {
+            let line = match self.lines.recv_timeout(deadline.saturating_duration_since(Instant::now())) {
+                Ok(line) => line,
+                Err(RecvTimeoutError::Timeout) if !stopped => {
+                    self.send("stop")?;
+                    stopped = true;
+                    deadline = Instant::now() + SEARCH_TIMEOUT_MARGIN;
+                    continue;
+                }
+                Err(e) => return Err(receive_error(e)),
+            };
+            if let Some(info) = line.strip_prefix("info ") {
+                if let Some(iteration) = parse_info(info, position) {
+                    result = iteration;
+                }
+            } else if let Some(rest) = line.strip_prefix("bestmove") {
+                let best_move = rest
+                    .split_whitespace()
+                    .next()
+                    .and_then(|uci| Move::from_uci(uci, position).ok())
+                    .filter(|mv| !mv.is_null);
+                if best_move.is_some() && result.pv.first() != best_move.as_ref() {
+                    result.pv = best_move.into_iter().collect();
+                }
+                result.best_move = best_move;
+                return Ok(result);
+            }
+        }
+    }
+
+    /// Switch the engine's rules with `UCI_Chess960` and `UCI_Variant`. Engines that
+    /// play only chess ignore the options they do not have.
+    fn set_variant(&mut self, variant: Variant) -> Result<()> {
+        self.send(&format!("setoption name UCI_Chess960 value {}", variant == Variant::Chess960))?;
+        self.send(&format!("setoption name UCI_Variant value {}", variant.uci_name()))?;
+        self.variant = variant;
+        Ok(())
+    }
+
+    fn wait_ready(&mut self) -> Result<()> {
+        self.send("isready")?;
+        while self.next_line(HANDSHAKE_TIMEOUT)?.trim() != "readyok" {}
+        Ok(())
+    }
+
+    fn send(&mut self, command: &str) -> Result<()> {
+        writeln!(self.stdin, "{}", command)
+            .and_then(|_| self.stdin.flush())
+            .map_err(|e| io_error(&format!("engine stopped accepting commands: {}", e)))
+    }
+
+    fn next_line(&self, timeout: Duration) -> Result<String> {
+        self.lines.recv_timeout(timeout).map_err(receive_error)
+    }
+}
+
+impl Drop for ExternalEngine {
+    fn drop(&mut self) {
+        let _ = self.send("quit");
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+fn io_error(reason: &str) -> ChessError {
+    ChessError::Io {
+        reason: reason.to_string(),
+    }
+}
+
+fn receive_error(error: RecvTimeoutError) -> ChessError {
+    match error {
+        RecvTimeoutError::Timeout => io_error("engine did not respond in time"),
+        RecvTimeoutError::Disconnected => io_error("engine exited"),
+    }
+}
+
+/// Translate the fields of an `info` line carrying a score and PV; other lines
+/// (currmove updates, bounds, secondary `multipv` lines) yield `None`
+fn parse_info(info: &str, position: &Position) -> Option<SearchResult> {
+    let tokens: Vec<&str> = info.split_whitespace().collect();
+    let value = |name: &str| -> Option<&str> {
+        let index = tokens.iter().position(|&token| token == name)?;
+        tokens.get(index + 1).copied()
+    };
+
+    if value("multipv").is_some_and(|multipv| multipv != "1")
+        || tokens.contains(&"lowerbound")
+        || tokens.contains(&"upperbound")
+    {
+        return None;
+    }
+
+    let score_at = tokens.iter().position(|&token| token == "score")?;
+    let amount: i32 = tokens.get(score_at + 2)?.parse().ok()?;
+    let score = match *tokens.get(score_at + 1)? {
+        "cp" => amount,
+        "mate" if amount > 0 => MATE_SCORE - (2 * amount - 1),
+        "mate" => -MATE_SCORE + 2 * -amount,
+        _ => return None,
+    };
+
+    // Keep the PV up to the first move that is not legal in the resulting position
+    let pv_at = tokens.iter().position(|&token| token == "pv")?;
+    let mut pv = Vec::new();
+    let mut current = position.clone();
+    for uci in &tokens[pv_at + 1..] {
+        let Ok(mv) = Move::from_uci(uci, &current) else {
+            break;
+        };
+        current = position_after_move(&current, &mv);
+        pv.push(mv);
+    }
+
+    Some(SearchResult {
+        best_move: pv.first().copied(),
+        score,
+        depth: value("depth")?.parse().ok()?,
+        nodes: value("nodes").and_then(|nodes| nodes.parse().ok()).unwrap_or(0),
+        pv,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chess_engine::fen::parse_fen;
+
+    #[test]
+    fn test_parse_info_centipawns() {
+        let position = Position::new();
+        let result = parse_info(
+            "depth 12 seldepth 18 multipv 1 score cp 31 nodes 52000 nps 900000 pv e2e4 e7e5 g1f3",
+            &position,
+        )
+        .unwrap();
+
+        assert_eq!(result.depth, 12);
+        assert_eq!(result.score, 31);
+        assert_eq!(result.nodes, 52000);
+        let pv: Vec<String> = result.pv.iter().map(|mv| mv.to_uci()).collect();
+        assert_eq!(pv, vec!["e2e4", "e7e5", "g1f3"]);
+        assert_eq!(result.best_move, result.pv.first().copied());
+    }
+
+    #[test]
+    fn test_parse_info_mate_scores_match_search() {
+        let position = parse_fen("6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1").unwrap();
+        let result = parse_info("depth 1 score mate 1 pv a1a8", &position).unwrap();
+        assert_eq!(result.score, MATE_SCORE - 1);
+
+        let result = parse_info("depth 4 score mate -2 pv g1f1", &position).unwrap();
+        assert_eq!(result.score, -MATE_SCORE + 4);
+    }
+
+    #[test]
+    fn test_parse_info_skips_partial_lines() {
+        let position = Position::new();
+        assert!(parse_info("depth 10 currmove e2e4 currmovenumber 1", &position).is_none());
+        assert!(parse_info("depth 10 score cp 20 lowerbound pv e2e4", &position).is_none());
+        assert!(parse_info("depth 10 multipv 2 score cp 15 pv d2d4", &position).is_none());
+
+        // An illegal move truncates the PV
+        let result = parse_info("depth 3 score cp 5 pv e2e4 e2e4", &position).unwrap();
+        assert_eq!(result.pv.len(), 1);
+    }
+
+    #[test]
+    fn test_spawn_missing_engine() {
+        assert!(ExternalEngine::spawn("/nonexistent/uci-engine").is_err());
+    }
+}
@@ -1,5 +1,6 @@
 use crate::chess_engine::board::Board;
-use crate::chess_engine::position::{Position, CastlingRights};
+use crate::chess_engine::position::{Pockets, Position, CastlingRights};
+use crate::chess_engine::rules::Variant;
 use crate::chess_engine::types::{Color, Piece, Square};
 use crate::chess_engine::error::{ChessError, Result};
 
@@ -7,24 +8,78 @@ use crate::chess_engine::error::{ChessError, Result};
 pub const STARTING_FEN: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
 
 pub fn parse_fen(fen: &str) -> Result<Position> {
-    let parts: Vec<&str> = fen.split_whitespace().collect();
+    let position = parse_fen_fields(fen)?;
 
-    if parts.len() != 6 {
+    // Validate the position
+    validate_position(&position)?;
+
+    Ok(position)
+}
+
+/// Parses a FEN for a game of `variant`, checked against that variant's rules: a horde
+/// has no king, for example
+pub fn parse_fen_for(fen: &str, variant: Variant) -> Result<Position> {
+    let position = parse_fields(fen, Some(variant))?;
+    validate_position(&position)?;
+    Ok(position)
+}
+
+/// Reads the FEN fields without checking that the position makes sense. The variant
+/// comes from the fields themselves: pieces in hand mean Crazyhouse, a checks field
+/// three-check, and castling rights a standard king could not have Chess960.
+pub(crate) fn parse_fen_fields(fen: &str) -> Result<Position> {
+    parse_fields(fen, None)
+}
+
+/// Reads the FEN fields for `variant`, or the variant they imply when it is `None`.
+/// Beyond the six standard fields this takes the variant extensions in their common
+/// forms:
+/// - pieces in hand as a bracketed suffix ("RNBQKBNR[Qp]") or a ninth rank ("RNBQKBNR/Qp")
+/// - checks given as a trailing "+1+0", or checks remaining as "2+3" after the en passant field
+/// - castling rooks as K/Q (X-FEN, the outermost rook) or by file (Shredder-FEN, "HAha")
+fn parse_fields(fen: &str, variant: Option<Variant>) -> Result<Position> {
+    let mut parts: Vec<&str> = fen.split_whitespace().collect();
+
+    if !(6..=7).contains(&parts.len()) {
         return Err(ChessError::InvalidFen {
             reason: format!("Expected 6 fields, got {}", parts.len()),
         });
     }
 
+    // Checks given follow the fullmove number; checks remaining come before the clocks
+    let checks_given = match parts.len() {
+        7 if parts[4].contains('+') => Some(parse_checks_remaining(parts.remove(4))?),
+        7 => Some(parse_checks_given(parts[6])?),
+        _ => None,
+    };
+
     let mut position = Position::empty();
 
-    // Parse piece placement (field 1)
-    parse_piece_placement(&mut position.board, parts[0])?;
+    // Parse piece placement (field 1), which ends in the pieces in hand when the
+    // variant has pockets
+    let (placement, pockets) = split_pockets(parts[0]);
+    if let Some(pockets) = pockets {
+        position.pockets = parse_pockets(pockets)?;
+    }
+    parse_piece_placement(&mut position.board, &mut position.promoted, placement)?;
+
+    position.variant = variant.unwrap_or(if pockets.is_some() {
+        Variant::Crazyhouse
+    } else if checks_given.is_some() {
+        Variant::ThreeCheck
+    } else if needs_chess960_castling(parts[2], &position.board) {
+        Variant::Chess960
+    } else {
+        Variant::Standard
+    });
+    position.checks_given = checks_given.unwrap_or_default();
 
     // Parse active color (field 2)
     position.side_to_move = parse_active_color(parts[1])?;
 
     // Parse castling rights (field 3)
-    position.castling_rights = parse_castling_rights(parts[2])?;
+    let chess960 = position.variant.rules().chess960_castling();
+    position.castling_rights = parse_castling_rights(parts[2], &position.board, chess960)?;
 
     // Parse en passant target (field 4)
     position.en_passant_target = parse_en_passant(parts[3])?;
@@ -39,17 +94,44 @@ pub fn parse_fen(fen: &str) -> Result<Position> {
         reason: format!("Invalid fullmove number: {}", parts[5]),
     })?;
 
-    // Validate the position
-    validate_position(&position)?;
-
     // Initialize position history
-    let hash = position.compute_zobrist_hash();
-    position.position_history.push(hash);
+    position.record_position();
 
     Ok(position)
 }
 
-fn parse_piece_placement(board: &mut Board, placement: &str) -> Result<()> {
+/// Whether the castling field only makes sense in Chess960: it names a rook's file, or
+/// gives a right to a side whose king is on its back rank away from the e-file, or
+/// whose outermost rook on that side is not in the corner
+fn needs_chess960_castling(castling: &str, board: &Board) -> bool {
+    castling.chars().any(|c| {
+        let color = if c.is_ascii_uppercase() { Color::White } else { Color::Black };
+        let (kingside, corner) = match c.to_ascii_lowercase() {
+            'a'..='h' => return true,
+            'k' => (true, 7),
+            'q' => (false, 0),
+            _ => return false,
+        };
+        match back_rank_king_file(board, color) {
+            Some(4) => outermost_rook_file(board, color, kingside).is_some_and(|file| file != corner),
+            Some(_) => true,
+            None => false,
+        }
+    })
+}
+
+/// Splits the pieces in hand off the piece placement, from brackets or a ninth rank
+fn split_pockets(field: &str) -> (&str, Option<&str>) {
+    if let Some((placement, pockets)) = field.strip_suffix(']').and_then(|rest| rest.split_once('[')) {
+        return (placement, Some(pockets));
+    }
+    match field.rsplit_once('/') {
+        Some((placement, pockets)) if field.matches('/').count() == 8 => (placement, Some(pockets)),
+        _ => (field, None),
+    }
+}
+
+fn parse_piece_placement(board: &mut Board, promoted: &mut u64, placement: &str) -> Result<()> {
     let ranks: Vec<&str> = placement.split('/').collect();
 
     if ranks.len() != 8 {
@@ -61,8 +143,19 @@ fn parse_piece_placement(board: &mut Board, placement: &str) -> Result<()> {
     for (rank_index, rank_str) in ranks.iter().enumerate() {
         let rank = 7 - rank_index; // FEN starts from rank 8
         let mut file = 0;
+        let mut last_piece = None;
 
         for c in rank_str.chars() {
+            // "~" marks the piece before it as promoted from a pawn
+            if c == '~' {
+                let square: Square = last_piece.take().ok_or_else(|| ChessError::InvalidFen {
+                    reason: format!("'~' must follow a piece in rank {}", rank + 1),
+                })?;
+                *promoted |= 1u64 << square.index();
+                continue;
+            }
+            last_piece = None;
+
             if file >= 8 {
                 return Err(ChessError::InvalidFen {
                     reason: format!("Too many squares in rank {}", rank + 1),
@@ -107,6 +200,7 @@ fn parse_piece_placement(board: &mut Board, placement: &str) -> Result<()> {
                 })?;
 
                 board.set(square, Some((piece, color)));
+                last_piece = Some(square);
                 file += 1;
             }
         }
@@ -121,6 +215,40 @@ fn parse_piece_placement(board: &mut Board, placement: &str) -> Result<()> {
     Ok(())
 }
 
+fn parse_pockets(s: &str) -> Result<Pockets> {
+    let mut pockets = Pockets::default();
+    for c in s.chars().filter(|&c| c != '-') {
+        match fen_char_to_piece(c) {
+            Some((piece, color)) if piece != Piece::King => pockets.add(color, piece),
+            _ => {
+                return Err(ChessError::InvalidFen {
+                    reason: format!("Invalid piece in hand: {}", c),
+                })
+            }
+        }
+    }
+    Ok(pockets)
+}
+
+fn parse_checks_given(s: &str) -> Result<[u8; 2]> {
+    let invalid = || ChessError::InvalidFen {
+        reason: format!("Invalid checks given: {}", s),
+    };
+    let (white, black) = s.strip_prefix('+').and_then(|rest| rest.split_once('+')).ok_or_else(invalid)?;
+    let count = |text: &str| text.parse::<u8>().ok().filter(|&count| count <= 3).ok_or_else(invalid);
+    Ok([count(white)?, count(black)?])
+}
+
+/// Checks each side still needs to win ("3+3"), turned into the checks given
+fn parse_checks_remaining(s: &str) -> Result<[u8; 2]> {
+    let invalid = || ChessError::InvalidFen {
+        reason: format!("Invalid checks remaining: {}", s),
+    };
+    let (white, black) = s.split_once('+').ok_or_else(invalid)?;
+    let given = |text: &str| text.parse::<u8>().ok().filter(|&count| count <= 3).map(|count| 3 - count).ok_or_else(invalid);
+    Ok([given(white)?, given(black)?])
+}
+
 fn parse_active_color(s: &str) -> Result<Color> {
     match s {
         "w" => Ok(Color::White),
@@ -131,7 +259,10 @@ fn parse_active_color(s: &str) -> Result<Color> {
     }
 }
 
-fn parse_castling_rights(s: &str) -> Result<CastlingRights> {
+/// Reads the castling field against the board it belongs to. Standard castling always
+/// uses the corner rooks; in Chess960 K and Q mean the outermost rook on that side of
+/// the king, and a file letter names the rook directly.
+fn parse_castling_rights(s: &str, board: &Board, chess960: bool) -> Result<CastlingRights> {
     if s == "-" {
         return Ok(CastlingRights::none());
     }
@@ -139,22 +270,52 @@ fn parse_castling_rights(s: &str) -> Result<CastlingRights> {
     let mut rights = CastlingRights::none();
 
     for c in s.chars() {
-        match c {
-            'K' => rights.white_kingside = true,
-            'Q' => rights.white_queenside = true,
-            'k' => rights.black_kingside = true,
-            'q' => rights.black_queenside = true,
-            _ => {
-                return Err(ChessError::InvalidFen {
-                    reason: format!("Invalid castling character: {}", c),
-                })
+        let color = if c.is_ascii_uppercase() { Color::White } else { Color::Black };
+        let invalid = || ChessError::InvalidFen {
+            reason: format!("Invalid castling character: {}", c),
+        };
+        let (kingside, rook_file) = match c.to_ascii_lowercase() {
+            'k' if chess960 => (true, outermost_rook_file(board, color, true).ok_or_else(invalid)?),
+            'q' if chess960 => (false, outermost_rook_file(board, color, false).ok_or_else(invalid)?),
+            'k' => (true, 7),
+            'q' => (false, 0),
+            file @ 'a'..='h' => {
+                let file = file as u8 - b'a';
+                let king_file = back_rank_king_file(board, color).ok_or_else(invalid)?;
+                (file > king_file, file)
             }
-        }
+            _ => return Err(invalid()),
+        };
+        rights.set(color, kingside, true, rook_file);
     }
 
     Ok(rights)
 }
 
+fn back_rank(color: Color) -> u8 {
+    if color == Color::White { 0 } else { 7 }
+}
+
+/// File of `color`'s king, if it stands on its back rank
+fn back_rank_king_file(board: &Board, color: Color) -> Option<u8> {
+    (0..8).find(|&file| {
+        Square::from_rank_file(back_rank(color), file).and_then(|square| board.get(square)) == Some((Piece::King, color))
+    })
+}
+
+/// File of `color`'s rook furthest from the king on one side of it, along the back rank
+fn outermost_rook_file(board: &Board, color: Color, kingside: bool) -> Option<u8> {
+    let king_file = back_rank_king_file(board, color)?;
+    let is_rook = |file: &u8| {
+        Square::from_rank_file(back_rank(color), *file).and_then(|square| board.get(square)) == Some((Piece::Rook, color))
+    };
+    if kingside {
+        (king_file + 1..8).rev().find(is_rook)
+    } else {
+        (0..king_file).find(is_rook)
+    }
+}
+
 fn parse_en_passant(s: &str) -> Result<Option<Square>> {
     if s == "-" {
         Ok(None)
@@ -164,61 +325,43 @@ fn parse_en_passant(s: &str) -> Result<Option<Square>> {
 }
 
 fn validate_position(position: &Position) -> Result<()> {
-    // Count kings to ensure exactly one per side
-    let mut white_king_count = 0;
-    let mut black_king_count = 0;
-
-    for i in 0..64 {
-        if let Some(square) = Square::new(i) {
-            if let Some((Piece::King, color)) = position.board.get(square) {
-                match color {
-                    Color::White => white_king_count += 1,
-                    Color::Black => black_king_count += 1,
-                }
-            }
-        }
-    }
-
-    if white_king_count == 0 {
-        return Err(ChessError::InvalidFen {
-            reason: "White king not found".to_string(),
-        });
-    }
-
-    if white_king_count > 1 {
-        return Err(ChessError::InvalidFen {
-            reason: format!("Multiple white kings found: {}", white_king_count),
-        });
-    }
-
-    if black_king_count == 0 {
-        return Err(ChessError::InvalidFen {
-            reason: "Black king not found".to_string(),
-        });
-    }
-
-    if black_king_count > 1 {
-        return Err(ChessError::InvalidFen {
-            reason: format!("Multiple black kings found: {}", black_king_count),
-        });
+    match placement_problems(position).into_iter().next() {
+        Some(reason) => Err(ChessError::InvalidFen { reason }),
+        None => Ok(()),
     }
+}
 
-    // Check no pawns on ranks 1 or 8
-    for file in 0..8 {
-        if let Some(square) = Square::from_rank_file(0, file) {
-            if let Some((Piece::Pawn, _)) = position.board.get(square) {
-                return Err(ChessError::InvalidFen {
-                    reason: "Pawn on rank 1".to_string(),
-                });
-            }
+/// Everything that stops a parsed FEN from being usable: king counts, pawns on the
+/// back ranks, the en passant rank and castling rights without their king and rook
+pub(crate) fn placement_problems(position: &Position) -> Vec<String> {
+    let mut problems = Vec::new();
+    let rules = position.variant.rules();
+
+    // Count kings to ensure exactly one per side, or none where the variant has none
+    for (color, name) in [(Color::White, "White"), (Color::Black, "Black")] {
+        let kings = (0..64)
+            .filter_map(Square::new)
+            .filter(|&square| position.board.get(square) == Some((Piece::King, color)))
+            .count();
+        match (kings, rules.has_king(color)) {
+            (0, true) => problems.push(format!("{} king not found", name)),
+            (1, true) | (0, false) => {}
+            (count, true) => problems.push(format!("Multiple {} kings found: {}", name.to_lowercase(), count)),
+            (_, false) => problems.push(format!("{} has no king in this variant", name)),
         }
+    }
 
-        if let Some(square) = Square::from_rank_file(7, file) {
-            if let Some((Piece::Pawn, _)) = position.board.get(square) {
-                return Err(ChessError::InvalidFen {
-                    reason: "Pawn on rank 8".to_string(),
-                });
-            }
+    // Check no pawns on ranks 1 or 8 (White's may stand on rank 1 in Horde)
+    for (rank, name) in [(0, "Pawn on rank 1"), (7, "Pawn on rank 8")] {
+        let pawn_on_rank = (0..8)
+            .filter_map(|file| Square::from_rank_file(rank, file))
+            .any(|square| match position.board.get(square) {
+                Some((Piece::Pawn, Color::White)) => !(rank == 0 && rules.first_rank_pawns()),
+                Some((Piece::Pawn, Color::Black)) => true,
+                _ => false,
+            });
+        if pawn_on_rank {
+            problems.push(name.to_string());
         }
     }
 
@@ -226,82 +369,54 @@ fn validate_position(position: &Position) -> Result<()> {
     if let Some(ep_square) = position.en_passant_target {
         let expected_rank = if position.side_to_move == Color::White { 5 } else { 2 };
         if ep_square.rank() != expected_rank {
-            return Err(ChessError::InvalidFen {
-                reason: format!("Invalid en passant square: {}", ep_square.to_algebraic()),
-            });
-        }
-    }
-
-    // Validate castling rights against board pieces
-    if position.castling_rights.white_kingside {
-        let king_square = Square::from_rank_file(0, 4).unwrap();
-        let rook_square = Square::from_rank_file(0, 7).unwrap();
-
-        if !matches!(position.board.get(king_square), Some((Piece::King, Color::White))) {
-            return Err(ChessError::InvalidFen {
-                reason: "White kingside castling right requires white king on e1".to_string(),
-            });
-        }
-        if !matches!(position.board.get(rook_square), Some((Piece::Rook, Color::White))) {
-            return Err(ChessError::InvalidFen {
-                reason: "White kingside castling right requires white rook on h1".to_string(),
-            });
-        }
-    }
-
-    if position.castling_rights.white_queenside {
-        let king_square = Square::from_rank_file(0, 4).unwrap();
-        let rook_square = Square::from_rank_file(0, 0).unwrap();
-
-        if !matches!(position.board.get(king_square), Some((Piece::King, Color::White))) {
-            return Err(ChessError::InvalidFen {
-                reason: "White queenside castling right requires white king on e1".to_string(),
-            });
-        }
-        if !matches!(position.board.get(rook_square), Some((Piece::Rook, Color::White))) {
-            return Err(ChessError::InvalidFen {
-                reason: "White queenside castling right requires white rook on a1".to_string(),
-            });
+            problems.push(format!("Invalid en passant square: {}", ep_square.to_algebraic()));
         }
     }
 
-    if position.castling_rights.black_kingside {
-        let king_square = Square::from_rank_file(7, 4).unwrap();
-        let rook_square = Square::from_rank_file(7, 7).unwrap();
-
-        if !matches!(position.board.get(king_square), Some((Piece::King, Color::Black))) {
-            return Err(ChessError::InvalidFen {
-                reason: "Black kingside castling right requires black king on e8".to_string(),
-            });
-        }
-        if !matches!(position.board.get(rook_square), Some((Piece::Rook, Color::Black))) {
-            return Err(ChessError::InvalidFen {
-                reason: "Black kingside castling right requires black rook on h8".to_string(),
-            });
-        }
-    }
-
-    if position.castling_rights.black_queenside {
-        let king_square = Square::from_rank_file(7, 4).unwrap();
-        let rook_square = Square::from_rank_file(7, 0).unwrap();
-
-        if !matches!(position.board.get(king_square), Some((Piece::King, Color::Black))) {
-            return Err(ChessError::InvalidFen {
-                reason: "Black queenside castling right requires black king on e8".to_string(),
-            });
+    // Validate castling rights against board pieces: in Chess960 the king may stand
+    // anywhere between its rooks, otherwise it must be on the e-file with rooks in the corners
+    let chess960 = rules.chess960_castling();
+    let castling = [
+        (Color::White, true, "White kingside"),
+        (Color::White, false, "White queenside"),
+        (Color::Black, true, "Black kingside"),
+        (Color::Black, false, "Black queenside"),
+    ];
+    for (color, kingside, name) in castling {
+        let Some(rook) = position.castling_rights.rook_square(color, kingside) else {
+            continue;
+        };
+        let color_name = if color == Color::White { "white" } else { "black" };
+        let king_file = back_rank_king_file(&position.board, color);
+        let king_in_place = match king_file {
+            Some(file) if chess960 => (rook.file() > file) == kingside,
+            Some(file) => file == 4,
+            None => false,
+        };
+        if !king_in_place {
+            let place = if chess960 {
+                format!("on the {} side of its rook", if kingside { "queen" } else { "king" })
+            } else {
+                format!("on {}", if color == Color::White { "e1" } else { "e8" })
+            };
+            problems.push(format!("{} castling right requires {} king {}", name, color_name, place));
         }
-        if !matches!(position.board.get(rook_square), Some((Piece::Rook, Color::Black))) {
-            return Err(ChessError::InvalidFen {
-                reason: "Black queenside castling right requires black rook on a8".to_string(),
-            });
+        if position.board.get(rook) != Some((Piece::Rook, color)) {
+            problems.push(format!(
+                "{} castling right requires {} rook on {}",
+                name,
+                color_name,
+                rook.to_algebraic()
+            ));
         }
     }
 
-    Ok(())
+    problems
 }
 
 pub fn position_to_fen(position: &Position) -> String {
     let mut fen = String::new();
+    let uses_pockets = position.variant.rules().uses_pockets();
 
     // Piece placement
     for rank in (0..8).rev() {
@@ -316,6 +431,9 @@ pub fn position_to_fen(position: &Position) -> String {
                     empty_count = 0;
                 }
                 fen.push(piece_to_fen_char(piece, color));
+                if uses_pockets && position.promoted & (1u64 << square.index()) != 0 {
+                    fen.push('~');
+                }
             } else {
                 empty_count += 1;
             }
@@ -330,6 +448,19 @@ pub fn position_to_fen(position: &Position) -> String {
         }
     }
 
+    // Pieces in hand
+    if uses_pockets {
+        fen.push('[');
+        for color in [Color::White, Color::Black] {
+            for piece in Pockets::PIECES.into_iter().rev() {
+                for _ in 0..position.pockets.count(color, piece) {
+                    fen.push(piece_to_fen_char(piece, color));
+                }
+            }
+        }
+        fen.push(']');
+    }
+
     // Active color
     fen.push(' ');
     fen.push(match position.side_to_move {
@@ -340,17 +471,18 @@ pub fn position_to_fen(position: &Position) -> String {
     // Castling rights
     fen.push(' ');
     let mut castling = String::new();
-    if position.castling_rights.white_kingside {
-        castling.push('K');
-    }
-    if position.castling_rights.white_queenside {
-        castling.push('Q');
-    }
-    if position.castling_rights.black_kingside {
-        castling.push('k');
-    }
-    if position.castling_rights.black_queenside {
-        castling.push('q');
+    let chess960 = position.variant.rules().chess960_castling();
+    for (color, kingside) in [(Color::White, true), (Color::White, false), (Color::Black, true), (Color::Black, false)] {
+        let Some(rook) = position.castling_rights.rook_square(color, kingside) else {
+            continue;
+        };
+        // X-FEN: K and Q unless another rook stands further out, then the rook's file
+        let letter = if !chess960 || outermost_rook_file(&position.board, color, kingside) == Some(rook.file()) {
+            if kingside { 'k' } else { 'q' }
+        } else {
+            (b'a' + rook.file()) as char
+        };
+        castling.push(if color == Color::White { letter.to_ascii_uppercase() } else { letter });
     }
     if castling.is_empty() {
         fen.push('-');
@@ -374,6 +506,11 @@ pub fn position_to_fen(position: &Position) -> String {
     fen.push(' ');
     fen.push_str(&position.fullmove_number.to_string());
 
+    // Checks given
+    if position.variant.rules().counts_checks() {
+        fen.push_str(&format!(" +{}+{}", position.checks_given[0], position.checks_given[1]));
+    }
+
     fen
 }
 
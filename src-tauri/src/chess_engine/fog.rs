@@ -0,0 +1,77 @@
+use crate::chess_engine::move_gen::{generate_pseudo_legal_moves, MAX_MOVES};
+use crate::chess_engine::position::Position;
+use crate::chess_engine::types::Color;
+
+/// Squares `color` can see in fog of war, as a bitboard: those its pieces stand on and
+/// those they can move to. A pawn sees the square ahead only when it could step there,
+/// and a diagonal only when there is something on it to take.
+pub fn visible_squares(position: &Position, color: Color) -> u64 {
+    // Moves are worked out as if it were `color`'s turn; an en passant target only
+    // belongs to the side to move
+    let mut view = position.clone();
+    if view.side_to_move != color {
+        view.side_to_move = color;
+        view.en_passant_target = None;
+    }
+
+    let mut moves = Vec::with_capacity(MAX_MOVES);
+    generate_pseudo_legal_moves(&view, &mut moves);
+    moves
+        .iter()
+        .fold(position.board.occupancy(color), |visible, mv| visible | 1u64 << mv.to.index())
+}
+
+/// The squares `color` can see, indexed like the board (0 = a1, 63 = h8)
+pub fn visibility_mask(position: &Position, color: Color) -> Vec<bool> {
+    let visible = visible_squares(position, color);
+    (0..64).map(|index| visible & (1u64 << index) != 0).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chess_engine::fen::parse_fen_for;
+    use crate::chess_engine::game::ChessGame;
+    use crate::chess_engine::rules::Variant;
+    use crate::chess_engine::types::{GameStatus, Square};
+
+    fn squares(mask: u64) -> Vec<String> {
+        crate::chess_engine::board::squares_in(mask).map(|square| square.to_algebraic()).collect()
+    }
+
+    #[test]
+    fn test_visible_squares() {
+        let game = ChessGame::new_variant(Variant::FogOfWar);
+        let position = game.get_board_state();
+
+        // Own two ranks, and the squares pawns and knights can reach
+        let white = visible_squares(position, Color::White);
+        assert_eq!(white.count_ones(), 32);
+        assert_eq!(white >> 32, 0);
+        assert_eq!(visible_squares(position, Color::Black).count_ones(), 32);
+
+        // Pawns see diagonals only with something to take there; the e-pawn's way is blocked
+        let position = parse_fen_for("4k3/8/8/3pp3/4P3/8/8/4K3 w - - 0 1", Variant::FogOfWar).unwrap();
+        assert_eq!(
+            squares(visible_squares(&position, Color::White)),
+            ["d1", "e1", "f1", "d2", "e2", "f2", "e4", "d5"]
+        );
+        let mask = visibility_mask(&position, Color::White);
+        assert!(mask[Square::from_algebraic("d5").unwrap().index() as usize]);
+        assert!(!mask[Square::from_algebraic("e8").unwrap().index() as usize]);
+    }
+
+    #[test]
+    fn test_dark_chess_legality() {
+        // No check: the king may walk into attack, and taking it wins
+        let mut game = ChessGame::from_position(
+            parse_fen_for("4k3/8/8/8/8/8/3r4/4K3 w - - 0 1", Variant::FogOfWar).unwrap(),
+        );
+        assert_eq!(game.get_status(), GameStatus::InProgress);
+        assert_eq!(game.get_legal_moves().len(), 5);
+        game.make_uci_move("e1e2").unwrap();
+        game.make_uci_move("d2e2").unwrap();
+        assert_eq!(game.get_status(), GameStatus::KingCaptured { winner: Color::Black });
+        assert_eq!(game.result().result, "0-1");
+    }
+}
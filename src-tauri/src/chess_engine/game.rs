@@ -1,316 +1,1185 @@
-use crate::chess_engine::position::Position;
-use crate::chess_engine::validation::{generate_legal_moves, is_legal_move, is_in_check, is_checkmate, is_stalemate};
-use crate::chess_engine::fen::{parse_fen, position_to_fen};
-use crate::chess_engine::types::{Color, Piece, Square, Move, GameStatus};
+use crate::chess_engine::position::{BoardState, Position, Undo};
+use crate::chess_engine::dead_position::is_dead_position;
+use crate::chess_engine::validation::{check_info, generate_legal_moves, is_playable_move, is_in_check, is_checkmate, get_pinned_pieces, check_move, destinations_by_origin, CheckInfo, Destination, IllegalMoveReason, MoveLegality, Pin};
+use crate::chess_engine::fen::{parse_fen, parse_fen_for, position_to_fen};
+use crate::chess_engine::chess960::Chess960Start;
+use crate::chess_engine::epd::EpdRecord;
+use crate::chess_engine::hint::{Hint, PendingHint};
+use crate::chess_engine::history::{CapturedPieces, HistoryView, LastMove, MoveListEntry, MoveNode, MoveRecord, MoveTree, PlyEntry};
+use crate::chess_engine::notation::Notation;
+use crate::chess_engine::odds::{odds_position, Odds};
+use crate::chess_engine::evaluator::WinProbability;
+use crate::chess_engine::search::{mate_in, search, SearchLimits};
+use crate::chess_engine::svg::{render_svg, SvgOptions};
+use crate::chess_engine::tactics::{position_motifs, Motif};
+use crate::chess_engine::openings::{identify_opening, Opening};
+use crate::chess_engine::polyglot::polyglot_key;
+use crate::chess_engine::pgn::{PgnGame, PgnMove};
+use crate::chess_engine::premove::{Premove, PremoveOutcome};
+use crate::chess_engine::rules::{Variant, VariantOptions};
+use crate::chess_engine::types::{Color, Piece, Square, Move, DrawRules, GameResult, GameStatus, Termination};
 use crate::chess_engine::error::{ChessError, Result};
+use once_cell::sync::OnceCell;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::time::{SystemTime, UNIX_EPOCH};
 
-#[derive(Debug, Clone)]
+/// Version of the saved-game JSON document; bump it when `ChessGame`'s fields change
+const SAVE_FORMAT_VERSION: u64 = 3;
+
+/// Version 2 and older documents kept undone moves on the redo stack instead of in the move tree
+const LAST_DETACHED_REDO_VERSION: u64 = 2;
+
+/// Version 1 documents also carried a copy of the position before every move
+const OLDEST_READABLE_FORMAT_VERSION: u64 = 1;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ChessGame {
     position: Position,
     move_history: Vec<Move>,
-    position_snapshots: Vec<Position>,
+    /// What each move of `move_history` changed, for taking it back; rebuilt from the
+    /// moves when a saved game is loaded
+    #[serde(skip)]
+    undo_stack: Vec<Undo>,
     status: GameStatus,
+    start_position: Position,
+    move_tree: MoveTree,
+    /// Child indices in the move tree of the undone moves, most recent last
+    #[serde(default)]
+    redo_stack: Vec<usize>,
+    /// Ply being reviewed in the move list, None when showing the live position
+    #[serde(default)]
+    view_ply: Option<usize>,
+    /// Side whose draw offer is waiting for an answer
+    #[serde(default)]
+    draw_offer: Option<Color>,
+    #[serde(default)]
+    draw_rules: DrawRules,
+    /// Armageddon scoring: a drawn game counts as a win for Black
+    #[serde(default)]
+    armageddon: bool,
+    /// Move queued by the side not to move; not saved with the game
+    #[serde(skip)]
+    premove: Option<Premove>,
+    /// Move a progressive hint has named the piece of, until it is revealed
+    #[serde(skip)]
+    pending_hint: Option<PendingHint>,
+    /// Legal moves in `position`, generated once per position change
+    #[serde(skip)]
+    legal_moves: OnceCell<Vec<Move>>,
 }
 
 impl ChessGame {
     pub fn new() -> Self {
         let position = Position::new();
-        let status = Self::compute_game_status_static(&position);
+        let legal_moves = generate_legal_moves(&position);
+        let status = Self::compute_game_status_static(&position, &legal_moves, DrawRules::default());
 
         ChessGame {
+            start_position: position.clone(),
             position,
             move_history: Vec::new(),
-            position_snapshots: Vec::new(),
+            undo_stack: Vec::new(),
             status,
+            move_tree: MoveTree::new(),
+            redo_stack: Vec::new(),
+            view_ply: None,
+            draw_offer: None,
+            draw_rules: DrawRules::default(),
+            armageddon: false,
+            premove: None,
+            pending_hint: None,
+            legal_moves: OnceCell::with_value(legal_moves),
         }
     }
 
+    /// A new game from the starting position of `variant`
+    pub fn new_variant(variant: Variant) -> Self {
+        let position = parse_fen_for(variant.rules().start_fen(), variant).expect("variant starting FEN is valid");
+        Self::from_position(position)
+    }
+
+    /// A new game of `variant` set up by `options`: from their FEN if given, and for
+    /// Chess960 from the arrangement they pick
+    pub fn new_with_options(variant: Variant, options: &VariantOptions) -> Result<Self> {
+        let position = match (&options.fen, variant) {
+            (Some(fen), _) => parse_fen_for(fen, variant)?,
+            (None, Variant::Chess960) => {
+                let start =
+                    Chess960Start::pick(options.chess960_number, options.double, options.chess960_black_number)?;
+                parse_fen_for(&start.fen, variant)?
+            }
+            (None, _) => return Ok(Self::new_variant(variant)),
+        };
+        Ok(Self::from_position(position))
+    }
+
+    /// A new game with `giver` playing at `odds`
+    pub fn new_with_odds(odds: Odds, giver: Color) -> Result<Self> {
+        Ok(Self::from_position(odds_position(odds, giver)?))
+    }
+
     pub fn from_fen(fen: &str) -> Result<Self> {
         let position = parse_fen(fen)?;
-        let status = Self::compute_game_status_static(&position);
+        Ok(Self::from_position(position))
+    }
+
+    /// Starts a game of `variant` from a FEN checked against that variant's rules
+    pub fn from_fen_for(fen: &str, variant: Variant) -> Result<Self> {
+        Ok(Self::from_position(parse_fen_for(fen, variant)?))
+    }
+
+    /// Starts a game from the position of an EPD record (its clocks come from `hmvc`/`fmvn`)
+    pub fn from_epd(epd: &str) -> Result<Self> {
+        let position = EpdRecord::parse(epd)?.position()?;
+        Ok(Self::from_position(position))
+    }
+
+    pub(crate) fn from_position(position: Position) -> Self {
+        let legal_moves = generate_legal_moves(&position);
+        let status = Self::compute_game_status_static(&position, &legal_moves, DrawRules::default());
 
-        Ok(ChessGame {
+        ChessGame {
+            start_position: position.clone(),
             position,
             move_history: Vec::new(),
-            position_snapshots: Vec::new(),
+            undo_stack: Vec::new(),
             status,
+            move_tree: MoveTree::new(),
+            redo_stack: Vec::new(),
+            view_ply: None,
+            draw_offer: None,
+            draw_rules: DrawRules::default(),
+            armageddon: false,
+            premove: None,
+            pending_hint: None,
+            legal_moves: OnceCell::with_value(legal_moves),
+        }
+    }
+
+    /// A game from the starting position after `moves`, each in UCI ("e2e4") or SAN ("Nf3").
+    /// On failure the error comes with the index of the first move that could not be played.
+    pub fn from_moves<S: AsRef<str>>(moves: &[S]) -> std::result::Result<Self, (usize, ChessError)> {
+        let mut game = Self::new();
+        for (index, text) in moves.iter().enumerate() {
+            let position = &game.position;
+            let mv = Move::from_uci(text.as_ref(), position)
+                .or_else(|_| Move::from_san(text.as_ref(), position))
+                .map_err(|e| (index, e))?;
+            game.make_move(mv).map_err(|e| (index, e))?;
+        }
+        Ok(game)
+    }
+
+    /// Replays a PGN game (starting from its FEN tag, if any, under the rules its
+    /// Variant tag names) so the moves can be undone. Variations are kept in the move
+    /// tree; the game ends up at the end of the main line.
+    pub fn from_pgn(pgn: &str) -> Result<Self> {
+        let pgn = PgnGame::parse(pgn)?;
+        let variant = match pgn.tag("Variant") {
+            Some(name) => Variant::from_pgn_name(name).ok_or_else(|| ChessError::ParseError {
+                input: format!("unsupported variant \"{}\"", name),
+            })?,
+            None => Variant::Standard,
+        };
+        let mut game = match (pgn.tag("FEN"), variant) {
+            // Without a variant to go by, the FEN's own fields tell which one it is
+            (Some(fen), Variant::Standard) => Self::from_fen(fen)?,
+            (Some(fen), _) => Self::from_fen_for(fen, variant)?,
+            (None, _) => Self::new_variant(variant),
+        };
+
+        game.move_tree.comment = pgn.comment;
+        game.replay_pgn_line(&pgn.moves)?;
+        let main_line = game.move_tree.main_line_end();
+        game.goto_variation(&main_line)?;
+
+        Ok(game)
+    }
+
+    /// The game with all its variations as PGN text
+    pub fn to_pgn(&self) -> String {
+        self.pgn_with_tree(&self.move_tree)
+    }
+
+    /// PGN text with moves written in the given notation. Only `Notation::San` is
+    /// standard PGN, but figurine exports can still be read back by `from_pgn`.
+    pub fn to_pgn_in(&self, notation: Notation) -> String {
+        self.pgn_with_tree(&self.move_tree.with_notation(notation))
+    }
+
+    /// PGN where every move of the current line carries an `[%eval]` comment (White's
+    /// point of view, in pawns or `#n` for mates) from a bounded search of the position
+    /// it leads to. With `classify`, moves that throw away winning chances are marked
+    /// `?!`, `?` or `??` unless they already carry a move glyph.
+    pub fn to_annotated_pgn(&self, limits: SearchLimits, classify: bool) -> String {
+        // Side to move's point of view, and whether the game goes on from the position
+        let evals: Vec<(i32, bool, Color)> = self.line_summaries(|_, position| {
+            (search(position, limits).score, !generate_legal_moves(position).is_empty(), position.side_to_move)
+        });
+        let scores: Vec<i32> = evals.iter().map(|&(score, _, _)| score).collect();
+
+        let mut tree = self.move_tree.clone();
+        for ply in 0..self.move_history.len() {
+            let Some(node) = tree.node_mut(&self.move_tree.current[..=ply]) else {
+                continue;
+            };
+
+            let (_, has_moves, side_to_move) = evals[ply + 1];
+            if has_moves {
+                let eval = format!("[%eval {}]", format_eval(scores[ply + 1], side_to_move));
+                node.comment = Some(match node.comment.take() {
+                    Some(comment) => format!("{} {}", eval, comment),
+                    None => eval,
+                });
+            }
+
+            if classify && !node.nags.iter().any(|nag| (1..=6).contains(nag)) {
+                if let Some(nag) = classify_move(scores[ply], -scores[ply + 1]) {
+                    node.nags.insert(0, nag);
+                }
+            }
+        }
+
+        self.pgn_with_tree(&tree)
+    }
+
+    fn pgn_with_tree(&self, tree: &MoveTree) -> String {
+        let outcome = self.result();
+        let result = outcome.result.as_str();
+
+        let mut tags: Vec<(String, String)> = [
+            ("Event", "?"),
+            ("Site", "?"),
+            ("Date", "????.??.??"),
+            ("Round", "?"),
+            ("White", "?"),
+            ("Black", "?"),
+            ("Result", result),
+        ]
+        .iter()
+        .map(|(name, value)| (name.to_string(), value.to_string()))
+        .collect();
+
+        if outcome.termination.is_some() {
+            tags.push(("Termination".to_string(), outcome.termination_tag.clone()));
+        }
+        if self.armageddon {
+            tags.push(("Armageddon".to_string(), "1".to_string()));
+        }
+
+        let variant = self.start_position.variant;
+        if variant != Variant::Standard {
+            tags.push(("Variant".to_string(), variant.pgn_name().to_string()));
+        }
+        let start_fen = position_to_fen(&self.start_position);
+        if start_fen != variant.rules().start_fen() {
+            tags.push(("SetUp".to_string(), "1".to_string()));
+            tags.push(("FEN".to_string(), start_fen));
+        }
+
+        PgnGame::from_tree(tags, tree, result).to_string()
+    }
+
+    /// The current position as an SVG image, with the last move and a king in check
+    /// highlighted when the options ask for it
+    pub fn to_svg(&self, options: &SvgOptions) -> Result<String> {
+        let side = self.position.side_to_move;
+        let check = if is_in_check(&self.position, side) {
+            self.position.board.find_king(side)
+        } else {
+            None
+        };
+        let last_move = self.move_history.last().filter(|mv| !mv.is_null);
+        render_svg(&self.position, options, last_move, check)
+    }
+
+    /// The complete game (positions, history, snapshots, move tree and status) as a
+    /// versioned JSON document, so nothing is lost the way it is with a FEN
+    pub fn to_json(&self) -> Result<String> {
+        let document = serde_json::json!({
+            "version": SAVE_FORMAT_VERSION,
+            "game": self,
+        });
+        serde_json::to_string(&document).map_err(|e| ChessError::ParseError {
+            input: e.to_string(),
         })
     }
 
-    pub fn get_legal_moves(&self) -> Vec<Move> {
-        if !matches!(self.status, GameStatus::InProgress | GameStatus::Check) {
-            return Vec::new();
+    /// Restores a game saved with [`ChessGame::to_json`]
+    pub fn from_json(json: &str) -> Result<Self> {
+        let parse_error = |input: String| ChessError::ParseError { input };
+        let mut document: serde_json::Value =
+            serde_json::from_str(json).map_err(|e| parse_error(e.to_string()))?;
+
+        let version = match document["version"].as_u64() {
+            Some(version) if (OLDEST_READABLE_FORMAT_VERSION..=SAVE_FORMAT_VERSION).contains(&version) => version,
+            Some(version) => return Err(parse_error(format!("unsupported saved game version {}", version))),
+            None => return Err(parse_error("saved game has no version".to_string())),
+        };
+
+        let detached_redo = match document["game"].get_mut("redo_stack") {
+            Some(stack) if version <= LAST_DETACHED_REDO_VERSION => {
+                let undone: Vec<(usize, MoveNode)> =
+                    serde_json::from_value(stack.take()).map_err(|e| parse_error(e.to_string()))?;
+                *stack = serde_json::json!([]);
+                Some(undone)
+            }
+            _ => None,
+        };
+
+        let mut game: ChessGame =
+            serde_json::from_value(document["game"].take()).map_err(|e| parse_error(e.to_string()))?;
+        if let Some(undone) = detached_redo {
+            game.redo_stack = game.move_tree.reattach(undone);
         }
-        generate_legal_moves(&self.position)
+
+        // Replaying the moves from the start has to arrive at the saved position
+        let mut replayed = game.start_position.clone();
+        game.undo_stack = game.move_history.iter().map(|mv| replayed.make_move(mv)).collect();
+        if position_to_fen(&replayed) != position_to_fen(&game.position)
+            || game.move_tree.nodes_along(&game.move_tree.current).map(|nodes| nodes.len())
+                != Some(game.move_history.len())
+        {
+            return Err(parse_error("saved game history is inconsistent".to_string()));
+        }
+
+        Ok(game)
+    }
+
+    pub fn get_legal_moves(&self) -> Vec<Move> {
+        self.playable_moves().to_vec()
     }
 
     pub fn get_legal_moves_for_square(&self, square: Square) -> Vec<Move> {
-        self.get_legal_moves()
-            .into_iter()
+        self.playable_moves()
+            .iter()
             .filter(|mv| mv.from == square)
+            .copied()
             .collect()
     }
 
+    /// Where each piece of the side to move can go, keyed by its square ("e2")
+    pub fn legal_destinations(&self) -> BTreeMap<String, Vec<Destination>> {
+        destinations_by_origin(self.playable_moves())
+    }
+
+    /// The cached legal moves, or none once the game is over
+    fn playable_moves(&self) -> &[Move] {
+        if self.status.is_over() {
+            return &[];
+        }
+        self.legal_moves()
+    }
+
+    /// Legal moves in the current position whether or not the game is over, generated on
+    /// first use after each position change
+    fn legal_moves(&self) -> &[Move] {
+        self.legal_moves.get_or_init(|| generate_legal_moves(&self.position))
+    }
+
     pub fn make_move(&mut self, mv: Move) -> Result<()> {
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).ok();
+        self.make_move_at(mv, now.map(|elapsed| elapsed.as_millis() as u64))
+    }
+
+    /// Plays a move, stamping its node in the move tree with `played_at`
+    fn make_move_at(&mut self, mv: Move, played_at: Option<u64>) -> Result<()> {
         // Check if game is already over
-        if !matches!(self.status, GameStatus::InProgress | GameStatus::Check) {
-            return Err(ChessError::GameOver {
-                status: format!("{:?}", self.status),
-            });
-        }
+        self.ensure_in_progress()?;
 
-        // Verify move is legal
-        if !is_legal_move(&self.position, &mv) {
+        // Verify move is legal; it may come from the frontend rather than the generator
+        if !is_playable_move(&self.position, &mv) {
             return Err(ChessError::InvalidMove {
                 reason: format!("Move {} is not legal", mv.to_uci()),
             });
         }
 
-        // Save current position for undo
-        self.position_snapshots.push(self.position.clone());
+        let san = mv.to_san(&self.position);
+        self.play_move(&mv);
+        self.move_tree.push(mv, san);
+        // Replaying the next undone move keeps the rest of the redo stack; any other move diverges
+        match self.redo_stack.last() {
+            Some(&index) if self.move_tree.current.last() == Some(&index) => {
+                self.redo_stack.pop();
+            }
+            _ => self.redo_stack.clear(),
+        }
+        let current = self.move_tree.current.clone();
+        if let Some(node) = self.move_tree.node_mut(&current) {
+            node.played_at = played_at;
+        }
+
+        Ok(())
+    }
+
+    /// Applies a move already known to be legal to the current line
+    fn play_move(&mut self, mv: &Move) {
+        // Keep only what the move changed for undo
+        let undo = self.position.make_move(mv);
+        self.undo_stack.push(undo);
 
-        // Apply the move (atomic operation for castling)
-        // If this fails (e.g., due to invalid castling state), restore the snapshot
-        if let Err(e) = self.apply_move_to_position(&mv) {
-            // Restore state by removing the snapshot we just added
-            self.position_snapshots.pop();
-            return Err(e);
+        // Add move to history and go back to the live position
+        self.move_history.push(*mv);
+        self.view_ply = None;
+
+        // Moving instead of answering declines the opponent's draw offer
+        let mover = self.position.side_to_move.opposite();
+        if self.draw_offer.is_some_and(|offerer| offerer != mover) {
+            self.draw_offer = None;
         }
 
-        // Add move to history
-        self.move_history.push(mv);
+        self.position_changed();
+    }
 
-        // Update game status
-        self.status = self.compute_game_status();
+    /// Whether a move could be played now, and if not, why
+    pub fn move_legality(&self, from: Square, to: Square, promotion: Option<Piece>) -> MoveLegality {
+        let check = if self.status.is_over() {
+            Err(IllegalMoveReason::GameOver)
+        } else {
+            check_move(&self.position, from, to, promotion)
+        };
+        MoveLegality::from_check(check)
+    }
 
-        Ok(())
+    /// Plays a move given in Standard Algebraic Notation and returns the resolved move
+    pub fn make_san_move(&mut self, san: &str) -> Result<Move> {
+        let mv = Move::from_san(san, &self.position)?;
+        self.make_move(mv)?;
+        Ok(mv)
+    }
+
+    /// Plays a move given as a UCI string ("e2e4", "e7e8q") and returns the resolved move
+    pub fn make_uci_move(&mut self, uci: &str) -> Result<Move> {
+        let mv = Move::from_uci(uci, &self.position)?;
+        self.make_move(mv)?;
+        Ok(mv)
     }
 
+    /// Takes back the last move. A game a player ended by resigning, losing on time or
+    /// agreeing a draw stays ended; taking a move back would quietly erase its result.
     pub fn undo_move(&mut self) -> Result<()> {
-        if self.position_snapshots.is_empty() {
+        if self.decided_by_players() {
+            return Err(ChessError::GameOver {
+                status: format!("{:?}", self.status),
+            });
+        }
+        let Some(undo) = self.undo_stack.pop() else {
             return Err(ChessError::InvalidMove {
                 reason: "No moves to undo".to_string(),
             });
-        }
+        };
 
         // Restore previous position
-        self.position = self.position_snapshots.pop().unwrap();
+        self.position.unmake_move(undo);
 
-        // Remove last move from history
+        // Remove last move from history, keeping it for redo
         self.move_history.pop();
+        self.view_ply = None;
+        self.draw_offer = None;
+        self.premove = None;
+        if let Some(index) = self.move_tree.pop() {
+            self.redo_stack.push(index);
+        }
 
-        // Update game status
-        self.status = self.compute_game_status();
-
+        self.position_changed();
         Ok(())
     }
 
-    pub fn get_status(&self) -> GameStatus {
-        self.status.clone()
+    /// Replays the most recently undone move
+    pub fn redo_move(&mut self) -> Result<Move> {
+        self.ensure_in_progress()?;
+        let index = *self.redo_stack.last().ok_or_else(|| ChessError::InvalidMove {
+            reason: "No moves to redo".to_string(),
+        })?;
+        let mut path = self.move_tree.current.clone();
+        path.push(index);
+        let mv = self.move_tree.node(&path).map(|node| node.mv).ok_or_else(|| ChessError::InvalidMove {
+            reason: "No moves to redo".to_string(),
+        })?;
+
+        self.redo_stack.pop();
+        self.play_move(&mv);
+        self.move_tree.current = path;
+        Ok(mv)
     }
 
-    pub fn to_fen(&self) -> String {
-        position_to_fen(&self.position)
+    /// Plays UCI moves in order, all or none. On failure the game is left as it was and
+    /// the error comes with the index of the first move that could not be played.
+    pub fn make_uci_moves<S: AsRef<str>>(&mut self, moves: &[S]) -> std::result::Result<(), (usize, ChessError)> {
+        let before = self.clone();
+        for (index, uci) in moves.iter().enumerate() {
+            if let Err(e) = self.make_uci_move(uci.as_ref()) {
+                *self = before;
+                return Err((index, e));
+            }
+        }
+        Ok(())
     }
 
-    pub fn get_board_state(&self) -> &Position {
-        &self.position
+    /// Queues a move for `premove.color` to play as soon as it is their turn, replacing
+    /// any earlier premove. Only the side not to move can premove.
+    pub fn set_premove(&mut self, premove: Premove) -> Result<()> {
+        self.ensure_in_progress()?;
+        if premove.color == self.position.side_to_move {
+            return Err(ChessError::InvalidMove {
+                reason: format!("It is already {:?}'s turn", premove.color),
+            });
+        }
+        if !matches!(self.position.board.get(premove.from), Some((_, color)) if color == premove.color) {
+            return Err(ChessError::InvalidMove {
+                reason: format!("No {:?} piece on {}", premove.color, premove.from.to_algebraic()),
+            });
+        }
+        self.premove = Some(premove);
+        Ok(())
     }
 
-    fn compute_game_status(&self) -> GameStatus {
-        Self::compute_game_status_static(&self.position)
+    pub fn premove(&self) -> Option<Premove> {
+        self.premove
     }
 
-    fn compute_game_status_static(position: &Position) -> GameStatus {
-        // Check for checkmate
-        if is_checkmate(position) {
-            return GameStatus::Checkmate {
-                winner: position.side_to_move.opposite(),
-            };
-        }
+    pub fn cancel_premove(&mut self) {
+        self.premove = None;
+    }
 
-        // Check for stalemate
-        if is_stalemate(position) {
-            return GameStatus::Stalemate;
-        }
+    /// Once the side that queued a premove is to move, plays it if it is legal and
+    /// discards it otherwise. Returns `None` while no premove is due.
+    pub fn play_premove(&mut self) -> Option<PremoveOutcome> {
+        let premove = self.premove.filter(|premove| premove.color == self.position.side_to_move)?;
+        self.premove = None;
 
-        // Check for fifty-move rule
-        if position.halfmove_clock >= 100 {
-            return GameStatus::DrawByFiftyMoveRule;
+        if self.status.is_over() {
+            return Some(PremoveOutcome::Discarded { premove, reason: "The game is over".to_string() });
         }
+        let mv = match check_move(&self.position, premove.from, premove.to, premove.promotion) {
+            Ok(mv) => mv,
+            Err(reason) => return Some(PremoveOutcome::Discarded { premove, reason: reason.to_string() }),
+        };
 
-        // Check for insufficient material
-        if position.has_insufficient_material() {
-            return GameStatus::DrawByInsufficientMaterial;
+        let san = mv.to_san(&self.position);
+        match self.make_move(mv) {
+            Ok(()) => Some(PremoveOutcome::Played { premove, san, status: self.status.clone() }),
+            Err(e) => Some(PremoveOutcome::Discarded { premove, reason: e.to_string() }),
         }
+    }
 
-        // Check for threefold repetition
-        if position.is_repetition() {
-            return GameStatus::DrawByRepetition;
-        }
+    /// Suggests a move for the side to move from a search under `limits`. A progressive
+    /// hint names only the piece to move; asking again in the same position reveals the move.
+    pub fn hint(&mut self, limits: SearchLimits, progressive: bool) -> Result<Hint> {
+        self.ensure_in_progress()?;
+        let position_hash = self.position.compute_zobrist_hash();
+        let pending = self.pending_hint.take().filter(|pending| pending.position_hash == position_hash);
 
-        // Check for check
-        if is_in_check(position, position.side_to_move) {
-            return GameStatus::Check;
+        let (mv, score) = match pending {
+            Some(pending) => (pending.mv, pending.score),
+            None => {
+                let result = search(&self.position, limits);
+                let mv = result.best_move.ok_or_else(|| ChessError::InvalidMove {
+                    reason: "No legal moves to hint at".to_string(),
+                })?;
+                if let (true, Some((piece, _))) = (progressive, self.position.board.get(mv.from)) {
+                    self.pending_hint = Some(PendingHint { position_hash, mv, score: result.score });
+                    return Ok(Hint::Piece { square: mv.from, piece });
+                }
+                (mv, result.score)
+            }
+        };
+
+        Ok(Hint::Move { mv, san: mv.to_san(&self.position), score })
+    }
+
+    /// Keeps the progressive hint state of a copy that [`hint`](Self::hint) was called
+    /// on, unless this game has moved on since the copy was taken
+    pub fn keep_hint_from(&mut self, copy: ChessGame) {
+        if copy.position.compute_zobrist_hash() == self.position.compute_zobrist_hash() {
+            self.pending_hint = copy.pending_hint;
         }
+    }
 
-        // Game is still in progress
-        GameStatus::InProgress
+    pub fn get_status(&self) -> GameStatus {
+        self.status.clone()
     }
 
-    fn apply_move_to_position(&mut self, mv: &Move) -> Result<()> {
-        // Handle special moves (castling must be checked first for atomicity)
-        if mv.is_castling {
-            // For castling, check preconditions and move pieces atomically
-            // If this fails, no state mutation occurs
-            self.apply_castling(mv)?;
-        } else if mv.is_en_passant {
-            self.apply_en_passant(mv);
+    /// The result and termination reason, matching the PGN `Result` and `Termination` tags
+    pub fn result(&self) -> GameResult {
+        let result = self.status.result();
+        if self.armageddon {
+            result.armageddon()
         } else {
-            self.apply_normal_move(mv);
+            result
         }
+    }
 
-        // Only update castling rights after successful move application
-        self.position.update_castling_rights_after_move(mv);
+    /// Whether a draw is scored as a win for Black, as in an Armageddon playoff game
+    pub fn armageddon(&self) -> bool {
+        self.armageddon
+    }
 
-        // Set en passant target for next move
-        self.update_en_passant_target(mv);
+    pub fn set_armageddon(&mut self, armageddon: bool) {
+        self.armageddon = armageddon;
+    }
 
-        // Update halfmove clock
-        self.update_halfmove_clock(mv);
+    /// Ends the game with `loser` resigning
+    pub fn resign(&mut self, loser: Color) -> Result<()> {
+        self.ensure_in_progress()?;
+        self.status = GameStatus::Resigned { winner: loser.opposite() };
+        Ok(())
+    }
 
-        // Update fullmove number (increment after Black's move)
-        if self.position.side_to_move == Color::Black {
-            self.position.fullmove_number += 1;
+    /// Ends the game with `loser` running out of time
+    pub fn forfeit_on_time(&mut self, loser: Color) -> Result<()> {
+        self.ensure_in_progress()?;
+        self.status = GameStatus::TimeForfeit { winner: loser.opposite() };
+        Ok(())
+    }
+
+    /// Ends the game in a draw both players agreed to
+    pub fn agree_draw(&mut self) -> Result<()> {
+        self.ensure_in_progress()?;
+        self.draw_offer = None;
+        self.status = GameStatus::DrawByAgreement;
+        Ok(())
+    }
+
+    /// Claims the draw the fifty-move rule or threefold repetition allows under
+    /// [`DrawRules::Claimable`], ending the game
+    pub fn claim_draw(&mut self) -> Result<()> {
+        self.status = match self.status {
+            GameStatus::DrawClaimable { reason: Termination::Repetition } => GameStatus::DrawByRepetition,
+            GameStatus::DrawClaimable { .. } => GameStatus::DrawByFiftyMoveRule,
+            _ => {
+                self.ensure_in_progress()?;
+                return Err(ChessError::InvalidMove {
+                    reason: "No draw can be claimed in this position".to_string(),
+                });
+            }
+        };
+        self.draw_offer = None;
+        Ok(())
+    }
+
+    pub fn draw_rules(&self) -> DrawRules {
+        self.draw_rules
+    }
+
+    /// Switches between automatic and claimable draws, updating the status of a game
+    /// the board position decides
+    pub fn set_draw_rules(&mut self, draw_rules: DrawRules) {
+        self.draw_rules = draw_rules;
+        if !self.decided_by_players() {
+            self.status = self.compute_game_status();
+        }
+    }
+
+    /// Whether a player ended the game rather than the position on the board
+    fn decided_by_players(&self) -> bool {
+        matches!(
+            self.status,
+            GameStatus::Resigned { .. } | GameStatus::TimeForfeit { .. } | GameStatus::DrawByAgreement
+        )
+    }
+
+    /// Offers a draw on behalf of `color`. An offer made while the opponent's own offer
+    /// is pending accepts it.
+    pub fn offer_draw(&mut self, color: Color) -> Result<()> {
+        self.ensure_in_progress()?;
+        match self.draw_offer {
+            Some(offerer) if offerer != color => self.agree_draw(),
+            _ => {
+                self.draw_offer = Some(color);
+                Ok(())
+            }
         }
+    }
 
-        // Switch side to move
-        self.position.side_to_move = self.position.side_to_move.opposite();
+    /// `color` accepts the opponent's pending draw offer, ending the game
+    pub fn accept_draw(&mut self, color: Color) -> Result<()> {
+        self.take_offer_to(color)?;
+        self.agree_draw()
+    }
 
-        // Update position history for repetition detection
-        let hash = self.position.compute_zobrist_hash();
-        self.position.position_history.push(hash);
+    /// `color` turns down the opponent's pending draw offer
+    pub fn decline_draw(&mut self, color: Color) -> Result<()> {
+        self.take_offer_to(color)
+    }
 
+    /// Side whose draw offer is waiting for an answer
+    pub fn draw_offer(&self) -> Option<Color> {
+        self.draw_offer
+    }
+
+    /// Removes the pending offer made to `color`, failing if there is none
+    fn take_offer_to(&mut self, color: Color) -> Result<()> {
+        self.ensure_in_progress()?;
+        if self.draw_offer != Some(color.opposite()) {
+            return Err(ChessError::InvalidMove {
+                reason: format!("{:?} has no draw offer to answer", color),
+            });
+        }
+        self.draw_offer = None;
         Ok(())
     }
 
-    fn apply_normal_move(&mut self, mv: &Move) {
-        let piece = self.position.board.get(mv.from);
+    fn ensure_in_progress(&self) -> Result<()> {
+        if self.status.is_over() {
+            return Err(ChessError::GameOver {
+                status: format!("{:?}", self.status),
+            });
+        }
+        Ok(())
+    }
+
+    /// Whether the side to move is in check and which pieces are giving it
+    pub fn check_info(&self) -> CheckInfo {
+        check_info(&self.position)
+    }
+
+    /// Pieces of `color` pinned to their king, with the pinning piece and the line
+    pub fn pinned_pieces(&self, color: Color) -> Vec<Pin> {
+        get_pinned_pieces(&self.position, color)
+    }
+
+    /// Forks, pins, skewers and double checks in the current position
+    pub fn tactical_motifs(&self) -> Vec<Motif> {
+        position_motifs(&self.position)
+    }
+
+    pub fn to_fen(&self) -> String {
+        position_to_fen(&self.position)
+    }
+
+    /// The current position with the game's status, for the frontend
+    pub fn board_state(&self) -> BoardState {
+        BoardState::new(&self.position, self.status.clone())
+    }
+
+    pub fn get_board_state(&self) -> &Position {
+        &self.position
+    }
+
+    /// Moves played so far in Standard Algebraic Notation
+    pub fn san_history(&self) -> Vec<String> {
+        self.san_history_in(Notation::San)
+    }
+
+    /// Moves played so far in the given notation
+    pub fn san_history_in(&self, notation: Notation) -> Vec<String> {
+        self.move_tree
+            .nodes_along(&self.move_tree.current)
+            .unwrap_or_default()
+            .into_iter()
+            .map(|node| notation.render(&node.san))
+            .collect()
+    }
 
-        // Move the piece
-        self.position.board.set(mv.from, None);
+    /// Moves played so far as numbered rows, with the position after every move
+    pub fn move_list(&self, notation: Notation) -> Vec<MoveListEntry> {
+        let positions = self.line_summaries(|_, position| {
+            (position.side_to_move, position.fullmove_number, position_to_fen(position))
+        });
+        let mut entries: Vec<MoveListEntry> = Vec::new();
+        for (pair, san) in positions.windows(2).zip(self.san_history_in(notation)) {
+            let ((side_to_move, fullmove_number, _), (_, _, fen)) = (&pair[0], &pair[1]);
+            let entry = PlyEntry { san, fen: fen.clone() };
 
-        // Handle promotion
-        if let Some(promotion_piece) = mv.promotion {
-            if let Some((_, color)) = piece {
-                self.position.board.set(mv.to, Some((promotion_piece, color)));
+            if *side_to_move == Color::White || entries.is_empty() {
+                entries.push(MoveListEntry {
+                    number: *fullmove_number,
+                    white: None,
+                    black: None,
+                });
+            }
+            let Some(row) = entries.last_mut() else {
+                continue;
+            };
+            match side_to_move {
+                Color::White => row.white = Some(entry),
+                Color::Black => row.black = Some(entry),
             }
-        } else {
-            self.position.board.set(mv.to, piece);
         }
+        entries
     }
 
-    fn apply_castling(&mut self, mv: &Move) -> Result<()> {
-        let rank = mv.from.rank();
+    /// Every move of the current line with its captured piece, resulting position,
+    /// check flags and the time it was played
+    pub fn move_records(&self) -> Vec<MoveRecord> {
+        let nodes = self.move_tree.nodes_along(&self.move_tree.current).unwrap_or_default();
+        let positions = self.line_summaries(|ply, position| PlySummary {
+            side_to_move: position.side_to_move,
+            captured: self.move_history.get(ply).and_then(|mv| captured_piece(position, mv)),
+            fen: position_to_fen(position),
+            is_check: is_in_check(position, position.side_to_move),
+            is_checkmate: is_checkmate(position),
+        });
 
-        // Precondition checks: verify king and rook presence before any state mutation
-        let king = self.position.board.get(mv.from);
-        let king_color = match king {
-            Some((Piece::King, color)) => color,
-            _ => {
-                return Err(ChessError::InvalidMove {
-                    reason: format!("King not found at castling origin square {}", mv.from.to_algebraic()),
-                });
+        positions
+            .windows(2)
+            .zip(nodes)
+            .enumerate()
+            .map(|(index, (pair, node))| {
+                let (before, after) = (&pair[0], &pair[1]);
+                MoveRecord {
+                    ply: index + 1,
+                    color: before.side_to_move,
+                    uci: node.mv.to_uci(),
+                    san: node.san.clone(),
+                    captured: before.captured,
+                    fen: after.fen.clone(),
+                    is_check: after.is_check,
+                    is_checkmate: after.is_checkmate,
+                    played_at: node.played_at,
+                }
+            })
+            .collect()
+    }
+
+    /// The move that led to the current position with the squares it changed;
+    /// None at the start of the game or after a null move
+    pub fn last_move(&self) -> Option<LastMove> {
+        let mv = *self.move_history.last().filter(|mv| !mv.is_null)?;
+        let node = self.move_tree.node(&self.move_tree.current)?;
+        let rook = mv.castling_rook_squares();
+        let captured_square = mv.en_passant_capture_square();
+
+        let mut highlight_squares = vec![mv.from, mv.to];
+        highlight_squares.extend(rook.map(|(from, to)| [from, to]).into_iter().flatten());
+        highlight_squares.extend(captured_square);
+
+        Some(LastMove {
+            mv,
+            san: node.san.clone(),
+            rook_from: rook.map(|(from, _)| from),
+            rook_to: rook.map(|(_, to)| to),
+            captured_square,
+            highlight_squares,
+        })
+    }
+
+    /// Pieces each side has captured so far on the current line, in the order taken
+    pub fn captured_pieces(&self) -> CapturedPieces {
+        let mut captured = CapturedPieces::default();
+        let captures = self.line_summaries(|ply, position| {
+            let piece = self.move_history.get(ply).and_then(|mv| captured_piece(position, mv));
+            (position.side_to_move, piece)
+        });
+        for (side_to_move, piece) in captures {
+            if let Some(piece) = piece {
+                match side_to_move {
+                    Color::White => captured.white.push(piece),
+                    Color::Black => captured.black.push(piece),
+                }
             }
-        };
+        }
+        captured
+    }
 
-        // Determine rook squares based on castling type
-        let (rook_from, rook_to) = if mv.to.file() > mv.from.file() {
-            // Kingside castling
-            (Square::from_rank_file(rank, 7).unwrap(), Square::from_rank_file(rank, 5).unwrap())
-        } else {
-            // Queenside castling
-            (Square::from_rank_file(rank, 0).unwrap(), Square::from_rank_file(rank, 3).unwrap())
-        };
+    /// The position being reviewed in the move list
+    pub fn history_view(&self) -> HistoryView {
+        let total_plies = self.move_history.len();
+        let ply = self.view_ply.unwrap_or(total_plies).min(total_plies);
+        let position = self.position_at(ply);
+        let san = ply
+            .checked_sub(1)
+            .and_then(|index| self.san_history().into_iter().nth(index));
 
-        // Verify rook is present and correct color before proceeding
-        let rook = self.position.board.get(rook_from);
-        if !matches!(rook, Some((Piece::Rook, c)) if c == king_color) {
+        HistoryView {
+            ply,
+            total_plies,
+            is_live: ply == total_plies,
+            san,
+            position,
+        }
+    }
+
+    /// Reviews the position after `ply` half-moves (0 for the start) without changing
+    /// the game; the latest ply goes back to the live position
+    pub fn view_ply(&mut self, ply: usize) -> Result<HistoryView> {
+        if ply > self.move_history.len() {
             return Err(ChessError::InvalidMove {
-                reason: format!("Rook not found at expected position {} for castling", rook_from.to_algebraic()),
+                reason: format!("No ply {} in a game of {} plies", ply, self.move_history.len()),
             });
         }
+        self.view_ply = (ply < self.move_history.len()).then_some(ply);
+        Ok(self.history_view())
+    }
+
+    /// The most specific known opening the game has reached, recognised by position so
+    /// that transpositions from other move orders are labelled too
+    pub fn opening(&self) -> Option<Opening> {
+        identify_opening(self.line_summaries(|_, position| polyglot_key(position)), &self.move_history)
+    }
+
+    /// `Position::position_key` of every position of the current line, from the start to the live one
+    pub fn position_hashes(&self) -> Vec<u64> {
+        self.line_summaries(|_, position| position.position_key())
+    }
+
+    /// What `summarize` makes of every position of the current line, from the start to
+    /// the live one, given how many moves led to it. The moves are taken back on a single
+    /// copy of the live position, so only the summaries are kept.
+    fn line_summaries<T>(&self, mut summarize: impl FnMut(usize, &Position) -> T) -> Vec<T> {
+        let mut position = self.position.clone();
+        let mut summaries = Vec::with_capacity(self.undo_stack.len() + 1);
+        summaries.push(summarize(self.undo_stack.len(), &position));
+        for (ply, undo) in self.undo_stack.iter().enumerate().rev() {
+            position.unmake_move(*undo);
+            summaries.push(summarize(ply, &position));
+        }
+        summaries.reverse();
+        summaries
+    }
+
+    /// The position after `ply` half-moves of the current line
+    fn position_at(&self, ply: usize) -> Position {
+        let mut position = self.position.clone();
+        for undo in self.undo_stack[ply..].iter().rev() {
+            position.unmake_move(*undo);
+        }
+        position
+    }
+
+    /// Every line played or imported, with the current node
+    pub fn move_tree(&self) -> &MoveTree {
+        &self.move_tree
+    }
 
-        // All preconditions satisfied, now apply the castling move
-        // Move king
-        self.position.board.set(mv.from, None);
-        self.position.board.set(mv.to, king);
+    /// Jumps to any node of the move tree (a path of child indices, empty for the
+    /// starting position) by replaying the moves that lead there
+    pub fn goto_variation(&mut self, path: &[usize]) -> Result<()> {
+        let moves: Vec<Move> = self
+            .move_tree
+            .nodes_along(path)
+            .ok_or_else(|| ChessError::InvalidMove {
+                reason: format!("No variation at path {:?}", path),
+            })?
+            .into_iter()
+            .map(|node| node.mv)
+            .collect();
+
+        self.position = self.start_position.clone();
+        self.move_history.clear();
+        self.undo_stack.clear();
+        self.redo_stack.clear();
+        self.premove = None;
+        self.position_changed();
 
-        // Move rook
-        self.position.board.set(rook_from, None);
-        self.position.board.set(rook_to, rook);
+        for mv in &moves {
+            self.play_move(mv);
+        }
+        self.move_tree.current = path.to_vec();
 
         Ok(())
     }
 
-    fn apply_en_passant(&mut self, mv: &Move) {
-        let pawn = self.position.board.get(mv.from);
+    /// Makes the variation at `path` the main line from its branch point
+    pub fn promote_variation(&mut self, path: &[usize]) -> Result<()> {
+        if !self.move_tree.promote(path) {
+            return Err(ChessError::InvalidMove {
+                reason: format!("No variation at path {:?}", path),
+            });
+        }
+        self.redo_stack.clear();
+        Ok(())
+    }
 
-        // Move pawn
-        self.position.board.set(mv.from, None);
-        self.position.board.set(mv.to, pawn);
+    /// Deletes the move at `path` with every move after it. When the current position
+    /// is inside the deleted line the game goes back to the branch point.
+    pub fn delete_variation(&mut self, path: &[usize]) -> Result<()> {
+        if self.move_tree.remove(path).is_none() {
+            return Err(ChessError::InvalidMove {
+                reason: format!("No variation at path {:?}", path),
+            });
+        }
+        self.redo_stack.clear();
 
-        // Remove captured pawn
-        let captured_pawn_rank = if self.position.side_to_move == Color::White {
-            mv.to.rank() - 1
-        } else {
-            mv.to.rank() + 1
-        };
+        if self.move_tree.current.len() != self.move_history.len() {
+            let current = self.move_tree.current.clone();
+            self.goto_variation(&current)?;
+        }
+        Ok(())
+    }
 
-        if let Some(captured_square) = Square::from_rank_file(captured_pawn_rank, mv.to.file()) {
-            self.position.board.set(captured_square, None);
+    /// Sets the comment and NAGs of a move in the tree (an empty path annotates the
+    /// starting position, which only takes a comment)
+    pub fn annotate_move(&mut self, path: &[usize], comment: Option<String>, nags: Vec<u8>) -> Result<()> {
+        if path.is_empty() {
+            self.move_tree.comment = comment;
+            return Ok(());
         }
+
+        let node = self.move_tree.node_mut(path).ok_or_else(|| ChessError::InvalidMove {
+            reason: format!("No move at path {:?}", path),
+        })?;
+        node.comment = comment;
+        node.nags = nags;
+        Ok(())
     }
 
-    fn update_en_passant_target(&mut self, mv: &Move) {
-        // Check if a pawn moved two squares
-        if let Some((Piece::Pawn, _)) = self.position.board.get(mv.to) {
-            let from_rank = mv.from.rank();
-            let to_rank = mv.to.rank();
+    /// Plays a PGN line from the current node, storing its variations as side lines
+    fn replay_pgn_line(&mut self, line: &[PgnMove]) -> Result<()> {
+        for pgn_move in line {
+            let branch_point = self.move_tree.current.clone();
+            let ply = branch_point.len() + 1;
+            Move::from_san(&pgn_move.san, &self.position)
+                .and_then(|mv| self.make_move_at(mv, None))
+                .map_err(|e| ChessError::InvalidMove {
+                    reason: format!("ply {} ({}): {}", ply, pgn_move.san, e),
+                })?;
+            self.annotate_move(&self.move_tree.current.clone(), pgn_move.comment.clone(), pgn_move.nags.clone())?;
 
-            if from_rank.abs_diff(to_rank) == 2 {
-                // Set en passant target to the square the pawn passed over
-                let ep_rank = (from_rank + to_rank) / 2;
-                self.position.en_passant_target = Square::from_rank_file(ep_rank, mv.from.file());
-                return;
+            if !pgn_move.variations.is_empty() {
+                let continuation = self.move_tree.current.clone();
+                for variation in &pgn_move.variations {
+                    self.goto_variation(&branch_point)?;
+                    self.replay_pgn_line(variation)?;
+                }
+                self.goto_variation(&continuation)?;
             }
         }
 
-        // Clear en passant target
-        self.position.en_passant_target = None;
+        Ok(())
     }
 
-    fn update_halfmove_clock(&mut self, mv: &Move) {
-        // Get the piece that's moving (it's already at the destination)
-        let is_pawn_move = if let Some((piece, _)) = self.position.board.get(mv.to) {
-            piece == Piece::Pawn
-        } else {
-            false
-        };
+    /// Drops the legal moves of the old position and updates the status for the new one
+    fn position_changed(&mut self) {
+        self.legal_moves = OnceCell::new();
+        self.status = self.compute_game_status();
+    }
 
-        // Check if there was a capture (position snapshot has the piece at destination)
-        let is_capture = if let Some(last_pos) = self.position_snapshots.last() {
-            last_pos.board.get(mv.to).is_some()
-        } else {
-            false
-        } || mv.is_en_passant;
+    fn compute_game_status(&self) -> GameStatus {
+        Self::compute_game_status_static(&self.position, self.legal_moves(), self.draw_rules)
+    }
 
-        if is_pawn_move || is_capture {
-            self.position.halfmove_clock = 0;
-        } else {
-            self.position.halfmove_clock += 1;
+    fn compute_game_status_static(position: &Position, legal_moves: &[Move], draw_rules: DrawRules) -> GameStatus {
+        // Endings particular to the variant come first
+        if let Some(status) = position.variant.rules().outcome(position, legal_moves) {
+            return status;
+        }
+
+        // Checkmate or stalemate
+        if legal_moves.is_empty() {
+            return if is_in_check(position, position.side_to_move) {
+                GameStatus::Checkmate {
+                    winner: position.side_to_move.opposite(),
+                }
+            } else {
+                GameStatus::Stalemate
+            };
+        }
+
+        if draw_rules == DrawRules::Claimable {
+            return Self::claimable_draw_status(position);
+        }
+
+        // Check for fifty-move rule
+        if position.halfmove_clock >= 100 {
+            return GameStatus::DrawByFiftyMoveRule;
+        }
+
+        // Check for insufficient material
+        if position.has_insufficient_material() {
+            return GameStatus::DrawByInsufficientMaterial;
+        }
+        if is_dead_position(position) {
+            return GameStatus::DrawByDeadPosition;
         }
+
+        // Check for threefold repetition
+        if position.is_repetition() {
+            return GameStatus::DrawByRepetition;
+        }
+
+        // Check for check
+        if is_in_check(position, position.side_to_move) {
+            return GameStatus::Check;
+        }
+
+        // Game is still in progress
+        GameStatus::InProgress
     }
+
+    /// Status of a position with neither checkmate nor stalemate under FIDE's claim rules
+    fn claimable_draw_status(position: &Position) -> GameStatus {
+        if position.halfmove_clock >= 150 {
+            return GameStatus::DrawBySeventyFiveMoveRule;
+        }
+        if position.has_insufficient_material() {
+            return GameStatus::DrawByInsufficientMaterial;
+        }
+        if is_dead_position(position) {
+            return GameStatus::DrawByDeadPosition;
+        }
+
+        let repetitions = position.repetition_count();
+        if repetitions >= 5 {
+            return GameStatus::DrawByFivefoldRepetition;
+        }
+        if repetitions >= 3 {
+            return GameStatus::DrawClaimable { reason: Termination::Repetition };
+        }
+        if position.halfmove_clock >= 100 {
+            return GameStatus::DrawClaimable { reason: Termination::FiftyMoveRule };
+        }
+
+        if is_in_check(position, position.side_to_move) {
+            return GameStatus::Check;
+        }
+        GameStatus::InProgress
+    }
+}
+
+/// Drops in the mover's expected score (win = 1, draw = 0.5) that make a move an
+/// inaccuracy (`?!`), a mistake (`?`) or a blunder (`??`)
+const INACCURACY_DROP: f64 = 0.10;
+const MISTAKE_DROP: f64 = 0.20;
+const BLUNDER_DROP: f64 = 0.30;
+
+/// NAG for a move given the mover's best score before it and the score it left them with
+fn classify_move(best: i32, played: i32) -> Option<u8> {
+    let expected = |centipawns: i32| WinProbability::from_centipawns(centipawns).expected_score;
+    let drop = expected(best) - expected(played);
+
+    if drop >= BLUNDER_DROP {
+        Some(4)
+    } else if drop >= MISTAKE_DROP {
+        Some(2)
+    } else if drop >= INACCURACY_DROP {
+        Some(6)
+    } else {
+        None
+    }
+}
+
+/// `[%eval]` value: White's advantage in pawns, or `#n` / `#-n` for a forced mate
+fn format_eval(score: i32, side_to_move: Color) -> String {
+    let white_score = if side_to_move == Color::White { score } else { -score };
+    match mate_in(white_score) {
+        Some(moves) => format!("#{}", moves),
+        None => format!("{:.2}", white_score as f64 / 100.0),
+    }
+}
+
+/// The piece a move takes from the position it is played in
+fn captured_piece(before: &Position, mv: &Move) -> Option<Piece> {
+    if mv.is_en_passant {
+        Some(Piece::Pawn)
+    } else if mv.is_null || mv.is_castling {
+        // A Chess960 king "captures" its own rook to castle
+        None
+    } else {
+        before.board.get(mv.to).map(|(piece, _)| piece)
+    }
+}
+
+/// What the move records need from one position of the line
+struct PlySummary {
+    side_to_move: Color,
+    /// Piece taken by the move played from this position
+    captured: Option<Piece>,
+    fen: String,
+    is_check: bool,
+    is_checkmate: bool,
 }
 
 impl Default for ChessGame {
@@ -0,0 +1,40 @@
+use crate::chess_engine::search::SearchLimits;
+use crate::chess_engine::types::{Move, Piece, Square};
+use serde::{Deserialize, Serialize};
+
+/// Deepest search behind a hint; the skill level can cap it further
+const HINT_DEPTH: u8 = 6;
+
+/// A hint never keeps the player waiting longer than this
+const HINT_MOVETIME_MS: u64 = 1_000;
+
+/// Limits for the short search behind a hint
+pub fn hint_limits() -> SearchLimits {
+    SearchLimits {
+        movetime_ms: Some(HINT_MOVETIME_MS),
+        ..SearchLimits::depth(HINT_DEPTH)
+    }
+}
+
+/// A suggested move for the side to move
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum Hint {
+    /// Only the piece to move; asking again in the same position reveals the move
+    Piece { square: Square, piece: Piece },
+    /// The whole move, with its score from the side to move's point of view
+    Move {
+        #[serde(rename = "move")]
+        mv: Move,
+        san: String,
+        score: i32,
+    },
+}
+
+/// The move a progressive hint has pointed at, remembered for the position it was given in
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PendingHint {
+    pub position_hash: u64,
+    pub mv: Move,
+    pub score: i32,
+}
@@ -0,0 +1,372 @@
+use crate::chess_engine::notation::Notation;
+use crate::chess_engine::position::Position;
+use crate::chess_engine::types::{Color, Move, Piece, Square};
+use serde::{Deserialize, Serialize};
+
+/// A played move and the moves that may follow it.
+/// `children[0]` continues the line; later children are alternative variations.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MoveNode {
+    pub mv: Move,
+    pub san: String,
+    #[serde(default)]
+    pub comment: Option<String>,
+    /// Numeric Annotation Glyphs (1 = "!", 2 = "?", ...)
+    #[serde(default)]
+    pub nags: Vec<u8>,
+    /// When the move was played on the board, in milliseconds since the Unix epoch;
+    /// None for moves loaded from PGN
+    #[serde(default)]
+    pub played_at: Option<u64>,
+    pub children: Vec<MoveNode>,
+}
+
+/// One move of a move-list row
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PlyEntry {
+    pub san: String,
+    /// Position after the move
+    pub fen: String,
+}
+
+/// A numbered row of the move list: White's move and Black's reply. A game starting
+/// with Black to move, or ending after White's move, leaves one side empty.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MoveListEntry {
+    pub number: u32,
+    pub white: Option<PlyEntry>,
+    pub black: Option<PlyEntry>,
+}
+
+/// A move of the current line with everything the move list needs to show it
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MoveRecord {
+    /// Half-move number from the start of the game, starting at 1
+    pub ply: usize,
+    pub color: Color,
+    pub uci: String,
+    pub san: String,
+    pub captured: Option<Piece>,
+    /// Position after the move
+    pub fen: String,
+    pub is_check: bool,
+    pub is_checkmate: bool,
+    pub played_at: Option<u64>,
+}
+
+/// The last move played and every square it changed
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LastMove {
+    pub mv: Move,
+    pub san: String,
+    /// Rook origin and destination when the move castles
+    pub rook_from: Option<Square>,
+    pub rook_to: Option<Square>,
+    /// Square of the pawn taken en passant
+    pub captured_square: Option<Square>,
+    /// From and to squares followed by any rook and en passant squares
+    pub highlight_squares: Vec<Square>,
+}
+
+/// An earlier position of the current line being reviewed, while the game itself
+/// stays at its latest move
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HistoryView {
+    /// Half-moves played to reach the viewed position, 0 for the starting position
+    pub ply: usize,
+    pub total_plies: usize,
+    /// Whether the viewed position is the live one
+    pub is_live: bool,
+    /// The move that led to the viewed position
+    pub san: Option<String>,
+    pub position: Position,
+}
+
+/// Pieces taken so far, by the side that took them
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CapturedPieces {
+    pub white: Vec<Piece>,
+    pub black: Vec<Piece>,
+}
+
+/// Tree of every line played or imported from the starting position, with a cursor.
+///
+/// Nodes are addressed by paths of child indices from the start position, so
+/// `[0, 0, 1]` is the first variation to the third move of the main line.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MoveTree {
+    /// Comment on the starting position
+    #[serde(default)]
+    pub comment: Option<String>,
+    pub moves: Vec<MoveNode>,
+    pub current: Vec<usize>,
+}
+
+impl MoveTree {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The node at a path, if the path exists
+    pub fn node(&self, path: &[usize]) -> Option<&MoveNode> {
+        let (&last, parent) = path.split_last()?;
+        self.children(parent)?.get(last)
+    }
+
+    /// Mutable access to the node at a path, e.g. to annotate it
+    pub fn node_mut(&mut self, path: &[usize]) -> Option<&mut MoveNode> {
+        let (&first, rest) = path.split_first()?;
+        let mut node = self.moves.get_mut(first)?;
+        for &index in rest {
+            node = node.children.get_mut(index)?;
+        }
+        Some(node)
+    }
+
+    /// Nodes from the start position along a path, if the path exists
+    pub fn nodes_along(&self, path: &[usize]) -> Option<Vec<&MoveNode>> {
+        let mut nodes = Vec::with_capacity(path.len());
+        let mut siblings = &self.moves;
+        for &index in path {
+            let node = siblings.get(index)?;
+            nodes.push(node);
+            siblings = &node.children;
+        }
+        Some(nodes)
+    }
+
+    /// Path of the last move of the main line
+    pub fn main_line_end(&self) -> Vec<usize> {
+        let mut path = Vec::new();
+        let mut siblings = &self.moves;
+        while let Some(node) = siblings.first() {
+            path.push(0);
+            siblings = &node.children;
+        }
+        path
+    }
+
+    /// Play a move after the current node, reusing an identical existing
+    /// continuation with its annotations or otherwise appending it as a new variation
+    pub fn push(&mut self, mv: Move, san: String) {
+        let Some(siblings) = self.children_mut_at_current() else {
+            return;
+        };
+
+        let index = match siblings.iter().position(|node| node.mv == mv) {
+            Some(index) => index,
+            None => {
+                siblings.push(MoveNode {
+                    mv,
+                    san,
+                    comment: None,
+                    nags: Vec::new(),
+                    played_at: None,
+                    children: Vec::new(),
+                });
+                siblings.len() - 1
+            }
+        };
+        self.current.push(index);
+    }
+
+    /// Step back from the current node, keeping it and everything after it in the tree;
+    /// returns the node's position among its siblings
+    pub fn pop(&mut self) -> Option<usize> {
+        self.current.pop()
+    }
+
+    /// Puts nodes that version 2 saved games kept off the tree, most recently undone
+    /// last, back below the current node. Returns their child indices in the same order.
+    pub fn reattach(&mut self, undone: Vec<(usize, MoveNode)>) -> Vec<usize> {
+        let mut path = self.current.clone();
+        let mut indices = Vec::new();
+        for (index, node) in undone.into_iter().rev() {
+            let Some(siblings) = children_along(&mut self.moves, &path) else {
+                break;
+            };
+            let index = index.min(siblings.len());
+            siblings.insert(index, node);
+            path.push(index);
+            indices.push(index);
+        }
+        indices.reverse();
+        indices
+    }
+
+    /// Makes the variation at `path` the main continuation from its branch point,
+    /// keeping the cursor on the same moves
+    pub fn promote(&mut self, path: &[usize]) -> bool {
+        let Some((&index, parent)) = path.split_last() else {
+            return false;
+        };
+        let Some(siblings) = children_along(&mut self.moves, parent).filter(|siblings| index < siblings.len()) else {
+            return false;
+        };
+        let node = siblings.remove(index);
+        siblings.insert(0, node);
+
+        if let Some(step) = self.cursor_step_below(parent) {
+            *step = match (*step).cmp(&index) {
+                std::cmp::Ordering::Equal => 0,
+                std::cmp::Ordering::Less => *step + 1,
+                std::cmp::Ordering::Greater => *step,
+            };
+        }
+        true
+    }
+
+    /// Deletes the node at `path` and everything after it. A cursor inside the deleted
+    /// line moves back to the branch point.
+    pub fn remove(&mut self, path: &[usize]) -> Option<MoveNode> {
+        let (&index, parent) = path.split_last()?;
+        let siblings = children_along(&mut self.moves, parent).filter(|siblings| index < siblings.len())?;
+        let node = siblings.remove(index);
+
+        if let Some(step) = self.cursor_step_below(parent) {
+            if *step == index {
+                self.current.truncate(parent.len());
+            } else if *step > index {
+                *step -= 1;
+            }
+        }
+        Some(node)
+    }
+
+    /// Copy of the tree with every move written in the given notation
+    pub fn with_notation(&self, notation: Notation) -> MoveTree {
+        fn render(nodes: &mut [MoveNode], notation: Notation) {
+            for node in nodes {
+                node.san = notation.render(&node.san);
+                render(&mut node.children, notation);
+            }
+        }
+
+        let mut tree = self.clone();
+        render(&mut tree.moves, notation);
+        tree
+    }
+
+    /// The cursor's child index just below `parent`, if the cursor passes through it
+    fn cursor_step_below(&mut self, parent: &[usize]) -> Option<&mut usize> {
+        if self.current.starts_with(parent) {
+            self.current.get_mut(parent.len())
+        } else {
+            None
+        }
+    }
+
+    fn children(&self, path: &[usize]) -> Option<&Vec<MoveNode>> {
+        let mut siblings = &self.moves;
+        for &index in path {
+            siblings = &siblings.get(index)?.children;
+        }
+        Some(siblings)
+    }
+
+    fn children_mut_at_current(&mut self) -> Option<&mut Vec<MoveNode>> {
+        children_along(&mut self.moves, &self.current)
+    }
+}
+
+/// The children of the node at `path` below a list of top-level moves
+fn children_along<'a>(moves: &'a mut Vec<MoveNode>, path: &[usize]) -> Option<&'a mut Vec<MoveNode>> {
+    let mut siblings = moves;
+    for &index in path {
+        siblings = &mut siblings.get_mut(index)?.children;
+    }
+    Some(siblings)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chess_engine::types::Square;
+
+    fn mv(from: &str, to: &str) -> Move {
+        Move::new(Square::from_algebraic(from).unwrap(), Square::from_algebraic(to).unwrap())
+    }
+
+    #[test]
+    fn test_variations_branch_from_current_node() {
+        let mut tree = MoveTree::new();
+        tree.push(mv("e2", "e4"), "e4".to_string());
+        tree.push(mv("e7", "e5"), "e5".to_string());
+
+        // Go back to after 1. e4 and try 1... c5 instead
+        tree.current = vec![0];
+        tree.push(mv("c7", "c5"), "c5".to_string());
+        assert_eq!(tree.current, vec![0, 1]);
+
+        // Replaying the main-line move reuses the existing node
+        tree.current = vec![0];
+        tree.push(mv("e7", "e5"), "e5".to_string());
+        assert_eq!(tree.current, vec![0, 0]);
+
+        assert_eq!(tree.main_line_end(), vec![0, 0]);
+        assert_eq!(tree.node(&[0, 1]).unwrap().san, "c5");
+        let line: Vec<Move> = tree.nodes_along(&[0, 1]).unwrap().iter().map(|node| node.mv).collect();
+        assert_eq!(line, vec![mv("e2", "e4"), mv("c7", "c5")]);
+        assert!(tree.nodes_along(&[0, 2]).is_none());
+    }
+
+    #[test]
+    fn test_pop_keeps_current_node() {
+        let mut tree = MoveTree::new();
+        tree.push(mv("e2", "e4"), "e4".to_string());
+        tree.push(mv("e7", "e5"), "e5".to_string());
+        tree.current = vec![0];
+        tree.push(mv("c7", "c5"), "c5".to_string());
+
+        assert_eq!(tree.pop(), Some(1));
+        assert_eq!(tree.current, vec![0]);
+        assert_eq!(tree.node(&[0, 0]).unwrap().san, "e5");
+        assert_eq!(tree.node(&[0, 1]).unwrap().san, "c5");
+
+        // A different move after stepping back becomes another variation
+        tree.push(mv("e7", "e6"), "e6".to_string());
+        assert_eq!(tree.current, vec![0, 2]);
+        assert_eq!(tree.main_line_end(), vec![0, 0]);
+    }
+
+    #[test]
+    fn test_reattach_undone_nodes() {
+        let mut tree = MoveTree::new();
+        tree.push(mv("e2", "e4"), "e4".to_string());
+        let node = |from: &str, to: &str, san: &str| MoveNode {
+            mv: mv(from, to),
+            san: san.to_string(),
+            comment: None,
+            nags: Vec::new(),
+            played_at: None,
+            children: Vec::new(),
+        };
+
+        // Undone 2. Nf3, then 1... e5, as version 2 saves stacked them
+        let indices = tree.reattach(vec![(0, node("g1", "f3", "Nf3")), (0, node("e7", "e5", "e5"))]);
+        assert_eq!(indices, vec![0, 0]);
+        assert_eq!(tree.current, vec![0]);
+        assert_eq!(tree.node(&[0, 0, 0]).unwrap().san, "Nf3");
+    }
+
+    #[test]
+    fn test_promote_and_remove_variations() {
+        let mut tree = MoveTree::new();
+        tree.push(mv("e2", "e4"), "e4".to_string());
+        tree.push(mv("e7", "e5"), "e5".to_string());
+        tree.current = vec![0];
+        tree.push(mv("c7", "c5"), "c5".to_string());
+        tree.push(mv("g1", "f3"), "Nf3".to_string());
+
+        assert!(tree.promote(&[0, 1]));
+        assert_eq!(tree.node(&[0, 0]).unwrap().san, "c5");
+        assert_eq!(tree.node(&[0, 1]).unwrap().san, "e5");
+        assert_eq!(tree.current, vec![0, 0, 0]);
+        assert!(!tree.promote(&[0, 2]));
+
+        assert_eq!(tree.remove(&[0, 0]).unwrap().san, "c5");
+        assert_eq!(tree.current, vec![0]);
+        assert_eq!(tree.node(&[0, 0]).unwrap().san, "e5");
+        assert!(tree.remove(&[]).is_none());
+    }
+}
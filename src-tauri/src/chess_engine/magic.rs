@@ -0,0 +1,256 @@
+use crate::chess_engine::types::Square;
+use once_cell::sync::Lazy;
+
+const BISHOP_DIRECTIONS: [(i8, i8); 4] = [(-1, -1), (-1, 1), (1, -1), (1, 1)];
+const ROOK_DIRECTIONS: [(i8, i8); 4] = [(-1, 0), (1, 0), (0, -1), (0, 1)];
+
+/// Fixed so every run finds the same magics
+const MAGIC_SEED: u64 = 0x9E37_79B9_7F4A_7C15;
+
+static BISHOP_TABLE: Lazy<SliderTable> = Lazy::new(|| SliderTable::new(&BISHOP_DIRECTIONS));
+static ROOK_TABLE: Lazy<SliderTable> = Lazy::new(|| SliderTable::new(&ROOK_DIRECTIONS));
+static LINES: Lazy<LineTables> = Lazy::new(LineTables::new);
+
+/// Squares a bishop on `square` attacks, up to and including the first piece on each diagonal
+pub fn bishop_attacks(square: Square, occupied: u64) -> u64 {
+    BISHOP_TABLE.attacks(square.index() as usize, occupied)
+}
+
+/// Squares a rook on `square` attacks, up to and including the first piece on each line
+pub fn rook_attacks(square: Square, occupied: u64) -> u64 {
+    ROOK_TABLE.attacks(square.index() as usize, occupied)
+}
+
+pub fn queen_attacks(square: Square, occupied: u64) -> u64 {
+    bishop_attacks(square, occupied) | rook_attacks(square, occupied)
+}
+
+/// Squares strictly between two squares on a shared rank, file or diagonal; empty for
+/// squares that share none, and for neighbours
+pub fn between(a: Square, b: Square) -> u64 {
+    LINES.between[a.index() as usize][b.index() as usize]
+}
+
+/// The whole rank, file or diagonal through both squares, edge to edge; empty for
+/// squares that share none
+pub fn line(a: Square, b: Square) -> u64 {
+    LINES.line[a.index() as usize][b.index() as usize]
+}
+
+/// [`between`] and [`line`] for every pair of squares, read off the slider tables
+struct LineTables {
+    between: Vec<[u64; 64]>,
+    line: Vec<[u64; 64]>,
+}
+
+impl LineTables {
+    fn new() -> Self {
+        let mut tables = LineTables {
+            between: vec![[0; 64]; 64],
+            line: vec![[0; 64]; 64],
+        };
+
+        for a in 0..64u8 {
+            for b in 0..64u8 {
+                let (square_a, square_b) = (Square::new(a).unwrap(), Square::new(b).unwrap());
+                let (a_bit, b_bit) = (1u64 << a, 1u64 << b);
+                for attacks in [rook_attacks, bishop_attacks] {
+                    if a != b && attacks(square_a, 0) & b_bit != 0 {
+                        tables.between[a as usize][b as usize] =
+                            attacks(square_a, b_bit) & attacks(square_b, a_bit);
+                        tables.line[a as usize][b as usize] =
+                            (attacks(square_a, 0) & attacks(square_b, 0)) | a_bit | b_bit;
+                    }
+                }
+            }
+        }
+        tables
+    }
+}
+
+struct Magic {
+    /// Squares whose occupancy changes the attacks
+    mask: u64,
+    magic: u64,
+    shift: u32,
+    /// Start of this square's slice of the shared attack table
+    offset: usize,
+}
+
+/// Attack table for one kind of slider. For each square, the occupied squares that can
+/// block it (its rays minus the board edge) are multiplied by a magic number whose top
+/// bits index the attacks for that exact set of blockers. The magics are found at first
+/// use by trying random sparse numbers until one maps every blocker set without a
+/// harmful collision.
+struct SliderTable {
+    magics: Vec<Magic>,
+    attacks: Vec<u64>,
+}
+
+impl SliderTable {
+    fn new(directions: &[(i8, i8)]) -> Self {
+        let mut random = XorShift(MAGIC_SEED);
+        let mut magics = Vec::with_capacity(64);
+        let mut attacks = Vec::new();
+
+        for square in 0..64 {
+            let mask = blocker_mask(square, directions);
+            let bits = mask.count_ones();
+            let blocker_sets = subsets(mask);
+            let expected: Vec<u64> = blocker_sets
+                .iter()
+                .map(|&blockers| ray_attacks(square, blockers, directions))
+                .collect();
+
+            let shift = 64 - bits;
+            let table = loop {
+                let magic = random.sparse();
+                // Magics that leave the top bits nearly empty rarely work; skip them early
+                if (mask.wrapping_mul(magic) >> 56).count_ones() < 6 {
+                    continue;
+                }
+                if let Some(table) = try_magic(magic, shift, &blocker_sets, &expected) {
+                    magics.push(Magic { mask, magic, shift, offset: attacks.len() });
+                    break table;
+                }
+            };
+            attacks.extend(table);
+        }
+
+        SliderTable { magics, attacks }
+    }
+
+    fn attacks(&self, square: usize, occupied: u64) -> u64 {
+        let magic = &self.magics[square];
+        let index = ((occupied & magic.mask).wrapping_mul(magic.magic) >> magic.shift) as usize;
+        self.attacks[magic.offset + index]
+    }
+}
+
+/// The square's table for `magic`, or `None` if two blocker sets with different attacks
+/// share an index. A slider always attacks at least one square, so 0 marks a free slot.
+fn try_magic(magic: u64, shift: u32, blocker_sets: &[u64], expected: &[u64]) -> Option<Vec<u64>> {
+    let mut table = vec![0u64; 1 << (64 - shift)];
+    for (&blockers, &attacks) in blocker_sets.iter().zip(expected) {
+        let slot = &mut table[(blockers.wrapping_mul(magic) >> shift) as usize];
+        if *slot == 0 {
+            *slot = attacks;
+        } else if *slot != attacks {
+            return None;
+        }
+    }
+    Some(table)
+}
+
+/// Every subset of `mask`, the empty set included
+fn subsets(mask: u64) -> Vec<u64> {
+    let mut subsets = Vec::with_capacity(1 << mask.count_ones());
+    let mut subset = 0u64;
+    loop {
+        subsets.push(subset);
+        subset = subset.wrapping_sub(mask) & mask;
+        if subset == 0 {
+            return subsets;
+        }
+    }
+}
+
+/// Squares along the rays that could block them: a piece on the last square of a ray
+/// blocks nothing further, so the edges are left out
+fn blocker_mask(square: usize, directions: &[(i8, i8)]) -> u64 {
+    let mut mask = 0u64;
+    for &(rank_dir, file_dir) in directions {
+        let mut rank = (square / 8) as i8 + rank_dir;
+        let mut file = (square % 8) as i8 + file_dir;
+        while on_board(rank + rank_dir, file + file_dir) {
+            mask |= 1u64 << (rank * 8 + file);
+            rank += rank_dir;
+            file += file_dir;
+        }
+    }
+    mask
+}
+
+/// Attacks found by walking each ray, used to fill the tables
+fn ray_attacks(square: usize, occupied: u64, directions: &[(i8, i8)]) -> u64 {
+    let mut attacks = 0u64;
+    for &(rank_dir, file_dir) in directions {
+        let mut rank = (square / 8) as i8 + rank_dir;
+        let mut file = (square % 8) as i8 + file_dir;
+        while on_board(rank, file) {
+            let bit = 1u64 << (rank * 8 + file);
+            attacks |= bit;
+            if occupied & bit != 0 {
+                break;
+            }
+            rank += rank_dir;
+            file += file_dir;
+        }
+    }
+    attacks
+}
+
+fn on_board(rank: i8, file: i8) -> bool {
+    (0..8).contains(&rank) && (0..8).contains(&file)
+}
+
+pub(crate) struct XorShift(pub(crate) u64);
+
+impl XorShift {
+    pub(crate) fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    /// A number with few bits set, which makes a good magic candidate
+    fn sparse(&mut self) -> u64 {
+        self.next() & self.next() & self.next()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bit(square: &str) -> u64 {
+        1u64 << Square::from_algebraic(square).unwrap().index()
+    }
+
+    #[test]
+    fn test_lookups_match_ray_walks() {
+        let mut random = XorShift(1);
+        for _ in 0..200 {
+            let occupied = random.next() & random.next();
+            for index in 0..64u8 {
+                let square = Square::new(index).unwrap();
+                let index = index as usize;
+                assert_eq!(bishop_attacks(square, occupied), ray_attacks(index, occupied, &BISHOP_DIRECTIONS));
+                assert_eq!(rook_attacks(square, occupied), ray_attacks(index, occupied, &ROOK_DIRECTIONS));
+            }
+        }
+    }
+
+    #[test]
+    fn test_between_and_line() {
+        let square = |name| Square::from_algebraic(name).unwrap();
+        assert_eq!(between(square("a1"), square("a4")), bit("a2") | bit("a3"));
+        assert_eq!(between(square("h8"), square("e5")), bit("g7") | bit("f6"));
+        assert_eq!(between(square("a1"), square("b3")), 0);
+        assert_eq!(between(square("d4"), square("e5")), 0);
+
+        assert_eq!(line(square("c3"), square("e5")), ray_attacks(0, 0, &[(1, 1)]) | bit("a1"));
+        assert_eq!(line(square("b2"), square("b7")).count_ones(), 8);
+        assert_eq!(line(square("a1"), square("b3")), 0);
+    }
+
+    #[test]
+    fn test_rook_stops_at_blockers() {
+        let a1 = Square::from_algebraic("a1").unwrap();
+        let attacks = rook_attacks(a1, bit("a3") | bit("c1") | bit("h8"));
+        assert_eq!(attacks, bit("a2") | bit("a3") | bit("b1") | bit("c1"));
+        assert_eq!(rook_attacks(a1, 0).count_ones(), 14);
+        assert_eq!(bishop_attacks(Square::from_algebraic("d4").unwrap(), 0).count_ones(), 13);
+    }
+}
@@ -1,19 +1,72 @@
 mod types;
 mod board;
+pub mod capablanca;
+mod chess960;
 mod position;
 mod move_gen;
 mod validation;
 mod fen;
+mod fog;
 mod game;
+mod dead_position;
+mod diff;
+mod epd;
+mod hint;
+mod history;
+mod magic;
+mod notation;
+mod odds;
+mod openings;
+mod perft;
+mod pgn;
+mod pgn_lint;
+mod polyglot;
+mod premove;
+mod puzzle;
+mod rules;
+mod search;
+mod setup;
+mod svg;
+mod tactics;
+mod transposition;
+mod external_engine;
 mod error;
+mod endgame;
+mod engine_options;
+mod eval_cache;
 pub mod analysis;
 pub mod evaluator;
+pub mod uci;
 
 #[cfg(test)]
 mod tests;
 
 pub use game::ChessGame;
-pub use position::Position;
-pub use types::{Piece, Square, Move, GameStatus, Color};
-pub use analysis::{MoveAnalysis, analyze_all_moves};
-pub use evaluator::Evaluator;
+pub use error::ChessError;
+pub use hint::{hint_limits, Hint};
+pub use history::{CapturedPieces, HistoryView, LastMove, MoveListEntry, MoveRecord, MoveTree};
+pub use notation::Notation;
+pub use chess960::Chess960Start;
+pub use fog::visibility_mask;
+pub use odds::Odds;
+pub use openings::Opening;
+pub use perft::{perft, perft_divide, perft_hashed, PerftDivide, MAX_PERFT_DEPTH, PERFT_HASH_MEGABYTES};
+pub use pgn::{PgnReader, PgnSummary};
+pub use pgn_lint::{lint_pgn, PgnProblem};
+pub use epd::{EpdAnalysis, EpdRecord};
+pub use polyglot::{BookMove, probe_book};
+pub use premove::{Premove, PremoveOutcome};
+pub use puzzle::{parse_puzzles, Puzzle, PuzzleFilter, PuzzleOutcome, PuzzleRushStatus, PuzzleSession, PuzzleStats};
+pub use rules::{Variant, VariantOptions};
+pub use search::{clear_transposition_table, search, SearchLimits, SearchResult, MAX_DEPTH};
+pub use engine_options::{EngineInfo, EngineOption, EngineOptions};
+pub use setup::validate_setup;
+pub use svg::SvgOptions;
+pub use tactics::Motif;
+pub use external_engine::ExternalEngine;
+pub use position::{BoardState, Position};
+pub use validation::{CheckInfo, Destination, MoveLegality, Pin};
+pub use diff::{diff_positions, PositionDiff};
+pub use types::{Piece, Square, Move, DrawRules, GameResult, GameStatus, Color};
+pub use analysis::{MaterialBalance, MoveAnalysis, analyze_all_moves, material_balance};
+pub use evaluator::{Evaluator, EvalPerspective, EvalPersonality, PieceSquareTables, WinProbability};
@@ -1,30 +1,60 @@
-use crate::chess_engine::board::{Board, is_valid_square};
+use crate::chess_engine::board::{squares_in, Board, is_valid_square};
+use crate::chess_engine::magic::{bishop_attacks, queen_attacks, rook_attacks};
 use crate::chess_engine::position::Position;
 use crate::chess_engine::types::{Color, Piece, Square, Move};
 
-pub fn generate_pseudo_legal_moves(position: &Position) -> Vec<Move> {
-    let mut moves = Vec::new();
+/// Most legal moves any position has; move lists start this big so they never grow
+pub const MAX_MOVES: usize = 218;
+
+/// Appends the pseudo-legal moves to `moves`, so that callers such as the search can
+/// reuse one list instead of allocating a new one per position
+pub fn generate_pseudo_legal_moves(position: &Position, moves: &mut Vec<Move>) {
     let color = position.side_to_move;
 
     for (square, piece) in position.board.pieces_of_color(color) {
-        match piece {
-            Piece::Pawn => moves.extend(generate_pawn_moves(&position.board, square, color, position.en_passant_target)),
-            Piece::Knight => moves.extend(generate_knight_moves(&position.board, square, color)),
-            Piece::Bishop => moves.extend(generate_bishop_moves(&position.board, square, color)),
-            Piece::Rook => moves.extend(generate_rook_moves(&position.board, square, color)),
-            Piece::Queen => moves.extend(generate_queen_moves(&position.board, square, color)),
-            Piece::King => moves.extend(generate_king_moves(&position.board, square, color)),
-        }
+        generate_piece_moves(position, square, piece, color, moves);
     }
 
     // Add castling moves
-    moves.extend(generate_castling_moves(position));
+    generate_castling_moves(position, moves);
 
-    moves
+    // Add whatever else the variant allows
+    position.variant.rules().add_moves(position, moves);
 }
 
-fn generate_pawn_moves(board: &Board, from: Square, color: Color, en_passant: Option<Square>) -> Vec<Move> {
+/// Pseudo-legal moves of the side to move's piece on `from`, castling included for the king
+pub fn generate_pseudo_legal_moves_from(position: &Position, from: Square) -> Vec<Move> {
+    let color = position.side_to_move;
     let mut moves = Vec::new();
+    match position.board.get(from) {
+        Some((Piece::King, piece_color)) if piece_color == color => {
+            generate_king_moves(&position.board, from, color, &mut moves);
+            generate_castling_moves(position, &mut moves);
+        }
+        Some((piece, piece_color)) if piece_color == color => {
+            generate_piece_moves(position, from, piece, color, &mut moves)
+        }
+        _ => return moves,
+    }
+
+    let mut variant_moves = Vec::new();
+    position.variant.rules().add_moves(position, &mut variant_moves);
+    moves.extend(variant_moves.into_iter().filter(|mv| mv.from == from && mv.drop.is_none()));
+    moves
+}
+
+fn generate_piece_moves(position: &Position, square: Square, piece: Piece, color: Color, moves: &mut Vec<Move>) {
+    match piece {
+        Piece::Pawn => generate_pawn_moves(&position.board, square, color, position.en_passant_target, moves),
+        Piece::Knight => generate_knight_moves(&position.board, square, color, moves),
+        Piece::Bishop => generate_bishop_moves(&position.board, square, color, moves),
+        Piece::Rook => generate_rook_moves(&position.board, square, color, moves),
+        Piece::Queen => generate_queen_moves(&position.board, square, color, moves),
+        Piece::King => generate_king_moves(&position.board, square, color, moves),
+    }
+}
+
+fn generate_pawn_moves(board: &Board, from: Square, color: Color, en_passant: Option<Square>, moves: &mut Vec<Move>) {
     let direction: i8 = if color == Color::White { 1 } else { -1 };
     let start_rank = if color == Color::White { 1 } else { 6 };
     let promotion_rank = if color == Color::White { 7 } else { 0 };
@@ -70,13 +100,7 @@ fn generate_pawn_moves(board: &Board, from: Square, color: Color, en_passant: Op
 
         if is_valid_square(capture_rank, capture_file) {
             if let Some(capture_square) = Square::from_rank_file(capture_rank as u8, capture_file as u8) {
-                let can_capture = if let Some((_, piece_color)) = board.get(capture_square) {
-                    piece_color != color
-                } else {
-                    false
-                };
-
-                if can_capture {
+                if board.occupancy(color.opposite()) & (1u64 << capture_square.index()) != 0 {
                     if capture_rank as u8 == promotion_rank {
                         // Promotion captures
                         for promotion_piece in [Piece::Queen, Piece::Rook, Piece::Bishop, Piece::Knight] {
@@ -101,177 +125,77 @@ fn generate_pawn_moves(board: &Board, from: Square, color: Color, en_passant: Op
         }
     }
 
-    moves
 }
 
-fn generate_knight_moves(board: &Board, from: Square, color: Color) -> Vec<Move> {
-    let mut moves = Vec::new();
-    const KNIGHT_OFFSETS: [(i8, i8); 8] = [
-        (-2, -1), (-2, 1), (-1, -2), (-1, 2),
-        (1, -2), (1, 2), (2, -1), (2, 1),
-    ];
-
-    let from_rank = from.rank() as i8;
-    let from_file = from.file() as i8;
-
-    for (rank_offset, file_offset) in KNIGHT_OFFSETS {
-        let to_rank = from_rank + rank_offset;
-        let to_file = from_file + file_offset;
-
-        if is_valid_square(to_rank, to_file) {
-            if let Some(to_square) = Square::from_rank_file(to_rank as u8, to_file as u8) {
-                let can_move = if let Some((_, piece_color)) = board.get(to_square) {
-                    piece_color != color
-                } else {
-                    true
-                };
-
-                if can_move {
-                    moves.push(Move::new(from, to_square));
-                }
-            }
-        }
-    }
-
-    moves
+fn generate_knight_moves(board: &Board, from: Square, color: Color, moves: &mut Vec<Move>) {
+    push_moves(from, board.attacks_from(from, Piece::Knight, color) & !board.occupancy(color), moves)
 }
 
-fn generate_bishop_moves(board: &Board, from: Square, color: Color) -> Vec<Move> {
-    const BISHOP_DIRECTIONS: [(i8, i8); 4] = [(-1, -1), (-1, 1), (1, -1), (1, 1)];
-    generate_sliding_moves(board, from, color, &BISHOP_DIRECTIONS)
+fn generate_bishop_moves(board: &Board, from: Square, color: Color, moves: &mut Vec<Move>) {
+    push_moves(from, bishop_attacks(from, board.occupied()) & !board.occupancy(color), moves)
 }
 
-fn generate_rook_moves(board: &Board, from: Square, color: Color) -> Vec<Move> {
-    const ROOK_DIRECTIONS: [(i8, i8); 4] = [(-1, 0), (1, 0), (0, -1), (0, 1)];
-    generate_sliding_moves(board, from, color, &ROOK_DIRECTIONS)
+fn generate_rook_moves(board: &Board, from: Square, color: Color, moves: &mut Vec<Move>) {
+    push_moves(from, rook_attacks(from, board.occupied()) & !board.occupancy(color), moves)
 }
 
-fn generate_queen_moves(board: &Board, from: Square, color: Color) -> Vec<Move> {
-    let mut moves = Vec::new();
-    moves.extend(generate_bishop_moves(board, from, color));
-    moves.extend(generate_rook_moves(board, from, color));
-    moves
+fn generate_queen_moves(board: &Board, from: Square, color: Color, moves: &mut Vec<Move>) {
+    push_moves(from, queen_attacks(from, board.occupied()) & !board.occupancy(color), moves)
 }
 
-fn generate_king_moves(board: &Board, from: Square, color: Color) -> Vec<Move> {
-    let mut moves = Vec::new();
-    const KING_OFFSETS: [(i8, i8); 8] = [
-        (-1, -1), (-1, 0), (-1, 1),
-        (0, -1),           (0, 1),
-        (1, -1),  (1, 0),  (1, 1),
-    ];
-
-    let from_rank = from.rank() as i8;
-    let from_file = from.file() as i8;
-
-    for (rank_offset, file_offset) in KING_OFFSETS {
-        let to_rank = from_rank + rank_offset;
-        let to_file = from_file + file_offset;
-
-        if is_valid_square(to_rank, to_file) {
-            if let Some(to_square) = Square::from_rank_file(to_rank as u8, to_file as u8) {
-                let can_move = if let Some((_, piece_color)) = board.get(to_square) {
-                    piece_color != color
-                } else {
-                    true
-                };
+fn generate_king_moves(board: &Board, from: Square, color: Color, moves: &mut Vec<Move>) {
+    push_moves(from, board.attacks_from(from, Piece::King, color) & !board.occupancy(color), moves)
+}
 
-                if can_move {
-                    moves.push(Move::new(from, to_square));
-                }
-            }
-        }
+fn generate_castling_moves(position: &Position, moves: &mut Vec<Move>) {
+    for kingside in [true, false] {
+        moves.extend(castling_move(position, position.side_to_move, kingside));
     }
-
-    moves
 }
 
-fn generate_castling_moves(position: &Position) -> Vec<Move> {
-    let mut moves = Vec::new();
-    let color = position.side_to_move;
-    let rank = if color == Color::White { 0 } else { 7 };
-
-    // Kingside castling
-    if position.castling_rights.can_castle(color, true) {
-        let king_square = Square::from_rank_file(rank, 4).unwrap();
-        let rook_square = Square::from_rank_file(rank, 7).unwrap();
-        let f_square = Square::from_rank_file(rank, 5).unwrap();
-        let g_square = Square::from_rank_file(rank, 6).unwrap();
-
-        // Verify king is present on its starting square
-        let king_present = matches!(position.board.get(king_square), Some((Piece::King, c)) if c == color);
-
-        // Verify rook is present on the corner square
-        let rook_present = matches!(position.board.get(rook_square), Some((Piece::Rook, c)) if c == color);
-
-        if king_present && rook_present && position.board.is_empty(f_square) && position.board.is_empty(g_square) {
-            let mut mv = Move::new(king_square, g_square);
-            mv.is_castling = true;
-            moves.push(mv);
-        }
+/// The castling move `color` has on one side while it keeps the right, its king and
+/// rook stand ready and every square either crosses is empty. Whether the king passes
+/// through check is left to [`can_castle`](crate::chess_engine::validation::can_castle).
+pub(crate) fn castling_move(position: &Position, color: Color, kingside: bool) -> Option<Move> {
+    let rook_square = position.castling_rights.rook_square(color, kingside)?;
+    let rank = rook_square.rank();
+    let chess960 = position.variant.rules().chess960_castling();
+    let king_square = if chess960 {
+        (0..8)
+            .filter_map(|file| Square::from_rank_file(rank, file))
+            .find(|&square| position.board.get(square) == Some((Piece::King, color)))?
+    } else {
+        Square::from_rank_file(rank, 4)?
+    };
+
+    // Verify the king and rook are in place, the rook on the side being castled to
+    let king_present = position.board.get(king_square) == Some((Piece::King, color));
+    let rook_present = position.board.get(rook_square) == Some((Piece::Rook, color));
+    if !king_present || !rook_present || (rook_square.file() > king_square.file()) != kingside {
+        return None;
     }
 
-    // Queenside castling
-    if position.castling_rights.can_castle(color, false) {
-        let king_square = Square::from_rank_file(rank, 4).unwrap();
-        let rook_square = Square::from_rank_file(rank, 0).unwrap();
-        let b_square = Square::from_rank_file(rank, 1).unwrap();
-        let c_square = Square::from_rank_file(rank, 2).unwrap();
-        let d_square = Square::from_rank_file(rank, 3).unwrap();
-
-        // Verify king is present on its starting square
-        let king_present = matches!(position.board.get(king_square), Some((Piece::King, c)) if c == color);
-
-        // Verify rook is present on the corner square
-        let rook_present = matches!(position.board.get(rook_square), Some((Piece::Rook, c)) if c == color);
-
-        if king_present && rook_present &&
-           position.board.is_empty(b_square) &&
-           position.board.is_empty(c_square) &&
-           position.board.is_empty(d_square) {
-            let mut mv = Move::new(king_square, c_square);
-            mv.is_castling = true;
-            moves.push(mv);
-        }
+    let king_to = Square::from_rank_file(rank, if kingside { 6 } else { 2 })?;
+    let rook_to = Square::from_rank_file(rank, if kingside { 5 } else { 3 })?;
+    let crossed = rank_span(king_square, king_to) | rank_span(rook_square, rook_to);
+    let castlers = (1u64 << king_square.index()) | (1u64 << rook_square.index());
+    if crossed & position.board.occupied() & !castlers != 0 {
+        return None;
     }
 
-    moves
+    let mut mv = Move::new(king_square, king_to);
+    mv.is_castling = true;
+    mv.castling_rook = chess960.then_some(rook_square);
+    Some(mv)
 }
 
-fn generate_sliding_moves(
-    board: &Board,
-    from: Square,
-    color: Color,
-    directions: &[(i8, i8)],
-) -> Vec<Move> {
-    let mut moves = Vec::new();
-    let from_rank = from.rank() as i8;
-    let from_file = from.file() as i8;
-
-    for (rank_dir, file_dir) in directions {
-        let mut rank = from_rank;
-        let mut file = from_file;
-
-        loop {
-            rank += rank_dir;
-            file += file_dir;
-
-            if !is_valid_square(rank, file) {
-                break;
-            }
-
-            if let Some(to_square) = Square::from_rank_file(rank as u8, file as u8) {
-                if let Some((_, piece_color)) = board.get(to_square) {
-                    if piece_color != color {
-                        moves.push(Move::new(from, to_square));
-                    }
-                    break;
-                } else {
-                    moves.push(Move::new(from, to_square));
-                }
-            }
-        }
-    }
+/// Squares from `a` to `b` along a rank, both included
+pub(crate) fn rank_span(a: Square, b: Square) -> u64 {
+    let (low, high) = (a.index().min(b.index()), a.index().max(b.index()));
+    (!0u64 >> (63 - high)) & (!0u64 << low)
+}
 
-    moves
+/// One move from `from` to each square of `targets`
+fn push_moves(from: Square, targets: u64, moves: &mut Vec<Move>) {
+    moves.extend(squares_in(targets).map(|to| Move::new(from, to)));
 }
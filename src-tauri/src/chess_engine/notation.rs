@@ -0,0 +1,499 @@
+use crate::chess_engine::position::Position;
+use crate::chess_engine::types::{Piece, Square, Move};
+use crate::chess_engine::error::{ChessError, Result};
+use crate::chess_engine::validation::{generate_legal_moves, is_in_check, position_after_move};
+use serde::{Deserialize, Serialize};
+
+/// How moves are written when shown or exported
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Notation {
+    /// Standard Algebraic Notation with piece letters ("Nf3")
+    #[default]
+    San,
+    /// SAN with Unicode piece figurines in place of the letters ("\u{265E}f3")
+    Figurine,
+}
+
+impl Notation {
+    /// A SAN move written in this notation
+    pub fn render(self, san: &str) -> String {
+        match self {
+            Notation::San => san.to_string(),
+            Notation::Figurine => san
+                .chars()
+                .map(|c| piece_from_letter(c).map_or(c, figurine))
+                .collect(),
+        }
+    }
+}
+
+impl Move {
+    /// Standard Algebraic Notation for this move, given the position before it is played
+    pub fn to_san(self, position: &Position) -> String {
+        if self.is_null {
+            return "--".to_string();
+        }
+
+        let mut san = if let Some(piece) = self.drop {
+            format!("{}@{}", piece_letter(piece), self.to.to_algebraic())
+        } else if self.is_castling {
+            if self.is_kingside_castling() {
+                "O-O".to_string()
+            } else {
+                "O-O-O".to_string()
+            }
+        } else {
+            let Some((piece, _)) = position.board.get(self.from) else {
+                return self.to_uci();
+            };
+            let is_capture = self.is_en_passant || position.board.get(self.to).is_some();
+
+            let mut san = String::new();
+            if piece == Piece::Pawn {
+                if is_capture {
+                    san.push(file_char(self.from));
+                }
+            } else {
+                san.push(piece_letter(piece));
+                san.push_str(&disambiguation(&self, piece, position));
+            }
+
+            if is_capture {
+                san.push('x');
+            }
+            san.push_str(&self.to.to_algebraic());
+
+            if let Some(promotion) = self.promotion {
+                san.push('=');
+                san.push(piece_letter(promotion));
+            }
+            san
+        };
+
+        let after = position_after_move(position, &self);
+        if is_in_check(&after, after.side_to_move) {
+            san.push(if generate_legal_moves(&after).is_empty() { '#' } else { '+' });
+        }
+
+        san
+    }
+
+    /// Parse a SAN string ("Nf3", "exd6 e.p.", "O-O-O", "e8=Q+", "N@f3") into the matching
+    /// legal move. Figurines are accepted in place of piece letters, and "--" or "Z0" as a
+    /// null move.
+    pub fn from_san(san: &str, position: &Position) -> Result<Move> {
+        let legal_moves = generate_legal_moves(position);
+        let letters = figurines_to_letters(san);
+        let text = strip_san_suffixes(&letters);
+        if text == "--" || text == "Z0" {
+            return null_move(position, san);
+        }
+        if text.contains('@') {
+            return legal_drop(text, san, position);
+        }
+
+        let candidates: Vec<Move> = match text {
+            "O-O" | "0-0" => legal_moves
+                .into_iter()
+                .filter(|mv| mv.is_kingside_castling())
+                .collect(),
+            "O-O-O" | "0-0-0" => legal_moves
+                .into_iter()
+                .filter(|mv| mv.is_castling && !mv.is_kingside_castling())
+                .collect(),
+            _ => {
+                let pattern = SanPattern::parse(text).ok_or_else(|| ChessError::ParseError {
+                    input: san.to_string(),
+                })?;
+                legal_moves
+                    .into_iter()
+                    .filter(|mv| !mv.is_castling && pattern.matches(mv, position))
+                    .collect()
+            }
+        };
+
+        match candidates.as_slice() {
+            [mv] => Ok(*mv),
+            [] => Err(ChessError::InvalidMove {
+                reason: format!("{} is not a legal move", san),
+            }),
+            _ => Err(ChessError::InvalidMove {
+                reason: format!("{} is ambiguous", san),
+            }),
+        }
+    }
+
+    /// Resolve a UCI string ("e2e4", "e7e8q", "P@e4" for a drop) into the matching legal
+    /// move, filling in the castling and en passant flags. Chess960 castling is the king
+    /// taking its own rook ("e1h1").
+    pub fn from_uci(uci: &str, position: &Position) -> Result<Move> {
+        let uci = uci.trim();
+        if uci == "0000" {
+            return null_move(position, uci);
+        }
+        if uci.contains('@') {
+            return legal_drop(uci, uci, position);
+        }
+        if !(4..=5).contains(&uci.len()) || !uci.is_ascii() {
+            return Err(ChessError::ParseError { input: uci.to_string() });
+        }
+
+        let from = Square::from_algebraic(&uci[0..2])?;
+        let to = Square::from_algebraic(&uci[2..4])?;
+        let promotion = match uci[4..].chars().next() {
+            None => None,
+            Some(letter) => Some(
+                piece_from_letter(letter.to_ascii_uppercase())
+                    .filter(|piece| *piece != Piece::King)
+                    .ok_or_else(|| ChessError::ParseError { input: uci.to_string() })?,
+            ),
+        };
+
+        generate_legal_moves(position)
+            .into_iter()
+            .find(|mv| mv.from == from && mv.uci_target() == to && mv.promotion == promotion)
+            .ok_or_else(|| ChessError::InvalidMove {
+                reason: format!("{} is not a legal move", uci),
+            })
+    }
+}
+
+/// The null move, unless the side to move is in check and so cannot pass
+fn null_move(position: &Position, text: &str) -> Result<Move> {
+    if is_in_check(position, position.side_to_move) {
+        return Err(ChessError::InvalidMove {
+            reason: format!("{} (null move) is not allowed in check", text),
+        });
+    }
+    Ok(Move::null())
+}
+
+/// The legal drop written as `text` ("N@f3", and "P@e4" or "@e4" for a pawn), which
+/// is written the same in SAN and UCI; `input` is the move as given, for errors
+fn legal_drop(text: &str, input: &str, position: &Position) -> Result<Move> {
+    let parse_error = || ChessError::ParseError { input: input.to_string() };
+    let (letter, square) = text.split_once('@').ok_or_else(parse_error)?;
+    let piece = match letter {
+        "" | "P" => Piece::Pawn,
+        _ => letter
+            .chars()
+            .next()
+            .filter(|_| letter.len() == 1)
+            .and_then(piece_from_letter)
+            .filter(|piece| *piece != Piece::King)
+            .ok_or_else(parse_error)?,
+    };
+    let drop = Move::new_drop(piece, Square::from_algebraic(square)?);
+
+    generate_legal_moves(position)
+        .into_iter()
+        .find(|mv| *mv == drop)
+        .ok_or_else(|| ChessError::InvalidMove {
+            reason: format!("{} is not a legal move", input),
+        })
+}
+
+/// The pieces of a non-castling SAN move: piece letter, optional origin hints,
+/// destination and promotion
+struct SanPattern {
+    piece: Piece,
+    from_file: Option<u8>,
+    from_rank: Option<u8>,
+    to: Square,
+    promotion: Option<Piece>,
+}
+
+impl SanPattern {
+    fn parse(text: &str) -> Option<SanPattern> {
+        let mut chars: Vec<char> = text.chars().filter(|c| *c != 'x' && *c != ':' && *c != '-').collect();
+
+        // Promotion: "e8=Q" or "e8Q"
+        let mut promotion = None;
+        if let Some(&last) = chars.last() {
+            if let Some(piece) = piece_from_letter(last) {
+                promotion = Some(piece);
+                chars.pop();
+                if chars.last() == Some(&'=') {
+                    chars.pop();
+                }
+            }
+        }
+
+        if chars.len() < 2 {
+            return None;
+        }
+        let destination: String = chars.split_off(chars.len() - 2).into_iter().collect();
+        let to = Square::from_algebraic(&destination).ok()?;
+
+        let mut piece = Piece::Pawn;
+        if let Some(&first) = chars.first() {
+            if let Some(letter_piece) = piece_from_letter(first) {
+                piece = letter_piece;
+                chars.remove(0);
+            }
+        }
+
+        let mut from_file = None;
+        let mut from_rank = None;
+        for c in chars {
+            match c {
+                'a'..='h' if from_file.is_none() => from_file = Some(c as u8 - b'a'),
+                '1'..='8' if from_rank.is_none() => from_rank = Some(c as u8 - b'1'),
+                _ => return None,
+            }
+        }
+
+        Some(SanPattern { piece, from_file, from_rank, to, promotion })
+    }
+
+    fn matches(&self, mv: &Move, position: &Position) -> bool {
+        mv.to == self.to
+            && mv.promotion == self.promotion
+            && matches!(position.board.get(mv.from), Some((p, _)) if p == self.piece)
+            && self.from_file.is_none_or(|file| mv.from.file() == file)
+            && self.from_rank.is_none_or(|rank| mv.from.rank() == rank)
+    }
+}
+
+/// Drop check/mate marks, annotation glyphs and en passant markers
+fn strip_san_suffixes(san: &str) -> &str {
+    let mut text = san.trim();
+    for marker in ["e.p.", "ep"] {
+        if let Some(stripped) = text.strip_suffix(marker) {
+            text = stripped.trim_end();
+        }
+    }
+    text.trim_end_matches(['+', '#', '!', '?'])
+}
+
+/// Uppercase letter for a piece as used in SAN and FEN
+pub fn piece_letter(piece: Piece) -> char {
+    match piece {
+        Piece::Pawn => 'P',
+        Piece::Knight => 'N',
+        Piece::Bishop => 'B',
+        Piece::Rook => 'R',
+        Piece::Queen => 'Q',
+        Piece::King => 'K',
+    }
+}
+
+/// Piece for an uppercase SAN letter other than 'P'
+fn piece_from_letter(letter: char) -> Option<Piece> {
+    match letter {
+        'N' => Some(Piece::Knight),
+        'B' => Some(Piece::Bishop),
+        'R' => Some(Piece::Rook),
+        'Q' => Some(Piece::Queen),
+        'K' => Some(Piece::King),
+        _ => None,
+    }
+}
+
+/// Solid figurine for a piece, used for moves of either colour
+pub fn figurine(piece: Piece) -> char {
+    match piece {
+        Piece::Pawn => '\u{265F}',
+        Piece::Knight => '\u{265E}',
+        Piece::Bishop => '\u{265D}',
+        Piece::Rook => '\u{265C}',
+        Piece::Queen => '\u{265B}',
+        Piece::King => '\u{265A}',
+    }
+}
+
+/// Replace white or black figurines with the SAN piece letters
+fn figurines_to_letters(san: &str) -> String {
+    san.chars()
+        .map(|c| match c {
+            '\u{2654}' | '\u{265A}' => 'K',
+            '\u{2655}' | '\u{265B}' => 'Q',
+            '\u{2656}' | '\u{265C}' => 'R',
+            '\u{2657}' | '\u{265D}' => 'B',
+            '\u{2658}' | '\u{265E}' => 'N',
+            _ => c,
+        })
+        .collect()
+}
+
+fn file_char(square: Square) -> char {
+    (b'a' + square.file()) as char
+}
+
+fn rank_char(square: Square) -> char {
+    (b'1' + square.rank()) as char
+}
+
+/// Origin file, rank or full square needed to tell this move apart from other
+/// legal moves of the same piece type to the same destination
+fn disambiguation(mv: &Move, piece: Piece, position: &Position) -> String {
+    let rivals: Vec<Square> = generate_legal_moves(position)
+        .into_iter()
+        .filter(|other| other.to == mv.to && other.from != mv.from)
+        .filter(|other| matches!(position.board.get(other.from), Some((p, _)) if p == piece))
+        .map(|other| other.from)
+        .collect();
+
+    if rivals.is_empty() {
+        String::new()
+    } else if rivals.iter().all(|square| square.file() != mv.from.file()) {
+        file_char(mv.from).to_string()
+    } else if rivals.iter().all(|square| square.rank() != mv.from.rank()) {
+        rank_char(mv.from).to_string()
+    } else {
+        mv.from.to_algebraic()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chess_engine::fen::parse_fen;
+
+    fn san_of(fen: &str, uci: &str) -> String {
+        let position = parse_fen(fen).unwrap();
+        let mv = generate_legal_moves(&position)
+            .into_iter()
+            .find(|mv| mv.to_uci() == uci)
+            .unwrap_or_else(|| panic!("{} is not legal in {}", uci, fen));
+        mv.to_san(&position)
+    }
+
+    #[test]
+    fn test_basic_san() {
+        let start = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+        assert_eq!(san_of(start, "e2e4"), "e4");
+        assert_eq!(san_of(start, "g1f3"), "Nf3");
+
+        let open = "rnbqkbnr/ppp1pppp/8/3p4/4P3/8/PPPP1PPP/RNBQKBNR w KQkq d6 0 2";
+        assert_eq!(san_of(open, "e4d5"), "exd5");
+    }
+
+    #[test]
+    fn test_castling_and_promotion_san() {
+        let castle = "r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1";
+        assert_eq!(san_of(castle, "e1g1"), "O-O");
+        assert_eq!(san_of(castle, "e1c1"), "O-O-O");
+
+        let promote = "8/4P1k1/8/8/8/8/8/4K3 w - - 0 1";
+        assert_eq!(san_of(promote, "e7e8q"), "e8=Q");
+        assert_eq!(san_of(promote, "e7e8n"), "e8=N+");
+    }
+
+    #[test]
+    fn test_en_passant_san() {
+        let fen = "4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1";
+        assert_eq!(san_of(fen, "e5d6"), "exd6");
+    }
+
+    #[test]
+    fn test_disambiguation() {
+        // Knights on b1 and f1 can both reach d2: file disambiguation
+        assert_eq!(san_of("4k3/8/8/8/8/8/8/1N2KN2 w - - 0 1", "b1d2"), "Nbd2");
+        // Rooks on a1 and a5 share a file: rank disambiguation
+        assert_eq!(san_of("4k3/8/8/R7/8/8/8/R3K3 w - - 0 1", "a1a3"), "R1a3");
+        // Queens on d4, h4 and h8 all reach f6: h4 shares a rank with d4 and a file with h8
+        assert_eq!(san_of("K6Q/8/8/8/3Q3Q/8/8/2k5 w - - 0 1", "h4f6"), "Qh4f6");
+    }
+
+    #[test]
+    fn test_parse_san() {
+        let position = parse_fen("r3k2r/1P6/8/3pP3/8/8/8/RN2K2R w KQkq d6 0 1").unwrap();
+        let parse = |san: &str| Move::from_san(san, &position).map(|mv| mv.to_uci());
+
+        assert_eq!(parse("Nc3").unwrap(), "b1c3");
+        assert_eq!(parse("exd6 e.p.").unwrap(), "e5d6");
+        assert_eq!(parse("exd6").unwrap(), "e5d6");
+        assert_eq!(parse("0-0").unwrap(), "e1g1");
+        assert_eq!(parse("b8=Q+").unwrap(), "b7b8q");
+        assert_eq!(parse("bxa8N").unwrap(), "b7a8n");
+        assert!(parse("O-O-O").is_err());
+        assert!(parse("b8").is_err());
+        assert!(parse("Nd4").is_err());
+        assert!(parse("Zz9").is_err());
+    }
+
+    #[test]
+    fn test_parse_san_disambiguation() {
+        let position = parse_fen("4k3/8/8/R7/8/8/8/R3K3 w - - 0 1").unwrap();
+
+        assert!(matches!(
+            Move::from_san("Ra3", &position),
+            Err(ChessError::InvalidMove { .. })
+        ));
+        assert_eq!(Move::from_san("R1a3", &position).unwrap().to_uci(), "a1a3");
+        assert_eq!(Move::from_san("R5xa3", &position).unwrap().to_uci(), "a5a3");
+    }
+
+    #[test]
+    fn test_parse_uci() {
+        let position = parse_fen("r3k2r/1P6/8/3pP3/8/8/8/RN2K2R w KQkq d6 0 1").unwrap();
+
+        let castle = Move::from_uci("e1g1", &position).unwrap();
+        assert!(castle.is_castling);
+
+        let en_passant = Move::from_uci("e5d6", &position).unwrap();
+        assert!(en_passant.is_en_passant);
+
+        assert_eq!(Move::from_uci("b7a8n", &position).unwrap().promotion, Some(Piece::Knight));
+        assert!(matches!(Move::from_uci("b7b8", &position), Err(ChessError::InvalidMove { .. })));
+        assert!(matches!(Move::from_uci("b7b8k", &position), Err(ChessError::ParseError { .. })));
+        assert!(Move::from_uci("e2", &position).is_err());
+    }
+
+    #[test]
+    fn test_drop_notation() {
+        let position = parse_fen("4k3/8/8/8/8/8/8/4K3[NPn] w - - 0 1").unwrap();
+        let knight = Move::from_san("N@f3", &position).unwrap();
+        assert_eq!(knight, Move::new_drop(Piece::Knight, Square::from_algebraic("f3").unwrap()));
+        assert_eq!(knight.to_san(&position), "N@f3");
+        assert_eq!(Move::from_uci("N@f3", &position).unwrap(), knight);
+
+        let pawn = Move::from_san("@d7", &position).unwrap();
+        assert_eq!(pawn.to_san(&position), "P@d7+");
+        assert_eq!(Move::from_san("P@d7+", &position).unwrap(), pawn);
+        assert_eq!(Move::from_san("\u{2658}@f3", &position).unwrap(), knight);
+
+        assert!(matches!(Move::from_san("B@f3", &position), Err(ChessError::InvalidMove { .. })));
+        assert!(matches!(Move::from_san("P@e8", &position), Err(ChessError::InvalidMove { .. })));
+        assert!(matches!(Move::from_uci("K@f3", &position), Err(ChessError::ParseError { .. })));
+        assert!(matches!(Move::from_uci("Nf@3", &position), Err(ChessError::ParseError { .. })));
+    }
+
+    #[test]
+    fn test_figurine_notation() {
+        assert_eq!(Notation::Figurine.render("Nbd2"), "\u{265E}bd2");
+        assert_eq!(Notation::Figurine.render("exd8=Q+"), "exd8=\u{265B}+");
+        assert_eq!(Notation::Figurine.render("O-O-O"), "O-O-O");
+        assert_eq!(Notation::San.render("Nbd2"), "Nbd2");
+
+        let position = parse_fen("4k3/1P6/8/8/8/8/8/1N2K3 w - - 0 1").unwrap();
+        assert_eq!(Move::from_san("\u{2658}c3", &position).unwrap().to_uci(), "b1c3");
+        assert_eq!(Move::from_san("b8=\u{265B}+", &position).unwrap().to_uci(), "b7b8q");
+    }
+
+    #[test]
+    fn test_null_move_notation() {
+        let position = parse_fen("4k3/8/8/8/8/8/8/R3K3 w - - 0 1").unwrap();
+        let null = Move::from_san("--", &position).unwrap();
+
+        assert!(null.is_null);
+        assert_eq!(null.to_san(&position), "--");
+        assert_eq!(null.to_uci(), "0000");
+        assert_eq!(Move::from_san("Z0", &position).unwrap(), null);
+        assert_eq!(Move::from_uci("0000", &position).unwrap(), null);
+
+        let in_check = parse_fen("4k3/8/8/8/8/8/8/4K2r w - - 0 1").unwrap();
+        assert!(Move::from_san("--", &in_check).is_err());
+    }
+
+    #[test]
+    fn test_check_and_mate_suffixes() {
+        let back_rank = "6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1";
+        assert_eq!(san_of(back_rank, "a1a8"), "Ra8#");
+
+        let check = "4k3/8/8/8/8/8/8/R3K3 w - - 0 1";
+        assert_eq!(san_of(check, "a1a8"), "Ra8+");
+    }
+}
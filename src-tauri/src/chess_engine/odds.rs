@@ -0,0 +1,98 @@
+use crate::chess_engine::error::{ChessError, Result};
+use crate::chess_engine::position::Position;
+use crate::chess_engine::types::{Color, Square};
+use serde::{Deserialize, Serialize};
+
+/// A handicap the stronger player gives, so a beginner has a fair game against them
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Odds {
+    /// The f-pawn
+    Pawn,
+    /// The f-pawn, and the first move
+    PawnAndMove,
+    /// The queen's knight
+    Knight,
+    /// The queen's rook, and with it castling on that side
+    Rook,
+    Queen,
+    /// The first move
+    ExtraMove,
+}
+
+impl Odds {
+    /// Where the piece `giver` goes without starts, if the odds take one away
+    fn removed_square(self, giver: Color) -> Option<Square> {
+        let file = match self {
+            Odds::Pawn | Odds::PawnAndMove => 5,
+            Odds::Knight => 1,
+            Odds::Rook => 0,
+            Odds::Queen => 3,
+            Odds::ExtraMove => return None,
+        };
+        let rank = match (self, giver) {
+            (Odds::Pawn | Odds::PawnAndMove, Color::White) => 1,
+            (Odds::Pawn | Odds::PawnAndMove, Color::Black) => 6,
+            (_, Color::White) => 0,
+            (_, Color::Black) => 7,
+        };
+        Square::from_rank_file(rank, file)
+    }
+
+    fn gives_the_move(self) -> bool {
+        matches!(self, Odds::PawnAndMove | Odds::ExtraMove)
+    }
+}
+
+/// The starting position with `giver` playing at `odds`. Giving the first move makes
+/// Black start; as Black already moves second, only White can give it.
+pub fn odds_position(odds: Odds, giver: Color) -> Result<Position> {
+    if odds.gives_the_move() && giver == Color::Black {
+        return Err(ChessError::InvalidOption {
+            name: "odds".to_string(),
+            reason: "White already has the first move".to_string(),
+        });
+    }
+
+    let mut position = Position::new();
+    if let Some(square) = odds.removed_square(giver) {
+        position.board.set(square, None);
+    }
+    if odds == Odds::Rook {
+        match giver {
+            Color::White => position.castling_rights.white_queenside = false,
+            Color::Black => position.castling_rights.black_queenside = false,
+        }
+    }
+    if odds.gives_the_move() {
+        position.side_to_move = Color::Black;
+    }
+
+    position.position_history.clear();
+    position.record_position();
+    Ok(position)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chess_engine::fen::position_to_fen;
+
+    #[test]
+    fn test_odds_positions() {
+        let fen = |odds, giver| position_to_fen(&odds_position(odds, giver).unwrap());
+        assert_eq!(fen(Odds::Queen, Color::White), "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNB1KBNR w KQkq - 0 1");
+        assert_eq!(fen(Odds::Rook, Color::Black), "1nbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQk - 0 1");
+        assert_eq!(fen(Odds::Knight, Color::White), "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/R1BQKBNR w KQkq - 0 1");
+        assert_eq!(fen(Odds::PawnAndMove, Color::White), "rnbqkbnr/pppppppp/8/8/8/8/PPPPP1PP/RNBQKBNR b KQkq - 0 1");
+        assert_eq!(fen(Odds::ExtraMove, Color::White), "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR b KQkq - 0 1");
+        assert!(odds_position(Odds::ExtraMove, Color::Black).is_err());
+        assert_eq!(
+            odds_position(Odds::PawnAndMove, Color::Black).unwrap_err().to_string(),
+            odds_position(Odds::ExtraMove, Color::Black).unwrap_err().to_string()
+        );
+
+        let position = odds_position(Odds::Rook, Color::White).unwrap();
+        assert_eq!(position.position_history, vec![position.compute_zobrist_hash()]);
+    }
+}
@@ -0,0 +1,195 @@
+use crate::chess_engine::polyglot::polyglot_key;
+use crate::chess_engine::position::Position;
+use crate::chess_engine::types::Move;
+use crate::chess_engine::validation::position_after_move;
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// A named opening recognised in a game
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Opening {
+    pub eco: String,
+    pub name: String,
+    /// The opening's usual move order in SAN
+    pub moves: String,
+    /// Plies into the game at which the opening position was reached
+    pub ply: usize,
+    /// Whether the game reached the position through a different move order
+    pub transposition: bool,
+}
+
+/// ECO code, name and usual move order of the openings that are recognised
+const OPENINGS: &[(&str, &str, &str)] = &[
+    ("A00", "Polish Opening", "b4"),
+    ("A01", "Nimzo-Larsen Attack", "b3"),
+    ("A02", "Bird's Opening", "f4"),
+    ("A04", "Zukertort Opening", "Nf3"),
+    ("A09", "Réti Opening", "Nf3 d5 c4"),
+    ("A10", "English Opening", "c4"),
+    ("A20", "English Opening: King's English Variation", "c4 e5"),
+    ("A40", "Queen's Pawn Game", "d4"),
+    ("A43", "Old Benoni Defense", "d4 c5"),
+    ("A45", "Indian Defense", "d4 Nf6"),
+    ("A50", "Indian Defense: Normal Variation", "d4 Nf6 c4"),
+    ("A56", "Benoni Defense", "d4 Nf6 c4 c5"),
+    ("A57", "Benko Gambit", "d4 Nf6 c4 c5 d5 b5"),
+    ("A80", "Dutch Defense", "d4 f5"),
+    ("B00", "King's Pawn Game", "e4"),
+    ("B01", "Scandinavian Defense", "e4 d5"),
+    ("B02", "Alekhine Defense", "e4 Nf6"),
+    ("B06", "Modern Defense", "e4 g6"),
+    ("B07", "Pirc Defense", "e4 d6 d4 Nf6 Nc3"),
+    ("B10", "Caro-Kann Defense", "e4 c6"),
+    ("B12", "Caro-Kann Defense: Advance Variation", "e4 c6 d4 d5 e5"),
+    ("B13", "Caro-Kann Defense: Exchange Variation", "e4 c6 d4 d5 exd5 cxd5"),
+    ("B20", "Sicilian Defense", "e4 c5"),
+    ("B21", "Sicilian Defense: Smith-Morra Gambit", "e4 c5 d4 cxd4 c3"),
+    ("B22", "Sicilian Defense: Alapin Variation", "e4 c5 c3"),
+    ("B23", "Sicilian Defense: Closed", "e4 c5 Nc3"),
+    ("B27", "Sicilian Defense", "e4 c5 Nf3"),
+    ("B30", "Sicilian Defense: Old Sicilian", "e4 c5 Nf3 Nc6"),
+    ("B33", "Sicilian Defense: Sveshnikov Variation", "e4 c5 Nf3 Nc6 d4 cxd4 Nxd4 Nf6 Nc3 e5"),
+    ("B40", "Sicilian Defense: French Variation", "e4 c5 Nf3 e6"),
+    ("B50", "Sicilian Defense", "e4 c5 Nf3 d6"),
+    ("B54", "Sicilian Defense: Open", "e4 c5 Nf3 d6 d4 cxd4 Nxd4"),
+    ("B56", "Sicilian Defense: Classical Variation", "e4 c5 Nf3 d6 d4 cxd4 Nxd4 Nf6 Nc3 Nc6"),
+    ("B70", "Sicilian Defense: Dragon Variation", "e4 c5 Nf3 d6 d4 cxd4 Nxd4 Nf6 Nc3 g6"),
+    ("B80", "Sicilian Defense: Scheveningen Variation", "e4 c5 Nf3 d6 d4 cxd4 Nxd4 Nf6 Nc3 e6"),
+    ("B90", "Sicilian Defense: Najdorf Variation", "e4 c5 Nf3 d6 d4 cxd4 Nxd4 Nf6 Nc3 a6"),
+    ("C00", "French Defense", "e4 e6"),
+    ("C01", "French Defense: Exchange Variation", "e4 e6 d4 d5 exd5"),
+    ("C02", "French Defense: Advance Variation", "e4 e6 d4 d5 e5"),
+    ("C03", "French Defense: Tarrasch Variation", "e4 e6 d4 d5 Nd2"),
+    ("C11", "French Defense: Classical Variation", "e4 e6 d4 d5 Nc3 Nf6"),
+    ("C15", "French Defense: Winawer Variation", "e4 e6 d4 d5 Nc3 Bb4"),
+    ("C20", "King's Pawn Game", "e4 e5"),
+    ("C23", "Bishop's Opening", "e4 e5 Bc4"),
+    ("C25", "Vienna Game", "e4 e5 Nc3"),
+    ("C30", "King's Gambit", "e4 e5 f4"),
+    ("C33", "King's Gambit Accepted", "e4 e5 f4 exf4"),
+    ("C40", "King's Knight Opening", "e4 e5 Nf3"),
+    ("C41", "Philidor Defense", "e4 e5 Nf3 d6"),
+    ("C42", "Petrov's Defense", "e4 e5 Nf3 Nf6"),
+    ("C44", "Scotch Game", "e4 e5 Nf3 Nc6 d4"),
+    ("C45", "Scotch Game", "e4 e5 Nf3 Nc6 d4 exd4 Nxd4"),
+    ("C46", "Three Knights Opening", "e4 e5 Nf3 Nc6 Nc3"),
+    ("C47", "Four Knights Game", "e4 e5 Nf3 Nc6 Nc3 Nf6"),
+    ("C50", "Italian Game", "e4 e5 Nf3 Nc6 Bc4"),
+    ("C50", "Italian Game: Giuoco Piano", "e4 e5 Nf3 Nc6 Bc4 Bc5"),
+    ("C51", "Italian Game: Evans Gambit", "e4 e5 Nf3 Nc6 Bc4 Bc5 b4"),
+    ("C55", "Italian Game: Two Knights Defense", "e4 e5 Nf3 Nc6 Bc4 Nf6"),
+    ("C60", "Ruy Lopez", "e4 e5 Nf3 Nc6 Bb5"),
+    ("C65", "Ruy Lopez: Berlin Defense", "e4 e5 Nf3 Nc6 Bb5 Nf6"),
+    ("C68", "Ruy Lopez: Exchange Variation", "e4 e5 Nf3 Nc6 Bb5 a6 Bxc6"),
+    ("C70", "Ruy Lopez: Morphy Defense", "e4 e5 Nf3 Nc6 Bb5 a6"),
+    ("C80", "Ruy Lopez: Open", "e4 e5 Nf3 Nc6 Bb5 a6 Ba4 Nf6 O-O Nxe4"),
+    ("C84", "Ruy Lopez: Closed", "e4 e5 Nf3 Nc6 Bb5 a6 Ba4 Nf6 O-O Be7"),
+    ("D00", "Queen's Pawn Game", "d4 d5"),
+    ("D02", "Queen's Pawn Game: London System", "d4 d5 Nf3 Nf6 Bf4"),
+    ("D06", "Queen's Gambit", "d4 d5 c4"),
+    ("D07", "Queen's Gambit Declined: Chigorin Defense", "d4 d5 c4 Nc6"),
+    ("D08", "Queen's Gambit Declined: Albin Countergambit", "d4 d5 c4 e5"),
+    ("D10", "Slav Defense", "d4 d5 c4 c6"),
+    ("D20", "Queen's Gambit Accepted", "d4 d5 c4 dxc4"),
+    ("D30", "Queen's Gambit Declined", "d4 d5 c4 e6"),
+    ("D35", "Queen's Gambit Declined: Exchange Variation", "d4 d5 c4 e6 Nc3 Nf6 cxd5"),
+    ("D43", "Semi-Slav Defense", "d4 d5 c4 c6 Nf3 Nf6 Nc3 e6"),
+    ("D80", "Grünfeld Defense", "d4 Nf6 c4 g6 Nc3 d5"),
+    ("E00", "Indian Defense", "d4 Nf6 c4 e6"),
+    ("E00", "Catalan Opening", "d4 Nf6 c4 e6 g3"),
+    ("E12", "Queen's Indian Defense", "d4 Nf6 c4 e6 Nf3 b6"),
+    ("E20", "Nimzo-Indian Defense", "d4 Nf6 c4 e6 Nc3 Bb4"),
+    ("E60", "King's Indian Defense", "d4 Nf6 c4 g6"),
+    ("E70", "King's Indian Defense: Normal Variation", "d4 Nf6 c4 g6 Nc3 Bg7 e4 d6"),
+];
+
+/// Polyglot key of every opening position, pointing into `OPENINGS`. Keys leave out
+/// the move clocks and an en passant square no pawn can use, so a position reached
+/// through another move order maps to the same entry.
+static OPENING_POSITIONS: Lazy<HashMap<u64, usize>> = Lazy::new(|| {
+    let mut positions = HashMap::new();
+    for (index, (_, _, moves)) in OPENINGS.iter().enumerate() {
+        let (position, _) = play_line(moves);
+        positions.entry(polyglot_key(&position)).or_insert(index);
+    }
+    positions
+});
+
+/// The most specific known opening reached by a game, given the Polyglot keys of the
+/// positions it went through (starting position first) and the moves played between them
+pub fn identify_opening(keys: impl IntoIterator<Item = u64>, moves: &[Move]) -> Option<Opening> {
+    let (ply, index) = keys
+        .into_iter()
+        .enumerate()
+        .filter_map(|(ply, key)| Some((ply, *OPENING_POSITIONS.get(&key)?)))
+        .last()?;
+
+    let (eco, name, line) = OPENINGS[index];
+    let (_, line_moves) = play_line(line);
+
+    Some(Opening {
+        eco: eco.to_string(),
+        name: name.to_string(),
+        moves: line.to_string(),
+        ply,
+        transposition: moves.get(..ply) != Some(line_moves.as_slice()),
+    })
+}
+
+/// Position after a line of SAN moves from the standard starting position
+fn play_line(line: &str) -> (Position, Vec<Move>) {
+    let mut position = Position::new();
+    let mut moves = Vec::new();
+    for san in line.split_whitespace() {
+        let mv = Move::from_san(san, &position).expect("opening lines are legal");
+        position = position_after_move(&position, &mv);
+        moves.push(mv);
+    }
+    (position, moves)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn identify(line: &str) -> Option<Opening> {
+        let mut positions = vec![Position::new()];
+        let mut moves = Vec::new();
+        for san in line.split_whitespace() {
+            let position = positions.last().unwrap();
+            let mv = Move::from_san(san, position).unwrap();
+            positions.push(position_after_move(position, &mv));
+            moves.push(mv);
+        }
+        identify_opening(positions.iter().map(polyglot_key), &moves)
+    }
+
+    #[test]
+    fn test_opening_lines_are_distinct_positions() {
+        assert_eq!(OPENING_POSITIONS.len(), OPENINGS.len());
+    }
+
+    #[test]
+    fn test_deepest_known_position_wins() {
+        let opening = identify("e4 e5 Nf3 Nc6 Bb5 a6 Ba4 d6 c3").unwrap();
+        assert_eq!(opening.eco, "C70");
+        assert_eq!(opening.ply, 6);
+        assert!(!opening.transposition);
+
+        assert!(identify("a3").is_none());
+    }
+
+    #[test]
+    fn test_transposition_is_recognised() {
+        let opening = identify("c4 e6 d4 Nf6 Nc3 Bb4").unwrap();
+        assert_eq!(opening.name, "Nimzo-Indian Defense");
+        assert_eq!(opening.ply, 6);
+        assert!(opening.transposition);
+
+        // 2. d4 leaves an en passant square no pawn can use, unlike 2. c4 in the usual order
+        let opening = identify("c4 Nf6 d4").unwrap();
+        assert_eq!(opening.eco, "A50");
+        assert!(opening.transposition);
+    }
+}
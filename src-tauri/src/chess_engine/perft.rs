@@ -0,0 +1,188 @@
+use crate::chess_engine::move_gen::MAX_MOVES;
+use crate::chess_engine::position::Position;
+use crate::chess_engine::types::Move;
+use crate::chess_engine::validation::generate_legal_moves_into;
+use serde::{Deserialize, Serialize};
+
+/// Size of the table used by hashed perft when no other size is given
+pub const PERFT_HASH_MEGABYTES: usize = 64;
+
+/// Deepest perft the app will run; each extra ply multiplies the work by about 30
+pub const MAX_PERFT_DEPTH: u8 = 10;
+
+/// Bytes taken by one table entry: key, node count and depth, padded
+const ENTRY_BYTES: usize = std::mem::size_of::<TableEntry>();
+
+/// Nodes below one root move, in UCI notation to compare against other engines' divide output
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PerftEntry {
+    #[serde(rename = "move")]
+    pub mv: String,
+    pub nodes: u64,
+}
+
+/// Perft split by root move, sorted by move
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PerftDivide {
+    pub moves: Vec<PerftEntry>,
+    pub nodes: u64,
+}
+
+/// Counts the leaf nodes of the legal move tree `depth` plies deep
+pub fn perft(position: &Position, depth: u8) -> u64 {
+    let mut position = position.clone();
+    let mut move_lists = vec![Vec::with_capacity(MAX_MOVES); depth as usize];
+    count(&mut position, depth, &mut move_lists)
+}
+
+/// Like [`perft`], but subtrees already counted from the same position at the same
+/// depth are looked up in a table of about `megabytes` instead of being walked again
+pub fn perft_hashed(position: &Position, depth: u8, megabytes: usize) -> u64 {
+    let mut position = position.clone();
+    let mut move_lists = vec![Vec::with_capacity(MAX_MOVES); depth as usize];
+    let mut table = PerftTable::with_megabytes(megabytes);
+    count_hashed(&mut position, depth, &mut move_lists, &mut table)
+}
+
+/// Perft for each legal move from `position`, to find which move's subtree differs
+/// from a reference count
+pub fn perft_divide(position: &Position, depth: u8) -> PerftDivide {
+    let mut position = position.clone();
+    let mut moves = Vec::with_capacity(MAX_MOVES);
+    generate_legal_moves_into(&position, &mut moves);
+
+    let mut move_lists = vec![Vec::with_capacity(MAX_MOVES); depth.saturating_sub(1) as usize];
+    let mut entries: Vec<PerftEntry> = moves
+        .iter()
+        .map(|mv| {
+            let undo = position.make_move(mv);
+            let nodes = count(&mut position, depth.saturating_sub(1), &mut move_lists);
+            position.unmake_move(undo);
+            PerftEntry { mv: mv.to_uci(), nodes }
+        })
+        .collect();
+    entries.sort_by(|a, b| a.mv.cmp(&b.mv));
+
+    let nodes = entries.iter().map(|entry| entry.nodes).sum();
+    PerftDivide { moves: entries, nodes }
+}
+
+/// `move_lists` holds one reusable list per remaining ply
+fn count(position: &mut Position, depth: u8, move_lists: &mut [Vec<Move>]) -> u64 {
+    let Some((moves, deeper)) = move_lists.split_first_mut() else {
+        return 1;
+    };
+    generate_legal_moves_into(position, moves);
+    if depth == 1 {
+        return moves.len() as u64;
+    }
+
+    let mut nodes = 0;
+    for mv in moves.iter() {
+        let undo = position.make_move(mv);
+        nodes += count(position, depth - 1, deeper);
+        position.unmake_move(undo);
+    }
+    nodes
+}
+
+/// Same as `count`, with subtree counts of two plies or more kept in `table`
+fn count_hashed(position: &mut Position, depth: u8, move_lists: &mut [Vec<Move>], table: &mut PerftTable) -> u64 {
+    if depth < 2 {
+        return count(position, depth, move_lists);
+    }
+
+    let hash = position.hash();
+    if let Some(nodes) = table.probe(hash, depth) {
+        return nodes;
+    }
+
+    let (moves, deeper) = move_lists.split_first_mut().expect("one move list per ply");
+    generate_legal_moves_into(position, moves);
+    let mut nodes = 0;
+    for mv in moves.iter() {
+        let undo = position.make_move(mv);
+        nodes += count_hashed(position, depth - 1, deeper, table);
+        position.unmake_move(undo);
+    }
+
+    table.store(hash, depth, nodes);
+    nodes
+}
+
+#[derive(Clone, Copy, Default)]
+struct TableEntry {
+    key: u64,
+    nodes: u64,
+    /// Zero marks an empty entry; only depths of two or more are stored
+    depth: u8,
+}
+
+/// Always-replace table of subtree node counts keyed by Zobrist hash and depth
+struct PerftTable {
+    entries: Vec<TableEntry>,
+}
+
+impl PerftTable {
+    /// The largest power-of-two table that fits in `megabytes`
+    fn with_megabytes(megabytes: usize) -> Self {
+        let size = (megabytes * 1024 * 1024 / ENTRY_BYTES).max(1);
+        PerftTable {
+            entries: vec![TableEntry::default(); 1 << size.ilog2()],
+        }
+    }
+
+    fn probe(&self, key: u64, depth: u8) -> Option<u64> {
+        let entry = &self.entries[self.slot(key, depth)];
+        (entry.key == key && entry.depth == depth).then_some(entry.nodes)
+    }
+
+    fn store(&mut self, key: u64, depth: u8, nodes: u64) {
+        let slot = self.slot(key, depth);
+        self.entries[slot] = TableEntry { key, nodes, depth };
+    }
+
+    /// Depths get different slots so a position's counts at several depths can all be kept
+    fn slot(&self, key: u64, depth: u8) -> usize {
+        (key ^ u64::from(depth).wrapping_mul(0x9E37_79B9_7F4A_7C15)) as usize & (self.entries.len() - 1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chess_engine::fen::parse_fen;
+
+    #[test]
+    fn test_divide_adds_up_to_perft() {
+        let position = parse_fen("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1").unwrap();
+        let divide = perft_divide(&position, 2);
+        assert_eq!(divide.moves.len(), 48);
+        assert_eq!(divide.nodes, 2039);
+        assert_eq!(divide.nodes, perft(&position, 2));
+
+        let castle = divide.moves.iter().find(|entry| entry.mv == "e1g1").unwrap();
+        assert_eq!(castle.nodes, 43);
+    }
+
+    #[test]
+    fn test_hashed_matches_plain() {
+        let position = parse_fen("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1").unwrap();
+        assert_eq!(perft_hashed(&position, 3, 1), 97862);
+
+        // A table too small to keep anything useful still gives the right count
+        let position = Position::new();
+        assert_eq!(perft_hashed(&position, 4, 0), 197281);
+        assert_eq!(perft_hashed(&position, 0, 1), 1);
+        assert_eq!(perft_hashed(&position, 1, 1), 20);
+    }
+
+    #[test]
+    fn test_depth_zero() {
+        let position = Position::new();
+        assert_eq!(perft(&position, 0), 1);
+        let divide = perft_divide(&position, 1);
+        assert_eq!(divide.nodes, 20);
+        assert!(divide.moves.iter().all(|entry| entry.nodes == 1));
+    }
+}
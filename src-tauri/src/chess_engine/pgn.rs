@@ -0,0 +1,614 @@
+use crate::chess_engine::error::{ChessError, Result};
+use crate::chess_engine::history::{MoveNode, MoveTree};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::io::BufRead;
+
+/// A game parsed from PGN: tag pairs, the main line with its variations and the result
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PgnGame {
+    pub tags: Vec<(String, String)>,
+    /// Comment before the first move
+    pub comment: Option<String>,
+    pub moves: Vec<PgnMove>,
+    pub result: Option<String>,
+}
+
+/// A SAN move with its annotations and the variations (RAV) that replace it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PgnMove {
+    pub san: String,
+    /// Line of the PGN text the move was read from, starting at 1 (0 if not parsed from text)
+    pub line: usize,
+    pub comment: Option<String>,
+    pub nags: Vec<u8>,
+    pub variations: Vec<Vec<PgnMove>>,
+}
+
+pub const RESULTS: [&str; 4] = ["1-0", "0-1", "1/2-1/2", "*"];
+
+/// Move suffix glyphs and their Numeric Annotation Glyph codes
+const GLYPHS: [(&str, u8); 6] = [("!", 1), ("?", 2), ("!!", 3), ("??", 4), ("!?", 5), ("?!", 6)];
+
+/// Movetext lines are wrapped before this many characters
+const MAX_LINE_LENGTH: usize = 80;
+
+/// Characters of PGN text, counting lines as they are consumed
+struct Chars<'a> {
+    chars: std::iter::Peekable<std::str::Chars<'a>>,
+    line: usize,
+}
+
+impl<'a> Chars<'a> {
+    fn new(text: &'a str) -> Self {
+        Chars {
+            chars: text.chars().peekable(),
+            line: 1,
+        }
+    }
+
+    fn peek(&mut self) -> Option<&char> {
+        self.chars.peek()
+    }
+
+    fn next_if(&mut self, accept: impl FnOnce(&char) -> bool) -> Option<char> {
+        if self.chars.peek().is_some_and(accept) {
+            self.next()
+        } else {
+            None
+        }
+    }
+
+    fn error(&self, reason: &'static str) -> PgnSyntaxError {
+        PgnSyntaxError { line: self.line, reason }
+    }
+}
+
+impl Iterator for Chars<'_> {
+    type Item = char;
+
+    fn next(&mut self) -> Option<char> {
+        let c = self.chars.next()?;
+        if c == '\n' {
+            self.line += 1;
+        }
+        Some(c)
+    }
+}
+
+/// Where and why PGN text could not be parsed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PgnSyntaxError {
+    pub line: usize,
+    pub reason: &'static str,
+}
+
+impl From<PgnSyntaxError> for ChessError {
+    fn from(error: PgnSyntaxError) -> Self {
+        ChessError::ParseError {
+            input: format!("PGN {} on line {}", error.reason, error.line),
+        }
+    }
+}
+
+impl PgnGame {
+    /// Parse a single PGN game, including nested variations, comments and NAGs
+    pub fn parse(text: &str) -> Result<PgnGame> {
+        Ok(Self::parse_syntax(text)?)
+    }
+
+    /// Like [`PgnGame::parse`], reporting the line a syntax error was found on
+    pub fn parse_syntax(text: &str) -> std::result::Result<PgnGame, PgnSyntaxError> {
+        let mut game = PgnGame::default();
+        let mut chars = Chars::new(text);
+        game.moves = parse_line(&mut chars, &mut game, 0)?;
+        Ok(game)
+    }
+
+    /// Build a game from a move tree: `children[0]` continues a line, other children become variations
+    pub fn from_tree(tags: Vec<(String, String)>, tree: &MoveTree, result: &str) -> PgnGame {
+        let moves = match tree.moves.split_first() {
+            Some((first, alternatives)) => pgn_line(first, alternatives),
+            None => Vec::new(),
+        };
+
+        PgnGame {
+            tags,
+            comment: tree.comment.clone(),
+            moves,
+            result: Some(result.to_string()),
+        }
+    }
+
+    /// Value of a tag pair, e.g. `tag("White")`
+    pub fn tag(&self, name: &str) -> Option<&str> {
+        self.tags
+            .iter()
+            .find(|(tag, _)| tag == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// Half-move index of the first move (0 = White's first move), taken from the FEN tag
+    fn first_ply(&self) -> u32 {
+        let Some(fen) = self.tag("FEN") else {
+            return 0;
+        };
+        let fields: Vec<&str> = fen.split_whitespace().collect();
+        let fullmove = fields.get(5).and_then(|n| n.parse::<u32>().ok()).unwrap_or(1).max(1);
+        let black_to_move = fields.get(1) == Some(&"b");
+        (fullmove - 1) * 2 + black_to_move as u32
+    }
+}
+
+/// Headline information about one game of a PGN database
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PgnSummary {
+    /// Position of the game in the file, starting at 0
+    pub index: usize,
+    pub tags: Vec<(String, String)>,
+    /// Number of half-moves in the main line
+    pub ply_count: usize,
+    pub result: Option<String>,
+    /// Set when the game could not be parsed; the other fields are then empty
+    pub error: Option<String>,
+}
+
+impl PgnSummary {
+    fn new(index: usize, game: Result<PgnGame>) -> Self {
+        match game {
+            Ok(game) => PgnSummary {
+                index,
+                ply_count: game.moves.len(),
+                tags: game.tags,
+                result: game.result,
+                error: None,
+            },
+            Err(e) => PgnSummary {
+                index,
+                tags: Vec::new(),
+                ply_count: 0,
+                result: None,
+                error: Some(e.to_string()),
+            },
+        }
+    }
+}
+
+/// Reads the games of a multi-game PGN file one at a time, so only the game
+/// being parsed is held in memory
+pub struct PgnReader<R: BufRead> {
+    reader: R,
+    pending_line: Option<String>,
+}
+
+impl<R: BufRead> PgnReader<R> {
+    pub fn new(reader: R) -> Self {
+        PgnReader {
+            reader,
+            pending_line: None,
+        }
+    }
+
+    /// Raw text of the next game: its tag section followed by movetext, ending
+    /// where the next tag section begins
+    fn next_game_text(&mut self) -> Result<Option<String>> {
+        let mut text = String::new();
+        let mut seen_movetext = false;
+
+        loop {
+            let line = match self.pending_line.take() {
+                Some(line) => line,
+                None => {
+                    let mut line = String::new();
+                    let read = self.reader.read_line(&mut line).map_err(|e| ChessError::Io {
+                        reason: e.to_string(),
+                    })?;
+                    if read == 0 {
+                        break;
+                    }
+                    line
+                }
+            };
+
+            let trimmed = line.trim_start_matches('\u{feff}').trim();
+            if trimmed.starts_with('%') {
+                continue;
+            }
+            if trimmed.starts_with('[') && seen_movetext {
+                self.pending_line = Some(line);
+                break;
+            }
+            if !trimmed.is_empty() && !trimmed.starts_with('[') {
+                seen_movetext = true;
+            }
+            text.push_str(&line);
+        }
+
+        Ok(if text.trim().is_empty() { None } else { Some(text) })
+    }
+
+    /// Summaries of every remaining game; unparsable games are reported through
+    /// `PgnSummary::error` instead of stopping the import
+    pub fn summaries(mut self) -> Result<Vec<PgnSummary>> {
+        let mut summaries = Vec::new();
+        while let Some(text) = self.next_game_text()? {
+            summaries.push(PgnSummary::new(summaries.len(), PgnGame::parse(&text)));
+        }
+        Ok(summaries)
+    }
+}
+
+impl<R: BufRead> Iterator for PgnReader<R> {
+    type Item = Result<PgnGame>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self.next_game_text() {
+            Ok(Some(text)) => Some(PgnGame::parse(&text)),
+            Ok(None) => None,
+            Err(e) => Some(Err(e)),
+        }
+    }
+}
+
+impl fmt::Display for PgnGame {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (name, value) in &self.tags {
+            writeln!(f, "[{} \"{}\"]", name, value.replace('\\', "\\\\").replace('"', "\\\""))?;
+        }
+        if !self.tags.is_empty() {
+            writeln!(f)?;
+        }
+
+        let mut tokens = Vec::new();
+        if let Some(comment) = &self.comment {
+            push_comment(&mut tokens, comment);
+        }
+        write_line(&mut tokens, &self.moves, self.first_ply());
+        tokens.push(self.result.clone().unwrap_or_else(|| "*".to_string()));
+
+        let mut line_length = 0;
+        for token in tokens {
+            if line_length > 0 && line_length + 1 + token.len() > MAX_LINE_LENGTH {
+                writeln!(f)?;
+                line_length = 0;
+            }
+            if line_length > 0 {
+                write!(f, " ")?;
+                line_length += 1;
+            }
+            write!(f, "{}", token)?;
+            line_length += token.len();
+        }
+        writeln!(f)
+    }
+}
+
+/// Parse moves until the end of input (top level) or the closing ')' of a variation
+fn parse_line(chars: &mut Chars, game: &mut PgnGame, depth: usize) -> std::result::Result<Vec<PgnMove>, PgnSyntaxError> {
+    let mut line: Vec<PgnMove> = Vec::new();
+
+    while let Some(&c) = chars.peek() {
+        match c {
+            c if c.is_whitespace() => {
+                chars.next();
+            }
+            '[' => {
+                chars.next();
+                game.tags.push(parse_tag(chars)?);
+            }
+            '{' => {
+                chars.next();
+                let mut text = String::new();
+                loop {
+                    match chars.next() {
+                        Some('}') => break,
+                        Some(c) => text.push(c),
+                        None => return Err(chars.error("unterminated comment")),
+                    }
+                }
+                let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+
+                // Comments before the first move of a variation have nowhere to go and are dropped
+                let target = match line.last_mut() {
+                    Some(mv) => Some(&mut mv.comment),
+                    None if depth == 0 => Some(&mut game.comment),
+                    None => None,
+                };
+                if let Some(comment) = target {
+                    *comment = match comment.take() {
+                        Some(existing) => Some(format!("{} {}", existing, text)),
+                        None => Some(text),
+                    };
+                }
+            }
+            '}' => {
+                chars.next();
+                return Err(chars.error("unbalanced '}'"));
+            }
+            ';' => {
+                chars.find(|&c| c == '\n');
+            }
+            '(' => {
+                chars.next();
+                let variation = parse_line(chars, game, depth + 1)?;
+                line.last_mut()
+                    .ok_or_else(|| chars.error("variation before any move"))?
+                    .variations
+                    .push(variation);
+            }
+            ')' => {
+                if depth == 0 {
+                    return Err(chars.error("unbalanced ')'"));
+                }
+                chars.next();
+                return Ok(line);
+            }
+            '$' => {
+                chars.next();
+                let mut digits = String::new();
+                while let Some(c) = chars.next_if(|c| c.is_ascii_digit()) {
+                    digits.push(c);
+                }
+                let nag = digits.parse().map_err(|_| chars.error("invalid NAG"))?;
+                if let Some(mv) = line.last_mut() {
+                    mv.nags.push(nag);
+                }
+            }
+            _ => {
+                let token_line = chars.line;
+                let mut token = String::new();
+                while let Some(c) = chars.next_if(|c| !c.is_whitespace() && !"{}();[$".contains(*c)) {
+                    token.push(c);
+                }
+
+                if RESULTS.contains(&token.as_str()) {
+                    if depth == 0 {
+                        game.result = Some(token);
+                    }
+                    continue;
+                }
+
+                // Move numbers: "12." and "12..." alone or glued to the move ("12.e4")
+                let san = match token.rfind('.') {
+                    Some(dot) if token.starts_with(|c: char| c.is_ascii_digit()) => &token[dot + 1..],
+                    _ => token.as_str(),
+                };
+                if san.chars().all(|c| c.is_ascii_digit()) {
+                    continue;
+                }
+
+                // Suffix glyphs ("e4!?") or standalone ones ("e4 !?") become NAGs
+                let move_text = san.trim_end_matches(['!', '?']);
+                let nag = GLYPHS
+                    .iter()
+                    .find(|(glyph, _)| *glyph == &san[move_text.len()..])
+                    .map(|(_, nag)| *nag);
+
+                if !move_text.is_empty() {
+                    line.push(PgnMove {
+                        san: move_text.to_string(),
+                        line: token_line,
+                        comment: None,
+                        nags: Vec::new(),
+                        variations: Vec::new(),
+                    });
+                }
+                if let (Some(nag), Some(mv)) = (nag, line.last_mut()) {
+                    mv.nags.push(nag);
+                }
+            }
+        }
+    }
+
+    if depth > 0 {
+        return Err(chars.error("unterminated variation"));
+    }
+    Ok(line)
+}
+
+/// Parse `Name "Value"]` (the opening bracket is already consumed)
+fn parse_tag(chars: &mut Chars) -> std::result::Result<(String, String), PgnSyntaxError> {
+    let mut name = String::new();
+    while let Some(c) = chars.next_if(|c| *c != '"' && *c != ']') {
+        name.push(c);
+    }
+
+    if chars.next() != Some('"') {
+        return Err(chars.error("tag without a quoted value"));
+    }
+
+    let mut value = String::new();
+    loop {
+        match chars.next() {
+            Some('\\') => value.extend(chars.next()),
+            Some('"') => break,
+            Some(c) => value.push(c),
+            None => return Err(chars.error("unterminated tag value")),
+        }
+    }
+
+    chars.find(|&c| c == ']').ok_or_else(|| chars.error("unterminated tag"))?;
+    Ok((name.trim().to_string(), value))
+}
+
+/// A line starting with `first`, where `alternatives` are the variations to it
+fn pgn_line(first: &MoveNode, alternatives: &[MoveNode]) -> Vec<PgnMove> {
+    let mut line = vec![PgnMove {
+        san: first.san.clone(),
+        line: 0,
+        comment: first.comment.clone(),
+        nags: first.nags.clone(),
+        variations: alternatives.iter().map(|alternative| pgn_line(alternative, &[])).collect(),
+    }];
+
+    if let Some((next, rest)) = first.children.split_first() {
+        line.extend(pgn_line(next, rest));
+    }
+    line
+}
+
+/// Movetext tokens for a line starting at the given half-move index
+fn write_line(tokens: &mut Vec<String>, line: &[PgnMove], first_ply: u32) {
+    let mut needs_number = true;
+
+    for (offset, mv) in line.iter().enumerate() {
+        let ply = first_ply + offset as u32;
+        if ply.is_multiple_of(2) {
+            tokens.push(format!("{}.", ply / 2 + 1));
+        } else if needs_number {
+            tokens.push(format!("{}...", ply / 2 + 1));
+        }
+        // The first move-assessment NAG is written as a suffix glyph, others as $n
+        let suffix = mv.nags.first().and_then(|nag| GLYPHS.iter().find(|(_, code)| code == nag));
+        match suffix {
+            Some((glyph, _)) => tokens.push(format!("{}{}", mv.san, glyph)),
+            None => tokens.push(mv.san.clone()),
+        }
+        let other_nags = if suffix.is_some() { &mv.nags[1..] } else { &mv.nags[..] };
+        tokens.extend(other_nags.iter().map(|nag| format!("${}", nag)));
+
+        needs_number = false;
+        if let Some(comment) = &mv.comment {
+            push_comment(tokens, comment);
+            needs_number = true;
+        }
+
+        for variation in &mv.variations {
+            let start = tokens.len();
+            write_line(tokens, variation, ply);
+            if tokens.len() > start {
+                tokens[start].insert(0, '(');
+                tokens.last_mut().unwrap().push(')');
+                needs_number = true;
+            }
+        }
+    }
+}
+
+/// Comment words as separate tokens so long comments wrap like movetext
+fn push_comment(tokens: &mut Vec<String>, comment: &str) {
+    let words: Vec<&str> = comment.split_whitespace().collect();
+    if words.is_empty() {
+        tokens.push("{}".to_string());
+        return;
+    }
+
+    let start = tokens.len();
+    tokens.extend(words.iter().map(|word| word.replace('}', ")")));
+    tokens[start].insert(0, '{');
+    tokens.last_mut().unwrap().push('}');
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sans(line: &[PgnMove]) -> Vec<&str> {
+        line.iter().map(|mv| mv.san.as_str()).collect()
+    }
+
+    #[test]
+    fn test_parse_tags_and_moves() {
+        let pgn = r#"[Event "Casual \"blitz\""]
+[White "Alice"]
+[Black "Bob"]
+[Result "0-1"]
+
+1. f3 e5 2. g4 $4 {blunder} Qh4# 0-1"#;
+
+        let game = PgnGame::parse(pgn).unwrap();
+        assert_eq!(game.tag("White"), Some("Alice"));
+        assert_eq!(game.tag("Event"), Some("Casual \"blitz\""));
+        assert_eq!(game.tag("Site"), None);
+        assert_eq!(sans(&game.moves), vec!["f3", "e5", "g4", "Qh4#"]);
+        assert_eq!(game.result.as_deref(), Some("0-1"));
+    }
+
+    #[test]
+    fn test_parse_nested_variations() {
+        let pgn = "1.e4 e5 (1...c5 2.Nf3 (2.c3) d6) 2.Nf3 ; line comment\n2...Nc6 3.Bb5!? *";
+
+        let game = PgnGame::parse(pgn).unwrap();
+        assert_eq!(sans(&game.moves), vec!["e4", "e5", "Nf3", "Nc6", "Bb5"]);
+        assert_eq!(game.result.as_deref(), Some("*"));
+
+        let sicilian = &game.moves[1].variations[0];
+        assert_eq!(sans(sicilian), vec!["c5", "Nf3", "d6"]);
+        assert_eq!(sans(&sicilian[1].variations[0]), vec!["c3"]);
+    }
+
+    #[test]
+    fn test_write_round_trip() {
+        let pgn = "[White \"Alice\"]\n\n1. e4 e5 (1... c5 2. Nf3 (2. c3) 2... d6) 2. Nf3 Nc6 1-0\n";
+
+        let game = PgnGame::parse(pgn).unwrap();
+        assert_eq!(game.to_string(), pgn);
+        assert_eq!(PgnGame::parse(&game.to_string()).unwrap(), game);
+    }
+
+    #[test]
+    fn test_comments_and_nags() {
+        let pgn = "{Opening} 1. e4! {Best} e5 $6 2. Nf3 !? (2. Qh5 {Risky} $2) 2... Nc6 $14 *";
+
+        let game = PgnGame::parse(pgn).unwrap();
+        assert_eq!(game.comment.as_deref(), Some("Opening"));
+        assert_eq!(game.moves[0].nags, vec![1]);
+        assert_eq!(game.moves[0].comment.as_deref(), Some("Best"));
+        assert_eq!(game.moves[1].nags, vec![6]);
+        assert_eq!(game.moves[2].nags, vec![5]);
+        assert_eq!(game.moves[2].variations[0][0].comment.as_deref(), Some("Risky"));
+        assert_eq!(game.moves[2].variations[0][0].nags, vec![2]);
+        assert_eq!(game.moves[3].nags, vec![14]);
+
+        let written = game.to_string();
+        assert_eq!(written, "{Opening} 1. e4! {Best} 1... e5?! 2. Nf3!? (2. Qh5? {Risky}) 2... Nc6 $14 *\n");
+        assert_eq!(PgnGame::parse(&written).unwrap(), game);
+    }
+
+    #[test]
+    fn test_reader_splits_games() {
+        let database = "[Event \"One\"]\n[Result \"1-0\"]\n\n1. e4 e5 2. Qh5 Nc6\n3. Bc4 Nf6 4. Qxf7# 1-0\n\n\
+                        [Event \"Two\"]\n\n1. d4 {unterminated\n\n\
+                        [Event \"Three\"]\n[Result \"*\"]\n1. c4 *\n\n\
+                        [Event \"Four\"]\n\n1. e4 } e5 *\n";
+
+        let games: Vec<Result<PgnGame>> = PgnReader::new(database.as_bytes()).collect();
+        assert_eq!(games.len(), 4);
+        assert!(games[3].is_err());
+        assert_eq!(games[0].as_ref().unwrap().moves.len(), 7);
+        assert!(games[1].is_err());
+        assert_eq!(games[2].as_ref().unwrap().tag("Event"), Some("Three"));
+
+        let summaries = PgnReader::new(database.as_bytes()).summaries().unwrap();
+        assert_eq!(summaries.len(), 4);
+        assert_eq!(summaries[0].ply_count, 7);
+        assert_eq!(summaries[0].result.as_deref(), Some("1-0"));
+        assert!(summaries[1].error.is_some());
+        assert_eq!(summaries[2].index, 2);
+        assert_eq!(summaries[2].tags[0], ("Event".to_string(), "Three".to_string()));
+    }
+
+    #[test]
+    fn test_move_numbers_follow_fen_tag() {
+        let game = PgnGame {
+            tags: vec![("FEN".to_string(), "4k3/8/8/8/8/8/8/R3K3 b - - 0 12".to_string())],
+            comment: None,
+            moves: PgnGame::parse("Kd7 Ra7+").unwrap().moves,
+            result: None,
+        };
+
+        assert!(game.to_string().ends_with("\n\n12... Kd7 13. Ra7+ *\n"));
+    }
+
+    #[test]
+    fn test_malformed_pgn() {
+        assert!(PgnGame::parse("[White \"Alice]").is_err());
+        assert!(PgnGame::parse("1. e4 {unterminated").is_err());
+        assert!(PgnGame::parse("1. e4 (1. d4").is_err());
+        assert!(PgnGame::parse("(1. d4) 1. e4").is_err());
+        assert_eq!(
+            PgnGame::parse_syntax("1. e4 } e5"),
+            Err(PgnSyntaxError { line: 1, reason: "unbalanced '}'" })
+        );
+    }
+}
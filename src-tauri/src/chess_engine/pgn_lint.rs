@@ -0,0 +1,222 @@
+use crate::chess_engine::fen::parse_fen;
+use crate::chess_engine::pgn::{PgnGame, PgnMove, RESULTS};
+use crate::chess_engine::position::Position;
+use crate::chess_engine::types::{Color, Move};
+use crate::chess_engine::validation::{is_checkmate, is_stalemate, position_after_move};
+use serde::{Deserialize, Serialize};
+
+/// Tags every PGN game should carry, in their conventional order
+const SEVEN_TAG_ROSTER: [&str; 7] = ["Event", "Site", "Date", "Round", "White", "Black", "Result"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Severity {
+    /// The game cannot be loaded
+    Error,
+    /// The game loads, but something in it is wrong or missing
+    Warning,
+}
+
+/// A problem found in PGN text
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PgnProblem {
+    pub severity: Severity,
+    /// Line of the text, starting at 1, when the problem belongs to one
+    pub line: Option<usize>,
+    /// Half-move of the offending move counted from the game's first move, starting at 1
+    pub ply: Option<usize>,
+    pub message: String,
+}
+
+/// Checks a single PGN game without loading it: syntax, tag pairs, the legality of
+/// every move (variations included) and whether the result agrees with the final position
+pub fn lint_pgn(text: &str) -> Vec<PgnProblem> {
+    let mut problems = Vec::new();
+    let game = match PgnGame::parse_syntax(text) {
+        Ok(game) => game,
+        Err(error) => {
+            problems.push(problem(Severity::Error, Some(error.line), None, format!("PGN {}", error.reason)));
+            return problems;
+        }
+    };
+
+    let start = lint_tags(text, &game, &mut problems);
+    let end = start.and_then(|start| lint_line(&start, &game.moves, 1, &mut problems));
+
+    let tag_result = game.tag("Result");
+    match (tag_result, game.result.as_deref()) {
+        (_, None) => problems.push(problem(Severity::Warning, None, None, "movetext does not end with a result".to_string())),
+        (Some(tag), Some(movetext)) if tag != movetext => problems.push(problem(
+            Severity::Warning,
+            None,
+            None,
+            format!("Result tag \"{}\" differs from the movetext result \"{}\"", tag, movetext),
+        )),
+        _ => {}
+    }
+
+    if let (Some(end), Some(result)) = (end, game.result.as_deref().or(tag_result)) {
+        let expected = if is_checkmate(&end) {
+            Some(match end.side_to_move {
+                Color::White => ("0-1", "White is checkmated"),
+                Color::Black => ("1-0", "Black is checkmated"),
+            })
+        } else if is_stalemate(&end) {
+            Some(("1/2-1/2", "the game ends in stalemate"))
+        } else {
+            None
+        };
+
+        if let Some((expected, reason)) = expected.filter(|(expected, _)| *expected != result) {
+            problems.push(problem(
+                Severity::Warning,
+                None,
+                None,
+                format!("result \"{}\" but {}, so it should be \"{}\"", result, reason, expected),
+            ));
+        }
+    }
+
+    problems
+}
+
+/// Tag pair problems; returns the starting position unless the FEN tag is unusable
+fn lint_tags(text: &str, game: &PgnGame, problems: &mut Vec<PgnProblem>) -> Option<Position> {
+    // Lines of the tag pairs, if every tag is on a line of its own
+    let lines: Vec<usize> = text
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| line.trim_start().starts_with('['))
+        .map(|(index, _)| index + 1)
+        .collect();
+    let line_of = |index: usize| (lines.len() == game.tags.len()).then(|| lines[index]);
+
+    for (index, (name, value)) in game.tags.iter().enumerate() {
+        let mut warn = |message: String| problems.push(problem(Severity::Warning, line_of(index), None, message));
+
+        if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+            warn(format!("invalid tag name \"{}\"", name));
+        }
+        if game.tags[..index].iter().any(|(earlier, _)| earlier == name) {
+            warn(format!("duplicate {} tag", name));
+        }
+        match name.as_str() {
+            "Date" if !is_pgn_date(value) => warn(format!("Date \"{}\" is not in YYYY.MM.DD form", value)),
+            "Result" if !RESULTS.contains(&value.as_str()) => warn(format!("invalid Result \"{}\"", value)),
+            "FEN" if game.tag("SetUp") != Some("1") => warn("FEN tag without SetUp \"1\"".to_string()),
+            _ => {}
+        }
+    }
+
+    let missing: Vec<&str> = SEVEN_TAG_ROSTER.into_iter().filter(|name| game.tag(name).is_none()).collect();
+    if !missing.is_empty() {
+        problems.push(problem(Severity::Warning, None, None, format!("missing tags: {}", missing.join(", "))));
+    }
+
+    let Some(fen) = game.tag("FEN") else {
+        return Some(Position::new());
+    };
+    match parse_fen(fen) {
+        Ok(position) => Some(position),
+        Err(e) => {
+            let index = game.tags.iter().position(|(name, _)| name == "FEN");
+            problems.push(problem(Severity::Error, index.and_then(line_of), None, e.to_string()));
+            None
+        }
+    }
+}
+
+/// Replays a line and its variations, reporting the first impossible move of each;
+/// returns the final position if the whole line is legal
+fn lint_line(start: &Position, moves: &[PgnMove], first_ply: usize, problems: &mut Vec<PgnProblem>) -> Option<Position> {
+    let mut position = start.clone();
+    for (offset, pgn_move) in moves.iter().enumerate() {
+        let ply = first_ply + offset;
+        for variation in &pgn_move.variations {
+            lint_line(&position, variation, ply, problems);
+        }
+
+        match Move::from_san(&pgn_move.san, &position) {
+            Ok(mv) => position = position_after_move(&position, &mv),
+            Err(e) => {
+                problems.push(problem(Severity::Error, Some(pgn_move.line), Some(ply), format!("{}: {}", pgn_move.san, e)));
+                return None;
+            }
+        }
+    }
+    Some(position)
+}
+
+/// "YYYY.MM.DD" where unknown digits may be '?'
+fn is_pgn_date(value: &str) -> bool {
+    value.len() == 10
+        && value.char_indices().all(|(index, c)| match index {
+            4 | 7 => c == '.',
+            _ => c.is_ascii_digit() || c == '?',
+        })
+}
+
+fn problem(severity: Severity, line: Option<usize>, ply: Option<usize>, message: String) -> PgnProblem {
+    PgnProblem { severity, line, ply, message }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TAGS: &str = "[Event \"?\"]\n[Site \"?\"]\n[Date \"2024.05.??\"]\n[Round \"?\"]\n[White \"?\"]\n[Black \"?\"]\n";
+
+    #[test]
+    fn test_clean_game() {
+        let pgn = format!("{}[Result \"0-1\"]\n\n1. f3 e5 (1... d5) 2. g4 Qh4# 0-1\n", TAGS);
+        assert_eq!(lint_pgn(&pgn), Vec::new());
+    }
+
+    #[test]
+    fn test_syntax_error_line() {
+        let problems = lint_pgn("[Event \"?\"]\n\n1. e4 e5\n2. Nf3 {never closed\n");
+        assert_eq!(problems.len(), 1);
+        assert_eq!(problems[0].severity, Severity::Error);
+        assert_eq!(problems[0].line, Some(5));
+
+        let problems = lint_pgn("[Event \"?\"]\n\n1. e4 } e5 *\n");
+        assert_eq!(problems.len(), 1);
+        assert_eq!((problems[0].severity, problems[0].line), (Severity::Error, Some(3)));
+        assert_eq!(problems[0].message, "PGN unbalanced '}'");
+    }
+
+    #[test]
+    fn test_illegal_moves_in_line_and_variation() {
+        let pgn = format!("{}[Result \"*\"]\n\n1. e4 e5 (1... d5 2. Ke3)\n2. Nf3 Nc6 3. Bb6 *", TAGS);
+        let problems = lint_pgn(&pgn);
+
+        let errors: Vec<(Option<usize>, Option<usize>)> = problems
+            .iter()
+            .filter(|p| p.severity == Severity::Error)
+            .map(|p| (p.line, p.ply))
+            .collect();
+        assert_eq!(errors, vec![(Some(9), Some(3)), (Some(10), Some(5))]);
+    }
+
+    #[test]
+    fn test_tag_and_result_warnings() {
+        let pgn = "[Event \"?\"]\n[Date \"May 2024\"]\n[Event \"again\"]\n[Result \"1-0\"]\n\n1. f3 e5 2. g4 Qh4# 1-0";
+        let messages: Vec<(Option<usize>, String)> = lint_pgn(pgn).into_iter().map(|p| (p.line, p.message)).collect();
+
+        assert!(messages.contains(&(Some(2), "Date \"May 2024\" is not in YYYY.MM.DD form".to_string())));
+        assert!(messages.contains(&(Some(3), "duplicate Event tag".to_string())));
+        assert!(messages.contains(&(None, "missing tags: Site, Round, White, Black".to_string())));
+        assert!(messages.iter().any(|(_, message)| message.contains("White is checkmated")));
+
+        let mismatch = format!("{}[Result \"1-0\"]\n\n1. e4 *", TAGS);
+        assert!(lint_pgn(&mismatch)[0].message.contains("differs"));
+    }
+
+    #[test]
+    fn test_bad_fen_tag() {
+        let pgn = format!("{}[Result \"*\"]\n[SetUp \"1\"]\n[FEN \"8/8/8 w - - 0 1\"]\n\n1. e4 *", TAGS);
+        let problems = lint_pgn(&pgn);
+
+        assert_eq!(problems.len(), 1);
+        assert_eq!((problems[0].severity, problems[0].line), (Severity::Error, Some(9)));
+    }
+}
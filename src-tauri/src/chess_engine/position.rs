@@ -1,7 +1,10 @@
 use crate::chess_engine::board::Board;
-use crate::chess_engine::types::{Color, Piece, Square, Move};
+use crate::chess_engine::rules::Variant;
+use crate::chess_engine::types::{Color, GameStatus, Piece, Square, Move};
+use crate::chess_engine::validation::generate_legal_moves;
 use serde::{Deserialize, Serialize};
-use once_cell::sync::Lazy;
+use once_cell::sync::{Lazy, OnceCell};
+use std::collections::HashMap;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub struct CastlingRights {
@@ -9,6 +12,14 @@ pub struct CastlingRights {
     pub white_queenside: bool,
     pub black_kingside: bool,
     pub black_queenside: bool,
+    /// Files of the castling rooks in the same order as the rights, which Chess960 moves
+    /// away from the corners
+    #[serde(default = "corner_rook_files")]
+    pub rook_files: [u8; 4],
+}
+
+fn corner_rook_files() -> [u8; 4] {
+    [7, 0, 7, 0]
 }
 
 impl CastlingRights {
@@ -18,6 +29,7 @@ impl CastlingRights {
             white_queenside: true,
             black_kingside: true,
             black_queenside: true,
+            rook_files: corner_rook_files(),
         }
     }
 
@@ -27,6 +39,7 @@ impl CastlingRights {
             white_queenside: false,
             black_kingside: false,
             black_queenside: false,
+            rook_files: corner_rook_files(),
         }
     }
 
@@ -38,6 +51,139 @@ impl CastlingRights {
             (Color::Black, false) => self.black_queenside,
         }
     }
+
+    /// Grants or takes away castling on one side, with the rook on `rook_file`
+    pub fn set(&mut self, color: Color, kingside: bool, allowed: bool, rook_file: u8) {
+        let index = castling_index(color, kingside);
+        match index {
+            0 => self.white_kingside = allowed,
+            1 => self.white_queenside = allowed,
+            2 => self.black_kingside = allowed,
+            _ => self.black_queenside = allowed,
+        }
+        self.rook_files[index] = rook_file;
+    }
+
+    /// Square of the rook `color` castles with on that side, while it still may
+    pub fn rook_square(&self, color: Color, kingside: bool) -> Option<Square> {
+        if !self.can_castle(color, kingside) {
+            return None;
+        }
+        let rank = if color == Color::White { 0 } else { 7 };
+        Square::from_rank_file(rank, self.rook_files[castling_index(color, kingside)])
+    }
+}
+
+fn castling_index(color: Color, kingside: bool) -> usize {
+    color_index(color) * 2 + usize::from(!kingside)
+}
+
+/// Captured pieces each side holds in hand to drop back onto the board, in variants
+/// that have pockets
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Pockets {
+    /// Pawns, knights, bishops, rooks and queens, in that order
+    white: [u8; 5],
+    black: [u8; 5],
+}
+
+impl Pockets {
+    /// Pieces that can go into a pocket; a king is never captured
+    pub const PIECES: [Piece; 5] = [Piece::Pawn, Piece::Knight, Piece::Bishop, Piece::Rook, Piece::Queen];
+
+    pub fn count(&self, color: Color, piece: Piece) -> u8 {
+        pocket_index(piece).map_or(0, |index| self.side(color)[index])
+    }
+
+    pub fn add(&mut self, color: Color, piece: Piece) {
+        if let Some(index) = pocket_index(piece) {
+            let count = &mut self.side_mut(color)[index];
+            *count = count.saturating_add(1);
+        }
+    }
+
+    /// Takes a piece out for a drop; false if there was none to take
+    pub fn remove(&mut self, color: Color, piece: Piece) -> bool {
+        match pocket_index(piece).map(|index| &mut self.side_mut(color)[index]) {
+            Some(count) if *count > 0 => {
+                *count -= 1;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.white == [0; 5] && self.black == [0; 5]
+    }
+
+    fn side(&self, color: Color) -> &[u8; 5] {
+        match color {
+            Color::White => &self.white,
+            Color::Black => &self.black,
+        }
+    }
+
+    fn side_mut(&mut self, color: Color) -> &mut [u8; 5] {
+        match color {
+            Color::White => &mut self.white,
+            Color::Black => &mut self.black,
+        }
+    }
+}
+
+fn pocket_index(piece: Piece) -> Option<usize> {
+    Pockets::PIECES.iter().position(|&p| p == piece)
+}
+
+/// What [`Position::make_move`] changed, for [`Position::unmake_move`] to put back
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Undo {
+    mv: Move,
+    moved: Option<(Piece, Color)>,
+    captured: Option<(Piece, Color)>,
+    castling_rights: CastlingRights,
+    en_passant_target: Option<Square>,
+    halfmove_clock: u32,
+    fullmove_number: u32,
+    pockets: Pockets,
+    promoted: u64,
+    checks_given: [u8; 2],
+}
+
+/// A position as sent to the frontend: what it takes to draw the board and the game's
+/// status, without the repetition history that grows with every move
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BoardState {
+    pub board: Board,
+    pub side_to_move: Color,
+    pub castling_rights: CastlingRights,
+    pub en_passant_target: Option<Square>,
+    pub halfmove_clock: u32,
+    pub fullmove_number: u32,
+    /// Rules the game is played under, so the frontend knows which pieces and rules to show
+    #[serde(default)]
+    pub variant: Variant,
+    pub pockets: Pockets,
+    pub checks_given: [u8; 2],
+    pub status: GameStatus,
+}
+
+impl BoardState {
+    pub fn new(position: &Position, status: GameStatus) -> Self {
+        BoardState {
+            board: position.board.clone(),
+            side_to_move: position.side_to_move,
+            castling_rights: position.castling_rights,
+            en_passant_target: position.en_passant_target,
+            halfmove_clock: position.halfmove_clock,
+            fullmove_number: position.fullmove_number,
+            variant: position.variant,
+            pockets: position.pockets,
+            checks_given: position.checks_given,
+            status,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -48,7 +194,24 @@ pub struct Position {
     pub en_passant_target: Option<Square>,
     pub halfmove_clock: u32,
     pub fullmove_number: u32,
+    /// Rules the position is played under; saves from before variants are standard chess
+    #[serde(default)]
+    pub variant: Variant,
+    /// Pieces in hand, for variants where captured pieces can be dropped back in
+    #[serde(default)]
+    pub pockets: Pockets,
+    /// Squares of pieces that were promoted from pawns, which go back into a pocket as
+    /// pawns when captured
+    #[serde(default)]
+    pub promoted: u64,
+    /// Checks given by White and by Black, in variants that count them
+    #[serde(default)]
+    pub checks_given: [u8; 2],
     pub position_history: Vec<u64>,
+    /// How often each position since the last pawn move or capture has occurred, built
+    /// from `position_history` on first use. Earlier positions can never come back.
+    #[serde(skip)]
+    repetitions: OnceCell<HashMap<u64, u8>>,
 }
 
 impl Position {
@@ -60,11 +223,15 @@ impl Position {
             en_passant_target: None,
             halfmove_clock: 0,
             fullmove_number: 1,
+            variant: Variant::Standard,
+            pockets: Pockets::default(),
+            promoted: 0,
+            checks_given: [0, 0],
             position_history: Vec::new(),
+            repetitions: OnceCell::new(),
         };
 
-        let hash = position.compute_zobrist_hash();
-        position.position_history.push(hash);
+        position.record_position();
         position
     }
 
@@ -76,7 +243,24 @@ impl Position {
             en_passant_target: None,
             halfmove_clock: 0,
             fullmove_number: 1,
+            variant: Variant::Standard,
+            pockets: Pockets::default(),
+            promoted: 0,
+            checks_given: [0, 0],
             position_history: Vec::new(),
+            repetitions: OnceCell::new(),
+        }
+    }
+
+    /// Zobrist hash that counts the en passant square only when an en passant capture is
+    /// legal, so a position hashes alike whether or not a double push just led to it
+    pub fn position_key(&self) -> u64 {
+        let hash = self.compute_zobrist_hash();
+        match self.en_passant_target {
+            Some(square) if !generate_legal_moves(self).iter().any(|mv| mv.is_en_passant) => {
+                hash ^ ZOBRIST_EN_PASSANT[square.file() as usize]
+            }
+            _ => hash,
         }
     }
 
@@ -126,55 +310,298 @@ impl Position {
             hash ^= *ZOBRIST_SIDE_TO_MOVE;
         }
 
+        // Hash checks given
+        for (color_index, &checks) in self.checks_given.iter().enumerate() {
+            if checks > 0 {
+                hash ^= ZOBRIST_CHECKS[color_index].rotate_left(u32::from(checks));
+            }
+        }
+
+        // Hash the variant, so the evaluation cache and transposition table keep a
+        // position's scores under different rules apart
+        if self.variant != Variant::Standard {
+            hash ^= ZOBRIST_VARIANT.rotate_left(self.variant as u32);
+        }
+
+        // Hash pieces in hand
+        for (color_index, color) in [Color::White, Color::Black].into_iter().enumerate() {
+            for (piece_index, piece) in Pockets::PIECES.into_iter().enumerate() {
+                let count = self.pockets.count(color, piece);
+                if count > 0 {
+                    hash ^= ZOBRIST_POCKETS[color_index][piece_index].rotate_left(u32::from(count));
+                }
+            }
+        }
+
         hash
     }
 
-    pub fn is_repetition(&self) -> bool {
-        if self.position_history.len() < 3 {
-            return false;
+    /// Zobrist hash of the pawns alone, for caching terms that depend only on pawn structure
+    pub fn compute_pawn_hash(&self) -> u64 {
+        let mut hash = 0u64;
+        for (color_index, color) in [Color::White, Color::Black].into_iter().enumerate() {
+            for (square, piece) in self.board.pieces_of_color(color) {
+                if piece == Piece::Pawn {
+                    hash ^= ZOBRIST_PIECES[square.index() as usize][color_index][0];
+                }
+            }
+        }
+        hash
+    }
+
+    /// Plays a legal move in place, updating the board, castling rights, en passant
+    /// target, clocks, side to move and repetition history, and returns what
+    /// [`unmake_move`](Self::unmake_move) needs to take it back
+    pub fn make_move(&mut self, mv: &Move) -> Undo {
+        let captured_square = if mv.is_en_passant {
+            Square::from_rank_file(mv.from.rank(), mv.to.file()).unwrap_or(mv.to)
+        } else {
+            mv.to
+        };
+        let undo = Undo {
+            mv: *mv,
+            moved: self.board.get(mv.from),
+            captured: if mv.is_null || mv.is_castling { None } else { self.board.get(captured_square) },
+            castling_rights: self.castling_rights,
+            en_passant_target: self.en_passant_target,
+            halfmove_clock: self.halfmove_clock,
+            fullmove_number: self.fullmove_number,
+            pockets: self.pockets,
+            promoted: self.promoted,
+            checks_given: self.checks_given,
+        };
+
+        if mv.is_null {
+            self.pass_turn();
+            return undo;
         }
+        self.update_castling_rights_after_move(mv);
+        self.update_pockets_after_move(mv, undo.captured, captured_square);
+
+        let moving_pawn = matches!(undo.moved, Some((Piece::Pawn, _))) || mv.drop == Some(Piece::Pawn);
+        // A horde pawn stepping two squares off the first rank cannot be taken en passant
+        let double_step = mv.from.rank().abs_diff(mv.to.rank()) == 2 && matches!(mv.from.rank(), 1 | 6);
+        self.en_passant_target = if moving_pawn && double_step {
+            Square::from_rank_file((mv.from.rank() + mv.to.rank()) / 2, mv.from.file())
+        } else {
+            None
+        };
 
-        let current_hash = self.position_history.last().unwrap();
-        let mut count = 0;
+        if moving_pawn || undo.captured.is_some() {
+            self.halfmove_clock = 0;
+        } else {
+            self.halfmove_clock += 1;
+        }
 
-        for hash in &self.position_history {
-            if hash == current_hash {
-                count += 1;
-                if count >= 3 {
-                    return true;
+        if self.side_to_move == Color::Black {
+            self.fullmove_number += 1;
+        }
+
+        self.board.apply_move_for(mv, self.side_to_move);
+        self.side_to_move = self.side_to_move.opposite();
+        if self.variant.rules().counts_checks() && self.in_check() {
+            self.checks_given[color_index(self.side_to_move.opposite())] += 1;
+        }
+
+        self.record_position();
+        undo
+    }
+
+    /// Takes back the move `undo` came from, which must be the last one made
+    pub fn unmake_move(&mut self, undo: Undo) {
+        let mv = undo.mv;
+        self.forget_position();
+        self.side_to_move = self.side_to_move.opposite();
+        self.castling_rights = undo.castling_rights;
+        self.en_passant_target = undo.en_passant_target;
+        self.halfmove_clock = undo.halfmove_clock;
+        self.fullmove_number = undo.fullmove_number;
+        self.pockets = undo.pockets;
+        self.promoted = undo.promoted;
+        self.checks_given = undo.checks_given;
+
+        if mv.is_null {
+            return;
+        }
+        if mv.drop.is_some() {
+            self.board.set(mv.to, None);
+            return;
+        }
+
+        if let Some((rook_from, rook_to)) = mv.castling_rook_squares() {
+            let rook = self.board.get(rook_to);
+            self.board.set(mv.to, None);
+            self.board.set(rook_to, None);
+            self.board.set(rook_from, rook);
+            self.board.set(mv.from, undo.moved);
+            return;
+        }
+
+        self.board.set(mv.from, undo.moved);
+        if mv.is_en_passant {
+            self.board.set(mv.to, None);
+            if let Some(captured_square) = Square::from_rank_file(mv.from.rank(), mv.to.file()) {
+                self.board.set(captured_square, undo.captured);
+            }
+        } else {
+            self.board.set(mv.to, undo.captured);
+        }
+    }
+
+    /// Takes a dropped piece out of the mover's pocket, puts a captured piece into it when
+    /// the variant has pockets, and follows promoted pieces to their new squares
+    fn update_pockets_after_move(&mut self, mv: &Move, captured: Option<(Piece, Color)>, captured_square: Square) {
+        let color = self.side_to_move;
+        if let Some(piece) = mv.drop {
+            self.pockets.remove(color, piece);
+            return;
+        }
+
+        let captured_bit = 1u64 << captured_square.index();
+        if let Some((piece, _)) = captured {
+            if self.variant.rules().uses_pockets() {
+                let demoted = if self.promoted & captured_bit != 0 { Piece::Pawn } else { piece };
+                self.pockets.add(color, demoted);
+            }
+        }
+        self.promoted &= !captured_bit;
+
+        let from_bit = 1u64 << mv.from.index();
+        if self.promoted & from_bit != 0 || mv.promotion.is_some() {
+            self.promoted = (self.promoted & !from_bit) | 1u64 << mv.to.index();
+        }
+    }
+
+    /// Checks `color` has given so far, in variants that count them
+    pub fn checks_given(&self, color: Color) -> u8 {
+        self.checks_given[color_index(color)]
+    }
+
+    fn in_check(&self) -> bool {
+        self.board
+            .find_king(self.side_to_move)
+            .is_some_and(|king| self.board.is_attacked_by(king, self.side_to_move.opposite()))
+    }
+
+    /// Hands the move to the other side without moving a piece, as a null move does
+    pub fn pass_turn(&mut self) {
+        self.en_passant_target = None;
+        self.halfmove_clock += 1;
+        if self.side_to_move == Color::Black {
+            self.fullmove_number += 1;
+        }
+        self.side_to_move = self.side_to_move.opposite();
+
+        self.record_position();
+    }
+
+    /// Adds the current position to the repetition history; called once the board,
+    /// side to move and clocks are all updated
+    pub fn record_position(&mut self) {
+        let hash = self.compute_zobrist_hash();
+        self.position_history.push(hash);
+        if self.halfmove_clock == 0 {
+            self.repetitions = OnceCell::new();
+        } else if let Some(counts) = self.repetitions.get_mut() {
+            let count = counts.entry(hash).or_insert(0);
+            *count = count.saturating_add(1);
+        }
+    }
+
+    /// Removes the last position from the repetition history, before the clocks are restored
+    fn forget_position(&mut self) {
+        let Some(hash) = self.position_history.pop() else {
+            return;
+        };
+        if self.halfmove_clock == 0 {
+            // The earlier positions are back in play; count them again when next asked
+            self.repetitions = OnceCell::new();
+        } else if let Some(counts) = self.repetitions.get_mut() {
+            match counts.get_mut(&hash) {
+                Some(count) if *count > 1 => *count -= 1,
+                _ => {
+                    counts.remove(&hash);
                 }
             }
         }
+    }
 
-        false
+    /// Zobrist hash of the current position, from the history when it has been recorded
+    pub fn hash(&self) -> u64 {
+        self.position_history
+            .last()
+            .copied()
+            .unwrap_or_else(|| self.compute_zobrist_hash())
+    }
+
+    fn repetitions(&self) -> &HashMap<u64, u8> {
+        self.repetitions.get_or_init(|| {
+            let reversible = (self.halfmove_clock as usize + 1).min(self.position_history.len());
+            let mut counts = HashMap::new();
+            for &hash in &self.position_history[self.position_history.len() - reversible..] {
+                let count = counts.entry(hash).or_insert(0u8);
+                *count = count.saturating_add(1);
+            }
+            counts
+        })
+    }
+
+    pub fn is_repetition(&self) -> bool {
+        self.repetition_count() >= 3
+    }
+
+    /// How many times the current position has occurred, this occurrence included
+    pub fn repetition_count(&self) -> usize {
+        let Some(current_hash) = self.position_history.last() else {
+            return 1;
+        };
+        self.repetitions().get(current_hash).map_or(1, |&count| count as usize)
     }
 
     pub fn has_insufficient_material(&self) -> bool {
-        let white_pieces = self.board.pieces_of_color(Color::White);
-        let black_pieces = self.board.pieces_of_color(Color::Black);
+        // A piece in hand can always be dropped back in
+        if !self.pockets.is_empty() {
+            return false;
+        }
+
+        // Without a king to mate, or with a king that wins on its own, the game is won
+        // some other way
+        let rules = self.variant.rules();
+        if !rules.has_king(Color::White) || !rules.has_king(Color::Black) || rules.king_goal() {
+            return false;
+        }
+
+        let white_count = self.board.occupancy(Color::White).count_ones();
+        let black_count = self.board.occupancy(Color::Black).count_ones();
+        let has_minor = |color| {
+            self.board
+                .pieces_of_color(color)
+                .any(|(_, p)| p == Piece::Bishop || p == Piece::Knight)
+        };
 
         // K vs K
-        if white_pieces.len() == 1 && black_pieces.len() == 1 {
+        if white_count == 1 && black_count == 1 {
             return true;
         }
 
+        // Where checks win, any piece left can still give them
+        if self.variant.rules().counts_checks() {
+            return false;
+        }
+
         // K+B vs K or K+N vs K
-        if white_pieces.len() == 1 && black_pieces.len() == 2 {
-            if black_pieces.iter().any(|(_, p)| *p == Piece::Bishop || *p == Piece::Knight) {
-                return true;
-            }
+        if white_count == 1 && black_count == 2 && has_minor(Color::Black) {
+            return true;
         }
 
-        if black_pieces.len() == 1 && white_pieces.len() == 2 {
-            if white_pieces.iter().any(|(_, p)| *p == Piece::Bishop || *p == Piece::Knight) {
-                return true;
-            }
+        if black_count == 1 && white_count == 2 && has_minor(Color::White) {
+            return true;
         }
 
         // K+B vs K+B with same color bishops
-        if white_pieces.len() == 2 && black_pieces.len() == 2 {
-            let white_has_bishop = white_pieces.iter().find(|(_, p)| *p == Piece::Bishop);
-            let black_has_bishop = black_pieces.iter().find(|(_, p)| *p == Piece::Bishop);
+        if white_count == 2 && black_count == 2 {
+            let white_has_bishop = self.board.pieces_of_color(Color::White).find(|(_, p)| *p == Piece::Bishop);
+            let black_has_bishop = self.board.pieces_of_color(Color::Black).find(|(_, p)| *p == Piece::Bishop);
 
             if let (Some((white_sq, _)), Some((black_sq, _))) = (white_has_bishop, black_has_bishop) {
                 // Check if bishops are on same color squares
@@ -190,55 +617,25 @@ impl Position {
     }
 
     pub fn update_castling_rights_after_move(&mut self, mv: &Move) {
-        // If king moves, remove all castling rights for that color
-        if let Some((Piece::King, color)) = self.board.get(mv.from) {
-            match color {
-                Color::White => {
-                    self.castling_rights.white_kingside = false;
-                    self.castling_rights.white_queenside = false;
-                }
-                Color::Black => {
-                    self.castling_rights.black_kingside = false;
-                    self.castling_rights.black_queenside = false;
-                }
-            }
-        }
-
-        // If rook moves from starting position, remove that castling right
-        if let Some((Piece::Rook, color)) = self.board.get(mv.from) {
-            match (color, mv.from.index()) {
-                (Color::White, 0) => self.castling_rights.white_queenside = false,
-                (Color::White, 7) => self.castling_rights.white_kingside = false,
-                (Color::Black, 56) => self.castling_rights.black_queenside = false,
-                (Color::Black, 63) => self.castling_rights.black_kingside = false,
-                _ => {}
+        let mover = self.board.get(mv.from);
+        for (color, kingside) in [(Color::White, true), (Color::White, false), (Color::Black, true), (Color::Black, false)] {
+            let Some(rook_square) = self.castling_rights.rook_square(color, kingside) else {
+                continue;
+            };
+            // A king move gives up both sides; a rook leaving or taken on its square, just its own
+            let king_moved = matches!(mover, Some((Piece::King, c)) if c == color);
+            if king_moved || mv.from == rook_square || mv.to == rook_square {
+                self.castling_rights.set(color, kingside, false, rook_square.file());
             }
         }
+    }
+}
 
-        // If a rook is captured on its starting square, remove that castling right
-        match mv.to.index() {
-            0 => {
-                if matches!(self.board.get(mv.to), Some((Piece::Rook, Color::White))) {
-                    self.castling_rights.white_queenside = false;
-                }
-            }
-            7 => {
-                if matches!(self.board.get(mv.to), Some((Piece::Rook, Color::White))) {
-                    self.castling_rights.white_kingside = false;
-                }
-            }
-            56 => {
-                if matches!(self.board.get(mv.to), Some((Piece::Rook, Color::Black))) {
-                    self.castling_rights.black_queenside = false;
-                }
-            }
-            63 => {
-                if matches!(self.board.get(mv.to), Some((Piece::Rook, Color::Black))) {
-                    self.castling_rights.black_kingside = false;
-                }
-            }
-            _ => {}
-        }
+/// Index of `color` in arrays kept per side
+fn color_index(color: Color) -> usize {
+    match color {
+        Color::White => 0,
+        Color::Black => 1,
     }
 }
 
@@ -269,6 +666,27 @@ static ZOBRIST_EN_PASSANT: Lazy<[u64; 8]> = Lazy::new(|| {
     ]
 });
 
+static ZOBRIST_POCKETS: Lazy<[[u64; 5]; 2]> = Lazy::new(|| {
+    let mut rng = ZobristRng::new(741852963);
+    let mut table = [[0u64; 5]; 2];
+    for side in table.iter_mut() {
+        for key in side.iter_mut() {
+            *key = rng.next();
+        }
+    }
+    table
+});
+
+static ZOBRIST_CHECKS: Lazy<[u64; 2]> = Lazy::new(|| {
+    let mut rng = ZobristRng::new(369258147);
+    [rng.next(), rng.next()]
+});
+
+static ZOBRIST_VARIANT: Lazy<u64> = Lazy::new(|| {
+    let mut rng = ZobristRng::new(852741963);
+    rng.next()
+});
+
 static ZOBRIST_SIDE_TO_MOVE: Lazy<u64> = Lazy::new(|| {
     let mut rng = ZobristRng::new(321654987);
     rng.next()
@@ -289,3 +707,69 @@ impl ZobristRng {
         self.state
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chess_engine::fen::{parse_fen, position_to_fen};
+    use crate::chess_engine::validation::generate_legal_moves;
+
+    #[test]
+    fn test_unmake_restores_every_move() {
+        // Castling both ways, en passant, promotions with and without capture
+        let fens = [
+            "r3k2r/pPppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "r3k2r/8/8/8/3pP3/8/8/R3K2R b KQkq e3 0 1",
+            // Crazyhouse drops, and captures that fill the pockets
+            "r3k2r/pPppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R[Nq] w KQkq - 0 1",
+        ];
+        for fen in fens {
+            let mut position = parse_fen(fen).unwrap();
+            let board = position.board.clone();
+            let history = position.position_history.clone();
+            for mv in generate_legal_moves(&position) {
+                let undo = position.make_move(&mv);
+                assert_ne!(position_to_fen(&position), fen);
+                position.unmake_move(undo);
+                assert_eq!(position_to_fen(&position), fen, "after {}", mv.to_uci());
+                assert_eq!(position.board, board);
+                assert_eq!(position.position_history, history);
+            }
+        }
+    }
+
+    #[test]
+    fn test_repetition_counts_follow_make_and_unmake() {
+        let mut position = Position::new();
+        let mut undos = Vec::new();
+        for uci in ["g1f3", "g8f6", "f3g1", "f6g8", "g1f3", "g8f6", "f3g1", "f6g8"] {
+            let mv = Move::from_uci(uci, &position).unwrap();
+            undos.push(position.make_move(&mv));
+            assert!(position.repetition_count() <= 3);
+        }
+        assert_eq!(position.repetition_count(), 3);
+
+        position.unmake_move(undos.pop().unwrap());
+        assert_eq!(position.repetition_count(), 2);
+
+        // A pawn move starts the count afresh, and taking it back restores it
+        let undo = position.make_move(&Move::from_uci("e7e5", &position).unwrap());
+        assert_eq!(position.repetition_count(), 1);
+        position.unmake_move(undo);
+        assert_eq!(position.repetition_count(), 2);
+    }
+
+    #[test]
+    fn test_position_key_ignores_en_passant_without_a_capture() {
+        // After 1. e4 no black pawn can take en passant
+        let with_target = parse_fen("rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1").unwrap();
+        let without = parse_fen("rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq - 0 1").unwrap();
+        assert_ne!(with_target.compute_zobrist_hash(), without.compute_zobrist_hash());
+        assert_eq!(with_target.position_key(), without.position_key());
+
+        // With a pawn on d4 the capture is there, and it makes a different position
+        let with_target = parse_fen("rnbqkbnr/ppp1pppp/8/8/3pP3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1").unwrap();
+        let without = parse_fen("rnbqkbnr/ppp1pppp/8/8/3pP3/8/PPPP1PPP/RNBQKBNR b KQkq - 0 1").unwrap();
+        assert_ne!(with_target.position_key(), without.position_key());
+    }
+}
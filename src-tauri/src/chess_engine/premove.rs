@@ -0,0 +1,21 @@
+use crate::chess_engine::types::{Color, GameStatus, Piece, Square};
+use serde::{Deserialize, Serialize};
+
+/// A move queued by `color` while it is the opponent's turn, played as soon as the
+/// turn comes back if it is legal then
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Premove {
+    pub color: Color,
+    pub from: Square,
+    pub to: Square,
+    pub promotion: Option<Piece>,
+}
+
+/// What happened to a premove once its side was to move
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum PremoveOutcome {
+    Played { premove: Premove, san: String, status: GameStatus },
+    /// The premove was illegal in the new position (or the game had ended) and was dropped
+    Discarded { premove: Premove, reason: String },
+}
@@ -0,0 +1,507 @@
+use crate::chess_engine::error::{ChessError, Result};
+use crate::chess_engine::fen::{parse_fen, position_to_fen};
+use crate::chess_engine::game::ChessGame;
+use crate::chess_engine::magic::XorShift;
+use crate::chess_engine::position::Position;
+use crate::chess_engine::types::{GameStatus, Move};
+use crate::chess_engine::validation::position_after_move;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::hash_map::RandomState;
+use std::hash::BuildHasher;
+use std::time::{Duration, Instant};
+
+/// A tactics puzzle: the solver moves first from `fen`, then sides alternate
+/// through `solution`, whose odd entries are the opponent's replies
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Puzzle {
+    pub id: String,
+    pub fen: String,
+    /// Solution in UCI notation, starting with the solver's first move
+    pub solution: Vec<String>,
+    /// The opponent's move that set up the puzzle, for highlighting
+    pub last_move: Option<String>,
+    pub rating: Option<u32>,
+    pub themes: Vec<String>,
+}
+
+/// What happened to a move submitted in puzzle mode
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum PuzzleOutcome {
+    /// Correct so far; the opponent answered with `reply`
+    Correct { reply: String },
+    /// The last move of the solution was found
+    Solved,
+    /// Wrong move; the puzzle is over and the move was not played
+    Failed { expected: String },
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PuzzleStats {
+    pub attempted: u32,
+    pub solved: u32,
+    pub failed: u32,
+}
+
+/// Puzzle mode: a queue of puzzles, the one being solved and the running score
+#[derive(Debug, Clone, Default)]
+pub struct PuzzleSession {
+    puzzles: Vec<Puzzle>,
+    next: usize,
+    active: Option<(Puzzle, usize)>,
+    stats: PuzzleStats,
+    rush: Option<Rush>,
+}
+
+/// Which puzzles a puzzle rush draws from
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PuzzleFilter {
+    /// Lichess theme names ("fork", "mateIn2", ...), of which a puzzle needs at least
+    /// one; empty allows every puzzle
+    #[serde(default)]
+    pub themes: Vec<String>,
+    #[serde(default)]
+    pub min_rating: Option<u32>,
+    #[serde(default)]
+    pub max_rating: Option<u32>,
+}
+
+impl PuzzleFilter {
+    /// Whether `puzzle` has one of the themes and a rating in range; a puzzle without a
+    /// rating only passes when no range is set
+    pub fn matches(&self, puzzle: &Puzzle) -> bool {
+        let themed = self.themes.is_empty() || puzzle.themes.iter().any(|theme| self.themes.contains(theme));
+        let rated = match puzzle.rating {
+            Some(rating) => {
+                self.min_rating.is_none_or(|min| rating >= min) && self.max_rating.is_none_or(|max| rating <= max)
+            }
+            None => self.min_rating.is_none() && self.max_rating.is_none(),
+        };
+        themed && rated
+    }
+}
+
+/// A timed run through puzzles that ends when the clock runs out or too many are missed
+#[derive(Debug, Clone)]
+struct Rush {
+    started: Instant,
+    duration: Duration,
+}
+
+/// How a puzzle rush is going
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PuzzleRushStatus {
+    pub remaining_ms: u64,
+    /// Puzzles solved, the rush's score
+    pub solved: u32,
+    pub mistakes: u32,
+    /// The time is up or the mistakes allowed are used up
+    pub over: bool,
+}
+
+/// Wrong answers that end a puzzle rush
+pub const RUSH_MISTAKES_ALLOWED: u32 = 3;
+
+/// Width of the rating bands a rush climbs through; order within a band is random
+const RUSH_RATING_BAND: u32 = 200;
+
+/// Puzzles to fall back on when none are loaded: id, position, solution (UCI),
+/// rating and Lichess themes, in the database's CSV layout with the setup move first
+const BUILT_IN_PUZZLES: &[&str] = &[
+    "rush01,4k3/8/8/q7/8/8/3R4/4K3 b - - 0 1,a5d5 d2d5,500,75,90,100,hangingPiece oneMove endgame,,",
+    "rush02,r5k1/5ppp/8/8/8/8/8/1R4K1 b - - 0 1,a8a2 b1b8,600,75,90,100,mate mateIn1 backRankMate oneMove endgame,,",
+    "rush03,r1bqkbnr/pppp1ppp/2n5/4p2Q/2B1P3/8/PPPP1PPP/RNB1K1NR b KQkq - 3 3,g8f6 h5f7,650,75,90,100,mate mateIn1 opening oneMove,,",
+    "rush04,1q2k3/8/8/8/2N5/8/8/4K3 b - - 0 1,b8b7 c4d6 e8e7 d6b7,900,75,90,100,fork short endgame,,",
+    "rush05,q7/8/1k6/8/8/3B4/8/6K1 b - - 0 1,b6c6 d3e4 c6c5 e4a8,1000,75,90,100,skewer short endgame,,",
+    "rush06,6k1/2p2ppp/8/8/8/8/r4PPP/1R4K1 b - - 0 1,a2a1 b1a1 c7c6 a1a8,1100,75,90,100,mate mateIn2 backRankMate endgame,,",
+    "rush07,3k4/8/3p4/4n3/8/8/5P2/4RK2 b - - 0 1,d8e8 f2f4 e8f7 f4e5,1200,75,90,100,pin short endgame,,",
+    "rush08,5k2/8/8/7q/3N4/8/8/B5K1 b - - 0 1,h5h8 d4e6 f8f7 a1h8,1300,75,90,100,discoveredAttack short endgame,,",
+];
+
+/// The puzzles that come with the engine
+pub fn built_in_puzzles() -> Vec<Puzzle> {
+    BUILT_IN_PUZZLES
+        .iter()
+        .map(|line| Puzzle::from_csv(line).expect("built-in puzzles are valid"))
+        .collect()
+}
+
+/// The puzzles of `pool` that pass `filter`, shuffled by `seed` and then ordered by
+/// rating band, so a rush gets harder as it goes without running in a fixed order
+pub fn rush_sequence(pool: &[Puzzle], filter: &PuzzleFilter, seed: u64) -> Vec<Puzzle> {
+    let mut puzzles: Vec<Puzzle> = pool.iter().filter(|puzzle| filter.matches(puzzle)).cloned().collect();
+
+    let mut random = XorShift(seed | 1);
+    for i in (1..puzzles.len()).rev() {
+        puzzles.swap(i, (random.next() % (i as u64 + 1)) as usize);
+    }
+    puzzles.sort_by_key(|puzzle| puzzle.rating.unwrap_or(0) / RUSH_RATING_BAND);
+    puzzles
+}
+
+impl Puzzle {
+    /// One row of the Lichess puzzle database:
+    /// `PuzzleId,FEN,Moves,Rating,RatingDeviation,Popularity,NbPlays,Themes,GameUrl,OpeningTags`.
+    /// The FEN is the position before the opponent's setup move, which is `Moves`' first entry.
+    pub fn from_csv(line: &str) -> Result<Puzzle> {
+        let fields: Vec<&str> = line.trim().split(',').collect();
+        if fields.len() < 3 {
+            return Err(parse_error(format!("puzzle CSV needs id, FEN and moves: {}", line)));
+        }
+
+        let moves: Vec<String> = fields[2].split_whitespace().map(str::to_string).collect();
+        let (setup, solution) = moves
+            .split_first()
+            .ok_or_else(|| parse_error(format!("puzzle {} has no moves", fields[0])))?;
+
+        let start = parse_fen(fields[1])?;
+        let setup_move = Move::from_uci(setup, &start)?;
+        let position = position_after_move(&start, &setup_move);
+
+        Puzzle::new(
+            fields[0].to_string(),
+            &position,
+            solution.to_vec(),
+            Some(setup.clone()),
+            fields.get(3).and_then(|rating| rating.parse().ok()),
+            fields.get(7).map(|themes| themes.split_whitespace().map(str::to_string).collect()).unwrap_or_default(),
+        )
+    }
+
+    /// A puzzle as served by the Lichess API (`/api/puzzle/daily`, `/api/puzzle/{id}`):
+    /// the game's PGN leads to the puzzle position and `puzzle.solution` continues from there
+    pub fn from_lichess_json(value: &Value) -> Result<Puzzle> {
+        let puzzle = &value["puzzle"];
+        let pgn = value["game"]["pgn"]
+            .as_str()
+            .ok_or_else(|| parse_error("puzzle JSON needs game.pgn".to_string()))?;
+        let strings = |field: &Value| -> Vec<String> {
+            field
+                .as_array()
+                .map(|items| items.iter().filter_map(|item| item.as_str().map(str::to_string)).collect())
+                .unwrap_or_default()
+        };
+
+        let game = ChessGame::from_pgn(pgn)?;
+        let last_move = game
+            .move_tree()
+            .nodes_along(&game.move_tree().current)
+            .and_then(|nodes| nodes.last().map(|node| node.mv.to_uci()));
+
+        Puzzle::new(
+            puzzle["id"].as_str().unwrap_or_default().to_string(),
+            game.get_board_state(),
+            strings(&puzzle["solution"]),
+            last_move,
+            puzzle["rating"].as_u64().and_then(|rating| u32::try_from(rating).ok()),
+            strings(&puzzle["themes"]),
+        )
+    }
+
+    /// Checks that the solution is playable from the position
+    fn new(
+        id: String,
+        position: &Position,
+        solution: Vec<String>,
+        last_move: Option<String>,
+        rating: Option<u32>,
+        themes: Vec<String>,
+    ) -> Result<Puzzle> {
+        if solution.is_empty() {
+            return Err(parse_error(format!("puzzle {} has no solution", id)));
+        }
+
+        let mut current = position.clone();
+        for uci in &solution {
+            let mv = Move::from_uci(uci, &current)?;
+            current = position_after_move(&current, &mv);
+        }
+
+        Ok(Puzzle {
+            id,
+            fen: position_to_fen(position),
+            solution,
+            last_move,
+            rating,
+            themes,
+        })
+    }
+}
+
+/// Parses Lichess puzzles given either as database CSV rows (an optional header,
+/// one puzzle per line) or as API JSON (one object or an array of them)
+pub fn parse_puzzles(text: &str) -> Result<Vec<Puzzle>> {
+    let trimmed = text.trim_start();
+    if trimmed.starts_with('{') || trimmed.starts_with('[') {
+        let value: Value = serde_json::from_str(trimmed).map_err(|e| parse_error(e.to_string()))?;
+        return match value {
+            Value::Array(items) => items.iter().map(Puzzle::from_lichess_json).collect(),
+            object => Ok(vec![Puzzle::from_lichess_json(&object)?]),
+        };
+    }
+
+    text.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with("PuzzleId"))
+        .map(Puzzle::from_csv)
+        .collect()
+}
+
+impl PuzzleSession {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds puzzles to the end of the queue
+    pub fn load(&mut self, puzzles: Vec<Puzzle>) {
+        self.puzzles.extend(puzzles);
+    }
+
+    /// Number of puzzles not started yet
+    pub fn remaining(&self) -> usize {
+        self.puzzles.len() - self.next
+    }
+
+    pub fn stats(&self) -> PuzzleStats {
+        self.stats
+    }
+
+    /// Starts a puzzle rush of `duration` through the built-in and loaded puzzles that
+    /// pass `filter`, in random order and rising difficulty. The score starts over and
+    /// the queue is replaced by the rush's puzzles; play goes on with [`Self::next_puzzle`].
+    pub fn start_rush(&mut self, filter: &PuzzleFilter, duration: Duration) -> Result<PuzzleRushStatus> {
+        let mut pool = built_in_puzzles();
+        let loaded: Vec<Puzzle> = self.puzzles.iter().filter(|puzzle| !pool.contains(puzzle)).cloned().collect();
+        pool.extend(loaded);
+        let puzzles = rush_sequence(&pool, filter, RandomState::new().hash_one(()));
+        if puzzles.is_empty() {
+            return Err(ChessError::InvalidOption {
+                name: "filter".to_string(),
+                reason: "No puzzles match the themes and ratings asked for".to_string(),
+            });
+        }
+
+        *self = PuzzleSession {
+            puzzles,
+            rush: Some(Rush {
+                started: Instant::now(),
+                duration,
+            }),
+            ..PuzzleSession::default()
+        };
+        Ok(self.rush_status().expect("a rush was just started"))
+    }
+
+    /// The running puzzle rush's clock and score, if one was started
+    pub fn rush_status(&self) -> Option<PuzzleRushStatus> {
+        let rush = self.rush.as_ref()?;
+        let remaining = rush.duration.saturating_sub(rush.started.elapsed());
+        Some(PuzzleRushStatus {
+            remaining_ms: remaining.as_millis() as u64,
+            solved: self.stats.solved,
+            mistakes: self.stats.failed,
+            over: remaining.is_zero() || self.stats.failed >= RUSH_MISTAKES_ALLOWED,
+        })
+    }
+
+    fn check_rush_running(&self) -> Result<()> {
+        match self.rush_status() {
+            Some(status) if status.over => Err(ChessError::GameOver {
+                status: "Puzzle rush is over".to_string(),
+            }),
+            _ => Ok(()),
+        }
+    }
+
+    /// Starts the next puzzle, replacing `game` with its position. An unfinished
+    /// puzzle being skipped counts as failed.
+    pub fn next_puzzle(&mut self, game: &mut ChessGame) -> Result<Option<Puzzle>> {
+        self.check_rush_running()?;
+        let Some(puzzle) = self.puzzles.get(self.next).cloned() else {
+            return Ok(None);
+        };
+
+        *game = ChessGame::from_fen(&puzzle.fen)?;
+        if self.active.take().is_some() {
+            self.stats.failed += 1;
+        }
+        self.next += 1;
+        self.stats.attempted += 1;
+        self.active = Some((puzzle.clone(), 0));
+        Ok(Some(puzzle))
+    }
+
+    /// Checks the solver's move against the solution. A correct move is played on
+    /// `game` together with the opponent's reply. Any checkmate is accepted in place
+    /// of the solution's final move.
+    pub fn submit_move(&mut self, game: &mut ChessGame, uci: &str) -> Result<PuzzleOutcome> {
+        let (puzzle, progress) = self.active.as_ref().ok_or_else(|| ChessError::InvalidMove {
+            reason: "No puzzle in progress".to_string(),
+        })?;
+        let (puzzle, progress) = (puzzle.clone(), *progress);
+        self.check_rush_running()?;
+
+        let mv = Move::from_uci(uci, game.get_board_state())?;
+        let expected = &puzzle.solution[progress];
+        let is_last = progress + 1 >= puzzle.solution.len();
+
+        let mut after = game.clone();
+        after.make_move(mv)?;
+        let mates = matches!(after.get_status(), GameStatus::Checkmate { .. });
+
+        if mv.to_uci() != *expected && !(is_last && mates) {
+            self.active = None;
+            self.stats.failed += 1;
+            return Ok(PuzzleOutcome::Failed {
+                expected: expected.clone(),
+            });
+        }
+
+        *game = after;
+        if !is_last {
+            let reply = puzzle.solution[progress + 1].clone();
+            game.make_uci_move(&reply)?;
+
+            // A solution may end on the opponent's move
+            if progress + 2 < puzzle.solution.len() {
+                self.active = Some((puzzle, progress + 2));
+                return Ok(PuzzleOutcome::Correct { reply });
+            }
+        }
+
+        self.active = None;
+        self.stats.solved += 1;
+        Ok(PuzzleOutcome::Solved)
+    }
+}
+
+fn parse_error(input: String) -> ChessError {
+    ChessError::ParseError { input }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CSV: &str = "PuzzleId,FEN,Moves,Rating,RatingDeviation,Popularity,NbPlays,Themes,GameUrl,OpeningTags
+00008,r6k/pp2r2p/4Rp1Q/3p4/8/1N1P2R1/PqP2bPP/7K b - - 0 24,f2g3 e6e7 b2b1 b3c1 b1c1 h6c1,1913,75,94,6230,crushing hangingPiece long middlegame,https://lichess.org/787zsVup/black#48,
+000aY,6k1/2p2ppp/8/8/8/8/r4PPP/1R4K1 b - - 0 1,a2a1 b1a1 c7c6 a1a8,1200,80,90,100,mate mateIn2 endgame,,";
+
+    #[test]
+    fn test_parse_csv() {
+        let puzzles = parse_puzzles(CSV).unwrap();
+        assert_eq!(puzzles.len(), 2);
+
+        let puzzle = &puzzles[0];
+        assert_eq!(puzzle.id, "00008");
+        assert_eq!(puzzle.last_move.as_deref(), Some("f2g3"));
+        assert_eq!(puzzle.solution, vec!["e6e7", "b2b1", "b3c1", "b1c1", "h6c1"]);
+        assert_eq!(puzzle.rating, Some(1913));
+        assert!(puzzle.themes.contains(&"hangingPiece".to_string()));
+        assert!(puzzle.fen.starts_with("r6k/pp2r2p/4Rp1Q/3p4/8/1N1P2b1/PqP3PP/7K w"));
+
+        assert!(parse_puzzles("bad,8/8/8/8/8/8/8/8 w - - 0 1,e2e4").is_err());
+    }
+
+    #[test]
+    fn test_parse_api_json() {
+        let json = r#"{"game": {"pgn": "e4 e5 Qh5 Nc6 Bc4 Nf6"},
+            "puzzle": {"id": "abcde", "rating": 900, "solution": ["h5f7"], "themes": ["mate", "mateIn1"]}}"#;
+        let puzzles = parse_puzzles(json).unwrap();
+
+        assert_eq!(puzzles[0].id, "abcde");
+        assert_eq!(puzzles[0].last_move.as_deref(), Some("g8f6"));
+        assert_eq!(puzzles[0].solution, vec!["h5f7"]);
+        assert!(puzzles[0].fen.ends_with("w KQkq - 4 4"));
+    }
+
+    #[test]
+    fn test_solve_and_fail() {
+        let mut session = PuzzleSession::new();
+        session.load(parse_puzzles(CSV).unwrap());
+        let mut game = ChessGame::new();
+
+        session.next_puzzle(&mut game).unwrap().unwrap();
+        assert_eq!(
+            session.submit_move(&mut game, "e6e7").unwrap(),
+            PuzzleOutcome::Correct { reply: "b2b1".to_string() }
+        );
+        assert_eq!(
+            session.submit_move(&mut game, "h6c1").unwrap(),
+            PuzzleOutcome::Failed { expected: "b3c1".to_string() }
+        );
+        assert!(session.submit_move(&mut game, "b3c1").is_err());
+
+        session.next_puzzle(&mut game).unwrap().unwrap();
+        session.submit_move(&mut game, "b1a1").unwrap();
+        assert_eq!(session.submit_move(&mut game, "a1a8").unwrap(), PuzzleOutcome::Solved);
+
+        assert_eq!(session.stats(), PuzzleStats { attempted: 2, solved: 1, failed: 1 });
+        assert_eq!(session.remaining(), 0);
+        assert!(session.next_puzzle(&mut game).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_built_in_puzzles_have_the_solutions_the_engine_finds() {
+        use crate::chess_engine::search::{search, SearchLimits};
+
+        for puzzle in built_in_puzzles() {
+            let position = parse_fen(&puzzle.fen).unwrap();
+            let best = search(&position, SearchLimits::depth(4)).best_move.unwrap();
+            assert_eq!(best.to_uci(), puzzle.solution[0], "puzzle {}", puzzle.id);
+        }
+    }
+
+    #[test]
+    fn test_rush_sequence() {
+        let pool = built_in_puzzles();
+        let filter = PuzzleFilter {
+            themes: vec!["mate".to_string(), "fork".to_string()],
+            min_rating: Some(600),
+            max_rating: None,
+        };
+        let ids = |seed| -> Vec<String> { rush_sequence(&pool, &filter, seed).into_iter().map(|puzzle| puzzle.id).collect() };
+
+        // Mates and forks from 600 up, easiest band first; 600 and 650 share a band
+        let mut first_band = ids(1)[..2].to_vec();
+        first_band.sort();
+        assert_eq!(first_band, ["rush02", "rush03"]);
+        assert_eq!(ids(1)[2..], ["rush04", "rush06"]);
+        assert!(rush_sequence(&pool, &PuzzleFilter { min_rating: Some(3000), ..PuzzleFilter::default() }, 1).is_empty());
+    }
+
+    #[test]
+    fn test_rush_ends_after_three_mistakes_or_on_time() {
+        let mut session = PuzzleSession::new();
+        let mut game = ChessGame::new();
+        let status = session.start_rush(&PuzzleFilter::default(), Duration::from_secs(180)).unwrap();
+        assert_eq!(status.solved, 0);
+        assert!(!status.over && status.remaining_ms > 170_000);
+        assert_eq!(session.remaining(), built_in_puzzles().len());
+
+        // The easiest puzzle comes first
+        let puzzle = session.next_puzzle(&mut game).unwrap().unwrap();
+        assert_eq!(puzzle.id, "rush01");
+        assert_eq!(session.submit_move(&mut game, "d2d5").unwrap(), PuzzleOutcome::Solved);
+        assert_eq!(session.rush_status().unwrap().solved, 1);
+
+        for _ in 0..RUSH_MISTAKES_ALLOWED {
+            let puzzle = session.next_puzzle(&mut game).unwrap().unwrap();
+            let wrong = game
+                .get_legal_moves()
+                .into_iter()
+                .map(|mv| mv.to_uci())
+                .find(|uci| *uci != puzzle.solution[0])
+                .unwrap();
+            assert!(matches!(session.submit_move(&mut game, &wrong).unwrap(), PuzzleOutcome::Failed { .. }));
+        }
+        assert!(session.rush_status().unwrap().over);
+        assert!(session.next_puzzle(&mut game).is_err());
+
+        let mut session = PuzzleSession::new();
+        session.start_rush(&PuzzleFilter::default(), Duration::ZERO).unwrap();
+        assert!(session.rush_status().unwrap().over);
+        assert!(session.next_puzzle(&mut game).is_err());
+    }
+}
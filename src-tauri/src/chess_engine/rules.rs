@@ -0,0 +1,529 @@
+use crate::chess_engine::board::squares_in;
+use crate::chess_engine::fen::STARTING_FEN;
+use crate::chess_engine::position::{Pockets, Position};
+use crate::chess_engine::types::{Color, GameStatus, Move, Piece, Square};
+use serde::{Deserialize, Serialize};
+
+/// Rule set a game is played under, chosen when the game starts
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Variant {
+    #[default]
+    Standard,
+    /// Captured pieces change sides and can be dropped back onto the board
+    Crazyhouse,
+    /// Giving check three times wins
+    ThreeCheck,
+    /// White's 36 pawns and no king against the full black army
+    Horde,
+    /// The back-rank pieces start shuffled, and castling works from any king and rook squares
+    Chess960,
+    /// Dark chess: each side sees only the squares its pieces stand on or can move to,
+    /// there is no check, and taking the king wins
+    FogOfWar,
+    /// Bringing the king to one of the four center squares wins
+    KingOfTheHill,
+    /// No pawns and no checks: the first king to reach the eighth rank wins
+    RacingKings,
+}
+
+/// How to set up a new game beyond choosing its variant
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VariantOptions {
+    /// Starting position to play the variant from instead of its own
+    #[serde(default)]
+    pub fen: Option<String>,
+    /// Chess960 arrangement (0-959); a random one when left out
+    #[serde(default)]
+    pub chess960_number: Option<u16>,
+    /// Chess960 with a separate arrangement for Black (Double Fischer Random Chess)
+    #[serde(default)]
+    pub double: bool,
+    /// Black's arrangement when `double` is set; a random one when left out
+    #[serde(default)]
+    pub chess960_black_number: Option<u16>,
+}
+
+impl Variant {
+    /// The rules move generation, validation and the game status consult
+    pub fn rules(self) -> &'static dyn Rules {
+        match self {
+            Variant::Standard => &StandardRules,
+            Variant::Crazyhouse => &CrazyhouseRules,
+            Variant::ThreeCheck => &ThreeCheckRules,
+            Variant::Horde => &HordeRules,
+            Variant::Chess960 => &Chess960Rules,
+            Variant::FogOfWar => &FogOfWarRules,
+            Variant::KingOfTheHill => &KingOfTheHillRules,
+            Variant::RacingKings => &RacingKingsRules,
+        }
+    }
+
+    /// Name written in a PGN `Variant` tag
+    pub fn pgn_name(self) -> &'static str {
+        match self {
+            Variant::Standard => "Standard",
+            Variant::Crazyhouse => "Crazyhouse",
+            Variant::ThreeCheck => "Three-check",
+            Variant::Horde => "Horde",
+            Variant::Chess960 => "Chess960",
+            Variant::FogOfWar => "Fog of War",
+            Variant::KingOfTheHill => "King of the Hill",
+            Variant::RacingKings => "Racing Kings",
+        }
+    }
+
+    /// The variant a PGN `Variant` tag names, ignoring case, spaces and hyphens and
+    /// accepting the other names databases use ("Fischerandom", "3check", ...)
+    pub fn from_pgn_name(name: &str) -> Option<Variant> {
+        let name: String = name
+            .chars()
+            .filter(|c| !c.is_whitespace() && *c != '-')
+            .map(|c| c.to_ascii_lowercase())
+            .collect();
+        match name.as_str() {
+            "standard" | "chess" | "normal" | "fromposition" => Some(Variant::Standard),
+            "crazyhouse" | "zh" => Some(Variant::Crazyhouse),
+            "threecheck" | "3check" => Some(Variant::ThreeCheck),
+            "horde" => Some(Variant::Horde),
+            "chess960" | "fischerandom" | "fischerrandom" | "960" => Some(Variant::Chess960),
+            "fogofwar" | "darkchess" => Some(Variant::FogOfWar),
+            "kingofthehill" | "koth" => Some(Variant::KingOfTheHill),
+            "racingkings" => Some(Variant::RacingKings),
+            _ => None,
+        }
+    }
+
+    /// Value of the `UCI_Variant` option for engines that play variants (the names
+    /// Fairy-Stockfish uses). Chess960 is chess with `UCI_Chess960` set, and no engine
+    /// plays fog of war, so it is searched as chess.
+    pub fn uci_name(self) -> &'static str {
+        match self {
+            Variant::Standard | Variant::Chess960 | Variant::FogOfWar => "chess",
+            Variant::Crazyhouse => "crazyhouse",
+            Variant::ThreeCheck => "3check",
+            Variant::Horde => "horde",
+            Variant::KingOfTheHill => "kingofthehill",
+            Variant::RacingKings => "racingkings",
+        }
+    }
+}
+
+/// What a variant changes about the game. Every hook defaults to the standard rules, so
+/// a variant only overrides what it does differently.
+pub trait Rules: Sync {
+    /// Starting position as FEN
+    fn start_fen(&self) -> &'static str {
+        STARTING_FEN
+    }
+
+    /// Adds moves the pieces on the board do not make themselves (drops, for example)
+    /// to the side to move's pseudo-legal moves
+    fn add_moves(&self, _position: &Position, _moves: &mut Vec<Move>) {}
+
+    /// Whether captured pieces go into the capturer's pocket, and the FEN lists them
+    fn uses_pockets(&self) -> bool {
+        false
+    }
+
+    /// Whether `color` has a king to protect; a side without one cannot be checked
+    fn has_king(&self, _color: Color) -> bool {
+        true
+    }
+
+    /// Whether White's pawns may stand on the first rank, and push two squares from it
+    fn first_rank_pawns(&self) -> bool {
+        false
+    }
+
+    /// Whether the position keeps count of the checks each side gives, and the FEN
+    /// ends with them
+    fn counts_checks(&self) -> bool {
+        false
+    }
+
+    /// Whether the king and rooks may castle from any squares on the back rank, ending
+    /// on the same squares as in standard chess
+    fn chess960_castling(&self) -> bool {
+        false
+    }
+
+    /// Whether the game is won by taking the king instead of by checkmate: check places no
+    /// limits on moves, so a king may stay in or step into attack, and castle through it
+    fn king_capture(&self) -> bool {
+        false
+    }
+
+    /// Whether a move may give check
+    fn allows_checks(&self) -> bool {
+        true
+    }
+
+    /// Whether a king wins by walking to a goal square, so that no material is too
+    /// little to win with
+    fn king_goal(&self) -> bool {
+        false
+    }
+
+    /// How the game has ended by this variant's own rules, if it has; checked before
+    /// checkmate, stalemate and the draw rules
+    fn outcome(&self, _position: &Position, _legal_moves: &[Move]) -> Option<GameStatus> {
+        None
+    }
+}
+
+struct StandardRules;
+
+impl Rules for StandardRules {}
+
+struct CrazyhouseRules;
+
+impl Rules for CrazyhouseRules {
+    fn start_fen(&self) -> &'static str {
+        "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR[] w KQkq - 0 1"
+    }
+
+    /// A piece in hand may be dropped on any empty square, checks and mates included,
+    /// except that pawns never go on the first or last rank
+    fn add_moves(&self, position: &Position, moves: &mut Vec<Move>) {
+        let color = position.side_to_move;
+        let empty = !position.board.occupied();
+        for piece in Pockets::PIECES {
+            if position.pockets.count(color, piece) == 0 {
+                continue;
+            }
+            let targets = if piece == Piece::Pawn { empty & BACK_RANKS_EXCLUDED } else { empty };
+            moves.extend(squares_in(targets).map(|to| Move::new_drop(piece, to)));
+        }
+    }
+
+    fn uses_pockets(&self) -> bool {
+        true
+    }
+}
+
+struct ThreeCheckRules;
+
+/// Checks a side must give to win three-check
+const CHECKS_TO_WIN: u8 = 3;
+
+impl Rules for ThreeCheckRules {
+    fn start_fen(&self) -> &'static str {
+        "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1 +0+0"
+    }
+
+    fn outcome(&self, position: &Position, _legal_moves: &[Move]) -> Option<GameStatus> {
+        [Color::White, Color::Black]
+            .into_iter()
+            .find(|&color| position.checks_given(color) >= CHECKS_TO_WIN)
+            .map(|winner| GameStatus::ThreeCheckWin { winner })
+    }
+
+    fn counts_checks(&self) -> bool {
+        true
+    }
+}
+
+struct HordeRules;
+
+impl Rules for HordeRules {
+    fn start_fen(&self) -> &'static str {
+        "rnbqkbnr/pppppppp/8/1PP2PP1/PPPPPPPP/PPPPPPPP/PPPPPPPP/PPPPPPPP w kq - 0 1"
+    }
+
+    /// Pawns on the first rank may step two squares, as from the second
+    fn add_moves(&self, position: &Position, moves: &mut Vec<Move>) {
+        if position.side_to_move != Color::White {
+            return;
+        }
+        let empty = !position.board.occupied();
+        for (from, piece) in position.board.pieces_of_color(Color::White) {
+            if piece != Piece::Pawn || from.rank() != 0 {
+                continue;
+            }
+            let (Some(over), Some(to)) = (Square::new(from.index() + 8), Square::new(from.index() + 16)) else {
+                continue;
+            };
+            if empty & (1u64 << over.index()) != 0 && empty & (1u64 << to.index()) != 0 {
+                moves.push(Move::new(from, to));
+            }
+        }
+    }
+
+    fn outcome(&self, position: &Position, _legal_moves: &[Move]) -> Option<GameStatus> {
+        (position.board.occupancy(Color::White) == 0).then_some(GameStatus::HordeDestroyed { winner: Color::Black })
+    }
+
+    fn has_king(&self, color: Color) -> bool {
+        color == Color::Black
+    }
+
+    fn first_rank_pawns(&self) -> bool {
+        true
+    }
+}
+
+struct Chess960Rules;
+
+impl Rules for Chess960Rules {
+    fn chess960_castling(&self) -> bool {
+        true
+    }
+}
+
+struct FogOfWarRules;
+
+impl Rules for FogOfWarRules {
+    fn king_capture(&self) -> bool {
+        true
+    }
+
+    fn outcome(&self, position: &Position, _legal_moves: &[Move]) -> Option<GameStatus> {
+        [Color::White, Color::Black]
+            .into_iter()
+            .find(|&color| position.board.find_king(color).is_none())
+            .map(|loser| GameStatus::KingCaptured { winner: loser.opposite() })
+    }
+}
+
+struct KingOfTheHillRules;
+
+/// d4, e4, d5 and e5
+const HILL: u64 = 0x0000_0018_1800_0000;
+
+impl Rules for KingOfTheHillRules {
+    fn king_goal(&self) -> bool {
+        true
+    }
+
+    fn outcome(&self, position: &Position, _legal_moves: &[Move]) -> Option<GameStatus> {
+        [Color::White, Color::Black]
+            .into_iter()
+            .find(|&color| position.board.find_king(color).is_some_and(|king| HILL & (1u64 << king.index()) != 0))
+            .map(|winner| GameStatus::KingReachedHill { winner })
+    }
+}
+
+struct RacingKingsRules;
+
+impl Rules for RacingKingsRules {
+    fn start_fen(&self) -> &'static str {
+        "8/8/8/8/8/8/krbnNBRK/qrbnNBRQ w - - 0 1"
+    }
+
+    fn allows_checks(&self) -> bool {
+        false
+    }
+
+    fn king_goal(&self) -> bool {
+        true
+    }
+
+    /// White moves first, so when its king arrives Black has one move to draw by
+    /// arriving too
+    fn outcome(&self, position: &Position, legal_moves: &[Move]) -> Option<GameStatus> {
+        let arrived = |color| position.board.find_king(color).is_some_and(|king| king.rank() == 7);
+        match (arrived(Color::White), arrived(Color::Black)) {
+            (true, true) => Some(GameStatus::DrawByRace),
+            (false, true) => Some(GameStatus::RaceWon { winner: Color::Black }),
+            (true, false) => {
+                let black_can_arrive = position.side_to_move == Color::Black
+                    && legal_moves
+                        .iter()
+                        .any(|mv| position.board.get(mv.from) == Some((Piece::King, Color::Black)) && mv.to.rank() == 7);
+                (!black_can_arrive).then_some(GameStatus::RaceWon { winner: Color::White })
+            }
+            (false, false) => None,
+        }
+    }
+}
+
+/// Every square but those on the first and last ranks
+const BACK_RANKS_EXCLUDED: u64 = 0x00FF_FFFF_FFFF_FF00;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chess_engine::fen::{parse_fen, parse_fen_for, position_to_fen};
+    use crate::chess_engine::validation::{generate_legal_moves, IllegalMoveReason};
+    use crate::chess_engine::types::Square;
+    use crate::chess_engine::ChessGame;
+
+    #[test]
+    fn test_standard_variant() {
+        let game = ChessGame::new_variant(Variant::Standard);
+        assert_eq!(position_to_fen(game.get_board_state()), STARTING_FEN);
+        assert_eq!(game.get_board_state().variant, Variant::Standard);
+        assert_eq!(game.get_legal_moves().len(), 20);
+
+        assert_eq!(serde_json::to_string(&Variant::Standard).unwrap(), r#""standard""#);
+        assert_eq!(serde_json::from_str::<Variant>(r#""standard""#).unwrap(), Variant::Standard);
+    }
+
+    #[test]
+    fn test_variant_options() {
+        // A numbered Chess960 arrangement, reported back in the board state
+        let options = VariantOptions { chess960_number: Some(0), ..VariantOptions::default() };
+        let game = ChessGame::new_with_options(Variant::Chess960, &options).unwrap();
+        assert_eq!(game.to_fen(), "bbqnnrkr/pppppppp/8/8/8/8/PPPPPPPP/BBQNNRKR w KQkq - 0 1");
+        assert_eq!(game.board_state().variant, Variant::Chess960);
+
+        // Double Fischer Random gives Black its own arrangement
+        let options = VariantOptions {
+            chess960_number: Some(518),
+            double: true,
+            chess960_black_number: Some(0),
+            ..VariantOptions::default()
+        };
+        let game = ChessGame::new_with_options(Variant::Chess960, &options).unwrap();
+        assert_eq!(game.to_fen(), "bbqnnrkr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1");
+
+        // A custom start is read under the variant's rules
+        let options = VariantOptions { fen: Some("4k3/8/8/8/8/8/1p6/P7 w - - 0 1".to_string()), ..VariantOptions::default() };
+        let game = ChessGame::new_with_options(Variant::Horde, &options).unwrap();
+        assert_eq!(game.board_state().variant, Variant::Horde);
+        assert_eq!(game.get_legal_moves().len(), 3);
+        assert!(ChessGame::new_with_options(Variant::Standard, &options).is_err());
+
+        // Without options each variant starts from its own position
+        let game = ChessGame::new_with_options(Variant::Crazyhouse, &VariantOptions::default()).unwrap();
+        assert_eq!(game.to_fen(), CrazyhouseRules.start_fen());
+        let options: VariantOptions = serde_json::from_str(r#"{ "chess960_number": 959 }"#).unwrap();
+        assert_eq!(options.chess960_number, Some(959));
+        assert!(!options.double);
+    }
+
+    #[test]
+    fn test_crazyhouse_captures_go_to_the_pocket() {
+        let mut game = ChessGame::new_variant(Variant::Crazyhouse);
+        assert_eq!(game.to_fen(), CrazyhouseRules.start_fen());
+        assert_eq!(game.get_legal_moves().len(), 20);
+
+        game.make_uci_moves(&["e2e4", "d7d5", "e4d5", "d8d5"]).unwrap();
+        let position = game.get_board_state();
+        assert_eq!(position.pockets.count(Color::White, Piece::Pawn), 1);
+        assert_eq!(position.pockets.count(Color::Black, Piece::Pawn), 1);
+        assert_eq!(game.to_fen(), "rnb1kbnr/ppp1pppp/8/3q4/8/8/PPPP1PPP/RNBQKBNR[Pp] w KQkq - 0 3");
+
+        // Pawns drop anywhere empty but the first and last ranks
+        let drops: Vec<Move> = game.get_legal_moves().into_iter().filter(|mv| mv.drop.is_some()).collect();
+        assert_eq!(drops.len(), 33);
+        assert!(drops.iter().all(|mv| (1..7).contains(&mv.to.rank())));
+
+        let drop = game.make_uci_move("P@e4").unwrap();
+        assert_eq!(drop, Move::new_drop(Piece::Pawn, Square::from_algebraic("e4").unwrap()));
+        assert_eq!(drop.to_uci(), "P@e4");
+        assert_eq!(game.get_board_state().pockets.count(Color::White, Piece::Pawn), 0);
+        assert!(game.make_uci_move("P@e1").is_err());
+    }
+
+    #[test]
+    fn test_three_check_counts_checks() {
+        let mut game = ChessGame::new_variant(Variant::ThreeCheck);
+        assert_eq!(game.to_fen(), ThreeCheckRules.start_fen());
+
+        game.make_uci_moves(&["e2e4", "f7f6", "d1h5"]).unwrap();
+        assert_eq!(game.get_board_state().checks_given(Color::White), 1);
+        assert!(game.to_fen().ends_with(" +1+0"));
+        game.undo_move().unwrap();
+        assert_eq!(game.get_board_state().checks_given(Color::White), 0);
+
+        // Two checks given already, so the next one wins
+        let mut game = ChessGame::from_fen("4k3/8/8/8/8/8/8/4K2R w - - 0 1 +2+0").unwrap();
+        assert_eq!(game.get_status(), GameStatus::InProgress);
+        game.make_uci_move("h1h8").unwrap();
+        assert_eq!(game.get_status(), GameStatus::ThreeCheckWin { winner: Color::White });
+        assert_eq!(game.result().result, "1-0");
+    }
+
+    #[test]
+    fn test_horde() {
+        let game = ChessGame::new_variant(Variant::Horde);
+        let position = game.get_board_state();
+        assert_eq!(position.board.occupancy(Color::White).count_ones(), 36);
+        assert_eq!(game.get_status(), GameStatus::InProgress);
+        assert_eq!(game.get_legal_moves().len(), 8);
+
+        // A pawn on the first rank may step two squares, without leaving an en passant target
+        let mut position = parse_fen_for("4k3/8/8/8/8/8/1p6/P7 w - - 0 1", Variant::Horde).unwrap();
+        let moves: Vec<String> = generate_legal_moves(&position).iter().map(|mv| mv.to_uci()).collect();
+        assert_eq!(moves, ["a1a2", "a1b2", "a1a3"]);
+        let undo = position.make_move(&Move::from_uci("a1a3", &position).unwrap());
+        assert_eq!(position.en_passant_target, None);
+        position.unmake_move(undo);
+
+        // Taking the last white piece wins for Black
+        position.side_to_move = Color::Black;
+        position.make_move(&Move::from_uci("b2a1q", &position).unwrap());
+        assert_eq!(
+            Variant::Horde.rules().outcome(&position, &[]),
+            Some(GameStatus::HordeDestroyed { winner: Color::Black })
+        );
+        assert!(parse_fen("4k3/8/8/8/8/8/1p6/P7 w - - 0 1").is_err());
+    }
+
+    #[test]
+    fn test_king_of_the_hill() {
+        let mut game = ChessGame::from_position(
+            parse_fen_for("4k3/8/8/8/8/3K4/8/8 w - - 0 1", Variant::KingOfTheHill).unwrap(),
+        );
+        // Bare kings still play on, since either may reach the center
+        assert_eq!(game.get_status(), GameStatus::InProgress);
+        game.make_uci_move("d3d4").unwrap();
+        assert_eq!(game.get_status(), GameStatus::KingReachedHill { winner: Color::White });
+        assert_eq!(game.result().result, "1-0");
+        assert_eq!(Variant::from_pgn_name("King of the Hill"), Some(Variant::KingOfTheHill));
+    }
+
+    #[test]
+    fn test_racing_kings() {
+        let game = ChessGame::new_variant(Variant::RacingKings);
+        assert_eq!(game.get_legal_moves().len(), 21);
+
+        // No move may give check
+        let position = parse_fen_for("8/8/8/8/8/8/k7/6RK w - - 0 1", Variant::RacingKings).unwrap();
+        assert!(generate_legal_moves(&position).iter().all(|mv| mv.to_uci() != "g1a1"));
+        let game = ChessGame::from_position(position);
+        let a1 = Square::from_algebraic("a1").unwrap();
+        let legality = game.move_legality(Square::from_algebraic("g1").unwrap(), a1, None);
+        assert_eq!(legality.reason, Some(IllegalMoveReason::GivesCheck));
+
+        // Black may draw by arriving right after White
+        let start = "8/1k4K1/8/8/8/8/8/8 w - - 0 1";
+        let mut game = ChessGame::from_position(parse_fen_for(start, Variant::RacingKings).unwrap());
+        game.make_uci_move("g7g8").unwrap();
+        assert_eq!(game.get_status(), GameStatus::InProgress);
+        game.make_uci_move("b7b8").unwrap();
+        assert_eq!(game.get_status(), GameStatus::DrawByRace);
+        assert_eq!(game.result().result, "1/2-1/2");
+
+        // or lose when it cannot
+        let mut game = ChessGame::from_position(parse_fen_for("8/6K1/1k6/8/8/8/8/8 w - - 0 1", Variant::RacingKings).unwrap());
+        game.make_uci_move("g7g8").unwrap();
+        assert_eq!(game.get_status(), GameStatus::RaceWon { winner: Color::White });
+
+        // and wins by arriving first
+        let mut game = ChessGame::from_position(parse_fen_for("8/1k6/8/8/8/8/8/6K1 b - - 0 1", Variant::RacingKings).unwrap());
+        game.make_uci_move("b7b8").unwrap();
+        assert_eq!(game.get_status(), GameStatus::RaceWon { winner: Color::Black });
+    }
+
+    #[test]
+    fn test_crazyhouse_promoted_pieces_return_as_pawns() {
+        let mut game = ChessGame::from_fen("4k3/8/8/8/8/8/8/Rq~2K3[QNPp] w Q - 0 1").unwrap();
+        assert_eq!(game.to_fen(), "4k3/8/8/8/8/8/8/Rq~2K3[QNPp] w Q - 0 1");
+
+        // In check from the rook's side, a drop may block; a pawn may not go on the first rank
+        let drops: Vec<String> = game
+            .get_legal_moves()
+            .into_iter()
+            .filter(|mv| mv.drop.is_some())
+            .map(|mv| mv.to_uci())
+            .collect();
+        assert_eq!(drops, ["N@c1", "N@d1", "Q@c1", "Q@d1"]);
+
+        game.make_uci_move("a1b1").unwrap();
+        assert_eq!(game.get_board_state().pockets.count(Color::White, Piece::Pawn), 2);
+        assert_eq!(game.get_board_state().pockets.count(Color::White, Piece::Queen), 1);
+    }
+}
@@ -0,0 +1,526 @@
+use crate::chess_engine::analysis::piece_value;
+use crate::chess_engine::evaluator::{EvalPerspective, Evaluator, VariantParams};
+use crate::chess_engine::position::Position;
+use crate::chess_engine::types::{Color, Move, Piece};
+use crate::chess_engine::move_gen::MAX_MOVES;
+use crate::chess_engine::transposition::{Bound, TranspositionTable, TtEntry, TRANSPOSITION_TABLE_MEGABYTES};
+use crate::chess_engine::validation::{generate_legal_moves, generate_legal_moves_into, gives_check, is_in_check};
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{PoisonError, RwLock};
+use std::time::{Duration, Instant};
+
+/// Score for delivering checkmate; mates further away score slightly less
+pub const MATE_SCORE: i32 = 100_000;
+
+/// Scores at or beyond this magnitude are mate scores
+pub const MATE_THRESHOLD: i32 = MATE_SCORE - 1_000;
+
+const INFINITY: i32 = MATE_SCORE + 1;
+
+/// Depth searched when no other limit is given
+const DEFAULT_DEPTH: u8 = 4;
+
+/// Deepest iteration ever started (used for open-ended searches)
+pub const MAX_DEPTH: u8 = 64;
+
+/// Node interval between checks of the node limit, time limit and stop flag
+const LIMIT_CHECK_INTERVAL: u64 = 1024;
+
+/// Results shared by every search, so later searches of the same game start ahead
+static TRANSPOSITION_TABLE: Lazy<RwLock<TranspositionTable>> =
+    Lazy::new(|| RwLock::new(TranspositionTable::with_megabytes(TRANSPOSITION_TABLE_MEGABYTES)));
+
+/// Replace the transposition table with an empty one of about `megabytes` in size
+pub fn set_transposition_table_megabytes(megabytes: usize) {
+    *TRANSPOSITION_TABLE.write().unwrap_or_else(PoisonError::into_inner) = TranspositionTable::with_megabytes(megabytes);
+}
+
+/// Forget everything earlier searches stored
+pub fn clear_transposition_table() {
+    TRANSPOSITION_TABLE.read().unwrap_or_else(PoisonError::into_inner).clear();
+}
+
+/// When to stop searching; the depth limit always applies, the others are optional
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SearchLimits {
+    pub depth: u8,
+    pub nodes: Option<u64>,
+    pub movetime_ms: Option<u64>,
+    /// Centipawns the side to move at the root gives up to avoid a draw (negative to seek one)
+    #[serde(default)]
+    pub contempt: i32,
+}
+
+impl SearchLimits {
+    pub fn depth(depth: u8) -> Self {
+        SearchLimits {
+            depth: depth.max(1),
+            ..Self::default()
+        }
+    }
+}
+
+impl Default for SearchLimits {
+    fn default() -> Self {
+        SearchLimits {
+            depth: DEFAULT_DEPTH,
+            nodes: None,
+            movetime_ms: None,
+            contempt: 0,
+        }
+    }
+}
+
+/// Outcome of the deepest fully completed iteration
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SearchResult {
+    pub best_move: Option<Move>,
+    /// Centipawns from the side to move's point of view (mates are near ±MATE_SCORE)
+    pub score: i32,
+    pub depth: u8,
+    pub nodes: u64,
+    /// Principal variation, starting with the best move
+    pub pv: Vec<Move>,
+}
+
+/// Full moves until mate for a mate score, negative when the side the score
+/// belongs to is the one being mated; `None` for ordinary scores
+pub fn mate_in(score: i32) -> Option<i32> {
+    if score.abs() < MATE_THRESHOLD {
+        return None;
+    }
+
+    let moves = (MATE_SCORE - score.abs() + 1) / 2;
+    Some(if score > 0 { moves } else { -moves })
+}
+
+/// Iterative-deepening alpha-beta search with quiescence
+pub fn search(position: &Position, limits: SearchLimits) -> SearchResult {
+    search_with_control(position, limits, &AtomicBool::new(false), |_| {})
+}
+
+/// Like [`search`], but stops early once `stop` is set and reports every completed iteration
+pub fn search_with_control(
+    position: &Position,
+    limits: SearchLimits,
+    stop: &AtomicBool,
+    on_iteration: impl FnMut(&SearchResult),
+) -> SearchResult {
+    Search::new(limits, stop).run(position, on_iteration)
+}
+
+struct Search<'a> {
+    limits: SearchLimits,
+    deadline: Option<Instant>,
+    stop: &'a AtomicBool,
+    nodes: u64,
+    aborted: bool,
+    previous_pv: Vec<Move>,
+    /// One move list per ply, reused from node to node so the search does not allocate them
+    move_lists: Vec<Vec<Move>>,
+    /// Mixed into table keys so searches with different contempt keep their draw scores apart
+    key_salt: u64,
+}
+
+impl<'a> Search<'a> {
+    fn new(limits: SearchLimits, stop: &'a AtomicBool) -> Self {
+        Search {
+            limits,
+            deadline: limits.movetime_ms.map(|ms| Instant::now() + Duration::from_millis(ms)),
+            stop,
+            nodes: 0,
+            aborted: false,
+            previous_pv: Vec::new(),
+            move_lists: Vec::new(),
+            key_salt: 0,
+        }
+    }
+
+    fn run(&mut self, position: &Position, mut on_iteration: impl FnMut(&SearchResult)) -> SearchResult {
+        let mut result = SearchResult {
+            best_move: generate_legal_moves(position).first().copied(),
+            score: 0,
+            depth: 0,
+            nodes: 0,
+            pv: Vec::new(),
+        };
+
+        // Draw scores depend on contempt and on which side it is for
+        if self.limits.contempt != 0 {
+            let side = match position.side_to_move {
+                Color::White => 1,
+                Color::Black => 2,
+            };
+            self.key_salt = (self.limits.contempt as u64 ^ side << 32).wrapping_mul(0x9E37_79B9_7F4A_7C15);
+        }
+
+        // Moves are made and unmade on one copy rather than cloning a position per move
+        let mut position = position.clone();
+        for depth in 1..=self.limits.depth.clamp(1, MAX_DEPTH) {
+            let (score, pv) = self.negamax(&mut position, depth, 0, -INFINITY, INFINITY, true);
+            if self.aborted {
+                break;
+            }
+
+            result.best_move = pv.first().copied().or(result.best_move);
+            result.score = score;
+            result.depth = depth;
+            result.pv = pv.clone();
+            result.nodes = self.nodes;
+            self.previous_pv = pv;
+            on_iteration(&result);
+
+            // No point searching deeper once a forced mate is found
+            if score.abs() >= MATE_THRESHOLD {
+                break;
+            }
+        }
+
+        result.nodes = self.nodes;
+        result
+    }
+
+    /// Score from the side to move's point of view, with the principal variation
+    fn negamax(
+        &mut self,
+        position: &mut Position,
+        depth: u8,
+        ply: usize,
+        mut alpha: i32,
+        beta: i32,
+        on_pv: bool,
+    ) -> (i32, Vec<Move>) {
+        let mut pv = Vec::new();
+
+        if self.out_of_budget() {
+            return (0, pv);
+        }
+
+        if ply > 0 && is_draw(position) {
+            return (self.draw_score(ply), pv);
+        }
+
+        // Quiescence scores checkmate and stalemate itself
+        if depth == 0 {
+            return (self.quiescence(position, ply, alpha, beta), pv);
+        }
+
+        // Earlier results can end the search here, except along the principal variation
+        let key = position.hash() ^ self.key_salt;
+        let stored = TRANSPOSITION_TABLE.read().unwrap_or_else(PoisonError::into_inner).probe(key);
+        if let Some(entry) = stored.filter(|entry| !on_pv && ply > 0 && entry.depth >= depth) {
+            let score = score_from_table(entry.score, ply);
+            let usable = match entry.bound {
+                Bound::Exact => true,
+                Bound::Lower => score >= beta,
+                Bound::Upper => score <= alpha,
+            };
+            if usable {
+                return (score.clamp(alpha, beta), pv);
+            }
+        }
+
+        let mut moves = self.take_move_list(ply);
+        generate_legal_moves_into(position, &mut moves);
+        if let Some(score) = self.variant_outcome_score(position, &moves, ply) {
+            self.move_lists[ply] = moves;
+            return (score, pv);
+        }
+        if moves.is_empty() {
+            self.move_lists[ply] = moves;
+            let score = if is_in_check(position, position.side_to_move) {
+                -MATE_SCORE + ply as i32
+            } else {
+                self.draw_score(ply)
+            };
+            return (score, pv);
+        }
+
+        // The previous iteration's move first, or else the best move stored for this position
+        let pv_move = if on_pv { self.previous_pv.get(ply).copied() } else { None };
+        let hash_move = stored.and_then(|entry| moves.iter().copied().find(|mv| entry.is_best_move(mv)));
+        order_moves(position, &mut moves, pv_move.or(hash_move));
+
+        let original_alpha = alpha;
+        let mut best_move = None;
+        for &mv in &moves {
+            let undo = position.make_move(&mv);
+            let (child_score, child_pv) =
+                self.negamax(position, depth - 1, ply + 1, -beta, -alpha, Some(mv) == pv_move);
+            position.unmake_move(undo);
+            let score = -child_score;
+
+            if self.aborted {
+                self.move_lists[ply] = moves;
+                return (0, pv);
+            }
+
+            if score > alpha {
+                alpha = score;
+                best_move = Some(mv);
+                pv.clear();
+                pv.push(mv);
+                pv.extend(child_pv);
+
+                if alpha >= beta {
+                    break;
+                }
+            }
+        }
+
+        self.move_lists[ply] = moves;
+
+        let bound = if alpha >= beta {
+            Bound::Lower
+        } else if alpha > original_alpha {
+            Bound::Exact
+        } else {
+            Bound::Upper
+        };
+        let entry = TtEntry {
+            best_move: best_move.map(|mv| (mv.from.index(), mv.to.index(), mv.promotion)),
+            depth,
+            score: score_to_table(alpha, ply),
+            bound,
+        };
+        TRANSPOSITION_TABLE.read().unwrap_or_else(PoisonError::into_inner).store(key, entry);
+
+        (alpha, pv)
+    }
+
+    /// Resolve captures (or check evasions) until the position is quiet
+    fn quiescence(&mut self, position: &mut Position, ply: usize, mut alpha: i32, beta: i32) -> i32 {
+        if self.out_of_budget() {
+            return 0;
+        }
+
+        let in_check = is_in_check(position, position.side_to_move);
+        let mut moves = self.take_move_list(ply);
+        generate_legal_moves_into(position, &mut moves);
+        if let Some(score) = self.variant_outcome_score(position, &moves, ply) {
+            self.move_lists[ply] = moves;
+            return score;
+        }
+        if moves.is_empty() {
+            self.move_lists[ply] = moves;
+            return if in_check { -MATE_SCORE + ply as i32 } else { self.draw_score(ply) };
+        }
+
+        if !in_check {
+            let stand_pat = Evaluator::evaluate_from(position, EvalPerspective::SideToMove);
+            if stand_pat >= beta {
+                self.move_lists[ply] = moves;
+                return stand_pat;
+            }
+            alpha = alpha.max(stand_pat);
+        }
+
+        // Where checks win the game, they are as forcing as captures
+        let search_checks = VariantParams::for_variant(position.variant).quiescence_checks;
+        moves.retain(|mv| {
+            in_check || is_capture(position, mv) || mv.promotion.is_some() || (search_checks && gives_check(position, mv))
+        });
+        order_moves(position, &mut moves, None);
+
+        for &mv in &moves {
+            let undo = position.make_move(&mv);
+            let score = -self.quiescence(position, ply + 1, -beta, -alpha);
+            position.unmake_move(undo);
+
+            if self.aborted {
+                self.move_lists[ply] = moves;
+                return 0;
+            }
+
+            if score > alpha {
+                alpha = score;
+                if alpha >= beta {
+                    break;
+                }
+            }
+        }
+
+        self.move_lists[ply] = moves;
+        alpha
+    }
+
+    /// The move list for `ply`, to be put back in `move_lists` before returning
+    fn take_move_list(&mut self, ply: usize) -> Vec<Move> {
+        if self.move_lists.len() <= ply {
+            self.move_lists.resize_with(ply + 1, || Vec::with_capacity(MAX_MOVES));
+        }
+        std::mem::take(&mut self.move_lists[ply])
+    }
+
+    /// The score of a game the variant's own rules have ended (three checks given, the
+    /// horde destroyed), from the side to move's point of view; wins count as mates
+    fn variant_outcome_score(&self, position: &Position, moves: &[Move], ply: usize) -> Option<i32> {
+        let status = position.variant.rules().outcome(position, moves)?;
+        Some(match status.result().winner {
+            Some(winner) if winner == position.side_to_move => MATE_SCORE - ply as i32,
+            Some(_) => -MATE_SCORE + ply as i32,
+            None => self.draw_score(ply),
+        })
+    }
+
+    /// A draw scored from the side to move's point of view, with the root side's contempt
+    fn draw_score(&self, ply: usize) -> i32 {
+        if ply.is_multiple_of(2) {
+            -self.limits.contempt
+        } else {
+            self.limits.contempt
+        }
+    }
+
+    /// Count a node and report whether the node or time budget is used up
+    fn out_of_budget(&mut self) -> bool {
+        self.nodes += 1;
+
+        if self.nodes.is_multiple_of(LIMIT_CHECK_INTERVAL) {
+            let node_limit_hit = self.limits.nodes.is_some_and(|limit| self.nodes >= limit);
+            let time_up = self.deadline.is_some_and(|deadline| Instant::now() >= deadline);
+            if node_limit_hit || time_up || self.stop.load(Ordering::Relaxed) {
+                self.aborted = true;
+            }
+        }
+
+        self.aborted
+    }
+}
+
+/// Fifty-move rule, insufficient material or a repeated position (twofold is enough inside the search)
+fn is_draw(position: &Position) -> bool {
+    position.halfmove_clock >= 100 || position.has_insufficient_material() || position.repetition_count() >= 2
+}
+
+/// Mate scores count plies from the root; the table stores them counted from the node
+/// so they stay right when the position is reached at another ply
+fn score_to_table(score: i32, ply: usize) -> i32 {
+    if score >= MATE_THRESHOLD {
+        score + ply as i32
+    } else if score <= -MATE_THRESHOLD {
+        score - ply as i32
+    } else {
+        score
+    }
+}
+
+fn score_from_table(score: i32, ply: usize) -> i32 {
+    if score >= MATE_THRESHOLD {
+        score - ply as i32
+    } else if score <= -MATE_THRESHOLD {
+        score + ply as i32
+    } else {
+        score
+    }
+}
+
+fn is_capture(position: &Position, mv: &Move) -> bool {
+    mv.is_en_passant || (!mv.is_castling && position.board.get(mv.to).is_some())
+}
+
+/// Principal-variation move first, then captures by most valuable victim /
+/// least valuable attacker, promotions, and quiet moves
+fn order_moves(position: &Position, moves: &mut [Move], pv_move: Option<Move>) {
+    moves.sort_by_cached_key(|mv| {
+        if Some(*mv) == pv_move {
+            return i32::MIN;
+        }
+
+        let mut score = 0;
+        if let Some((victim, _)) = position.board.get(mv.to).filter(|_| !mv.is_castling) {
+            let attacker = position.board.get(mv.from).map(|(piece, _)| piece_value(piece)).unwrap_or(0);
+            score += 10 * piece_value(victim) - attacker;
+        } else if mv.is_en_passant {
+            score += 9 * piece_value(Piece::Pawn);
+        }
+        if let Some(promotion) = mv.promotion {
+            score += piece_value(promotion);
+        }
+        -score
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chess_engine::fen::parse_fen;
+
+    #[test]
+    fn test_finds_mate_in_one() {
+        let position = parse_fen("6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1").unwrap();
+        let result = search(&position, SearchLimits::depth(3));
+
+        assert_eq!(result.best_move.unwrap().to_uci(), "a1a8");
+        assert_eq!(result.score, MATE_SCORE - 1);
+        assert_eq!(result.pv.len(), 1);
+    }
+
+    #[test]
+    fn test_wins_hanging_queen() {
+        let position = parse_fen("4k3/8/8/3q4/8/8/3R4/4K3 w - - 0 1").unwrap();
+        let result = search(&position, SearchLimits::depth(2));
+
+        assert_eq!(result.best_move.unwrap().to_uci(), "d2d5");
+        assert!(result.score > 300);
+    }
+
+    #[test]
+    fn test_node_limit_stops_search() {
+        let position = Position::new();
+        let limits = SearchLimits {
+            depth: 20,
+            nodes: Some(2_000),
+            movetime_ms: None,
+            contempt: 0,
+        };
+        let result = search(&position, limits);
+
+        assert!(result.best_move.is_some());
+        assert!(result.depth < 20);
+        assert!(result.nodes <= 2_000 + LIMIT_CHECK_INTERVAL);
+    }
+
+    #[test]
+    fn test_stored_mate_scores_keep_their_distance() {
+        let position = parse_fen("k7/8/2K5/8/8/8/8/1R6 w - - 0 1").unwrap();
+        let first = search(&position, SearchLimits::depth(5));
+        let second = search(&position, SearchLimits::depth(5));
+
+        assert_eq!(mate_in(first.score), Some(2));
+        assert_eq!(mate_in(second.score), Some(2));
+        assert!(second.nodes <= first.nodes);
+    }
+
+    #[test]
+    fn test_variant_wins_score_as_mates() {
+        // Two checks given already, so Qh5+ wins three-check outright
+        let position = parse_fen("rnbqkbnr/ppppp1pp/5p2/8/4P3/8/PPPP1PPP/RNBQKBNR w KQkq - 0 2 +2+0").unwrap();
+        let result = search(&position, SearchLimits::depth(2));
+
+        assert_eq!(result.best_move.unwrap().to_uci(), "d1h5");
+        assert_eq!(mate_in(result.score), Some(1));
+    }
+
+    #[test]
+    fn test_contempt_scores_draws() {
+        // Every move leaves king and knight against king, a draw
+        let position = parse_fen("4k3/8/8/8/8/8/8/4KN2 w - - 0 1").unwrap();
+        let limits = SearchLimits { contempt: 50, ..SearchLimits::depth(1) };
+
+        assert_eq!(search(&position, limits).score, -50);
+        assert_eq!(search(&position, SearchLimits::depth(1)).score, 0);
+    }
+
+    #[test]
+    fn test_chess960_castling_is_not_a_capture() {
+        // The king castles by moving onto its own g1 rook
+        let position = parse_fen("4k3/8/8/8/8/8/8/RK4R1 w AG - 0 1").unwrap();
+        let castle = Move::from_uci("b1g1", &position).unwrap();
+
+        assert!(castle.is_castling);
+        assert!(!is_capture(&position, &castle));
+    }
+}
@@ -0,0 +1,129 @@
+use crate::chess_engine::fen::{parse_fen_fields, placement_problems};
+use crate::chess_engine::position::Position;
+use crate::chess_engine::types::{Color, Piece, Square};
+use crate::chess_engine::validation::checkers;
+
+/// Every reason a FEN from the board editor could not come from a real game, beyond
+/// what the FEN parser itself rejects. An empty list means the position can be loaded.
+pub fn validate_setup(fen: &str) -> Vec<String> {
+    let position = match parse_fen_fields(fen) {
+        Ok(position) => position,
+        Err(e) => return vec![e.to_string()],
+    };
+
+    let mut problems = placement_problems(&position);
+    for color in [Color::White, Color::Black] {
+        material_problems(&position, color, &mut problems);
+    }
+
+    let (white_king, black_king) = (position.board.find_king(Color::White), position.board.find_king(Color::Black));
+    if let (Some(white), Some(black)) = (white_king, black_king) {
+        if white.rank().abs_diff(black.rank()) <= 1 && white.file().abs_diff(black.file()) <= 1 {
+            problems.push("The kings are on adjacent squares".to_string());
+        }
+    }
+
+    let side = position.side_to_move;
+    if !checkers(&position, side.opposite()).is_empty() {
+        problems.push(format!("{} is in check but it is {}'s turn", name(side.opposite()), name(side)));
+    }
+    if checkers(&position, side).len() > 2 {
+        problems.push(format!("{} is in check from more than two pieces", name(side)));
+    }
+
+    en_passant_problems(&position, &mut problems);
+    problems
+}
+
+/// Pawn count, and whether there were enough pawns to promote into the extra pieces
+fn material_problems(position: &Position, color: Color, problems: &mut Vec<String>) {
+    let count = |piece: Piece| position.board.pieces_of_color(color).filter(|(_, p)| *p == piece).count();
+
+    let pawns = count(Piece::Pawn);
+    if pawns > 8 {
+        problems.push(format!("{} has {} pawns", name(color), pawns));
+    }
+
+    let promoted: usize = [(Piece::Queen, 1), (Piece::Rook, 2), (Piece::Bishop, 2), (Piece::Knight, 2)]
+        .into_iter()
+        .map(|(piece, starting)| count(piece).saturating_sub(starting))
+        .sum();
+    if promoted > 8usize.saturating_sub(pawns) {
+        problems.push(format!(
+            "{} has {} promoted pieces but only {} missing pawns",
+            name(color),
+            promoted,
+            8usize.saturating_sub(pawns)
+        ));
+    }
+}
+
+/// An en passant target needs the pawn that just moved two squares in front of it,
+/// with the square it passed and the square it came from both empty
+fn en_passant_problems(position: &Position, problems: &mut Vec<String>) {
+    let Some(target) = position.en_passant_target else {
+        return;
+    };
+    let mover = position.side_to_move.opposite();
+    let (pawn_rank, start_rank) = match mover {
+        Color::White => (3, 1),
+        Color::Black => (4, 6),
+    };
+
+    let pawn = Square::from_rank_file(pawn_rank, target.file()).and_then(|square| position.board.get(square));
+    let start_empty =
+        Square::from_rank_file(start_rank, target.file()).is_some_and(|square| position.board.is_empty(square));
+    if pawn != Some((Piece::Pawn, mover)) || !position.board.is_empty(target) || !start_empty {
+        problems.push(format!(
+            "En passant square {} does not follow a two-square {} pawn move",
+            target.to_algebraic(),
+            name(mover).to_lowercase()
+        ));
+    }
+}
+
+fn name(color: Color) -> &'static str {
+    match color {
+        Color::White => "White",
+        Color::Black => "Black",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chess_engine::fen::STARTING_FEN;
+
+    #[test]
+    fn test_legal_positions() {
+        assert!(validate_setup(STARTING_FEN).is_empty());
+        assert!(validate_setup("rnbqkbnr/ppp1pppp/8/3pP3/8/8/PPPP1PPP/RNBQKBNR w KQkq d6 0 3").is_empty());
+        // Promoted queens replacing captured pawns
+        assert!(validate_setup("4k3/8/8/8/8/8/PPPPPP2/QQQ1K3 w - - 0 1").is_empty());
+    }
+
+    #[test]
+    fn test_reports_every_violation() {
+        let problems = validate_setup("4k3/4K3/8/8/8/8/PPPPPPPP/P3R3 w - - 0 1");
+        assert_eq!(
+            problems,
+            vec![
+                "Pawn on rank 1",
+                "White has 9 pawns",
+                "The kings are on adjacent squares",
+                "Black is in check but it is White's turn",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_promotions_and_en_passant() {
+        let problems = validate_setup("4k3/8/8/8/8/8/PPPPPPP1/QQQ1K3 w - - 0 1");
+        assert_eq!(problems, vec!["White has 2 promoted pieces but only 1 missing pawns"]);
+
+        let problems = validate_setup("4k3/8/8/8/8/8/8/4K3 w - e6 0 1");
+        assert_eq!(problems, vec!["En passant square e6 does not follow a two-square black pawn move"]);
+
+        assert_eq!(validate_setup("8/8/8 w - - 0 1").len(), 1);
+    }
+}
@@ -0,0 +1,277 @@
+use crate::chess_engine::error::{ChessError, Result};
+use crate::chess_engine::notation::figurine;
+use crate::chess_engine::position::Position;
+use crate::chess_engine::types::{Color, Move, Square};
+use serde::{Deserialize, Serialize};
+use std::fmt::Write;
+
+const DEFAULT_SIZE: u32 = 400;
+const LIGHT_SQUARE: &str = "#f0d9b5";
+const DARK_SQUARE: &str = "#b58863";
+const LAST_MOVE: &str = "#cdd26a";
+const CHECK: &str = "#e03030";
+const ARROW: &str = "#15781b";
+
+/// What to draw on top of the board in an SVG export
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SvgOptions {
+    /// Side shown at the bottom; White when not given
+    pub orientation: Option<Color>,
+    /// Width and height of the image in pixels
+    pub size: Option<u32>,
+    pub highlight_last_move: bool,
+    pub highlight_check: bool,
+    pub arrows: Vec<Arrow>,
+}
+
+/// An arrow between two squares given in algebraic notation
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Arrow {
+    pub from: String,
+    pub to: String,
+    /// CSS colour such as "#ff0000" or "blue"
+    #[serde(default)]
+    pub color: Option<String>,
+}
+
+/// The position as a standalone SVG image. `last_move` and `check` (the king in
+/// check) are only drawn when the matching option is set.
+pub fn render_svg(
+    position: &Position,
+    options: &SvgOptions,
+    last_move: Option<&Move>,
+    check: Option<Square>,
+) -> Result<String> {
+    let size = options.size.unwrap_or(DEFAULT_SIZE).max(64);
+    let board = Layout {
+        square: f64::from(size) / 8.0,
+        flipped: options.orientation == Some(Color::Black),
+    };
+
+    let mut svg = String::new();
+    let _ = write!(
+        svg,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{size}" height="{size}" viewBox="0 0 {size} {size}">"#
+    );
+
+    for index in 0..64 {
+        let Some(square) = Square::new(index) else {
+            continue;
+        };
+        let fill = if (square.rank() + square.file()) % 2 == 0 { DARK_SQUARE } else { LIGHT_SQUARE };
+        board.rect(&mut svg, square, fill, 1.0);
+    }
+
+    if options.highlight_last_move {
+        if let Some(mv) = last_move {
+            board.rect(&mut svg, mv.from, LAST_MOVE, 0.8);
+            board.rect(&mut svg, mv.to, LAST_MOVE, 0.8);
+        }
+    }
+    if let (true, Some(king)) = (options.highlight_check, check) {
+        let (x, y) = board.center(king);
+        let _ = write!(
+            svg,
+            r#"<circle cx="{x:.1}" cy="{y:.1}" r="{:.1}" fill="{CHECK}" fill-opacity="0.7"/>"#,
+            board.square * 0.48
+        );
+    }
+
+    board.coordinates(&mut svg);
+
+    for index in 0..64 {
+        let Some(square) = Square::new(index) else {
+            continue;
+        };
+        let Some((piece, color)) = position.board.get(square) else {
+            continue;
+        };
+        let (x, y) = board.center(square);
+        let (fill, stroke) = match color {
+            Color::White => ("#ffffff", "#000000"),
+            Color::Black => ("#000000", "#ffffff"),
+        };
+        let _ = write!(
+            svg,
+            r#"<text x="{x:.1}" y="{y:.1}" font-size="{:.1}" text-anchor="middle" dominant-baseline="central" fill="{fill}" stroke="{stroke}" stroke-width="{:.1}" paint-order="stroke">{}</text>"#,
+            board.square * 0.8,
+            board.square * 0.02,
+            figurine(piece)
+        );
+    }
+
+    for arrow in &options.arrows {
+        board.arrow(&mut svg, arrow)?;
+    }
+
+    svg.push_str("</svg>");
+    Ok(svg)
+}
+
+/// Pixel geometry of the board for a given size and orientation
+struct Layout {
+    square: f64,
+    flipped: bool,
+}
+
+impl Layout {
+    /// Top-left corner of a square
+    fn corner(&self, square: Square) -> (f64, f64) {
+        let (column, row) = if self.flipped {
+            (7 - square.file(), square.rank())
+        } else {
+            (square.file(), 7 - square.rank())
+        };
+        (f64::from(column) * self.square, f64::from(row) * self.square)
+    }
+
+    fn center(&self, square: Square) -> (f64, f64) {
+        let (x, y) = self.corner(square);
+        (x + self.square / 2.0, y + self.square / 2.0)
+    }
+
+    fn rect(&self, svg: &mut String, square: Square, fill: &str, opacity: f64) {
+        let (x, y) = self.corner(square);
+        let _ = write!(
+            svg,
+            r#"<rect x="{x:.1}" y="{y:.1}" width="{0:.1}" height="{0:.1}" fill="{fill}" fill-opacity="{opacity}"/>"#,
+            self.square
+        );
+    }
+
+    /// Rank numbers down the left edge and file letters along the bottom
+    fn coordinates(&self, svg: &mut String) {
+        let font_size = self.square * 0.2;
+        for i in 0..8u8 {
+            let (rank, file) = if self.flipped { (i, 7 - i) } else { (7 - i, i) };
+            let row = f64::from(i) * self.square;
+            let _ = write!(
+                svg,
+                r#"<text x="{:.1}" y="{:.1}" font-size="{font_size:.1}" font-family="sans-serif" fill="{}">{}</text>"#,
+                self.square * 0.05,
+                row + font_size,
+                if i % 2 == 0 { DARK_SQUARE } else { LIGHT_SQUARE },
+                rank + 1
+            );
+            let _ = write!(
+                svg,
+                r#"<text x="{:.1}" y="{:.1}" font-size="{font_size:.1}" font-family="sans-serif" text-anchor="end" fill="{}">{}</text>"#,
+                row + self.square * 0.95,
+                self.square * 7.95,
+                if i % 2 == 0 { LIGHT_SQUARE } else { DARK_SQUARE },
+                (b'a' + file) as char
+            );
+        }
+    }
+
+    /// A shaft from the centre of `from` ending in a triangular head on `to`
+    fn arrow(&self, svg: &mut String, arrow: &Arrow) -> Result<()> {
+        let from = Square::from_algebraic(&arrow.from)?;
+        let to = Square::from_algebraic(&arrow.to)?;
+        let color = match arrow.color.as_deref() {
+            None => ARROW,
+            Some(color) if is_safe_color(color) => color,
+            Some(color) => {
+                return Err(ChessError::ParseError {
+                    input: color.to_string(),
+                })
+            }
+        };
+
+        let (x1, y1) = self.center(from);
+        let (x2, y2) = self.center(to);
+        let length = (x2 - x1).hypot(y2 - y1);
+        if length == 0.0 {
+            return Ok(());
+        }
+        let (ux, uy) = ((x2 - x1) / length, (y2 - y1) / length);
+        let head = self.square * 0.4;
+        let half_width = self.square * 0.2;
+        let (bx, by) = (x2 - ux * head, y2 - uy * head);
+
+        let _ = write!(
+            svg,
+            r#"<g fill="{color}" stroke="{color}" opacity="0.8"><line x1="{x1:.1}" y1="{y1:.1}" x2="{bx:.1}" y2="{by:.1}" stroke-width="{:.1}" stroke-linecap="round"/><polygon points="{x2:.1},{y2:.1} {:.1},{:.1} {:.1},{:.1}" stroke="none"/></g>"#,
+            self.square * 0.15,
+            bx - uy * half_width,
+            by + ux * half_width,
+            bx + uy * half_width,
+            by - ux * half_width
+        );
+        Ok(())
+    }
+}
+
+/// Hex codes and colour names only, so nothing can break out of the attribute
+fn is_safe_color(color: &str) -> bool {
+    match color.strip_prefix('#') {
+        Some(hex) => matches!(hex.len(), 3 | 4 | 6 | 8) && hex.chars().all(|c| c.is_ascii_hexdigit()),
+        None => !color.is_empty() && color.chars().all(|c| c.is_ascii_alphabetic()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chess_engine::fen::parse_fen;
+
+    fn arrow(from: &str, to: &str, color: Option<&str>) -> Arrow {
+        Arrow {
+            from: from.to_string(),
+            to: to.to_string(),
+            color: color.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn test_starting_position() {
+        let svg = render_svg(&Position::new(), &SvgOptions::default(), None, None).unwrap();
+
+        assert!(svg.starts_with("<svg") && svg.ends_with("</svg>"));
+        assert_eq!(svg.matches("<rect").count(), 64);
+        assert_eq!(svg.matches("paint-order").count(), 32);
+        assert!(!svg.contains("<circle"));
+    }
+
+    #[test]
+    fn test_orientation() {
+        let position = parse_fen("4k3/8/8/8/8/8/8/K7 w - - 0 1").unwrap();
+        let king = |orientation| {
+            let options = SvgOptions {
+                orientation,
+                size: Some(80),
+                ..SvgOptions::default()
+            };
+            let svg = render_svg(&position, &options, None, None).unwrap();
+            let at = svg.find("\u{265A}</text>").unwrap();
+            svg[..at].rsplit("<text ").next().unwrap().to_string()
+        };
+
+        // a1 is bottom left for White and top right for Black
+        assert!(king(None).starts_with(r#"x="5.0" y="75.0""#));
+        assert!(king(Some(Color::Black)).starts_with(r#"x="75.0" y="5.0""#));
+    }
+
+    #[test]
+    fn test_highlights_and_arrows() {
+        let position = parse_fen("4k3/8/8/8/8/8/8/R3K3 b - - 0 1").unwrap();
+        let last_move = Move::from_uci("a1a8", &parse_fen("4k3/8/8/8/8/8/8/R3K3 w - - 0 1").unwrap()).unwrap();
+        let options = SvgOptions {
+            highlight_last_move: true,
+            highlight_check: true,
+            arrows: vec![arrow("e8", "d7", None), arrow("e1", "e2", Some("#00f"))],
+            ..SvgOptions::default()
+        };
+
+        let svg = render_svg(&position, &options, Some(&last_move), Square::from_algebraic("e8").ok()).unwrap();
+        assert_eq!(svg.matches(LAST_MOVE).count(), 2);
+        assert!(svg.contains(CHECK));
+        assert_eq!(svg.matches("<polygon").count(), 2);
+        assert!(svg.contains(r##"fill="#00f""##));
+
+        let bad_options = |arrows| SvgOptions { arrows, ..SvgOptions::default() };
+        assert!(render_svg(&position, &bad_options(vec![arrow("e9", "e2", None)]), None, None).is_err());
+        assert!(render_svg(&position, &bad_options(vec![arrow("e1", "e2", Some("red\"/>"))]), None, None).is_err());
+    }
+}
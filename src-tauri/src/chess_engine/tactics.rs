@@ -0,0 +1,200 @@
+use crate::chess_engine::analysis::piece_value;
+use crate::chess_engine::board::Board;
+use crate::chess_engine::types::{Color, Move, Piece, Square};
+use crate::chess_engine::validation::checkers;
+use crate::chess_engine::position::Position;
+use serde::{Deserialize, Serialize};
+
+const DIAGONALS: [(i8, i8); 4] = [(-1, -1), (-1, 1), (1, -1), (1, 1)];
+const ORTHOGONALS: [(i8, i8); 4] = [(-1, 0), (1, 0), (0, -1), (0, 1)];
+const ALL_DIRECTIONS: [(i8, i8); 8] = [(-1, -1), (-1, 1), (1, -1), (1, 1), (-1, 0), (1, 0), (0, -1), (0, 1)];
+
+/// A tactical pattern on the board. Squares name the pieces standing on them.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum Motif {
+    /// One piece attacks two or more targets worth attacking: the king, pieces worth
+    /// more than the attacker, or undefended pieces
+    Fork { attacker: Square, targets: Vec<Square> },
+    /// A slider attacks `pinned`, which shields the more valuable `behind`
+    Pin { attacker: Square, pinned: Square, behind: Square },
+    /// A slider attacks a valuable piece (or the king) that shields a lesser one behind it
+    Skewer { attacker: Square, front: Square, behind: Square },
+    /// Moving a piece opened a line for `attacker` onto new targets
+    DiscoveredAttack { attacker: Square, targets: Vec<Square> },
+    DoubleCheck { checkers: Vec<Square> },
+}
+
+/// Every fork, pin and skewer either side has in a position, plus a double check
+/// against the side to move
+pub fn position_motifs(position: &Position) -> Vec<Motif> {
+    let board = &position.board;
+    let mut motifs = Vec::new();
+    for color in [Color::White, Color::Black] {
+        for (square, piece) in board.pieces_of_color(color) {
+            motifs.extend(fork(board, square, piece, color));
+            motifs.extend(line_motifs(board, square, piece, color));
+        }
+    }
+    motifs.extend(double_check(position, position.side_to_move));
+    motifs
+}
+
+/// Motifs a move creates: forks, pins and skewers by the moved piece, attacks
+/// discovered by moving it out of the way, and double check
+pub fn move_motifs(before: &Position, after: &Position, mv: &Move) -> Vec<Motif> {
+    if mv.is_null {
+        return Vec::new();
+    }
+    let color = before.side_to_move;
+    let board = &after.board;
+    let mut motifs = Vec::new();
+
+    if let Some((piece, _)) = board.get(mv.to) {
+        motifs.extend(fork(board, mv.to, piece, color));
+        motifs.extend(line_motifs(board, mv.to, piece, color));
+    }
+
+    let rook_to = mv.castling_rook_squares().map(|(_, to)| to);
+    for (square, piece) in board.pieces_of_color(color) {
+        if square == mv.to || Some(square) == rook_to {
+            continue;
+        }
+        let attacked_before = attacked_enemies(&before.board, square, piece, color);
+        let targets: Vec<Square> = attacked_enemies(board, square, piece, color)
+            .into_iter()
+            .filter(|target| !attacked_before.contains(target))
+            .collect();
+        if !targets.is_empty() {
+            motifs.push(Motif::DiscoveredAttack { attacker: square, targets });
+        }
+    }
+
+    motifs.extend(double_check(after, color.opposite()));
+    motifs
+}
+
+fn fork(board: &Board, square: Square, piece: Piece, color: Color) -> Option<Motif> {
+    let targets: Vec<Square> = attacked_enemies(board, square, piece, color)
+        .into_iter()
+        .filter(|&target| {
+            let Some((target_piece, _)) = board.get(target) else {
+                return false;
+            };
+            target_piece == Piece::King
+                || value(target_piece) > value(piece)
+                || !board.is_attacked_by(target, color.opposite())
+        })
+        .collect();
+
+    (targets.len() >= 2).then_some(Motif::Fork { attacker: square, targets })
+}
+
+/// Pins and skewers by a bishop, rook or queen: two enemy pieces in a row on one of its lines
+fn line_motifs(board: &Board, square: Square, piece: Piece, color: Color) -> Vec<Motif> {
+    let directions: &[(i8, i8)] = match piece {
+        Piece::Bishop => &DIAGONALS,
+        Piece::Rook => &ORTHOGONALS,
+        Piece::Queen => &ALL_DIRECTIONS,
+        _ => return Vec::new(),
+    };
+
+    let mut motifs = Vec::new();
+    for &(rank_dir, file_dir) in directions {
+        let mut pieces = ray(square, rank_dir, file_dir).filter_map(|s| board.get(s).map(|(p, c)| (s, p, c)));
+        let (Some((front, front_piece, front_color)), Some((behind, behind_piece, behind_color))) =
+            (pieces.next(), pieces.next())
+        else {
+            continue;
+        };
+        if front_color == color || behind_color == color {
+            continue;
+        }
+
+        if value(behind_piece) > value(front_piece) {
+            motifs.push(Motif::Pin { attacker: square, pinned: front, behind });
+        } else if value(front_piece) > value(behind_piece) {
+            motifs.push(Motif::Skewer { attacker: square, front, behind });
+        }
+    }
+    motifs
+}
+
+fn double_check(position: &Position, king_color: Color) -> Option<Motif> {
+    let checkers: Vec<Square> = checkers(position, king_color).into_iter().map(|checker| checker.square).collect();
+    (checkers.len() >= 2).then_some(Motif::DoubleCheck { checkers })
+}
+
+/// Enemy pieces the piece on `square` attacks
+fn attacked_enemies(board: &Board, square: Square, piece: Piece, color: Color) -> Vec<Square> {
+    let attacks = board.attacks_from(square, piece, color) & board.occupancy(color.opposite());
+    (0..64).filter(|index| attacks & (1u64 << index) != 0).filter_map(Square::new).collect()
+}
+
+/// Squares from `square` (exclusive) to the edge of the board in one direction
+fn ray(square: Square, rank_dir: i8, file_dir: i8) -> impl Iterator<Item = Square> {
+    (1..8).map_while(move |step| {
+        let rank = square.rank() as i8 + rank_dir * step;
+        let file = square.file() as i8 + file_dir * step;
+        if (0..8).contains(&rank) && (0..8).contains(&file) {
+            Square::from_rank_file(rank as u8, file as u8)
+        } else {
+            None
+        }
+    })
+}
+
+/// Piece values for comparing targets, with the king above everything
+fn value(piece: Piece) -> i32 {
+    match piece {
+        Piece::King => 10_000,
+        _ => piece_value(piece),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chess_engine::fen::parse_fen;
+    use crate::chess_engine::validation::position_after_move;
+
+    fn square(name: &str) -> Square {
+        Square::from_algebraic(name).unwrap()
+    }
+
+    fn motifs_after(fen: &str, uci: &str) -> Vec<Motif> {
+        let before = parse_fen(fen).unwrap();
+        let mv = Move::from_uci(uci, &before).unwrap();
+        move_motifs(&before, &position_after_move(&before, &mv), &mv)
+    }
+
+    #[test]
+    fn test_knight_fork() {
+        let motifs = motifs_after("r3k3/8/8/3N4/8/8/8/4K3 w - - 0 1", "d5c7");
+        assert!(motifs.contains(&Motif::Fork {
+            attacker: square("c7"),
+            targets: vec![square("a8"), square("e8")],
+        }));
+    }
+
+    #[test]
+    fn test_pin_and_skewer() {
+        // The bishop pins the knight to the queen behind it
+        let position = parse_fen("3qk3/8/1n6/B7/8/8/8/4K3 w - - 0 1").unwrap();
+        let motifs = position_motifs(&position);
+        assert_eq!(motifs, vec![Motif::Pin { attacker: square("a5"), pinned: square("b6"), behind: square("d8") }]);
+
+        // Checking the king along the e-file skewers it to the rook behind it
+        let motifs = motifs_after("4r3/8/8/4k3/8/8/8/R6K w - - 0 1", "a1e1");
+        assert!(motifs.contains(&Motif::Skewer { attacker: square("e1"), front: square("e5"), behind: square("e8") }));
+    }
+
+    #[test]
+    fn test_discovered_and_double_check() {
+        // Moving the knight off the e-file uncovers the rook and checks from f6 as well
+        let motifs = motifs_after("4k3/8/8/8/4N3/8/8/4R1K1 w - - 0 1", "e4f6");
+
+        assert!(motifs.contains(&Motif::DiscoveredAttack { attacker: square("e1"), targets: vec![square("e8")] }));
+        assert!(motifs.contains(&Motif::DoubleCheck { checkers: vec![square("e1"), square("f6")] }));
+    }
+}
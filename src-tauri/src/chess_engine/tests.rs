@@ -1,100 +1,10 @@
 use crate::chess_engine::game::ChessGame;
-use crate::chess_engine::fen::{parse_fen, position_to_fen, STARTING_FEN};
+use crate::chess_engine::fen::{parse_fen, parse_fen_for, position_to_fen, STARTING_FEN};
 use crate::chess_engine::validation::{generate_legal_moves, is_in_check, is_checkmate, is_stalemate};
-use crate::chess_engine::types::{Color, Piece, Square, Move, GameStatus};
+use crate::chess_engine::types::{Color, Piece, Square, Move, DrawRules, GameStatus, Termination};
 use crate::chess_engine::position::Position;
-
-// Helper function for perft testing
-fn perft(position: &mut Position, depth: u8) -> u64 {
-    if depth == 0 {
-        return 1;
-    }
-
-    let moves = generate_legal_moves(position);
-
-    if depth == 1 {
-        return moves.len() as u64;
-    }
-
-    let mut count = 0;
-    for mv in moves {
-        let snapshot = position.clone();
-
-        // Apply move
-        apply_move_for_perft(position, &mv);
-
-        count += perft(position, depth - 1);
-
-        // Restore position
-        *position = snapshot;
-    }
-
-    count
-}
-
-fn apply_move_for_perft(position: &mut Position, mv: &Move) {
-    // Update castling rights
-    position.update_castling_rights_after_move(mv);
-
-    // Handle en passant capture
-    if mv.is_en_passant {
-        let captured_pawn_rank = if position.side_to_move == Color::White {
-            mv.to.rank() - 1
-        } else {
-            mv.to.rank() + 1
-        };
-        if let Some(captured_square) = Square::from_rank_file(captured_pawn_rank, mv.to.file()) {
-            position.board.set(captured_square, None);
-        }
-    }
-
-    // Handle castling
-    if mv.is_castling {
-        let rank = mv.from.rank();
-        if mv.to.file() > mv.from.file() {
-            let rook_from = Square::from_rank_file(rank, 7).unwrap();
-            let rook_to = Square::from_rank_file(rank, 5).unwrap();
-            let rook = position.board.get(rook_from);
-            position.board.set(rook_from, None);
-            position.board.set(rook_to, rook);
-        } else {
-            let rook_from = Square::from_rank_file(rank, 0).unwrap();
-            let rook_to = Square::from_rank_file(rank, 3).unwrap();
-            let rook = position.board.get(rook_from);
-            position.board.set(rook_from, None);
-            position.board.set(rook_to, rook);
-        }
-    }
-
-    // Move the piece
-    let piece = position.board.get(mv.from);
-    position.board.set(mv.from, None);
-
-    if let Some(promotion_piece) = mv.promotion {
-        if let Some((_, color)) = piece {
-            position.board.set(mv.to, Some((promotion_piece, color)));
-        }
-    } else {
-        position.board.set(mv.to, piece);
-    }
-
-    // Update en passant target
-    if let Some((Piece::Pawn, _)) = position.board.get(mv.to) {
-        let from_rank = mv.from.rank();
-        let to_rank = mv.to.rank();
-        if from_rank.abs_diff(to_rank) == 2 {
-            let ep_rank = (from_rank + to_rank) / 2;
-            position.en_passant_target = Square::from_rank_file(ep_rank, mv.from.file());
-        } else {
-            position.en_passant_target = None;
-        }
-    } else {
-        position.en_passant_target = None;
-    }
-
-    // Switch side to move
-    position.side_to_move = position.side_to_move.opposite();
-}
+use crate::chess_engine::notation::Notation;
+use crate::chess_engine::rules::Variant;
 
 // Helper functions for testing
 fn assert_move_legal(game: &ChessGame, from: &str, to: &str) {
@@ -193,6 +103,32 @@ mod basic_moves {
         assert_move_legal(&game, "e5", "d6");
     }
 
+    #[test]
+    fn test_legal_destinations_by_origin() {
+        let game = ChessGame::new();
+        let destinations = game.legal_destinations();
+        assert_eq!(destinations.len(), 10);
+        assert_eq!(destinations["e2"].len(), 2);
+        assert_eq!(destinations["g1"].len(), 2);
+        assert!(!destinations.contains_key("e1"));
+
+        // Four promotions to one square are one destination
+        let game = ChessGame::from_fen("8/P7/8/8/8/8/8/K6k w - - 0 1").unwrap();
+        let a7 = &game.legal_destinations()["a7"];
+        assert_eq!(a7.len(), 1);
+        assert!(a7[0].promotion && !a7[0].castling);
+
+        let game = ChessGame::from_fen("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1").unwrap();
+        assert_eq!(game.legal_destinations()["e1"].iter().filter(|d| d.castling).count(), 2);
+
+        // A Chess960 king on f1 reaches g1 by stepping or by castling with the h1 rook
+        let game = ChessGame::from_fen("4k3/8/8/8/8/8/8/5K1R w H - 0 1").unwrap();
+        let f1 = &game.legal_destinations()["f1"];
+        let g1 = f1.iter().filter(|d| d.to == Square::from_algebraic("g1").unwrap()).collect::<Vec<_>>();
+        assert_eq!(g1.len(), 1);
+        assert!(g1[0].castling);
+    }
+
     #[test]
     fn test_pawn_promotion() {
         let game = ChessGame::from_fen("8/P7/8/8/8/8/8/K6k w - - 0 1").unwrap();
@@ -202,11 +138,41 @@ mod basic_moves {
         let promotion_moves: Vec<_> = moves.iter().filter(|m| m.promotion.is_some()).collect();
         assert_eq!(promotion_moves.len(), 4);
     }
+
+    #[test]
+    fn test_board_state_leaves_out_history() {
+        let mut game = ChessGame::new();
+        make_moves(&mut game, &[("e2", "e4"), ("e7", "e5"), ("d1", "h5")]);
+        let json = serde_json::to_value(game.board_state()).unwrap();
+        assert!(json.get("position_history").is_none());
+        assert_eq!(json["status"]["type"], "InProgress");
+        assert_eq!(json["side_to_move"], "Black");
+        assert_eq!(json["board"].as_array().unwrap().len(), 64);
+    }
+
+    #[test]
+    fn test_legal_moves_follow_the_position() {
+        let mut game = ChessGame::new();
+        make_moves(&mut game, &[("e2", "e4")]);
+        assert_eq!(game.get_legal_moves(), generate_legal_moves(game.get_board_state()));
+        assert_eq!(game.get_legal_moves_for_square(Square::from_algebraic("e4").unwrap()).len(), 0);
+
+        game.undo_move().unwrap();
+        assert_eq!(game.get_legal_moves().len(), 20);
+        game.redo_move().unwrap();
+        assert_eq!(game.get_legal_moves().len(), 20);
+        assert!(game.get_legal_moves().iter().all(|mv| mv.from.rank() >= 6));
+
+        game.goto_variation(&[]).unwrap();
+        assert_eq!(game.get_legal_moves(), generate_legal_moves(game.get_board_state()));
+    }
 }
 
 #[cfg(test)]
 mod castling {
     use super::*;
+    use crate::chess_engine::analysis::MoveAnalysis;
+    use crate::chess_engine::history::CapturedPieces;
 
     #[test]
     fn test_white_kingside_castling() {
@@ -260,6 +226,39 @@ mod castling {
         // White can still castle queenside (rook on a1)
         assert_move_legal(&game, "e1", "c1");
     }
+
+    #[test]
+    fn test_chess960_castling() {
+        // King on b1 between rooks on a1 and g1
+        let mut game = ChessGame::from_fen("4k3/8/8/8/8/8/8/RK4R1 w AG - 0 1").unwrap();
+        let position = game.get_board_state().clone();
+
+        // Castling is written as the king taking its rook; b1c1 stays a king step
+        let queenside = Move::from_uci("b1a1", &position).unwrap();
+        assert!(queenside.is_castling);
+        assert_eq!(queenside.to_san(&position), "O-O-O");
+        assert!(!Move::from_uci("b1c1", &position).unwrap().is_castling);
+
+        // The kingside rook stands on the king's destination
+        let kingside = Move::from_san("O-O", &position).unwrap();
+        assert_eq!(kingside.to_uci(), "b1g1");
+        game.make_move(kingside).unwrap();
+        assert_eq!(game.to_fen(), "4k3/8/8/8/8/8/8/R4RK1 b - - 1 1");
+        assert_eq!(game.captured_pieces(), CapturedPieces::default());
+        assert_eq!(game.move_records()[0].captured, None);
+        assert!(!MoveAnalysis::analyze(&kingside, &position).is_capture);
+        game.undo_move().unwrap();
+        assert_eq!(game.to_fen(), "4k3/8/8/8/8/8/8/RK4R1 w KQ - 0 1");
+    }
+
+    #[test]
+    fn test_chess960_castling_rook_shields_the_king() {
+        // The b1 rook blocks a1 from the king's path until it moves to d1
+        let game = ChessGame::from_fen("4k3/8/8/8/8/8/8/rR3K2 w B - 0 1").unwrap();
+        let position = game.get_board_state();
+        assert!(Move::from_uci("f1b1", position).is_err());
+        assert!(!game.get_legal_moves().iter().any(|mv| mv.is_castling));
+    }
 }
 
 #[cfg(test)]
@@ -308,6 +307,24 @@ mod check_and_checkmate {
         assert!(is_stalemate(&position));
     }
 
+    #[test]
+    fn test_check_info_lists_checkers() {
+        let game = ChessGame::new();
+        let info = game.check_info();
+        assert!(!info.in_check && info.checkers.is_empty());
+        assert_eq!(info.king_square, Square::from_algebraic("e1").ok());
+
+        // Discovered double check from the rook on e1 and the knight on f6
+        let game = ChessGame::from_fen("4k3/8/5N2/8/8/8/8/4R1K1 b - - 0 1").unwrap();
+        let info = game.check_info();
+        assert!(info.in_check && !info.is_checkmate);
+        let checkers: Vec<Piece> = info.checkers.iter().map(|checker| checker.piece).collect();
+        assert_eq!(checkers, vec![Piece::Rook, Piece::Knight]);
+
+        let mated = ChessGame::from_fen("R5k1/5ppp/8/8/8/8/8/7K b - - 0 1").unwrap();
+        assert!(mated.check_info().is_checkmate);
+    }
+
     #[test]
     fn test_must_block_check() {
         // Black king is in check from White queen on e2
@@ -387,73 +404,128 @@ mod fen_parsing {
             assert!(e.to_string().contains("Multiple black kings"));
         }
     }
+
+    #[test]
+    fn test_variant_fen_round_trips() {
+        let round_trip = |fen: &str| position_to_fen(&parse_fen(fen).unwrap());
+
+        // Crazyhouse pockets, written in brackets whichever form they came in
+        let crazyhouse = "r1bqkbnr/pppp1ppp/2n5/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R[Pp] w KQkq - 2 3";
+        assert_eq!(round_trip(crazyhouse), crazyhouse);
+        assert_eq!(
+            round_trip("r1bqkbnr/pppp1ppp/2n5/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R/Pp w KQkq - 2 3"),
+            crazyhouse
+        );
+
+        // Three-check counters, given or remaining
+        let three_check = "rnbqkbnr/pppp1ppp/8/4p3/4P3/8/PPPP1PPP/RNBQKBNR w KQkq - 0 2 +1+0";
+        assert_eq!(round_trip(three_check), three_check);
+        assert_eq!(round_trip("rnbqkbnr/pppp1ppp/8/4p3/4P3/8/PPPP1PPP/RNBQKBNR w KQkq - 2+3 0 2"), three_check);
+
+        // Chess960 castling as X-FEN, from Shredder-FEN file letters too; a rook's file is
+        // only named when another rook stands further out on its side
+        let chess960 = "bqnb1rkr/pp3ppp/3ppn2/2p5/5P2/P2P4/NPP1P1PP/BQ1BNRKR w KQkq - 2 9";
+        let position = parse_fen("bqnb1rkr/pp3ppp/3ppn2/2p5/5P2/P2P4/NPP1P1PP/BQ1BNRKR w HFhf - 2 9").unwrap();
+        assert_eq!(position_to_fen(&position), chess960);
+        assert_eq!(position_to_fen(&parse_fen_for(chess960, Variant::Chess960).unwrap()), chess960);
+        assert_eq!(round_trip("4k3/8/8/8/8/8/8/1K3RR1 w F - 0 1"), "4k3/8/8/8/8/8/8/1K3RR1 w F - 0 1");
+    }
 }
 
 #[cfg(test)]
 mod perft_tests {
     use super::*;
+    use crate::chess_engine::perft::{perft, perft_hashed};
 
     #[test]
     fn test_perft_starting_position_depth_1() {
-        let mut position = Position::new();
-        assert_eq!(perft(&mut position, 1), 20);
+        let position = Position::new();
+        assert_eq!(perft(&position, 1), 20);
     }
 
     #[test]
     fn test_perft_starting_position_depth_2() {
-        let mut position = Position::new();
-        assert_eq!(perft(&mut position, 2), 400);
+        let position = Position::new();
+        assert_eq!(perft(&position, 2), 400);
     }
 
     #[test]
     fn test_perft_starting_position_depth_3() {
-        let mut position = Position::new();
-        assert_eq!(perft(&mut position, 3), 8902);
+        let position = Position::new();
+        assert_eq!(perft(&position, 3), 8902);
     }
 
     #[test]
     fn test_perft_starting_position_depth_4() {
-        let mut position = Position::new();
-        assert_eq!(perft(&mut position, 4), 197281);
+        let position = Position::new();
+        assert_eq!(perft(&position, 4), 197281);
     }
 
     #[test]
     fn test_perft_kiwipete_depth_1() {
-        let mut position = parse_fen("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1").unwrap();
-        assert_eq!(perft(&mut position, 1), 48);
+        let position = parse_fen("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1").unwrap();
+        assert_eq!(perft(&position, 1), 48);
     }
 
     #[test]
     fn test_perft_kiwipete_depth_2() {
-        let mut position = parse_fen("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1").unwrap();
-        assert_eq!(perft(&mut position, 2), 2039);
+        let position = parse_fen("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1").unwrap();
+        assert_eq!(perft(&position, 2), 2039);
     }
 
     #[test]
     fn test_perft_kiwipete_depth_3() {
-        let mut position = parse_fen("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1").unwrap();
-        assert_eq!(perft(&mut position, 3), 97862);
+        let position = parse_fen("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1").unwrap();
+        assert_eq!(perft(&position, 3), 97862);
+    }
+
+    #[test]
+    fn test_perft_hashed_kiwipete_depth_4() {
+        let position = parse_fen("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1").unwrap();
+        assert_eq!(perft_hashed(&position, 4, 16), 4085603);
     }
 
     #[test]
     fn test_perft_with_en_passant() {
-        let mut position = parse_fen("rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1").unwrap();
-        assert_eq!(perft(&mut position, 1), 20);
+        let position = parse_fen("rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1").unwrap();
+        assert_eq!(perft(&position, 1), 20);
     }
 
     #[test]
     fn test_perft_position_3() {
-        let mut position = parse_fen("8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1").unwrap();
-        assert_eq!(perft(&mut position, 1), 14);
-        assert_eq!(perft(&mut position, 2), 191);
-        assert_eq!(perft(&mut position, 3), 2812);
+        let position = parse_fen("8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1").unwrap();
+        assert_eq!(perft(&position, 1), 14);
+        assert_eq!(perft(&position, 2), 191);
+        assert_eq!(perft(&position, 3), 2812);
     }
 
     #[test]
     fn test_perft_position_4() {
-        let mut position = parse_fen("r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1").unwrap();
-        assert_eq!(perft(&mut position, 1), 6);
-        assert_eq!(perft(&mut position, 2), 264);
+        let position = parse_fen("r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1").unwrap();
+        assert_eq!(perft(&position, 1), 6);
+        assert_eq!(perft(&position, 2), 264);
+        assert_eq!(perft(&position, 3), 9467);
+    }
+
+    #[test]
+    fn test_perft_position_5() {
+        let position = parse_fen("rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8").unwrap();
+        assert_eq!(perft(&position, 1), 44);
+        assert_eq!(perft(&position, 2), 1486);
+        assert_eq!(perft(&position, 3), 62379);
+    }
+
+    #[test]
+    fn test_perft_chess960() {
+        let position = parse_fen("bqnb1rkr/pp3ppp/3ppn2/2p5/5P2/P2P4/NPP1P1PP/BQ1BNRKR w HFhf - 2 9").unwrap();
+        assert_eq!(perft(&position, 1), 21);
+        assert_eq!(perft(&position, 2), 528);
+        assert_eq!(perft(&position, 3), 12189);
+
+        let position = parse_fen("2nnrbkr/p1qppppp/8/1ppb4/6PP/3PP3/PPP2P2/BQNNRBKR w HEhe - 1 9").unwrap();
+        assert_eq!(perft(&position, 1), 21);
+        assert_eq!(perft(&position, 2), 807);
+        assert_eq!(perft(&position, 3), 18002);
     }
 }
 
@@ -517,6 +589,57 @@ mod game_endings {
         // Position should have occurred 3 times now
         assert!(game.get_board_state().is_repetition());
     }
+
+    fn shuffle_knights(game: &mut ChessGame, times: usize) {
+        for _ in 0..times {
+            make_moves(game, &[("g1", "f3"), ("g8", "f6"), ("f3", "g1"), ("f6", "g8")]);
+        }
+    }
+
+    #[test]
+    fn test_dead_position_status() {
+        let game = ChessGame::from_fen("8/4k3/8/1p1p1p1p/pPpPpPpP/P1P1P1P1/8/4K3 w - - 0 1").unwrap();
+        assert_eq!(game.get_status(), GameStatus::DrawByDeadPosition);
+        assert_eq!(game.get_status().result().result, "1/2-1/2");
+    }
+
+    #[test]
+    fn test_claimable_repetition() {
+        let mut game = ChessGame::new();
+        game.set_draw_rules(DrawRules::Claimable);
+        assert!(game.claim_draw().is_err());
+
+        shuffle_knights(&mut game, 2);
+        assert_eq!(game.get_status(), GameStatus::DrawClaimable { reason: Termination::Repetition });
+        assert!(!game.get_status().is_over());
+
+        // Playing on is allowed, and fivefold repetition ends the game without a claim
+        shuffle_knights(&mut game, 2);
+        assert_eq!(game.get_status(), GameStatus::DrawByFivefoldRepetition);
+        assert!(game.get_status().is_over());
+
+        game.undo_move().unwrap();
+        game.claim_draw().unwrap();
+        assert_eq!(game.get_status(), GameStatus::DrawByRepetition);
+    }
+
+    #[test]
+    fn test_claimable_fifty_and_seventy_five_move_rules() {
+        let mut game = ChessGame::from_fen("k7/8/8/8/8/8/7R/K7 w - - 100 80").unwrap();
+        assert_eq!(game.get_status(), GameStatus::DrawByFiftyMoveRule);
+
+        game.set_draw_rules(DrawRules::Claimable);
+        assert_eq!(game.get_status(), GameStatus::DrawClaimable { reason: Termination::FiftyMoveRule });
+        make_moves(&mut game, &[("h2", "h3")]);
+        game.claim_draw().unwrap();
+        assert_eq!(game.get_status().result().termination, Some(Termination::FiftyMoveRule));
+
+        let mut game = ChessGame::from_fen("k7/8/8/8/8/8/7R/K7 w - - 149 80").unwrap();
+        game.set_draw_rules(DrawRules::Claimable);
+        make_moves(&mut game, &[("h2", "h3")]);
+        assert_eq!(game.get_status(), GameStatus::DrawBySeventyFiveMoveRule);
+        assert!(game.claim_draw().is_err());
+    }
 }
 
 #[cfg(test)]
@@ -538,6 +661,61 @@ mod edge_cases {
         assert_move_legal(&game, "d4", "f6");
     }
 
+    #[test]
+    fn test_pinned_pieces_report_pinner_and_ray() {
+        let game = ChessGame::from_fen("6k1/6b1/8/8/3Q4/8/8/K7 w - - 0 1").unwrap();
+        let pins = game.pinned_pieces(Color::White);
+
+        assert_eq!(pins.len(), 1);
+        assert_eq!((pins[0].square, pins[0].piece), (Square::from_algebraic("d4").unwrap(), Piece::Queen));
+        assert_eq!((pins[0].pinner_square, pins[0].pinner), (Square::from_algebraic("g7").unwrap(), Piece::Bishop));
+        let ray: Vec<String> = pins[0].ray.iter().map(|square| square.to_algebraic()).collect();
+        assert_eq!(ray, vec!["b2", "c3", "d4", "e5", "f6", "g7"]);
+        assert!(game.pinned_pieces(Color::Black).is_empty());
+    }
+
+    #[test]
+    fn test_make_move_checks_moves_not_from_generator() {
+        let mut game = ChessGame::new();
+        let e2 = Square::from_algebraic("e2").unwrap();
+
+        // A pawn cannot jump like a knight, nor castle, even though its king stays safe
+        let forged = Move::new(e2, Square::from_algebraic("f4").unwrap());
+        assert!(game.make_move(forged).is_err());
+        let forged = Move { is_castling: true, ..Move::new(e2, Square::from_algebraic("e4").unwrap()) };
+        assert!(game.make_move(forged).is_err());
+        assert_eq!(game.get_board_state().side_to_move, Color::White);
+
+        // The exact move the generator gave back is played as is
+        let mv = game.get_legal_moves_for_square(e2).into_iter().find(|mv| mv.to.to_algebraic() == "e4").unwrap();
+        game.make_move(mv).unwrap();
+        assert_eq!(game.san_history(), vec!["e4"]);
+    }
+
+    #[test]
+    fn test_uci_moves_all_or_none() {
+        let mut game = ChessGame::new();
+        game.make_uci_moves(&["e2e4", "e7e5", "g1f3"]).unwrap();
+        assert_eq!(game.san_history(), vec!["e4", "e5", "Nf3"]);
+
+        let before = game.to_fen();
+        let (index, _) = game.make_uci_moves(&["b8c6", "f1c4", "c6c4", "g8f6"]).unwrap_err();
+        assert_eq!(index, 2);
+        assert_eq!(game.to_fen(), before);
+        assert_eq!(game.san_history().len(), 3);
+    }
+
+    #[test]
+    fn test_game_from_uci_and_san_moves() {
+        let game = ChessGame::from_moves(&["e2e4", "e5", "Nf3", "b8c6", "Bb5"]).unwrap();
+        assert_eq!(game.san_history(), vec!["e4", "e5", "Nf3", "Nc6", "Bb5"]);
+        assert_eq!(game.get_board_state().side_to_move, Color::Black);
+
+        let (index, _) = ChessGame::from_moves(&["e4", "e5", "Ke3"]).unwrap_err();
+        assert_eq!(index, 2);
+        assert!(ChessGame::from_moves::<&str>(&[]).is_ok());
+    }
+
     #[test]
     fn test_en_passant_exposes_king() {
         // Black king on a4, Black pawn on e4, White pawn just moved d2-d4 (en passant target d3)
@@ -573,6 +751,69 @@ mod edge_cases {
         assert_eq!(game.to_fen(), initial_fen);
     }
 
+    #[test]
+    fn test_redo_replays_undone_moves() {
+        let mut game = ChessGame::new();
+        for san in ["e4", "e5", "Nf3"] {
+            game.make_san_move(san).unwrap();
+        }
+        game.annotate_move(&[0, 0, 0], Some("develops".to_string()), vec![1]).unwrap();
+        game.undo_move().unwrap();
+        game.undo_move().unwrap();
+
+        assert_eq!(game.redo_move().unwrap().to_uci(), "e7e5");
+        game.redo_move().unwrap();
+        assert_eq!(game.san_history(), vec!["e4", "e5", "Nf3"]);
+        assert_eq!(game.move_tree().node(&[0, 0, 0]).unwrap().comment.as_deref(), Some("develops"));
+        assert!(game.redo_move().is_err());
+
+        // Playing the undone move again keeps the redo stack; a different move clears it
+        game.undo_move().unwrap();
+        game.undo_move().unwrap();
+        game.make_san_move("e5").unwrap();
+        assert_eq!(game.redo_move().unwrap().to_uci(), "g1f3");
+        assert_eq!(game.move_tree().node(&[0, 0, 0]).unwrap().nags, vec![1]);
+
+        // Playing an undone move by hand reuses its node and annotations too
+        game.undo_move().unwrap();
+        game.make_san_move("Nf3").unwrap();
+        assert_eq!(game.move_tree().current, vec![0, 0, 0]);
+        assert_eq!(game.move_tree().node(&[0, 0, 0]).unwrap().comment.as_deref(), Some("develops"));
+        game.undo_move().unwrap();
+        game.make_san_move("d4").unwrap();
+        assert!(game.redo_move().is_err());
+
+        // The undone continuation stays in the tree as the main line
+        assert_eq!(game.move_tree().node(&[0, 0, 0]).unwrap().san, "Nf3");
+        assert_eq!(game.move_tree().current, vec![0, 0, 1]);
+        assert!(game.to_pgn().ends_with("1. e4 e5 2. Nf3! {develops} (2. d4) *\n"));
+    }
+
+    #[test]
+    fn test_viewing_earlier_plies_keeps_the_game() {
+        let mut game = ChessGame::new();
+        for san in ["e4", "e5", "Nf3"] {
+            game.make_san_move(san).unwrap();
+        }
+        let live_fen = game.to_fen();
+
+        let view = game.view_ply(1).unwrap();
+        assert_eq!((view.ply, view.total_plies, view.is_live), (1, 3, false));
+        assert_eq!(view.san.as_deref(), Some("e4"));
+        assert_eq!(position_to_fen(&view.position), "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1");
+        assert_eq!(game.to_fen(), live_fen);
+        assert_eq!(game.history_view().ply, 1);
+
+        let start = game.view_ply(0).unwrap();
+        assert_eq!((start.san, position_to_fen(&start.position)), (None, STARTING_FEN.to_string()));
+        assert!(game.view_ply(4).is_err());
+
+        // Playing on returns to the live position
+        game.make_san_move("Nc6").unwrap();
+        assert!(game.history_view().is_live);
+        assert_eq!(position_to_fen(&game.view_ply(4).unwrap().position), game.to_fen());
+    }
+
     #[test]
     fn test_cannot_undo_with_no_moves() {
         let mut game = ChessGame::new();
@@ -658,6 +899,9 @@ mod atomic_operations {
             promotion: None,
             is_castling: true,
             is_en_passant: false,
+            is_null: false,
+            drop: None,
+            castling_rook: None,
         };
 
         // Apply the legal move - should succeed
@@ -688,6 +932,9 @@ mod atomic_operations {
             promotion: None,
             is_castling: true,
             is_en_passant: false,
+            is_null: false,
+            drop: None,
+            castling_rook: None,
         };
 
         // Attempt the illegal move - should fail (no kingside castling rights)
@@ -699,3 +946,641 @@ mod atomic_operations {
     }
 
 }
+
+#[cfg(test)]
+mod san_notation {
+    use super::*;
+
+    #[test]
+    fn test_san_history_follows_game() {
+        let mut game = ChessGame::new();
+        make_moves(&mut game, &[("f2", "f3"), ("e7", "e5"), ("g2", "g4"), ("d8", "h4")]);
+
+        assert_eq!(game.san_history(), vec!["f3", "e5", "g4", "Qh4#"]);
+
+        game.undo_move().unwrap();
+        assert_eq!(game.san_history(), vec!["f3", "e5", "g4"]);
+    }
+
+    #[test]
+    fn test_make_san_moves() {
+        let mut game = ChessGame::new();
+        for san in ["e4", "e5", "Nf3", "Nc6", "Bb5", "a6", "Bxc6", "dxc6", "O-O"] {
+            game.make_san_move(san).unwrap();
+        }
+
+        assert_eq!(game.san_history(), vec!["e4", "e5", "Nf3", "Nc6", "Bb5", "a6", "Bxc6", "dxc6", "O-O"]);
+        assert!(game.make_san_move("Nf3").is_err());
+    }
+
+    #[test]
+    fn test_figurine_history_and_pgn() {
+        let mut game = ChessGame::from_pgn("1. e4 e5 (1... c5 2. Nf3) 2. Nf3 Nc6 *").unwrap();
+
+        assert_eq!(game.san_history_in(Notation::Figurine), vec!["e4", "e5", "\u{265E}f3", "\u{265E}c6"]);
+
+        let pgn = game.to_pgn_in(Notation::Figurine);
+        assert!(pgn.contains("(1... c5 2. \u{265E}f3)"));
+        assert_eq!(ChessGame::from_pgn(&pgn).unwrap().to_pgn(), game.to_pgn());
+
+        game.undo_move().unwrap();
+        assert_eq!(game.san_history_in(Notation::San), vec!["e4", "e5", "Nf3"]);
+    }
+
+    #[test]
+    fn test_move_list_rows() {
+        let mut game = ChessGame::new();
+        make_moves(&mut game, &[("e2", "e4"), ("e7", "e5"), ("g1", "f3")]);
+
+        let rows = game.move_list(Notation::San);
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[0].number, 1);
+        assert_eq!(rows[0].white.as_ref().unwrap().san, "e4");
+        assert_eq!(rows[0].black.as_ref().unwrap().fen, "rnbqkbnr/pppp1ppp/8/4p3/4P3/8/PPPP1PPP/RNBQKBNR w KQkq e6 0 2");
+        assert_eq!(rows[1].number, 2);
+        assert_eq!(rows[1].white.as_ref().unwrap().fen, game.to_fen());
+        assert!(rows[1].black.is_none());
+    }
+
+    #[test]
+    fn test_move_list_starting_with_black() {
+        let game = ChessGame::from_pgn("[SetUp \"1\"]\n[FEN \"4k3/8/8/8/8/8/8/R3K3 b - - 0 12\"]\n\n12... Kd7 13. Ra7+ *").unwrap();
+
+        let rows = game.move_list(Notation::Figurine);
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[0].number, 12);
+        assert!(rows[0].white.is_none());
+        assert_eq!(rows[0].black.as_ref().unwrap().san, "\u{265A}d7");
+        assert_eq!(rows[1].number, 13);
+        assert_eq!(rows[1].white.as_ref().unwrap().san, "\u{265C}a7+");
+    }
+}
+
+#[cfg(test)]
+mod pgn_import {
+    use super::*;
+
+    #[test]
+    fn test_load_pgn_replays_history() {
+        let pgn = "[Event \"Test\"]\n\n1. e4 e5 2. Nf3 Nc6 3. Bb5 a6 *";
+        let mut game = ChessGame::from_pgn(pgn).unwrap();
+
+        assert_eq!(game.san_history(), vec!["e4", "e5", "Nf3", "Nc6", "Bb5", "a6"]);
+        assert_eq!(game.to_fen(), "r1bqkbnr/1ppp1ppp/p1n5/1B2p3/4P3/5N2/PPPP1PPP/RNBQK2R w KQkq - 0 4");
+
+        game.undo_move().unwrap();
+        assert_eq!(game.san_history().len(), 5);
+    }
+
+    #[test]
+    fn test_load_pgn_from_fen_tag() {
+        let pgn = "[SetUp \"1\"]\n[FEN \"6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1\"]\n\n1. Ra8# 1-0";
+        let game = ChessGame::from_pgn(pgn).unwrap();
+
+        assert_eq!(game.get_status(), GameStatus::Checkmate { winner: Color::White });
+    }
+
+    #[test]
+    fn test_variant_pgn_round_trip() {
+        let mut game = ChessGame::new_variant(Variant::Crazyhouse);
+        game.make_uci_moves(&["e2e4", "d7d5", "e4d5", "d8d5", "P@e4", "d5a5", "g1f3", "P@e5"]).unwrap();
+        assert_eq!(game.san_history(), vec!["e4", "d5", "exd5", "Qxd5", "P@e4", "Qa5", "Nf3", "P@e5"]);
+
+        let pgn = game.to_pgn();
+        assert!(pgn.contains("[Variant \"Crazyhouse\"]"));
+        assert!(!pgn.contains("[FEN "));
+        assert!(pgn.contains("3. P@e4 Qa5 4. Nf3 P@e5"));
+        let reloaded = ChessGame::from_pgn(&pgn).unwrap();
+        assert_eq!(reloaded.to_fen(), game.to_fen());
+        assert_eq!(reloaded.get_board_state().variant, Variant::Crazyhouse);
+
+        // Other names for the variants, and a FEN read under the tag's rules
+        let pgn = "[Variant \"3check\"]\n[FEN \"4k3/8/8/8/8/8/8/4K2R w - - 0 1 +2+0\"]\n\n1. Rh8+ 1-0";
+        let game = ChessGame::from_pgn(pgn).unwrap();
+        assert_eq!(game.get_status(), GameStatus::ThreeCheckWin { winner: Color::White });
+        assert!(game.to_pgn().contains("[Variant \"Three-check\"]"));
+        assert!(ChessGame::from_pgn("[Variant \"Atomic\"]\n\n1. e4 *").is_err());
+    }
+
+    #[test]
+    fn test_load_pgn_keeps_variations() {
+        let pgn = "1. e4 e5 (1... c5 2. Nf3) 2. Nf3 *";
+        let game = ChessGame::from_pgn(pgn).unwrap();
+
+        assert_eq!(game.san_history(), vec!["e4", "e5", "Nf3"]);
+        assert_eq!(game.move_tree().moves[0].children[1].san, "c5");
+        assert!(game.to_pgn().ends_with("\n\n1. e4 e5 (1... c5 2. Nf3) 2. Nf3 *\n"));
+    }
+
+    #[test]
+    fn test_branch_promote_and_delete_variations() {
+        let mut game = ChessGame::from_pgn("1. e4 e5 2. Nf3 *").unwrap();
+
+        // Explore 1... c5 from the earlier position without losing the main line
+        game.goto_variation(&[0]).unwrap();
+        game.make_san_move("c5").unwrap();
+        game.make_san_move("Nf3").unwrap();
+        assert!(game.to_pgn().ends_with("1. e4 e5 (1... c5 2. Nf3) 2. Nf3 *\n"));
+
+        game.promote_variation(&[0, 1]).unwrap();
+        assert!(game.to_pgn().ends_with("1. e4 c5 (1... e5 2. Nf3) 2. Nf3 *\n"));
+        assert_eq!(game.move_tree().current, vec![0, 0, 0]);
+
+        game.delete_variation(&[0, 0]).unwrap();
+        assert_eq!(game.san_history(), vec!["e4"]);
+        assert!(game.to_pgn().ends_with("1. e4 e5 2. Nf3 *\n"));
+        assert!(game.delete_variation(&[0, 3]).is_err());
+    }
+
+    #[test]
+    fn test_null_moves_in_variations() {
+        // The variation shows the threat behind 2. Bc4 by letting White move twice
+        let pgn = "1. e4 e5 2. Bc4 (2. Qh5 -- 3. Qxe5+) 2... Nc6 3. Qh5 -- *";
+        let mut game = ChessGame::from_pgn(pgn).unwrap();
+
+        assert_eq!(game.san_history(), vec!["e4", "e5", "Bc4", "Nc6", "Qh5", "--"]);
+        assert_eq!(game.to_fen(), "r1bqkbnr/pppp1ppp/2n5/4p2Q/2B1P3/8/PPPP1PPP/RNB1K1NR w KQkq - 4 4");
+        assert!(game.to_pgn().ends_with("2. Bc4 (2. Qh5 -- 3. Qxe5+) 2... Nc6 3. Qh5 -- *\n"));
+        assert!(game.make_san_move("Qxf7#").is_ok());
+
+        game.undo_move().unwrap();
+        game.undo_move().unwrap();
+        assert_eq!(game.get_board_state().side_to_move, Color::Black);
+        assert_eq!(game.to_fen(), "r1bqkbnr/pppp1ppp/2n5/4p2Q/2B1P3/8/PPPP1PPP/RNB1K1NR b KQkq - 3 3");
+
+        // Passing out of check is not allowed
+        let mut checked = ChessGame::from_fen("4k3/8/8/8/8/8/8/4K2r w - - 0 1").unwrap();
+        assert!(checked.make_san_move("--").is_err());
+    }
+
+    #[test]
+    fn test_annotations_round_trip() {
+        let pgn = "1. e4 {King's pawn} e5 2. Qh5?! Nc6 *";
+        let mut game = ChessGame::from_pgn(pgn).unwrap();
+
+        assert_eq!(game.move_tree().moves[0].comment.as_deref(), Some("King's pawn"));
+        assert_eq!(game.move_tree().node(&[0, 0, 0]).unwrap().nags, vec![6]);
+
+        game.annotate_move(&[0, 0, 0, 0], Some("Defends e5".to_string()), vec![1]).unwrap();
+        assert!(game.to_pgn().ends_with("1. e4 {King's pawn} 1... e5 2. Qh5?! Nc6! {Defends e5} *\n"));
+        assert!(game.annotate_move(&[1], None, vec![]).is_err());
+    }
+
+    #[test]
+    fn test_side_lines_do_not_replace_main_line() {
+        let mut game = ChessGame::new();
+        make_moves(&mut game, &[("e2", "e4"), ("e7", "e5"), ("g1", "f3")]);
+
+        // Go back to after 1. e4 and explore 1... c5
+        game.goto_variation(&[0]).unwrap();
+        game.make_san_move("c5").unwrap();
+        assert_eq!(game.san_history(), vec!["e4", "c5"]);
+
+        game.goto_variation(&[0, 0, 0]).unwrap();
+        assert_eq!(game.san_history(), vec!["e4", "e5", "Nf3"]);
+        assert_eq!(game.to_fen(), "rnbqkbnr/pppp1ppp/8/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R b KQkq - 1 2");
+        assert!(game.goto_variation(&[0, 2]).is_err());
+    }
+
+    #[test]
+    fn test_load_pgn_reports_illegal_ply() {
+        let err = ChessGame::from_pgn("1. e4 e5 2. Ke3 *").unwrap_err();
+        assert!(err.to_string().contains("ply 3 (Ke3)"), "unexpected error: {}", err);
+
+        let err = ChessGame::from_pgn("1. e4 e5 (1... Nf4) 2. Nf3 *").unwrap_err();
+        assert!(err.to_string().contains("ply 2 (Nf4)"), "unexpected error: {}", err);
+    }
+}
+
+#[cfg(test)]
+mod saved_games {
+    use super::*;
+
+    #[test]
+    fn test_json_round_trip_keeps_history() {
+        let mut game = ChessGame::new();
+        make_moves(&mut game, &[("g1", "f3"), ("g8", "f6"), ("f3", "g1"), ("f6", "g8"), ("e2", "e4")]);
+
+        let mut restored = ChessGame::from_json(&game.to_json().unwrap()).unwrap();
+        assert_eq!(restored.to_fen(), game.to_fen());
+        assert_eq!(restored.san_history(), game.san_history());
+        assert_eq!(restored.get_board_state().position_history, game.get_board_state().position_history);
+
+        // Snapshots survive, so moves can still be taken back
+        assert_eq!(restored.get_legal_moves(), game.get_legal_moves());
+        assert_eq!(restored.move_records(), game.move_records());
+        restored.undo_move().unwrap();
+        game.undo_move().unwrap();
+        assert_eq!(restored.to_fen(), game.to_fen());
+        assert_eq!(restored.get_legal_moves(), game.get_legal_moves());
+    }
+
+    #[test]
+    fn test_json_rejects_bad_documents() {
+        let json = ChessGame::new().to_json().unwrap();
+        assert!(ChessGame::from_json(&json.replace("\"version\":3", "\"version\":99")).is_err());
+        assert!(ChessGame::from_json("{\"game\": {}}").is_err());
+        assert!(ChessGame::from_json("not json").is_err());
+
+        // Moves that do not lead to the saved position
+        let mut game = ChessGame::new();
+        game.make_uci_move("e2e4").unwrap();
+        let json = game.to_json().unwrap().replacen("\"fullmove_number\":1,", "\"fullmove_number\":7,", 1);
+        assert!(ChessGame::from_json(&json).is_err());
+    }
+
+    #[test]
+    fn test_version_1_with_position_snapshots_loads() {
+        // Saved before undo records replaced snapshots: 1. e4 d5 2. exd5 Qxd5 with Qxd5 undone
+        let mut game = ChessGame::from_json(include_str!("testdata/saved_game_v1.json")).unwrap();
+        assert_eq!(game.san_history(), vec!["e4", "d5", "exd5"]);
+        assert_eq!(game.captured_pieces().white, vec![Piece::Pawn]);
+
+        assert_eq!(game.redo_move().unwrap().to_uci(), "d8d5");
+        assert_eq!(game.to_fen(), "rnb1kbnr/ppp1pppp/8/3q4/8/8/PPPP1PPP/RNBQKBNR w KQkq - 0 3");
+        game.undo_move().unwrap();
+        game.undo_move().unwrap();
+        assert_eq!(game.to_fen(), "rnbqkbnr/ppp1pppp/8/3p4/4P3/8/PPPP1PPP/RNBQKBNR w KQkq d6 0 2");
+    }
+
+    #[test]
+    fn test_version_2_redo_stack_moves_into_the_tree() {
+        let mut game = ChessGame::new();
+        make_moves(&mut game, &[("e2", "e4"), ("e7", "e5"), ("g1", "f3")]);
+        game.undo_move().unwrap();
+        game.undo_move().unwrap();
+
+        // Version 2 took undone moves out of the tree and kept them on the redo stack
+        let mut document: serde_json::Value = serde_json::from_str(&game.to_json().unwrap()).unwrap();
+        let mut e5 = document["game"]["move_tree"]["moves"][0]["children"][0].take();
+        let nf3 = e5["children"][0].take();
+        e5["children"] = serde_json::json!([]);
+        document["game"]["move_tree"]["moves"][0]["children"] = serde_json::json!([]);
+        document["game"]["redo_stack"] = serde_json::json!([[0, nf3], [0, e5]]);
+        document["version"] = serde_json::json!(2);
+
+        let mut restored = ChessGame::from_json(&document.to_string()).unwrap();
+        assert_eq!(restored.redo_move().unwrap().to_uci(), "e7e5");
+        assert_eq!(restored.redo_move().unwrap().to_uci(), "g1f3");
+        assert_eq!(restored.move_tree().moves, game.move_tree().moves);
+    }
+}
+
+#[cfg(test)]
+mod annotated_pgn {
+    use super::*;
+    use crate::chess_engine::search::SearchLimits;
+
+    #[test]
+    fn test_eval_comments_and_blunder_marks() {
+        let mut game = ChessGame::from_fen("4k3/8/8/3q4/8/8/3R4/4K3 b - - 0 1").unwrap();
+        // Black leaves the queen where the rook takes it, then White declines the capture
+        for san in ["Qd6", "Rxd6", "Ke7"] {
+            game.make_san_move(san).unwrap();
+        }
+        let pgn = game.to_annotated_pgn(SearchLimits::depth(2), true);
+
+        assert!(pgn.contains("1... Qd6?? {[%eval "), "unexpected PGN: {}", pgn);
+        assert!(pgn.contains("2. Rxd6 {[%eval "), "unexpected PGN: {}", pgn);
+
+        let plain = game.to_annotated_pgn(SearchLimits::depth(1), false);
+        assert!(plain.contains("1... Qd6 {[%eval "), "unexpected PGN: {}", plain);
+    }
+
+    #[test]
+    fn test_mate_is_written_as_mate() {
+        let mut game = ChessGame::from_fen("7k/8/6K1/8/8/8/8/1R6 w - - 0 1").unwrap();
+        for san in ["Rb2", "Kg8", "Rb8#"] {
+            game.make_san_move(san).unwrap();
+        }
+        let pgn = game.to_annotated_pgn(SearchLimits::depth(3), false);
+
+        // The final position is already mate and gets no evaluation
+        assert!(pgn.contains("1. Rb2 {[%eval #1]} 1... Kg8 {[%eval #1]} 2. Rb8# 1-0"), "unexpected PGN: {}", pgn);
+    }
+}
+
+#[cfg(test)]
+mod game_results {
+    use super::*;
+    use crate::chess_engine::types::Termination;
+
+    #[test]
+    fn test_result_of_unfinished_and_mated_games() {
+        let mut game = ChessGame::new();
+        let result = game.result();
+        assert_eq!(result.result, "*");
+        assert_eq!(result.termination, None);
+        assert_eq!(result.termination_tag, "unterminated");
+
+        make_moves(&mut game, &[("f2", "f3"), ("e7", "e5"), ("g2", "g4"), ("d8", "h4")]);
+        let result = game.result();
+        assert_eq!(result.result, "0-1");
+        assert_eq!(result.winner, Some(Color::Black));
+        assert_eq!(result.termination, Some(Termination::Checkmate));
+        assert!(game.to_pgn().contains("[Termination \"normal\"]"));
+    }
+
+    #[test]
+    fn test_resignation_timeout_and_agreement() {
+        let mut game = ChessGame::new();
+        game.make_san_move("e4").unwrap();
+        game.resign(Color::Black).unwrap();
+        assert_eq!(game.get_status(), GameStatus::Resigned { winner: Color::White });
+        assert_eq!(game.result().result, "1-0");
+        assert!(game.make_san_move("e5").is_err());
+        assert!(game.get_legal_moves().is_empty());
+        assert!(game.agree_draw().is_err());
+
+        let mut game = ChessGame::new();
+        game.forfeit_on_time(Color::White).unwrap();
+        let result = game.result();
+        assert_eq!((result.result.as_str(), result.termination), ("0-1", Some(Termination::Timeout)));
+        assert!(game.to_pgn().contains("[Termination \"time forfeit\"]"));
+
+        let mut game = ChessGame::new();
+        game.agree_draw().unwrap();
+        assert_eq!(game.result().result, "1/2-1/2");
+        assert_eq!(game.result().termination, Some(Termination::Agreement));
+    }
+
+    #[test]
+    fn test_undo_keeps_a_result_players_decided() {
+        let mut game = ChessGame::new();
+        game.make_san_move("e4").unwrap();
+        game.resign(Color::Black).unwrap();
+        assert!(game.undo_move().is_err());
+        assert_eq!(game.get_status(), GameStatus::Resigned { winner: Color::White });
+        assert_eq!(game.san_history(), ["e4"]);
+
+        let mut game = ChessGame::new();
+        game.make_san_move("e4").unwrap();
+        game.agree_draw().unwrap();
+        assert!(game.undo_move().is_err());
+        assert_eq!(game.result().termination, Some(Termination::Agreement));
+
+        // A mate is the position's doing, so it can still be taken back
+        let mut game = ChessGame::new();
+        make_moves(&mut game, &[("f2", "f3"), ("e7", "e5"), ("g2", "g4"), ("d8", "h4")]);
+        game.undo_move().unwrap();
+        assert_eq!(game.get_status(), GameStatus::InProgress);
+    }
+
+    #[test]
+    fn test_draw_offers() {
+        let mut game = ChessGame::new();
+        game.make_san_move("e4").unwrap();
+        game.offer_draw(Color::White).unwrap();
+        assert_eq!(game.draw_offer(), Some(Color::White));
+        assert!(game.accept_draw(Color::White).is_err());
+
+        // Black declines, then moves on after a second offer, which also declines it
+        game.decline_draw(Color::Black).unwrap();
+        assert_eq!(game.draw_offer(), None);
+        game.offer_draw(Color::White).unwrap();
+        game.make_san_move("e5").unwrap();
+        assert_eq!(game.draw_offer(), None);
+        assert!(game.decline_draw(Color::Black).is_err());
+
+        // An offer stands while the offering side makes its move
+        game.offer_draw(Color::White).unwrap();
+        game.make_san_move("Nf3").unwrap();
+        assert_eq!(game.draw_offer(), Some(Color::White));
+        game.accept_draw(Color::Black).unwrap();
+        assert_eq!(game.get_status(), GameStatus::DrawByAgreement);
+        assert_eq!(game.draw_offer(), None);
+
+        // Crossing offers agree the draw
+        let mut game = ChessGame::new();
+        game.offer_draw(Color::Black).unwrap();
+        game.offer_draw(Color::White).unwrap();
+        assert_eq!(game.result().termination, Some(Termination::Agreement));
+    }
+
+    #[test]
+    fn test_armageddon_scores_draws_for_black() {
+        let mut game = ChessGame::new();
+        game.set_armageddon(true);
+        assert_eq!(game.result().result, "*");
+        assert!(game.to_pgn().contains("[Armageddon \"1\"]"));
+
+        game.agree_draw().unwrap();
+        let result = game.result();
+        assert_eq!((result.result.as_str(), result.winner), ("0-1", Some(Color::Black)));
+        assert_eq!(result.termination, Some(Termination::Agreement));
+        assert!(game.to_pgn().contains("[Result \"0-1\"]"));
+
+        game.set_armageddon(false);
+        assert_eq!(game.result().result, "1/2-1/2");
+
+        // Decisive results stand
+        let mut game = ChessGame::new();
+        game.set_armageddon(true);
+        game.resign(Color::Black).unwrap();
+        assert_eq!(game.result().result, "1-0");
+    }
+}
+
+#[cfg(test)]
+mod move_records {
+    use super::*;
+
+    #[test]
+    fn test_records_of_played_moves() {
+        let mut game = ChessGame::new();
+        for san in ["e4", "d5", "exd5", "Qxd5", "Nc3", "Qe5+"] {
+            game.make_san_move(san).unwrap();
+        }
+
+        let records = game.move_records();
+        assert_eq!(records.len(), 6);
+        assert_eq!((records[0].ply, records[0].color), (1, Color::White));
+        assert_eq!(records[2].uci, "e4d5");
+        assert_eq!(records[2].captured, Some(Piece::Pawn));
+        assert_eq!(records[3].captured, Some(Piece::Pawn));
+        assert_eq!(records[4].captured, None);
+        assert!(records[5].is_check && !records[5].is_checkmate);
+        assert_eq!(records[5].fen, game.to_fen());
+        assert!(records.iter().all(|record| record.played_at.is_some()));
+    }
+
+    #[test]
+    fn test_records_of_imported_moves() {
+        let game = ChessGame::from_pgn("1. e4 f5 2. e5 d5 3. exd6 g5 4. Qh5+ *").unwrap();
+        let records = game.move_records();
+
+        // En passant captures a pawn that was not on the destination square
+        assert_eq!(records[4].san, "exd6");
+        assert_eq!(records[4].captured, Some(Piece::Pawn));
+        assert!(records[6].is_check && !records[6].is_checkmate);
+        assert!(records.iter().all(|record| record.played_at.is_none()));
+    }
+}
+
+#[cfg(test)]
+mod captured_pieces {
+    use super::*;
+
+    #[test]
+    fn test_captures_follow_moves_and_undo() {
+        let mut game = ChessGame::from_pgn("1. e4 d5 2. exd5 Qxd5 3. Nc3 Qxa2 4. Rxa2 *").unwrap();
+        let captured = game.captured_pieces();
+        assert_eq!(captured.white, vec![Piece::Pawn, Piece::Queen]);
+        assert_eq!(captured.black, vec![Piece::Pawn, Piece::Pawn]);
+
+        game.undo_move().unwrap();
+        assert_eq!(game.captured_pieces().white, vec![Piece::Pawn]);
+    }
+}
+
+#[cfg(test)]
+mod last_move {
+    use super::*;
+
+    fn square(name: &str) -> Square {
+        Square::from_algebraic(name).unwrap()
+    }
+
+    #[test]
+    fn test_last_move_highlights() {
+        let mut game = ChessGame::new();
+        assert!(game.last_move().is_none());
+
+        game.make_san_move("e4").unwrap();
+        let last = game.last_move().unwrap();
+        assert_eq!(last.san, "e4");
+        assert_eq!(last.highlight_squares, vec![square("e2"), square("e4")]);
+        assert_eq!((last.rook_from, last.captured_square), (None, None));
+
+        let mut game = ChessGame::from_fen("r3k2r/8/8/8/8/8/8/R3K2R b KQkq - 0 1").unwrap();
+        game.make_san_move("O-O-O").unwrap();
+        let last = game.last_move().unwrap();
+        assert_eq!((last.rook_from, last.rook_to), (Some(square("a8")), Some(square("d8"))));
+        assert_eq!(last.highlight_squares.len(), 4);
+
+        let game = ChessGame::from_pgn("1. e4 a6 2. e5 d5 3. exd6 *").unwrap();
+        assert_eq!(game.last_move().unwrap().captured_square, Some(square("d5")));
+    }
+}
+
+#[cfg(test)]
+mod move_legality {
+    use super::*;
+    use crate::chess_engine::validation::IllegalMoveReason;
+
+    fn reason(game: &ChessGame, from: &str, to: &str, promotion: Option<Piece>) -> Option<IllegalMoveReason> {
+        let from = Square::from_algebraic(from).unwrap();
+        let to = Square::from_algebraic(to).unwrap();
+        game.move_legality(from, to, promotion).reason
+    }
+
+    #[test]
+    fn test_reasons_for_illegal_moves() {
+        let game = ChessGame::new();
+        assert!(game.move_legality(Square::from_algebraic("e2").unwrap(), Square::from_algebraic("e4").unwrap(), None).legal);
+        assert_eq!(reason(&game, "e4", "e5", None), Some(IllegalMoveReason::NoPiece));
+        assert_eq!(reason(&game, "e7", "e5", None), Some(IllegalMoveReason::WrongTurn { side_to_move: Color::White }));
+        assert_eq!(reason(&game, "d1", "d2", None), Some(IllegalMoveReason::OwnPieceOnTarget));
+        assert_eq!(reason(&game, "g1", "g3", None), Some(IllegalMoveReason::UnreachableSquare { piece: Piece::Knight }));
+        assert_eq!(reason(&game, "e1", "g1", None), Some(IllegalMoveReason::OwnPieceOnTarget));
+
+        // Queen pinned on the diagonal by the bishop
+        let game = ChessGame::from_fen("6k1/6b1/8/8/3Q4/8/8/K7 w - - 0 1").unwrap();
+        assert_eq!(
+            reason(&game, "d4", "d5", None),
+            Some(IllegalMoveReason::PinnedPiece { pinner: Piece::Bishop, pinner_square: Square::from_algebraic("g7").unwrap() })
+        );
+
+        let game = ChessGame::from_fen("4k3/P7/8/8/8/8/8/4K2R w K - 0 1").unwrap();
+        assert_eq!(reason(&game, "a7", "a8", None), Some(IllegalMoveReason::PromotionRequired));
+        assert_eq!(reason(&game, "e1", "e2", Some(Piece::Queen)), Some(IllegalMoveReason::InvalidPromotion));
+
+        let game = ChessGame::from_fen("4kr2/8/8/8/8/8/8/4K2R w K - 0 1").unwrap();
+        assert_eq!(reason(&game, "e1", "g1", None), Some(IllegalMoveReason::CastlingThroughCheck));
+        assert_eq!(reason(&game, "e1", "f1", None), Some(IllegalMoveReason::KingIntoCheck));
+
+        let game = ChessGame::from_fen("4k3/8/8/8/8/8/3P4/r3K3 w - - 0 1").unwrap();
+        let legality = game.move_legality(Square::from_algebraic("d2").unwrap(), Square::from_algebraic("d3").unwrap(), None);
+        assert_eq!(legality.reason, Some(IllegalMoveReason::DoesNotResolveCheck));
+        assert_eq!(legality.message.as_deref(), Some("the move does not get the king out of check"));
+    }
+}
+
+#[cfg(test)]
+mod premoves {
+    use super::*;
+    use crate::chess_engine::premove::{Premove, PremoveOutcome};
+
+    fn premove(color: Color, from: &str, to: &str) -> Premove {
+        Premove {
+            color,
+            from: Square::from_algebraic(from).unwrap(),
+            to: Square::from_algebraic(to).unwrap(),
+            promotion: None,
+        }
+    }
+
+    #[test]
+    fn test_premove_played_when_turn_flips() {
+        let mut game = ChessGame::new();
+        assert!(game.set_premove(premove(Color::White, "e2", "e4")).is_err());
+        assert!(game.set_premove(premove(Color::Black, "e2", "e4")).is_err());
+
+        game.set_premove(premove(Color::Black, "e7", "e5")).unwrap();
+        assert_eq!(game.play_premove(), None);
+
+        game.make_san_move("e4").unwrap();
+        let outcome = game.play_premove().unwrap();
+        assert!(matches!(outcome, PremoveOutcome::Played { ref san, .. } if san == "e5"));
+        assert_eq!(game.san_history(), vec!["e4", "e5"]);
+        assert_eq!(game.premove(), None);
+    }
+
+    #[test]
+    fn test_illegal_premove_discarded() {
+        let mut game = ChessGame::new();
+        game.make_san_move("e4").unwrap();
+        game.set_premove(premove(Color::White, "e4", "e5")).unwrap();
+
+        // Black blocks the pawn, so the premove can no longer be played
+        game.make_san_move("e5").unwrap();
+        assert!(matches!(game.play_premove(), Some(PremoveOutcome::Discarded { .. })));
+        assert_eq!(game.san_history(), vec!["e4", "e5"]);
+        assert_eq!(game.play_premove(), None);
+    }
+}
+
+#[cfg(test)]
+mod hints {
+    use super::*;
+    use crate::chess_engine::hint::{hint_limits, Hint};
+
+    #[test]
+    fn test_hint_finds_mate() {
+        let mut game = ChessGame::from_fen("6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1").unwrap();
+        let hint = game.hint(hint_limits(), false).unwrap();
+        assert!(matches!(hint, Hint::Move { ref san, .. } if san == "Ra8#"));
+    }
+
+    #[test]
+    fn test_progressive_hint_reveals_piece_then_move() {
+        let mut game = ChessGame::from_fen("6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1").unwrap();
+        let a1 = Square::from_algebraic("a1").unwrap();
+
+        assert_eq!(game.hint(hint_limits(), true).unwrap(), Hint::Piece { square: a1, piece: Piece::Rook });
+        let hint = game.hint(hint_limits(), true).unwrap();
+        assert!(matches!(hint, Hint::Move { ref san, .. } if san == "Ra8#"));
+
+        // Revealed, so the next hint starts over with the piece
+        assert!(matches!(game.hint(hint_limits(), true).unwrap(), Hint::Piece { .. }));
+    }
+
+    #[test]
+    fn test_progressive_hint_forgotten_after_move() {
+        let mut game = ChessGame::new();
+        assert!(matches!(game.hint(hint_limits(), true).unwrap(), Hint::Piece { .. }));
+
+        game.make_san_move("e4").unwrap();
+        assert!(matches!(game.hint(hint_limits(), true).unwrap(), Hint::Piece { .. }));
+    }
+}
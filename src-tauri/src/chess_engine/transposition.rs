@@ -0,0 +1,156 @@
+use crate::chess_engine::types::{Move, Piece};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// Size of the shared transposition table until the engine's Hash option changes it
+pub const TRANSPOSITION_TABLE_MEGABYTES: usize = 16;
+
+/// Bytes taken by one entry: two 64-bit atomics
+const ENTRY_BYTES: usize = 16;
+
+/// How a stored score relates to the node's true score
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Bound {
+    Exact,
+    /// The search failed high; the true score is at least this
+    Lower,
+    /// The search failed low; the true score is at most this
+    Upper,
+}
+
+/// What an earlier search learned about a position
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TtEntry {
+    /// Origin, destination and promotion of the best move found, if any
+    pub best_move: Option<(u8, u8, Option<Piece>)>,
+    pub depth: u8,
+    pub score: i32,
+    pub bound: Bound,
+}
+
+impl TtEntry {
+    /// Whether `mv` is the stored best move
+    pub fn is_best_move(&self, mv: &Move) -> bool {
+        self.best_move == Some((mv.from.index(), mv.to.index(), mv.promotion))
+    }
+
+    fn pack(&self) -> u64 {
+        let (from, to, promotion) = self.best_move.unwrap_or((0, 0, None));
+        let promotion = match promotion {
+            None => 0,
+            Some(Piece::Knight) => 1,
+            Some(Piece::Bishop) => 2,
+            Some(Piece::Rook) => 3,
+            Some(_) => 4,
+        };
+        let has_move = self.best_move.is_some() as u64;
+        let bound = match self.bound {
+            Bound::Exact => 1,
+            Bound::Lower => 2,
+            Bound::Upper => 3,
+        };
+
+        u64::from(from)
+            | u64::from(to) << 6
+            | promotion << 12
+            | has_move << 15
+            | u64::from(self.depth) << 16
+            | bound << 24
+            | u64::from(self.score as u32) << 32
+    }
+
+    fn unpack(data: u64) -> Option<Self> {
+        let bound = match (data >> 24) & 3 {
+            1 => Bound::Exact,
+            2 => Bound::Lower,
+            3 => Bound::Upper,
+            _ => return None,
+        };
+        let promotion = match (data >> 12) & 7 {
+            1 => Some(Piece::Knight),
+            2 => Some(Piece::Bishop),
+            3 => Some(Piece::Rook),
+            4 => Some(Piece::Queen),
+            _ => None,
+        };
+        let best_move = (data >> 15 & 1 == 1).then_some(((data & 63) as u8, (data >> 6 & 63) as u8, promotion));
+
+        Some(TtEntry {
+            best_move,
+            depth: (data >> 16) as u8,
+            score: (data >> 32) as u32 as i32,
+            bound,
+        })
+    }
+}
+
+/// Fixed-size, always-replace table of search results keyed by Zobrist hash.
+///
+/// Entries are stored as `(key ^ data, data)` pairs of atomics, like the evaluation
+/// cache, so searches running at the same time can share it without locking.
+pub struct TranspositionTable {
+    entries: Vec<(AtomicU64, AtomicU64)>,
+}
+
+impl TranspositionTable {
+    /// The largest power-of-two table that fits in `megabytes`
+    pub fn with_megabytes(megabytes: usize) -> Self {
+        let size = (megabytes * 1024 * 1024 / ENTRY_BYTES).max(1);
+        TranspositionTable {
+            entries: (0..1usize << size.ilog2()).map(|_| (AtomicU64::new(0), AtomicU64::new(0))).collect(),
+        }
+    }
+
+    pub fn probe(&self, key: u64) -> Option<TtEntry> {
+        let (checked_key, data) = &self.entries[self.slot(key)];
+        let data = data.load(Ordering::Relaxed);
+        if checked_key.load(Ordering::Relaxed) ^ data != key {
+            return None;
+        }
+        TtEntry::unpack(data)
+    }
+
+    pub fn store(&self, key: u64, entry: TtEntry) {
+        let (checked_key, data) = &self.entries[self.slot(key)];
+        let value = entry.pack();
+        checked_key.store(key ^ value, Ordering::Relaxed);
+        data.store(value, Ordering::Relaxed);
+    }
+
+    /// Remove every entry, so the next search starts from nothing
+    pub fn clear(&self) {
+        for (checked_key, data) in &self.entries {
+            checked_key.store(0, Ordering::Relaxed);
+            data.store(0, Ordering::Relaxed);
+        }
+    }
+
+    fn slot(&self, key: u64) -> usize {
+        (key as usize) & (self.entries.len() - 1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_store_and_probe() {
+        let table = TranspositionTable::with_megabytes(1);
+        let entry = TtEntry {
+            best_move: Some((12, 60, Some(Piece::Queen))),
+            depth: 7,
+            score: -99_950,
+            bound: Bound::Lower,
+        };
+        table.store(0xDEAD_BEEF, entry);
+        assert_eq!(table.probe(0xDEAD_BEEF), Some(entry));
+        assert_eq!(table.probe(0xBEEF), None);
+
+        let no_move = TtEntry { best_move: None, depth: 1, score: 35, bound: Bound::Upper };
+        table.store(0xDEAD_BEEF, no_move);
+        assert_eq!(table.probe(0xDEAD_BEEF), Some(no_move));
+
+        table.clear();
+        assert_eq!(table.probe(0xDEAD_BEEF), None);
+    }
+}
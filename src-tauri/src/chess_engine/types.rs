@@ -1,5 +1,6 @@
 use serde::{Deserialize, Serialize};
 use crate::chess_engine::error::{ChessError, Result};
+use crate::chess_engine::notation::piece_letter;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum Color {
@@ -107,6 +108,17 @@ pub struct Move {
     pub promotion: Option<Piece>,
     pub is_castling: bool,
     pub is_en_passant: bool,
+    /// A pass ("--" in SAN, "0000" in UCI) from an analysis line: only the side to move changes
+    #[serde(default)]
+    pub is_null: bool,
+    /// The piece a drop ("N@f3") brings onto `to` from the mover's pocket; `from` is
+    /// then the same square as `to`
+    #[serde(default)]
+    pub drop: Option<Piece>,
+    /// Where the rook castling with the king starts, in Chess960, where `to` is still the
+    /// king's destination. Standard castling leaves it out: the rook is in the corner.
+    #[serde(default)]
+    pub castling_rook: Option<Square>,
 }
 
 impl Move {
@@ -117,11 +129,70 @@ impl Move {
             promotion: None,
             is_castling: false,
             is_en_passant: false,
+            is_null: false,
+            drop: None,
+            castling_rook: None,
         }
     }
 
+    /// A drop of `piece` from the pocket onto `to`
+    pub fn new_drop(piece: Piece, to: Square) -> Self {
+        Move {
+            drop: Some(piece),
+            ..Move::new(to, to)
+        }
+    }
+
+    /// The null move; `from` and `to` are placeholders
+    pub fn null() -> Self {
+        Move {
+            is_null: true,
+            ..Move::new(Square { index: 0 }, Square { index: 0 })
+        }
+    }
+
+    /// Where the rook starts and ends for a castling move
+    pub fn castling_rook_squares(&self) -> Option<(Square, Square)> {
+        if !self.is_castling {
+            return None;
+        }
+        let rank = self.from.rank();
+        let rook_from = match self.castling_rook {
+            Some(square) => square,
+            None => Square::from_rank_file(rank, if self.to.file() > self.from.file() { 7 } else { 0 })?,
+        };
+        let to_file = if rook_from.file() > self.from.file() { 5 } else { 3 };
+        Some((rook_from, Square::from_rank_file(rank, to_file)?))
+    }
+
+    /// Whether this is castling with the rook on the king's h-file side (O-O)
+    pub fn is_kingside_castling(&self) -> bool {
+        self.castling_rook_squares()
+            .is_some_and(|(rook_from, _)| rook_from.file() > self.from.file())
+    }
+
+    /// The square UCI names as the destination: the king's own, except in Chess960
+    /// castling, which is written as the king taking its own rook
+    pub fn uci_target(&self) -> Square {
+        self.castling_rook.unwrap_or(self.to)
+    }
+
+    /// The square of the pawn taken by an en passant capture, beside the destination
+    pub fn en_passant_capture_square(&self) -> Option<Square> {
+        if !self.is_en_passant {
+            return None;
+        }
+        Square::from_rank_file(self.from.rank(), self.to.file())
+    }
+
     pub fn to_uci(&self) -> String {
-        let mut uci = format!("{}{}", self.from.to_algebraic(), self.to.to_algebraic());
+        if self.is_null {
+            return "0000".to_string();
+        }
+        if let Some(piece) = self.drop {
+            return format!("{}@{}", piece_letter(piece), self.to.to_algebraic());
+        }
+        let mut uci = format!("{}{}", self.from.to_algebraic(), self.uci_target().to_algebraic());
         if let Some(promotion) = self.promotion {
             let promo_char = match promotion {
                 Piece::Queen => 'q',
@@ -145,5 +216,131 @@ pub enum GameStatus {
     Stalemate,
     DrawByFiftyMoveRule,
     DrawByInsufficientMaterial,
+    /// Neither side can ever checkmate, whatever the material (FIDE 5.2.2)
+    DrawByDeadPosition,
     DrawByRepetition,
+    Resigned { winner: Color },
+    TimeForfeit { winner: Color },
+    DrawByAgreement,
+    /// Either player may claim a draw for `reason` (fifty-move rule or threefold
+    /// repetition) under [`DrawRules::Claimable`]; the game goes on until they do
+    DrawClaimable { reason: Termination },
+    DrawBySeventyFiveMoveRule,
+    DrawByFivefoldRepetition,
+    /// `winner` has given check three times in a three-check game
+    ThreeCheckWin { winner: Color },
+    /// Black has captured every piece of the horde
+    HordeDestroyed { winner: Color },
+    /// `winner` has taken the opposing king, in a variant played to the king's capture
+    KingCaptured { winner: Color },
+    /// `winner`'s king has reached the center in King of the Hill
+    KingReachedHill { winner: Color },
+    /// `winner`'s king has reached the eighth rank first in Racing Kings
+    RaceWon { winner: Color },
+    /// Both kings have reached the eighth rank in Racing Kings
+    DrawByRace,
+}
+
+/// How the fifty-move rule and threefold repetition end a game
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DrawRules {
+    /// The game is drawn as soon as either rule applies
+    #[default]
+    Automatic,
+    /// As in FIDE play: the draw must be claimed, and only the 75-move rule and
+    /// fivefold repetition end the game on their own
+    Claimable,
+}
+
+/// Why a game ended
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Termination {
+    Checkmate,
+    Resignation,
+    Timeout,
+    Agreement,
+    Stalemate,
+    Repetition,
+    FiftyMoveRule,
+    InsufficientMaterial,
+    DeadPosition,
+    ThreeChecks,
+    HordeDestroyed,
+    KingCaptured,
+    KingOfTheHill,
+    Race,
+}
+
+/// The outcome of a game as PGN records it
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GameResult {
+    /// PGN `Result` tag: "1-0", "0-1", "1/2-1/2" or "*" while the game is in progress
+    pub result: String,
+    pub winner: Option<Color>,
+    pub termination: Option<Termination>,
+    /// PGN `Termination` tag: "normal", "time forfeit" or "unterminated"
+    pub termination_tag: String,
+}
+
+impl GameResult {
+    /// The result under Armageddon scoring, where Black wins a drawn game
+    pub fn armageddon(self) -> GameResult {
+        if self.winner.is_some() || self.termination.is_none() {
+            return self;
+        }
+        GameResult {
+            result: "0-1".to_string(),
+            winner: Some(Color::Black),
+            ..self
+        }
+    }
+}
+
+impl GameStatus {
+    pub fn is_over(&self) -> bool {
+        !matches!(self, GameStatus::InProgress | GameStatus::Check | GameStatus::DrawClaimable { .. })
+    }
+
+    pub fn result(&self) -> GameResult {
+        let (winner, termination) = match *self {
+            GameStatus::InProgress | GameStatus::Check | GameStatus::DrawClaimable { .. } => (None, None),
+            GameStatus::Checkmate { winner } => (Some(winner), Some(Termination::Checkmate)),
+            GameStatus::Resigned { winner } => (Some(winner), Some(Termination::Resignation)),
+            GameStatus::TimeForfeit { winner } => (Some(winner), Some(Termination::Timeout)),
+            GameStatus::ThreeCheckWin { winner } => (Some(winner), Some(Termination::ThreeChecks)),
+            GameStatus::HordeDestroyed { winner } => (Some(winner), Some(Termination::HordeDestroyed)),
+            GameStatus::KingCaptured { winner } => (Some(winner), Some(Termination::KingCaptured)),
+            GameStatus::KingReachedHill { winner } => (Some(winner), Some(Termination::KingOfTheHill)),
+            GameStatus::RaceWon { winner } => (Some(winner), Some(Termination::Race)),
+            GameStatus::DrawByRace => (None, Some(Termination::Race)),
+            GameStatus::Stalemate => (None, Some(Termination::Stalemate)),
+            GameStatus::DrawByFiftyMoveRule => (None, Some(Termination::FiftyMoveRule)),
+            GameStatus::DrawByInsufficientMaterial => (None, Some(Termination::InsufficientMaterial)),
+            GameStatus::DrawByDeadPosition => (None, Some(Termination::DeadPosition)),
+            GameStatus::DrawByRepetition | GameStatus::DrawByFivefoldRepetition => {
+                (None, Some(Termination::Repetition))
+            }
+            GameStatus::DrawBySeventyFiveMoveRule => (None, Some(Termination::FiftyMoveRule)),
+            GameStatus::DrawByAgreement => (None, Some(Termination::Agreement)),
+        };
+
+        let result = match (winner, termination) {
+            (Some(Color::White), _) => "1-0",
+            (Some(Color::Black), _) => "0-1",
+            (None, Some(_)) => "1/2-1/2",
+            (None, None) => "*",
+        };
+        let termination_tag = match termination {
+            None => "unterminated",
+            Some(Termination::Timeout) => "time forfeit",
+            Some(_) => "normal",
+        };
+
+        GameResult {
+            result: result.to_string(),
+            winner,
+            termination,
+            termination_tag: termination_tag.to_string(),
+        }
+    }
 }
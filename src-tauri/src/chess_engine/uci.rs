@@ -0,0 +1,284 @@
+use crate::chess_engine::engine_options::{ENGINE_AUTHOR, ENGINE_NAME};
+use crate::chess_engine::fen::parse_fen;
+use crate::chess_engine::perft::perft_divide;
+use crate::chess_engine::position::Position;
+use crate::chess_engine::search::{
+    clear_transposition_table, mate_in, search_with_control, SearchLimits, SearchResult, MAX_DEPTH,
+};
+use crate::chess_engine::types::{Color, Move};
+use crate::chess_engine::validation::position_after_move;
+use std::io::{self, BufRead, Write};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, PoisonError};
+use std::thread::JoinHandle;
+
+/// Moves assumed to remain when the GUI gives a clock but no `movestogo`
+const DEFAULT_MOVES_TO_GO: u64 = 30;
+
+/// Time kept in reserve so the engine never flags on communication delays
+const MOVE_OVERHEAD_MS: u64 = 50;
+
+/// Line-oriented output shared between the command loop and the search thread
+struct Output<W: Write> {
+    writer: Arc<Mutex<W>>,
+}
+
+impl<W: Write> Clone for Output<W> {
+    fn clone(&self) -> Self {
+        Output {
+            writer: Arc::clone(&self.writer),
+        }
+    }
+}
+
+impl<W: Write> Output<W> {
+    fn send(&self, line: &str) -> io::Result<()> {
+        let mut writer = self.writer.lock().unwrap_or_else(PoisonError::into_inner);
+        writeln!(writer, "{}", line)?;
+        writer.flush()
+    }
+}
+
+/// A search running in the background
+struct ActiveSearch {
+    stop: Arc<AtomicBool>,
+    handle: JoinHandle<()>,
+}
+
+impl ActiveSearch {
+    /// Ask the search to stop and wait for its `bestmove`
+    fn stop(self) {
+        self.stop.store(true, Ordering::Relaxed);
+        self.finish();
+    }
+
+    /// Wait for the search to end on its own
+    fn finish(self) {
+        let _ = self.handle.join();
+    }
+}
+
+/// Speak the Universal Chess Interface protocol until `quit` or end of input.
+/// Searches run on a background thread so `stop` and `isready` are answered while
+/// thinking; a search still running at end of input is allowed to finish.
+pub fn run<R: BufRead, W: Write + Send + 'static>(input: R, output: W) -> io::Result<()> {
+    let output = Output {
+        writer: Arc::new(Mutex::new(output)),
+    };
+    let mut position = Position::new();
+    let mut active: Option<ActiveSearch> = None;
+
+    for line in input.lines() {
+        let line = line?;
+        let tokens: Vec<&str> = line.split_whitespace().collect();
+        let Some((&command, args)) = tokens.split_first() else {
+            continue;
+        };
+
+        match command {
+            "uci" => {
+                output.send(&format!("id name {}", ENGINE_NAME))?;
+                output.send(&format!("id author {}", ENGINE_AUTHOR))?;
+                output.send("uciok")?;
+            }
+            "isready" => output.send("readyok")?,
+            "ucinewgame" => {
+                if let Some(search) = active.take() {
+                    search.stop();
+                }
+                position = Position::new();
+                clear_transposition_table();
+            }
+            "position" => match parse_position(args) {
+                Ok(parsed) => position = parsed,
+                Err(reason) => output.send(&format!("info string {}", reason))?,
+            },
+            "go" => {
+                if let Some(search) = active.take() {
+                    search.stop();
+                }
+                if let Some((&"perft", rest)) = args.split_first() {
+                    match rest.first().and_then(|depth| depth.parse().ok()) {
+                        Some(depth) => send_perft(&output, &position, depth)?,
+                        None => output.send("info string expected go perft <depth>")?,
+                    }
+                    continue;
+                }
+                let limits = parse_go(args, position.side_to_move);
+                active = Some(start_search(position.clone(), limits, output.clone()));
+            }
+            "stop" => {
+                if let Some(search) = active.take() {
+                    search.stop();
+                }
+            }
+            "quit" => {
+                if let Some(search) = active.take() {
+                    search.stop();
+                }
+                return Ok(());
+            }
+            // Options, debug mode and registration are not supported yet
+            "setoption" | "debug" | "register" | "ponderhit" => {}
+            _ => output.send(&format!("info string unknown command: {}", command))?,
+        }
+    }
+
+    if let Some(search) = active.take() {
+        search.finish();
+    }
+    Ok(())
+}
+
+fn start_search<W: Write + Send + 'static>(position: Position, limits: SearchLimits, output: Output<W>) -> ActiveSearch {
+    let stop = Arc::new(AtomicBool::new(false));
+    let thread_stop = Arc::clone(&stop);
+
+    let handle = std::thread::spawn(move || {
+        let result = search_with_control(&position, limits, &thread_stop, |iteration| {
+            let _ = output.send(&info_line(iteration));
+        });
+
+        let best = result.best_move.map(|mv| mv.to_uci()).unwrap_or_else(|| "0000".to_string());
+        let _ = output.send(&format!("bestmove {}", best));
+    });
+
+    ActiveSearch { stop, handle }
+}
+
+/// `go perft <depth>`, answered in the same format as Stockfish so the output can be diffed
+fn send_perft<W: Write>(output: &Output<W>, position: &Position, depth: u8) -> io::Result<()> {
+    let divide = perft_divide(position, depth);
+    for entry in &divide.moves {
+        output.send(&format!("{}: {}", entry.mv, entry.nodes))?;
+    }
+    output.send("")?;
+    output.send(&format!("Nodes searched: {}", divide.nodes))
+}
+
+/// `position [startpos | fen <fen>] [moves <uci>...]`
+fn parse_position(args: &[&str]) -> Result<Position, String> {
+    let moves_at = args.iter().position(|&token| token == "moves").unwrap_or(args.len());
+    let (setup, moves) = args.split_at(moves_at);
+
+    let mut position = match setup.split_first() {
+        Some((&"startpos", _)) => Position::new(),
+        Some((&"fen", fen)) => parse_fen(&fen.join(" ")).map_err(|e| e.to_string())?,
+        _ => return Err("expected startpos or fen".to_string()),
+    };
+
+    for uci in moves.iter().skip(1) {
+        let mv = Move::from_uci(uci, &position).map_err(|e| e.to_string())?;
+        position = position_after_move(&position, &mv);
+    }
+
+    Ok(position)
+}
+
+/// Turn `go` parameters into search limits, budgeting clock time for one move
+fn parse_go(args: &[&str], side_to_move: Color) -> SearchLimits {
+    let value = |name: &str| -> Option<u64> {
+        let index = args.iter().position(|&token| token == name)?;
+        args.get(index + 1)?.parse().ok()
+    };
+
+    let mut limits = SearchLimits {
+        depth: MAX_DEPTH,
+        nodes: value("nodes"),
+        movetime_ms: value("movetime"),
+        ..SearchLimits::default()
+    };
+
+    if let Some(depth) = value("depth") {
+        limits.depth = depth.clamp(1, MAX_DEPTH as u64) as u8;
+    }
+
+    let (time, increment) = match side_to_move {
+        Color::White => (value("wtime"), value("winc")),
+        Color::Black => (value("btime"), value("binc")),
+    };
+    if let (Some(time), None) = (time, limits.movetime_ms) {
+        let moves_to_go = value("movestogo").unwrap_or(DEFAULT_MOVES_TO_GO).max(1);
+        let budget = time / moves_to_go + increment.unwrap_or(0) / 2;
+        limits.movetime_ms = Some(budget.min(time.saturating_sub(MOVE_OVERHEAD_MS)).max(1));
+    }
+
+    limits
+}
+
+fn info_line(result: &SearchResult) -> String {
+    let score = match mate_in(result.score) {
+        Some(moves) => format!("mate {}", moves),
+        None => format!("cp {}", result.score),
+    };
+
+    let pv: Vec<String> = result.pv.iter().map(|mv| mv.to_uci()).collect();
+    format!("info depth {} score {} nodes {} pv {}", result.depth, score, result.nodes, pv.join(" "))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Writer that appends to a shared buffer so the test can read it afterwards
+    #[derive(Clone, Default)]
+    struct Capture(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for Capture {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    fn run_session(input: &str) -> Vec<String> {
+        let capture = Capture::default();
+        run(input.as_bytes(), capture.clone()).unwrap();
+        let bytes = capture.0.lock().unwrap().clone();
+        String::from_utf8(bytes).unwrap().lines().map(str::to_string).collect()
+    }
+
+    #[test]
+    fn test_handshake() {
+        let lines = run_session("uci\nisready\n");
+        assert_eq!(lines.last().unwrap(), "readyok");
+        assert!(lines.contains(&"uciok".to_string()));
+        assert!(lines[0].starts_with("id name"));
+    }
+
+    #[test]
+    fn test_go_finds_mate() {
+        let lines = run_session("position fen 6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1\ngo depth 3\n");
+        assert_eq!(lines.last().unwrap(), "bestmove a1a8");
+        assert!(lines.iter().any(|line| line.contains("score mate 1")));
+    }
+
+    #[test]
+    fn test_position_with_moves() {
+        let position = parse_position(&["startpos", "moves", "e2e4", "e7e5", "g1f3"]).unwrap();
+        assert_eq!(position.side_to_move, Color::Black);
+        assert_eq!(position.fullmove_number, 2);
+        assert!(parse_position(&["startpos", "moves", "e2e5"]).is_err());
+    }
+
+    #[test]
+    fn test_go_perft() {
+        let lines = run_session("position startpos moves e2e4\ngo perft 2\n");
+        assert_eq!(lines.last().unwrap(), "Nodes searched: 600");
+        assert!(lines.contains(&"e7e5: 29".to_string()));
+    }
+
+    #[test]
+    fn test_time_budget() {
+        let limits = parse_go(&["wtime", "60000", "btime", "1000", "winc", "1000"], Color::White);
+        assert_eq!(limits.movetime_ms, Some(60000 / DEFAULT_MOVES_TO_GO + 500));
+
+        let limits = parse_go(&["depth", "5"], Color::Black);
+        assert_eq!(limits.depth, 5);
+        assert_eq!(limits.movetime_ms, None);
+    }
+}
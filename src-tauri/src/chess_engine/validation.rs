@@ -1,269 +1,491 @@
-use crate::chess_engine::move_gen::generate_pseudo_legal_moves;
+use crate::chess_engine::board::squares_in;
+use crate::chess_engine::magic::{between, bishop_attacks, line, queen_attacks, rook_attacks};
+use crate::chess_engine::move_gen::{castling_move, generate_pseudo_legal_moves_from, generate_pseudo_legal_moves, rank_span, MAX_MOVES};
 use crate::chess_engine::position::Position;
 use crate::chess_engine::types::{Color, Piece, Square, Move};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use thiserror::Error;
+
+/// A piece giving check
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Checker {
+    pub square: Square,
+    pub piece: Piece,
+}
 
-pub fn generate_legal_moves(position: &Position) -> Vec<Move> {
-    let pseudo_legal_moves = generate_pseudo_legal_moves(position);
-    pseudo_legal_moves
-        .into_iter()
-        .filter(|mv| is_legal_move(position, mv))
-        .collect()
+/// A piece pinned to its own king
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Pin {
+    pub square: Square,
+    pub piece: Piece,
+    pub pinner_square: Square,
+    pub pinner: Piece,
+    /// Squares from beside the king up to and including the pinner: the line the
+    /// pinned piece may still move along
+    pub ray: Vec<Square>,
 }
 
-pub fn is_legal_move(position: &Position, mv: &Move) -> bool {
-    // Special validation for castling
-    if mv.is_castling {
-        let color = position.side_to_move;
-        let kingside = mv.to.file() > mv.from.file();
+/// Whether the side to move is in check, where its king stands and what is checking it
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CheckInfo {
+    pub in_check: bool,
+    pub is_checkmate: bool,
+    pub king_square: Option<Square>,
+    /// Two entries for a double check
+    pub checkers: Vec<Checker>,
+}
 
-        if kingside {
-            return can_castle_kingside(position, color);
-        } else {
-            return can_castle_queenside(position, color);
-        }
-    }
+/// Why a move cannot be played
+#[derive(Debug, Clone, PartialEq, Eq, Error, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum IllegalMoveReason {
+    #[error("the game is over")]
+    GameOver,
+    #[error("there is no piece on that square")]
+    NoPiece,
+    #[error("it is {side_to_move:?}'s turn")]
+    WrongTurn { side_to_move: Color },
+    #[error("a piece cannot capture its own side")]
+    OwnPieceOnTarget,
+    #[error("a {piece:?} cannot move there")]
+    UnreachableSquare { piece: Piece },
+    #[error("castling is not allowed: the king or rook has moved or the path is blocked")]
+    CastlingNotAllowed,
+    #[error("cannot castle out of check")]
+    CastlingOutOfCheck,
+    #[error("cannot castle through or into check")]
+    CastlingThroughCheck,
+    #[error("a pawn reaching the last rank must promote")]
+    PromotionRequired,
+    #[error("only a pawn reaching the last rank can promote, to a queen, rook, bishop or knight")]
+    InvalidPromotion,
+    #[error("the piece is pinned to its king by the {pinner:?} on {}", pinner_square.to_algebraic())]
+    PinnedPiece { pinner: Piece, pinner_square: Square },
+    #[error("the king cannot move into check")]
+    KingIntoCheck,
+    #[error("the move does not get the king out of check")]
+    DoesNotResolveCheck,
+    #[error("the move would leave the king in check")]
+    LeavesKingInCheck,
+    #[error("no move may give check in this variant")]
+    GivesCheck,
+}
 
-    let mut test_position = position.clone();
+/// Result of checking a move without playing it
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MoveLegality {
+    pub legal: bool,
+    pub reason: Option<IllegalMoveReason>,
+    /// The reason in words, for showing to the player
+    pub message: Option<String>,
+}
 
-    // Apply the move to test position
-    apply_move_for_validation(&mut test_position, mv);
+impl MoveLegality {
+    pub fn from_check(check: std::result::Result<Move, IllegalMoveReason>) -> Self {
+        match check {
+            Ok(_) => MoveLegality { legal: true, reason: None, message: None },
+            Err(reason) => MoveLegality {
+                legal: false,
+                message: Some(reason.to_string()),
+                reason: Some(reason),
+            },
+        }
+    }
+}
 
-    // Check if our king is in check after the move
-    let our_color = position.side_to_move;
-    !is_in_check(&test_position, our_color)
+/// A square a piece can move to. A promotion stands for all four promotion moves; the
+/// player still picks the piece.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Destination {
+    pub to: Square,
+    pub promotion: bool,
+    pub castling: bool,
+    pub en_passant: bool,
 }
 
-pub(crate) fn apply_move_for_validation(position: &mut Position, mv: &Move) {
-    // Handle en passant capture
-    if mv.is_en_passant {
-        let captured_pawn_rank = if position.side_to_move == Color::White {
-            mv.to.rank() - 1
-        } else {
-            mv.to.rank() + 1
-        };
-        if let Some(captured_square) = Square::from_rank_file(captured_pawn_rank, mv.to.file()) {
-            position.board.set(captured_square, None);
+/// Destinations of `moves` grouped by origin square, keyed in algebraic notation. Moves
+/// to the same square share a destination with their flags combined, as when a Chess960
+/// king can both step and castle there.
+pub fn destinations_by_origin(moves: &[Move]) -> BTreeMap<String, Vec<Destination>> {
+    let mut destinations: BTreeMap<String, Vec<Destination>> = BTreeMap::new();
+    for mv in moves.iter().filter(|mv| !mv.is_null) {
+        let targets = destinations.entry(mv.from.to_algebraic()).or_default();
+        match targets.iter_mut().find(|destination| destination.to == mv.to) {
+            Some(destination) => {
+                destination.promotion |= mv.promotion.is_some();
+                destination.castling |= mv.is_castling;
+                destination.en_passant |= mv.is_en_passant;
+            }
+            None => targets.push(Destination {
+                to: mv.to,
+                promotion: mv.promotion.is_some(),
+                castling: mv.is_castling,
+                en_passant: mv.is_en_passant,
+            }),
         }
     }
+    destinations
+}
 
-    // Handle castling
-    if mv.is_castling {
-        let rank = mv.from.rank();
-        let king_color = position.side_to_move;
-
-        if mv.to.file() > mv.from.file() {
-            // Kingside castling
-            let rook_from = Square::from_rank_file(rank, 7).unwrap();
-            let rook_to = Square::from_rank_file(rank, 5).unwrap();
-            let rook = position.board.get(rook_from);
-
-            // Verify rook is present and correct color
-            debug_assert!(
-                matches!(rook, Some((Piece::Rook, c)) if c == king_color),
-                "Rook not found or wrong color at kingside castling position"
-            );
-
-            position.board.set(rook_from, None);
-            position.board.set(rook_to, rook);
+/// Resolves a move given by its squares, or explains why it is illegal
+pub fn check_move(
+    position: &Position,
+    from: Square,
+    to: Square,
+    promotion: Option<Piece>,
+) -> std::result::Result<Move, IllegalMoveReason> {
+    let color = position.side_to_move;
+    let Some((piece, piece_color)) = position.board.get(from) else {
+        return Err(IllegalMoveReason::NoPiece);
+    };
+    if piece_color != color {
+        return Err(IllegalMoveReason::WrongTurn { side_to_move: color });
+    }
+
+    // Chess960 castling is the king moving onto its own rook, though the king's
+    // destination is accepted too when no ordinary move goes there
+    let moves = generate_pseudo_legal_moves_from(position, from);
+    let mut candidates: Vec<Move> = moves.iter().copied().filter(|mv| mv.uci_target() == to).collect();
+    if candidates.is_empty() {
+        candidates.extend(moves.iter().copied().filter(|mv| mv.is_castling && mv.to == to));
+    }
+    if candidates.is_empty() && matches!(position.board.get(to), Some((_, c)) if c == color) {
+        return Err(IllegalMoveReason::OwnPieceOnTarget);
+    }
+    if candidates.is_empty() {
+        let castling_attempt = piece == Piece::King && from.rank() == to.rank() && from.file().abs_diff(to.file()) == 2;
+        return Err(if castling_attempt {
+            IllegalMoveReason::CastlingNotAllowed
         } else {
-            // Queenside castling
-            let rook_from = Square::from_rank_file(rank, 0).unwrap();
-            let rook_to = Square::from_rank_file(rank, 3).unwrap();
-            let rook = position.board.get(rook_from);
-
-            // Verify rook is present and correct color
-            debug_assert!(
-                matches!(rook, Some((Piece::Rook, c)) if c == king_color),
-                "Rook not found or wrong color at queenside castling position"
-            );
-
-            position.board.set(rook_from, None);
-            position.board.set(rook_to, rook);
-        }
+            IllegalMoveReason::UnreachableSquare { piece }
+        });
     }
 
-    // Move the piece
-    let piece = position.board.get(mv.from);
-    position.board.set(mv.from, None);
+    let Some(mv) = candidates.into_iter().find(|mv| mv.promotion == promotion) else {
+        return Err(if promotion.is_none() {
+            IllegalMoveReason::PromotionRequired
+        } else {
+            IllegalMoveReason::InvalidPromotion
+        });
+    };
+    if keeps_king_safe(position, &mv) {
+        return if is_legal_move(position, &mv) {
+            Ok(mv)
+        } else {
+            Err(IllegalMoveReason::GivesCheck)
+        };
+    }
 
-    // Handle promotion
-    if let Some(promotion_piece) = mv.promotion {
-        if let Some((_, color)) = piece {
-            position.board.set(mv.to, Some((promotion_piece, color)));
+    let in_check = is_in_check(position, color);
+    Err(if mv.is_castling {
+        if in_check {
+            IllegalMoveReason::CastlingOutOfCheck
+        } else {
+            IllegalMoveReason::CastlingThroughCheck
         }
+    } else if piece == Piece::King {
+        IllegalMoveReason::KingIntoCheck
+    } else if let Some(pin) = get_pinned_pieces(position, color).into_iter().find(|pin| pin.square == from) {
+        IllegalMoveReason::PinnedPiece {
+            pinner: pin.pinner,
+            pinner_square: pin.pinner_square,
+        }
+    } else if in_check {
+        IllegalMoveReason::DoesNotResolveCheck
     } else {
-        position.board.set(mv.to, piece);
-    }
+        IllegalMoveReason::LeavesKingInCheck
+    })
 }
 
-pub fn is_in_check(position: &Position, color: Color) -> bool {
-    if let Some(king_square) = position.board.find_king(color) {
-        position.board.is_attacked_by(king_square, color.opposite())
-    } else {
-        false
-    }
+pub fn generate_legal_moves(position: &Position) -> Vec<Move> {
+    let mut moves = Vec::with_capacity(MAX_MOVES);
+    generate_legal_moves_into(position, &mut moves);
+    moves
 }
 
-pub fn is_checkmate(position: &Position) -> bool {
-    is_in_check(position, position.side_to_move) && generate_legal_moves(position).is_empty()
+/// Replaces the contents of `moves` with the legal moves, reusing its allocation
+pub fn generate_legal_moves_into(position: &Position, moves: &mut Vec<Move>) {
+    moves.clear();
+    generate_pseudo_legal_moves(position, moves);
+    let rules = position.variant.rules();
+    if !rules.king_capture() {
+        match position.board.find_king(position.side_to_move) {
+            Some(king_square) => {
+                let constraints = MoveConstraints::new(position, king_square);
+                moves.retain(|mv| constraints.allows(position, mv));
+            }
+            // A position being set up may have no king yet; test each move instead
+            None => moves.retain(|mv| keeps_king_safe(position, mv)),
+        }
+    }
+    if !rules.allows_checks() {
+        moves.retain(|mv| !gives_check(position, mv));
+    }
 }
 
-pub fn is_stalemate(position: &Position) -> bool {
-    !is_in_check(position, position.side_to_move) && generate_legal_moves(position).is_empty()
+/// What the king's safety demands of the side to move's moves, worked out once per
+/// position so each pseudo-legal move can be checked against a few masks instead of
+/// being played out
+struct MoveConstraints {
+    king_square: Square,
+    /// Squares the opponent attacks, found with the king lifted off the board so that it
+    /// cannot step back along the line of a slider checking it
+    danger: u64,
+    checkers: u64,
+    /// Squares a move other than the king's must end on: anywhere out of check, otherwise
+    /// the checker or a square between it and the king
+    evasion: u64,
+    pinned: u64,
+    /// For each pinned piece, the line it may still move along
+    pin_rays: [u64; 64],
 }
 
-pub fn can_castle_kingside(position: &Position, color: Color) -> bool {
-    if !position.castling_rights.can_castle(color, true) {
-        return false;
-    }
+impl MoveConstraints {
+    fn new(position: &Position, king_square: Square) -> Self {
+        let color = position.side_to_move;
+        let opponent = color.opposite();
+        let king_bit = 1u64 << king_square.index();
+        let occupied = position.board.occupied();
+        let own = position.board.occupancy(color);
+
+        let mut constraints = MoveConstraints {
+            king_square,
+            danger: position.board.attacked_by(opponent),
+            checkers: 0,
+            evasion: !0,
+            pinned: 0,
+            pin_rays: [!0; 64],
+        };
+        let in_check = constraints.danger & king_bit != 0;
+
+        // In check, the squares behind the king on a checking slider's line are attacked
+        // too, so the danger is worked out again without the king
+        let without_king = in_check.then(|| {
+            let mut board = position.board.clone();
+            board.set(king_square, None);
+            board
+        });
+        if let Some(board) = &without_king {
+            constraints.danger = board.attacked_by(opponent);
+        }
 
-    let rank = if color == Color::White { 0 } else { 7 };
-    let king_square = Square::from_rank_file(rank, 4).unwrap();
+        for (square, piece) in position.board.pieces_of_color(opponent) {
+            let square_bit = 1u64 << square.index();
+            let gives_check = without_king
+                .as_ref()
+                .is_some_and(|board| board.attacks_from(square, piece, opponent) & king_bit != 0);
+            if gives_check {
+                constraints.checkers |= square_bit;
+                constraints.evasion = square_bit | between(king_square, square);
+            }
 
-    // Verify king is present on its starting square
-    if !matches!(position.board.get(king_square), Some((Piece::King, c)) if c == color) {
-        return false;
+            // A slider lined up with the king pins the one piece of ours between them
+            if slides_towards(piece, king_square, square) {
+                let blockers = between(king_square, square) & occupied;
+                if blockers.count_ones() == 1 && blockers & own != 0 {
+                    constraints.pinned |= blockers;
+                    constraints.pin_rays[blockers.trailing_zeros() as usize] = line(king_square, square);
+                }
+            }
+        }
+
+        constraints
     }
 
-    let rook_square = Square::from_rank_file(rank, 7).unwrap();
-    let f_square = Square::from_rank_file(rank, 5).unwrap();
-    let g_square = Square::from_rank_file(rank, 6).unwrap();
+    fn allows(&self, position: &Position, mv: &Move) -> bool {
+        let to_bit = 1u64 << mv.to.index();
+        if mv.is_null {
+            return self.checkers == 0;
+        }
+        if mv.from == self.king_square {
+            // Castling has its own checks for the squares the king crosses
+            return if mv.is_castling { is_legal_move(position, mv) } else { self.danger & to_bit == 0 };
+        }
+        if self.checkers.count_ones() > 1 {
+            return false;
+        }
+        if mv.is_en_passant {
+            // Taking en passant empties two squares on one rank, which the masks miss
+            return is_legal_move(position, mv);
+        }
 
-    // Check rook is present on home square
-    if !matches!(position.board.get(rook_square), Some((Piece::Rook, c)) if c == color) {
-        return false;
+        self.evasion & to_bit != 0 && self.pin_rays[mv.from.index() as usize] & to_bit != 0
     }
+}
 
-    // Check squares are empty
-    if !position.board.is_empty(f_square) || !position.board.is_empty(g_square) {
-        return false;
+/// Whether `piece` on `from` moves along the line joining it to `to`
+fn slides_towards(piece: Piece, to: Square, from: Square) -> bool {
+    let to_bit = 1u64 << to.index();
+    match piece {
+        Piece::Bishop => bishop_attacks(from, 0) & to_bit != 0,
+        Piece::Rook => rook_attacks(from, 0) & to_bit != 0,
+        Piece::Queen => queen_attacks(from, 0) & to_bit != 0,
+        _ => false,
     }
+}
 
-    // Check king is not in check
-    if is_in_check(position, color) {
-        return false;
+/// Like [`is_legal_move`], but first checks the piece on `mv.from` can make the move at
+/// all, flags included, so it also holds for moves that did not come from the generator
+pub fn is_playable_move(position: &Position, mv: &Move) -> bool {
+    let possible = if mv.is_null {
+        true
+    } else if mv.drop.is_some() {
+        let mut moves = Vec::with_capacity(MAX_MOVES);
+        generate_pseudo_legal_moves(position, &mut moves);
+        moves.contains(mv)
+    } else {
+        generate_pseudo_legal_moves_from(position, mv.from).contains(mv)
+    };
+    possible && is_legal_move(position, mv)
+}
+
+pub fn is_legal_move(position: &Position, mv: &Move) -> bool {
+    // Passing is only possible when not in check
+    if mv.is_null {
+        return !is_in_check(position, position.side_to_move);
     }
 
-    // Check king doesn't move through check
-    let opponent = color.opposite();
-    if position.board.is_attacked_by(f_square, opponent) {
-        return false;
+    keeps_king_safe(position, mv) && (position.variant.rules().allows_checks() || !gives_check(position, mv))
+}
+
+/// Whether `mv` leaves the mover's king out of attack, or the variant does not mind
+fn keeps_king_safe(position: &Position, mv: &Move) -> bool {
+    // Where the king is there to be taken, any move the pieces can make is legal
+    if position.variant.rules().king_capture() {
+        return true;
     }
 
-    // Check king doesn't end in check
-    if position.board.is_attacked_by(g_square, opponent) {
-        return false;
+    // Special validation for castling
+    if mv.is_castling {
+        return can_castle(position, position.side_to_move, mv.is_kingside_castling());
     }
 
-    true
+    // Play the move on a copy of the board, which is fixed-size and so costs no
+    // allocation, and check our king is not left attacked
+    let our_color = position.side_to_move;
+    let mut board = position.board.clone();
+    board.apply_move_for(mv, our_color);
+    match board.find_king(our_color) {
+        Some(king_square) => !board.is_attacked_by(king_square, our_color.opposite()),
+        None => true,
+    }
 }
 
-pub fn can_castle_queenside(position: &Position, color: Color) -> bool {
-    if !position.castling_rights.can_castle(color, false) {
-        return false;
-    }
+/// Whether `mv` attacks the opponent's king, worked out on a copy of the board alone
+pub(crate) fn gives_check(position: &Position, mv: &Move) -> bool {
+    let mover = position.side_to_move;
+    let mut board = position.board.clone();
+    board.apply_move_for(mv, mover);
+    board
+        .find_king(mover.opposite())
+        .is_some_and(|king| board.attacked_by(mover) & (1u64 << king.index()) != 0)
+}
 
-    let rank = if color == Color::White { 0 } else { 7 };
-    let king_square = Square::from_rank_file(rank, 4).unwrap();
+/// The full position after a legal move: board, castling rights, en passant target,
+/// clocks, side to move and repetition history all updated
+pub(crate) fn position_after_move(position: &Position, mv: &Move) -> Position {
+    let mut after = position.clone();
+    after.make_move(mv);
+    after
+}
 
-    // Verify king is present on its starting square
-    if !matches!(position.board.get(king_square), Some((Piece::King, c)) if c == color) {
+/// Whether the king of `color` is attacked, in a variant where that is check
+pub fn is_in_check(position: &Position, color: Color) -> bool {
+    if position.variant.rules().king_capture() {
         return false;
     }
+    if let Some(king_square) = position.board.find_king(color) {
+        position.board.attacked_by(color.opposite()) & (1u64 << king_square.index()) != 0
+    } else {
+        false
+    }
+}
 
-    let rook_square = Square::from_rank_file(rank, 0).unwrap();
-    let b_square = Square::from_rank_file(rank, 1).unwrap();
-    let c_square = Square::from_rank_file(rank, 2).unwrap();
-    let d_square = Square::from_rank_file(rank, 3).unwrap();
+/// Enemy pieces attacking the king of `color`
+pub fn checkers(position: &Position, color: Color) -> Vec<Checker> {
+    let Some(king_square) = position.board.find_king(color) else {
+        return Vec::new();
+    };
+    let king_bit = 1u64 << king_square.index();
+
+    position
+        .board
+        .pieces_of_color(color.opposite())
+        .filter(|&(square, piece)| position.board.attacks_from(square, piece, color.opposite()) & king_bit != 0)
+        .map(|(square, piece)| Checker { square, piece })
+        .collect()
+}
 
-    // Check rook is present on home square
-    if !matches!(position.board.get(rook_square), Some((Piece::Rook, c)) if c == color) {
-        return false;
-    }
+pub fn check_info(position: &Position) -> CheckInfo {
+    let side = position.side_to_move;
+    let checkers = checkers(position, side);
+    let in_check = !checkers.is_empty();
 
-    // Check squares are empty
-    if !position.board.is_empty(b_square) ||
-       !position.board.is_empty(c_square) ||
-       !position.board.is_empty(d_square) {
-        return false;
+    CheckInfo {
+        in_check,
+        is_checkmate: in_check && generate_legal_moves(position).is_empty(),
+        king_square: position.board.find_king(side),
+        checkers,
     }
+}
 
-    // Check king is not in check
-    if is_in_check(position, color) {
-        return false;
-    }
+pub fn is_checkmate(position: &Position) -> bool {
+    is_in_check(position, position.side_to_move) && generate_legal_moves(position).is_empty()
+}
 
-    // Check king doesn't move through check
-    let opponent = color.opposite();
-    if position.board.is_attacked_by(d_square, opponent) {
-        return false;
-    }
+pub fn is_stalemate(position: &Position) -> bool {
+    !is_in_check(position, position.side_to_move) && generate_legal_moves(position).is_empty()
+}
 
-    // Check king doesn't end in check
-    if position.board.is_attacked_by(c_square, opponent) {
+/// Whether `color` can castle on one side now: the move is there to make, the king is
+/// not in check, and it neither passes through nor lands on an attacked square
+pub fn can_castle(position: &Position, color: Color, kingside: bool) -> bool {
+    let Some(mv) = castling_move(position, color, kingside) else {
         return false;
-    }
-
-    true
+    };
+
+    // A Chess960 rook can stand between the king's path and an attacker on the rank;
+    // it moves out of the way, so attacks are found without it
+    let attacked = match mv.castling_rook {
+        Some(rook_square) => {
+            let mut board = position.board.clone();
+            board.set(rook_square, None);
+            board.attacked_by(color.opposite())
+        }
+        None => position.board.attacked_by(color.opposite()),
+    };
+    attacked & rank_span(mv.from, mv.to) == 0
 }
 
-#[allow(dead_code)]
-pub fn get_pinned_pieces(position: &Position, color: Color) -> Vec<Square> {
-    let mut pinned = Vec::new();
+/// Pieces of `color` that cannot leave the line between their king and an enemy slider
+pub fn get_pinned_pieces(position: &Position, color: Color) -> Vec<Pin> {
+    let Some(king_square) = position.board.find_king(color) else {
+        return Vec::new();
+    };
+    let own = position.board.occupancy(color);
 
-    if let Some(king_square) = position.board.find_king(color) {
-        let _opponent = color.opposite();
-
-        // Check all sliding directions from the king
-        const DIRECTIONS: [(i8, i8); 8] = [
-            (-1, -1), (-1, 0), (-1, 1),
-            (0, -1),           (0, 1),
-            (1, -1),  (1, 0),  (1, 1),
-        ];
-
-        for (rank_dir, file_dir) in DIRECTIONS {
-            let mut our_piece: Option<Square> = None;
-            let mut rank = king_square.rank() as i8;
-            let mut file = king_square.file() as i8;
-
-            loop {
-                rank += rank_dir;
-                file += file_dir;
-
-                if rank < 0 || rank >= 8 || file < 0 || file >= 8 {
-                    break;
-                }
+    let mut pinned = Vec::new();
+    for (pinner_square, pinner) in position.board.pieces_of_color(color.opposite()) {
+        if !slides_towards(pinner, king_square, pinner_square) {
+            continue;
+        }
+        let blockers = between(king_square, pinner_square) & position.board.occupied();
+        if blockers.count_ones() != 1 || blockers & own == 0 {
+            continue;
+        }
 
-                if let Some(square) = Square::from_rank_file(rank as u8, file as u8) {
-                    if let Some((piece, piece_color)) = position.board.get(square) {
-                        if piece_color == color {
-                            if our_piece.is_some() {
-                                // Second piece of our color, no pin possible
-                                break;
-                            }
-                            our_piece = Some(square);
-                        } else {
-                            // Opponent piece
-                            if let Some(pinned_square) = our_piece {
-                                // Check if this opponent piece can pin along this direction
-                                let is_diagonal = rank_dir != 0 && file_dir != 0;
-                                let can_pin = match piece {
-                                    Piece::Queen => true,
-                                    Piece::Bishop => is_diagonal,
-                                    Piece::Rook => !is_diagonal,
-                                    _ => false,
-                                };
-
-                                if can_pin {
-                                    pinned.push(pinned_square);
-                                }
-                            }
-                            break;
-                        }
-                    }
-                }
-            }
+        let square = squares_in(blockers).next().unwrap();
+        let Some((piece, _)) = position.board.get(square) else {
+            continue;
+        };
+        // Ordered outwards from the king
+        let mut ray: Vec<Square> =
+            squares_in(between(king_square, pinner_square) | (1u64 << pinner_square.index())).collect();
+        if pinner_square.index() < king_square.index() {
+            ray.reverse();
         }
+        pinned.push(Pin { square, piece, pinner_square, pinner, ray });
     }
 
     pinned
@@ -0,0 +1,113 @@
+use crate::archive::ArchiveError;
+use crate::chess_engine::ChessError;
+use serde::Serialize;
+use thiserror::Error;
+
+/// Error returned by every command, tagged by `kind` so the frontend can tell failures
+/// apart and word them in the player's language
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Error)]
+#[serde(tag = "kind")]
+pub enum CommandError {
+    #[error("Invalid FEN: {details}")]
+    InvalidFen { details: String },
+
+    /// `move` is the offending move as the command received it, when there was one
+    #[error("Invalid move: {details}")]
+    InvalidMove {
+        details: String,
+        #[serde(rename = "move")]
+        mv: Option<String>,
+    },
+
+    /// Failure partway through a list of moves; `index` counts from the first move of the list
+    #[error("Invalid move {mv} at index {index}: {details}")]
+    InvalidMoveInSequence {
+        index: usize,
+        details: String,
+        #[serde(rename = "move")]
+        mv: String,
+    },
+
+    #[error("Invalid square: {square}")]
+    InvalidSquare { square: String },
+
+    #[error("Game is over: {status}")]
+    GameOver { status: String },
+
+    #[error("Parse error: {details}")]
+    ParseError { details: String },
+
+    #[error("I/O error: {details}")]
+    Io { details: String },
+
+    #[error("Invalid value for option {name}: {details}")]
+    InvalidOption { name: String, details: String },
+
+    /// Failures outside the rules of chess: an unknown game ID, a missing engine, ...
+    #[error("{details}")]
+    Other { details: String },
+}
+
+impl CommandError {
+    /// Names the move an `InvalidMove` error was about
+    pub fn with_move(self, mv: impl Into<String>) -> Self {
+        match self {
+            CommandError::InvalidMove { details, .. } => CommandError::InvalidMove { details, mv: Some(mv.into()) },
+            other => other,
+        }
+    }
+}
+
+impl From<ChessError> for CommandError {
+    fn from(error: ChessError) -> Self {
+        match error {
+            ChessError::InvalidFen { reason } => CommandError::InvalidFen { details: reason },
+            ChessError::InvalidMove { reason } => CommandError::InvalidMove { details: reason, mv: None },
+            ChessError::InvalidSquare { square } => CommandError::InvalidSquare { square },
+            ChessError::GameOver { status } => CommandError::GameOver { status },
+            ChessError::ParseError { input } => CommandError::ParseError { details: input },
+            ChessError::Io { reason } => CommandError::Io { details: reason },
+            ChessError::InvalidOption { name, reason } => CommandError::InvalidOption { name, details: reason },
+        }
+    }
+}
+
+impl From<std::io::Error> for CommandError {
+    fn from(error: std::io::Error) -> Self {
+        CommandError::Io { details: error.to_string() }
+    }
+}
+
+impl From<ArchiveError> for CommandError {
+    fn from(error: ArchiveError) -> Self {
+        match error {
+            ArchiveError::Game(error) => error.into(),
+            error @ ArchiveError::NotFound(_) => CommandError::Other { details: error.to_string() },
+            error => CommandError::Io { details: error.to_string() },
+        }
+    }
+}
+
+impl From<String> for CommandError {
+    fn from(details: String) -> Self {
+        CommandError::Other { details }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_serializes_with_kind_tag() {
+        let error = CommandError::from(ChessError::InvalidMove { reason: "e2 to e5".to_string() }).with_move("e2e5");
+        assert_eq!(
+            serde_json::to_value(&error).unwrap(),
+            serde_json::json!({ "kind": "InvalidMove", "details": "e2 to e5", "move": "e2e5" })
+        );
+        assert_eq!(error.to_string(), "Invalid move: e2 to e5");
+
+        let error = CommandError::from(ChessError::InvalidSquare { square: "z9".to_string() });
+        assert_eq!(serde_json::to_value(&error).unwrap(), serde_json::json!({ "kind": "InvalidSquare", "square": "z9" }));
+    }
+}
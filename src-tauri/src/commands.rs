@@ -1,130 +1,1294 @@
-use tauri::State;
-use std::sync::Mutex;
-use crate::chess_engine::{ChessGame, Position, Move, Square, GameStatus, Piece, MoveAnalysis, analyze_all_moves, Evaluator};
+use tauri::{AppHandle, Emitter, State};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::BufReader;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use crate::archive::{ArchiveError, ArchivedGame, GameArchive, PositionMatch};
+use crate::autosave::Autosave;
+use crate::command_error::CommandError;
+use crate::engine_pool::EnginePool;
+use crate::game_import::GameSource;
+use crate::sessions::{GameSessions, MAIN_GAME_ID};
+use crate::settings::{Settings, SettingsStore};
+use crate::chess_engine::{ChessGame, diff_positions, PositionDiff, CapturedPieces, HistoryView, LastMove, MoveListEntry, MoveRecord, MoveTree, Notation, Chess960Start, visibility_mask, Odds, SvgOptions, Opening, perft as count_perft, perft_divide as count_perft_divide, perft_hashed, MAX_PERFT_DEPTH, PERFT_HASH_MEGABYTES, PerftDivide, PgnReader, PgnSummary, lint_pgn, PgnProblem, Premove, PremoveOutcome, EpdAnalysis, EpdRecord, BookMove, probe_book, parse_puzzles, Puzzle, PuzzleFilter, PuzzleOutcome, PuzzleRushStatus, PuzzleSession, PuzzleStats, Variant, VariantOptions, ExternalEngine, clear_transposition_table, search, SearchLimits, SearchResult, MAX_DEPTH, hint_limits, Hint, EngineInfo, EngineOption, EngineOptions, validate_setup, BoardState, Position, CheckInfo, Destination, MoveLegality, Pin, Move, Square, DrawRules, GameResult, GameStatus, Color, Piece, MaterialBalance, MoveAnalysis, Motif, analyze_all_moves, material_balance, Evaluator, EvalPerspective, EvalPersonality, PieceSquareTables, WinProbability};
 
-// State type for managing the chess game
-pub type GameState = Mutex<ChessGame>;
+/// Open boards by game ID. Commands act on the board named by their `game_id`
+/// argument, or on the main board when it is left out.
+pub type GameState = Mutex<GameSessions>;
 
-/// Creates a new chess game, resetting to the starting position
+/// External UCI engine used for analysis instead of the built-in search, if configured;
+/// shared with the engine pool's workers while they search with it
+pub type EngineState = Arc<Mutex<Option<ExternalEngine>>>;
+
+/// Puzzle mode queue and score
+pub type PuzzleState = Mutex<PuzzleSession>;
+
+/// Saved preferences, the built-in engine's options among them
+pub type SettingsState = Mutex<Settings>;
+
+/// Games stored in the local database, or why it could not be opened at startup
+pub type ArchiveState = Mutex<Result<GameArchive, CommandError>>;
+
+/// Event emitted whenever a premove is played or discarded
+pub const PREMOVE_EVENT: &str = "premove";
+
+#[derive(Debug, Clone, Serialize)]
+struct PremoveEvent {
+    game_id: String,
+    outcome: PremoveOutcome,
+}
+
+/// Creates a new chess game, resetting to the starting position of `variant` (the
+/// variant chosen in the settings when it is left out), or the one `options` set up
 #[tauri::command]
-pub fn new_game(state: State<GameState>) -> Result<(), String> {
-    let mut game = state.lock().map_err(|e| e.to_string())?;
-    *game = ChessGame::new();
+pub fn new_game(
+    state: State<GameState>,
+    game_id: Option<String>,
+    autosave: State<Autosave>,
+    settings: State<SettingsState>,
+    variant: Option<Variant>,
+    options: Option<VariantOptions>,
+) -> Result<(), CommandError> {
+    let (draw_rules, default_variant) = {
+        let settings = settings.lock().map_err(|e| e.to_string())?;
+        (settings.draw_rules, settings.variant)
+    };
+    let new_game = ChessGame::new_with_options(variant.unwrap_or(default_variant), &options.unwrap_or_default())?;
+    let mut games = state.lock().map_err(|e| e.to_string())?;
+    let game = games.get_mut(game_id.as_deref())?;
+    *game = new_game;
+    game.set_draw_rules(draw_rules);
+    save_quietly(&autosave, game_id.as_deref(), game);
     Ok(())
 }
 
+/// Creates a new game in which `giver` plays at `odds` (a piece less, or without the
+/// first move), for a beginner taking on a stronger player or the engine
+#[tauri::command]
+pub fn new_game_with_odds(
+    state: State<GameState>,
+    game_id: Option<String>,
+    autosave: State<Autosave>,
+    settings: State<SettingsState>,
+    odds: Odds,
+    giver: Color,
+) -> Result<BoardState, CommandError> {
+    let mut new_game = ChessGame::new_with_odds(odds, giver)?;
+    new_game.set_draw_rules(settings.lock().map_err(|e| e.to_string())?.draw_rules);
+    let position = new_game.board_state();
+
+    let mut games = state.lock().map_err(|e| e.to_string())?;
+    let game = games.get_mut(game_id.as_deref())?;
+    *game = new_game;
+    save_quietly(&autosave, game_id.as_deref(), game);
+    Ok(position)
+}
+
+/// A Chess960 starting position: arrangement `number` (0-959, 518 being the standard
+/// one) or a random one, and for Double Fischer Random Chess (`double`) an independent
+/// arrangement for Black, numbered by `black_number` or random
+#[tauri::command]
+pub fn chess960_start_position(
+    number: Option<u16>,
+    double: bool,
+    black_number: Option<u16>,
+) -> Result<Chess960Start, CommandError> {
+    Ok(Chess960Start::pick(number, double, black_number)?)
+}
+
+/// Starts a new game from the moves given in UCI or SAN (mixed freely) and returns the
+/// position they reach. If one is illegal the game is unchanged and the error gives its index.
+#[tauri::command]
+pub fn new_game_from_moves(
+    state: State<GameState>,
+    game_id: Option<String>,
+    autosave: State<Autosave>,
+    settings: State<SettingsState>,
+    moves: Vec<String>,
+) -> Result<BoardState, CommandError> {
+    let mut new_game = ChessGame::from_moves(&moves).map_err(|(index, e)| CommandError::InvalidMoveInSequence {
+        index,
+        details: e.to_string(),
+        mv: moves[index].clone(),
+    })?;
+    new_game.set_draw_rules(settings.lock().map_err(|e| e.to_string())?.draw_rules);
+    let position = new_game.board_state();
+
+    let mut games = state.lock().map_err(|e| e.to_string())?;
+    let game = games.get_mut(game_id.as_deref())?;
+    *game = new_game;
+    save_quietly(&autosave, game_id.as_deref(), game);
+    Ok(position)
+}
+
+/// Deletes the autosaved game and starts a fresh one on the main board
+#[tauri::command]
+pub fn discard_autosave(state: State<GameState>, autosave: State<Autosave>) -> Result<BoardState, CommandError> {
+    autosave.discard()?;
+
+    let mut games = state.lock().map_err(|e| e.to_string())?;
+    let game = games.get_mut(None)?;
+    *game = ChessGame::new();
+    Ok(game.board_state())
+}
+
+/// Opens another board, from the starting position or a FEN, and returns its game ID
+#[tauri::command]
+pub fn create_game(state: State<GameState>, fen: Option<String>) -> Result<String, CommandError> {
+    let game = match fen {
+        Some(fen) => ChessGame::from_fen(&fen)?,
+        None => ChessGame::new(),
+    };
+    let mut games = state.lock().map_err(|e| e.to_string())?;
+    Ok(games.create(game))
+}
+
+/// Closes a board opened with `create_game`
+#[tauri::command]
+pub fn close_game(state: State<GameState>, game_id: String) -> Result<(), CommandError> {
+    let mut games = state.lock().map_err(|e| e.to_string())?;
+    games.close(&game_id).map_err(CommandError::from)
+}
+
+/// Returns the IDs of all open boards, the main board first
+#[tauri::command]
+pub fn list_games(state: State<GameState>) -> Result<Vec<String>, CommandError> {
+    let games = state.lock().map_err(|e| e.to_string())?;
+    Ok(games.ids())
+}
+
 /// Returns the current board state with full game information
 #[tauri::command]
-pub fn get_board_state(state: State<GameState>) -> Result<Position, String> {
-    let game = state.lock().map_err(|e| e.to_string())?;
-    Ok(game.get_board_state().clone())
+pub fn get_board_state(state: State<GameState>, game_id: Option<String>) -> Result<BoardState, CommandError> {
+    let games = state.lock().map_err(|e| e.to_string())?;
+    let game = games.get(game_id.as_deref())?;
+    Ok(game.board_state())
 }
 
 /// Returns all legal moves in the current position
 #[tauri::command]
-pub fn get_legal_moves(state: State<GameState>) -> Result<Vec<Move>, String> {
-    let game = state.lock().map_err(|e| e.to_string())?;
+pub fn get_legal_moves(state: State<GameState>, game_id: Option<String>) -> Result<Vec<Move>, CommandError> {
+    let games = state.lock().map_err(|e| e.to_string())?;
+    let game = games.get(game_id.as_deref())?;
     Ok(game.get_legal_moves())
 }
 
 /// Returns legal moves for a specific square
 #[tauri::command]
-pub fn get_legal_moves_for_square(state: State<GameState>, square: String) -> Result<Vec<Move>, String> {
-    let parsed_square = Square::from_algebraic(&square).map_err(|e| e.to_string())?;
-    let game = state.lock().map_err(|e| e.to_string())?;
+pub fn get_legal_moves_for_square(
+    state: State<GameState>,
+    game_id: Option<String>,
+    square: String,
+) -> Result<Vec<Move>, CommandError> {
+    let parsed_square = Square::from_algebraic(&square)?;
+    let games = state.lock().map_err(|e| e.to_string())?;
+    let game = games.get(game_id.as_deref())?;
     Ok(game.get_legal_moves_for_square(parsed_square))
 }
 
-/// Makes a move on the board and returns the updated game status
+/// Returns every legal destination of the side to move in one call, keyed by origin
+/// square, with promotion, castling and en passant marked
 #[tauri::command]
-pub fn make_move(
+pub fn get_all_legal_destinations(
+    state: State<GameState>,
+    game_id: Option<String>,
+) -> Result<BTreeMap<String, Vec<Destination>>, CommandError> {
+    let games = state.lock().map_err(|e| e.to_string())?;
+    let game = games.get(game_id.as_deref())?;
+    Ok(game.legal_destinations())
+}
+
+/// Checks whether a move would be legal without making it, giving the reason when it is not
+#[tauri::command]
+pub fn is_move_legal(
     state: State<GameState>,
+    game_id: Option<String>,
     from: String,
     to: String,
     promotion: Option<String>,
-) -> Result<GameStatus, String> {
-    let from_square = Square::from_algebraic(&from).map_err(|e| e.to_string())?;
-    let to_square = Square::from_algebraic(&to).map_err(|e| e.to_string())?;
+) -> Result<MoveLegality, CommandError> {
+    let from_square = Square::from_algebraic(&from)?;
+    let to_square = Square::from_algebraic(&to)?;
+    let promotion_piece = promotion.as_deref().map(parse_promotion).transpose()?;
 
-    let promotion_piece = match promotion.as_deref() {
-        Some(p) => Some(parse_promotion(p)?),
-        None => None,
+    let games = state.lock().map_err(|e| e.to_string())?;
+    let game = games.get(game_id.as_deref())?;
+    Ok(game.move_legality(from_square, to_square, promotion_piece))
+}
+
+/// Makes a move on the board and returns the updated game status. The move is either
+/// `mv`, passed back exactly as `get_legal_moves` returned it, or `from` and `to` squares
+/// (with `promotion` when needed) resolved against the legal moves.
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub fn make_move(
+    state: State<GameState>,
+    game_id: Option<String>,
+    autosave: State<Autosave>,
+    app: AppHandle,
+    settings: State<SettingsState>,
+    mv: Option<Move>,
+    from: Option<String>,
+    to: Option<String>,
+    promotion: Option<String>,
+) -> Result<GameStatus, CommandError> {
+    let auto_queen = settings.lock().map_err(|e| e.to_string())?.auto_queen;
+    let mut games = state.lock().map_err(|e| e.to_string())?;
+    let game = games.get_mut(game_id.as_deref())?;
+
+    let mv = match (mv, from, to) {
+        (Some(mv), _, _) => mv,
+        (None, Some(from), Some(to)) => resolve_move(game, &from, &to, promotion, auto_queen)?,
+        _ => {
+            return Err(CommandError::InvalidMove {
+                details: "Give either a move or its from and to squares".to_string(),
+                mv: None,
+            })
+        }
     };
 
-    let mut game = state.lock().map_err(|e| e.to_string())?;
+    game.make_move(mv).map_err(|e| CommandError::from(e).with_move(mv.to_uci()))?;
+    run_premove(&app, game_id.as_deref(), game);
+    save_quietly(&autosave, game_id.as_deref(), game);
+    Ok(game.get_status())
+}
 
-    // Get all legal moves and find the matching one with correct flags
-    let legal_moves = game.get_legal_moves();
-    let mv = legal_moves
-        .into_iter()
-        .find(|m| {
-            m.from == from_square
-                && m.to == to_square
-                && m.promotion == promotion_piece
-        })
-        .ok_or_else(|| {
-            format!(
-                "Illegal move: {} to {}{}",
-                from,
-                to,
-                promotion.map(|p| format!(" (promotion: {})", p)).unwrap_or_default()
-            )
-        })?;
+/// Makes a move given in Standard Algebraic Notation (e.g. "Nf3", "O-O", "e8=Q+")
+#[tauri::command]
+pub fn make_san_move(
+    state: State<GameState>,
+    game_id: Option<String>,
+    autosave: State<Autosave>,
+    app: AppHandle,
+    san: String,
+) -> Result<GameStatus, CommandError> {
+    let mut games = state.lock().map_err(|e| e.to_string())?;
+    let game = games.get_mut(game_id.as_deref())?;
+    game.make_san_move(&san).map_err(|e| CommandError::from(e).with_move(&san))?;
+    run_premove(&app, game_id.as_deref(), game);
+    save_quietly(&autosave, game_id.as_deref(), game);
+    Ok(game.get_status())
+}
+
+/// Makes a move given as a single UCI string (e.g. "e2e4", "e7e8q")
+#[tauri::command]
+pub fn make_move_uci(
+    state: State<GameState>,
+    game_id: Option<String>,
+    autosave: State<Autosave>,
+    app: AppHandle,
+    uci: String,
+) -> Result<GameStatus, CommandError> {
+    let mut games = state.lock().map_err(|e| e.to_string())?;
+    let game = games.get_mut(game_id.as_deref())?;
+    game.make_uci_move(&uci).map_err(|e| CommandError::from(e).with_move(&uci))?;
+    run_premove(&app, game_id.as_deref(), game);
+    save_quietly(&autosave, game_id.as_deref(), game);
+    Ok(game.get_status())
+}
 
-    game.make_move(mv).map_err(|e| e.to_string())?;
+/// Plays a list of UCI moves in order, all or none, and returns the updated game status.
+/// If one is illegal, no move is played and the error gives its index in the list.
+#[tauri::command]
+pub fn make_moves_uci(
+    state: State<GameState>,
+    game_id: Option<String>,
+    autosave: State<Autosave>,
+    app: AppHandle,
+    moves: Vec<String>,
+) -> Result<GameStatus, CommandError> {
+    let mut games = state.lock().map_err(|e| e.to_string())?;
+    let game = games.get_mut(game_id.as_deref())?;
+    game.make_uci_moves(&moves).map_err(|(index, e)| CommandError::InvalidMoveInSequence {
+        index,
+        details: e.to_string(),
+        mv: moves[index].clone(),
+    })?;
+    run_premove(&app, game_id.as_deref(), game);
+    save_quietly(&autosave, game_id.as_deref(), game);
     Ok(game.get_status())
 }
 
 /// Undoes the last move and returns the updated game status
 #[tauri::command]
-pub fn undo_move(state: State<GameState>) -> Result<GameStatus, String> {
-    let mut game = state.lock().map_err(|e| e.to_string())?;
-    game.undo_move().map_err(|e| e.to_string())?;
+pub fn undo_move(
+    state: State<GameState>,
+    game_id: Option<String>,
+    autosave: State<Autosave>,
+) -> Result<GameStatus, CommandError> {
+    let mut games = state.lock().map_err(|e| e.to_string())?;
+    let game = games.get_mut(game_id.as_deref())?;
+    game.undo_move()?;
+    save_quietly(&autosave, game_id.as_deref(), game);
+    Ok(game.get_status())
+}
+
+/// Replays the most recently undone move and returns the updated game status
+#[tauri::command]
+pub fn redo_move(
+    state: State<GameState>,
+    game_id: Option<String>,
+    autosave: State<Autosave>,
+    app: AppHandle,
+) -> Result<GameStatus, CommandError> {
+    let mut games = state.lock().map_err(|e| e.to_string())?;
+    let game = games.get_mut(game_id.as_deref())?;
+    game.redo_move()?;
+    run_premove(&app, game_id.as_deref(), game);
+    save_quietly(&autosave, game_id.as_deref(), game);
     Ok(game.get_status())
 }
 
+/// Queues a move for `color` while it is the opponent's turn. It is played the moment
+/// the turn comes back if it is legal then, and discarded otherwise; either way a
+/// `premove` event reports the outcome.
+#[tauri::command]
+pub fn set_premove(
+    state: State<GameState>,
+    game_id: Option<String>,
+    color: Color,
+    from: String,
+    to: String,
+    promotion: Option<String>,
+) -> Result<(), CommandError> {
+    let from = Square::from_algebraic(&from)?;
+    let to = Square::from_algebraic(&to)?;
+    let promotion = promotion.as_deref().map(parse_promotion).transpose()?;
+
+    let mut games = state.lock().map_err(|e| e.to_string())?;
+    let game = games.get_mut(game_id.as_deref())?;
+    game.set_premove(Premove { color, from, to, promotion }).map_err(CommandError::from)
+}
+
+/// Drops the queued premove, if any
+#[tauri::command]
+pub fn cancel_premove(state: State<GameState>, game_id: Option<String>) -> Result<(), CommandError> {
+    let mut games = state.lock().map_err(|e| e.to_string())?;
+    let game = games.get_mut(game_id.as_deref())?;
+    game.cancel_premove();
+    Ok(())
+}
+
+/// Returns the queued premove, if any
+#[tauri::command]
+pub fn get_premove(state: State<GameState>, game_id: Option<String>) -> Result<Option<Premove>, CommandError> {
+    let games = state.lock().map_err(|e| e.to_string())?;
+    let game = games.get(game_id.as_deref())?;
+    Ok(game.premove())
+}
+
+/// Shows the position after `ply` half-moves (0 for the start) for reviewing the move
+/// list; the game itself stays at its latest move
+#[tauri::command]
+pub fn view_ply(state: State<GameState>, game_id: Option<String>, ply: usize) -> Result<HistoryView, CommandError> {
+    let mut games = state.lock().map_err(|e| e.to_string())?;
+    let game = games.get_mut(game_id.as_deref())?;
+    game.view_ply(ply).map_err(CommandError::from)
+}
+
+/// Shows the starting position of the game for review
+#[tauri::command]
+pub fn view_first_ply(state: State<GameState>, game_id: Option<String>) -> Result<HistoryView, CommandError> {
+    let mut games = state.lock().map_err(|e| e.to_string())?;
+    let game = games.get_mut(game_id.as_deref())?;
+    game.view_ply(0).map_err(CommandError::from)
+}
+
+/// Goes back to the live position after reviewing earlier moves
+#[tauri::command]
+pub fn view_last_ply(state: State<GameState>, game_id: Option<String>) -> Result<HistoryView, CommandError> {
+    let mut games = state.lock().map_err(|e| e.to_string())?;
+    let game = games.get_mut(game_id.as_deref())?;
+    let last = game.history_view().total_plies;
+    game.view_ply(last).map_err(CommandError::from)
+}
+
+/// Returns the position currently shown for review, which is the live one unless a
+/// ply was selected
+#[tauri::command]
+pub fn get_history_view(state: State<GameState>, game_id: Option<String>) -> Result<HistoryView, CommandError> {
+    let games = state.lock().map_err(|e| e.to_string())?;
+    let game = games.get(game_id.as_deref())?;
+    Ok(game.history_view())
+}
+
 /// Returns the current game status
 #[tauri::command]
-pub fn get_game_status(state: State<GameState>) -> Result<GameStatus, String> {
-    let game = state.lock().map_err(|e| e.to_string())?;
+pub fn get_game_status(state: State<GameState>, game_id: Option<String>) -> Result<GameStatus, CommandError> {
+    let games = state.lock().map_err(|e| e.to_string())?;
+    let game = games.get(game_id.as_deref())?;
+    Ok(game.get_status())
+}
+
+/// Returns whether the side to move is in check, its king square and the pieces
+/// giving check
+#[tauri::command]
+pub fn get_check_info(state: State<GameState>, game_id: Option<String>) -> Result<CheckInfo, CommandError> {
+    let games = state.lock().map_err(|e| e.to_string())?;
+    let game = games.get(game_id.as_deref())?;
+    Ok(game.check_info())
+}
+
+/// Returns the pieces pinned to their king, with the pinning piece and the line they
+/// may still move along. Defaults to the side to move.
+#[tauri::command]
+pub fn get_pinned_pieces(
+    state: State<GameState>,
+    game_id: Option<String>,
+    color: Option<Color>,
+) -> Result<Vec<Pin>, CommandError> {
+    let games = state.lock().map_err(|e| e.to_string())?;
+    let game = games.get(game_id.as_deref())?;
+    let color = color.unwrap_or(game.get_board_state().side_to_move);
+    Ok(game.pinned_pieces(color))
+}
+
+/// Returns the game's result and why it ended, as recorded in PGN `Result` and `Termination` tags
+#[tauri::command]
+pub fn get_game_result(state: State<GameState>, game_id: Option<String>) -> Result<GameResult, CommandError> {
+    let games = state.lock().map_err(|e| e.to_string())?;
+    let game = games.get(game_id.as_deref())?;
+    Ok(game.result())
+}
+
+/// Turns Armageddon scoring on or off for the game, so that a draw counts as a win for
+/// Black, and returns the game's result under the new scoring
+#[tauri::command]
+pub fn set_armageddon(
+    state: State<GameState>,
+    game_id: Option<String>,
+    autosave: State<Autosave>,
+    enabled: bool,
+) -> Result<GameResult, CommandError> {
+    let mut games = state.lock().map_err(|e| e.to_string())?;
+    let game = games.get_mut(game_id.as_deref())?;
+    game.set_armageddon(enabled);
+    save_quietly(&autosave, game_id.as_deref(), game);
+    Ok(game.result())
+}
+
+/// Ends the game with the given side resigning
+#[tauri::command]
+pub fn resign_game(
+    state: State<GameState>,
+    game_id: Option<String>,
+    autosave: State<Autosave>,
+    color: Color,
+) -> Result<GameStatus, CommandError> {
+    let mut games = state.lock().map_err(|e| e.to_string())?;
+    let game = games.get_mut(game_id.as_deref())?;
+    game.resign(color)?;
+    save_quietly(&autosave, game_id.as_deref(), game);
     Ok(game.get_status())
 }
 
+/// Ends the game with the given side losing on time
+#[tauri::command]
+pub fn forfeit_on_time(
+    state: State<GameState>,
+    game_id: Option<String>,
+    autosave: State<Autosave>,
+    color: Color,
+) -> Result<GameStatus, CommandError> {
+    let mut games = state.lock().map_err(|e| e.to_string())?;
+    let game = games.get_mut(game_id.as_deref())?;
+    game.forfeit_on_time(color)?;
+    save_quietly(&autosave, game_id.as_deref(), game);
+    Ok(game.get_status())
+}
+
+/// Ends the game in a draw by agreement
+#[tauri::command]
+pub fn agree_draw(
+    state: State<GameState>,
+    game_id: Option<String>,
+    autosave: State<Autosave>,
+) -> Result<GameStatus, CommandError> {
+    let mut games = state.lock().map_err(|e| e.to_string())?;
+    let game = games.get_mut(game_id.as_deref())?;
+    game.agree_draw()?;
+    save_quietly(&autosave, game_id.as_deref(), game);
+    Ok(game.get_status())
+}
+
+/// Offers a draw on behalf of the given side; offering while the opponent's offer is
+/// pending accepts it
+#[tauri::command]
+pub fn offer_draw(
+    state: State<GameState>,
+    game_id: Option<String>,
+    autosave: State<Autosave>,
+    color: Color,
+) -> Result<GameStatus, CommandError> {
+    let mut games = state.lock().map_err(|e| e.to_string())?;
+    let game = games.get_mut(game_id.as_deref())?;
+    game.offer_draw(color)?;
+    save_quietly(&autosave, game_id.as_deref(), game);
+    Ok(game.get_status())
+}
+
+/// Accepts the opponent's pending draw offer on behalf of the given side
+#[tauri::command]
+pub fn accept_draw(
+    state: State<GameState>,
+    game_id: Option<String>,
+    autosave: State<Autosave>,
+    color: Color,
+) -> Result<GameStatus, CommandError> {
+    let mut games = state.lock().map_err(|e| e.to_string())?;
+    let game = games.get_mut(game_id.as_deref())?;
+    game.accept_draw(color)?;
+    save_quietly(&autosave, game_id.as_deref(), game);
+    Ok(game.get_status())
+}
+
+/// Declines the opponent's pending draw offer on behalf of the given side
+#[tauri::command]
+pub fn decline_draw(
+    state: State<GameState>,
+    game_id: Option<String>,
+    autosave: State<Autosave>,
+    color: Color,
+) -> Result<(), CommandError> {
+    let mut games = state.lock().map_err(|e| e.to_string())?;
+    let game = games.get_mut(game_id.as_deref())?;
+    game.decline_draw(color)?;
+    save_quietly(&autosave, game_id.as_deref(), game);
+    Ok(())
+}
+
+/// Returns the side whose draw offer is waiting for an answer, if any
+#[tauri::command]
+pub fn get_draw_offer(state: State<GameState>, game_id: Option<String>) -> Result<Option<Color>, CommandError> {
+    let games = state.lock().map_err(|e| e.to_string())?;
+    let game = games.get(game_id.as_deref())?;
+    Ok(game.draw_offer())
+}
+
+/// Claims a draw by the fifty-move rule or threefold repetition when claimable draws
+/// are on and the status is `DrawClaimable`
+#[tauri::command]
+pub fn claim_draw(
+    state: State<GameState>,
+    game_id: Option<String>,
+    autosave: State<Autosave>,
+) -> Result<GameStatus, CommandError> {
+    let mut games = state.lock().map_err(|e| e.to_string())?;
+    let game = games.get_mut(game_id.as_deref())?;
+    game.claim_draw()?;
+    save_quietly(&autosave, game_id.as_deref(), game);
+    Ok(game.get_status())
+}
+
+/// Chooses whether the fifty-move rule and threefold repetition draw automatically or
+/// only when claimed, returning the resulting status
+#[tauri::command]
+pub fn set_draw_rules(
+    state: State<GameState>,
+    game_id: Option<String>,
+    autosave: State<Autosave>,
+    draw_rules: DrawRules,
+) -> Result<GameStatus, CommandError> {
+    let mut games = state.lock().map_err(|e| e.to_string())?;
+    let game = games.get_mut(game_id.as_deref())?;
+    game.set_draw_rules(draw_rules);
+    save_quietly(&autosave, game_id.as_deref(), game);
+    Ok(game.get_status())
+}
+
+/// Returns whether draws are automatic or claimable
+#[tauri::command]
+pub fn get_draw_rules(state: State<GameState>, game_id: Option<String>) -> Result<DrawRules, CommandError> {
+    let games = state.lock().map_err(|e| e.to_string())?;
+    let game = games.get(game_id.as_deref())?;
+    Ok(game.draw_rules())
+}
+
+/// Checks a position from the board editor, returning every reason it could not occur in
+/// a real game (side not to move in check, too many pawns or promoted pieces, adjacent
+/// kings, impossible en passant square, ...). An empty list means it can be loaded.
+#[tauri::command]
+pub fn validate_position(fen: String) -> Vec<String> {
+    validate_setup(&fen)
+}
+
 /// Loads a position from FEN notation
 #[tauri::command]
-pub fn load_fen(state: State<GameState>, fen: String) -> Result<Position, String> {
-    let new_game = ChessGame::from_fen(&fen).map_err(|e| e.to_string())?;
-    let position = new_game.get_board_state().clone();
+pub fn load_fen(
+    state: State<GameState>,
+    game_id: Option<String>,
+    autosave: State<Autosave>,
+    settings: State<SettingsState>,
+    fen: String,
+) -> Result<BoardState, CommandError> {
+    let mut new_game = ChessGame::from_fen(&fen)?;
+    new_game.set_draw_rules(settings.lock().map_err(|e| e.to_string())?.draw_rules);
+    let position = new_game.board_state();
+
+    let mut games = state.lock().map_err(|e| e.to_string())?;
+    let game = games.get_mut(game_id.as_deref())?;
+    *game = new_game;
+    save_quietly(&autosave, game_id.as_deref(), game);
+    Ok(position)
+}
+
+/// Loads a game from PGN, replaying its moves so they can be undone
+#[tauri::command]
+pub fn load_pgn(
+    state: State<GameState>,
+    game_id: Option<String>,
+    autosave: State<Autosave>,
+    settings: State<SettingsState>,
+    pgn: String,
+) -> Result<BoardState, CommandError> {
+    let mut new_game = ChessGame::from_pgn(&pgn)?;
+    new_game.set_draw_rules(settings.lock().map_err(|e| e.to_string())?.draw_rules);
+    let position = new_game.board_state();
+
+    let mut games = state.lock().map_err(|e| e.to_string())?;
+    let game = games.get_mut(game_id.as_deref())?;
+    *game = new_game;
+    save_quietly(&autosave, game_id.as_deref(), game);
+    Ok(position)
+}
+
+/// Downloads a game from a Lichess or chess.com URL (or a bare Lichess game ID) and
+/// loads it for review
+#[tauri::command]
+pub async fn import_game_url(
+    state: State<'_, GameState>,
+    game_id: Option<String>,
+    autosave: State<'_, Autosave>,
+    settings: State<'_, SettingsState>,
+    url: String,
+) -> Result<BoardState, CommandError> {
+    let source = GameSource::parse(&url)?;
+    let pgn = tauri::async_runtime::spawn_blocking(move || source.fetch_pgn())
+        .await
+        .map_err(|e| e.to_string())??;
+
+    let mut new_game = ChessGame::from_pgn(&pgn)?;
+    new_game.set_draw_rules(settings.lock().map_err(|e| e.to_string())?.draw_rules);
+    let position = new_game.board_state();
+
+    let mut games = state.lock().map_err(|e| e.to_string())?;
+    let game = games.get_mut(game_id.as_deref())?;
+    *game = new_game;
+    save_quietly(&autosave, game_id.as_deref(), game);
+    Ok(position)
+}
+
+/// Checks a PGN game for syntax errors, malformed tags, impossible moves and an
+/// inconsistent result without loading it
+#[tauri::command]
+pub fn validate_pgn(pgn: String) -> Vec<PgnProblem> {
+    lint_pgn(&pgn)
+}
+
+/// Scans a PGN database file game by game and returns a summary of each game
+#[tauri::command]
+pub fn import_pgn_database(path: String) -> Result<Vec<PgnSummary>, CommandError> {
+    let file = File::open(&path)?;
+    PgnReader::new(BufReader::new(file))
+        .summaries()
+        .map_err(CommandError::from)
+}
+
+/// Exports the game, including variations, as PGN text in SAN (the default) or figurine notation
+#[tauri::command]
+pub fn get_pgn(state: State<GameState>, game_id: Option<String>, notation: Option<Notation>) -> Result<String, CommandError> {
+    let games = state.lock().map_err(|e| e.to_string())?;
+    let game = games.get(game_id.as_deref())?;
+    Ok(game.to_pgn_in(notation.unwrap_or_default()))
+}
+
+/// Exports the game as PGN with an engine evaluation on every move of the current line,
+/// optionally marking inaccuracies, mistakes and blunders
+#[tauri::command]
+pub async fn export_annotated_pgn(
+    state: State<'_, GameState>,
+    game_id: Option<String>,
+    settings: State<'_, SettingsState>,
+    pool: State<'_, EnginePool>,
+    depth: Option<u8>,
+    classify: Option<bool>,
+) -> Result<String, CommandError> {
+    if let Some(depth) = depth {
+        check_depth(depth, MAX_DEPTH)?;
+    }
+    // Analyse a copy so the game stays available while the search runs
+    let game = state.lock().map_err(|e| e.to_string())?.get(game_id.as_deref())?.clone();
+    let limits = depth.map(SearchLimits::depth).unwrap_or_default();
+    let limits = settings.lock().map_err(|e| e.to_string())?.engine.apply(limits);
+    Ok(pool.run(move || game.to_annotated_pgn(limits, classify.unwrap_or(true))).await?)
+}
+
+/// Renders the current position as an SVG image, optionally flipped and with last-move
+/// and check highlights and arrows
+#[tauri::command]
+pub fn export_svg(
+    state: State<GameState>,
+    game_id: Option<String>,
+    options: Option<SvgOptions>,
+) -> Result<String, CommandError> {
+    let games = state.lock().map_err(|e| e.to_string())?;
+    let game = games.get(game_id.as_deref())?;
+    game.to_svg(&options.unwrap_or_default()).map_err(CommandError::from)
+}
+
+/// Returns the tree of all played and imported lines, with the current node
+#[tauri::command]
+pub fn get_move_tree(state: State<GameState>, game_id: Option<String>) -> Result<MoveTree, CommandError> {
+    let games = state.lock().map_err(|e| e.to_string())?;
+    let game = games.get(game_id.as_deref())?;
+    Ok(game.move_tree().clone())
+}
+
+/// Sets the comment and NAGs of a move in the tree (an empty path comments the starting position)
+/// and returns the updated tree
+#[tauri::command]
+pub fn annotate_move(
+    state: State<GameState>,
+    game_id: Option<String>,
+    autosave: State<Autosave>,
+    path: Vec<usize>,
+    comment: Option<String>,
+    nags: Option<Vec<u8>>,
+) -> Result<MoveTree, CommandError> {
+    let mut games = state.lock().map_err(|e| e.to_string())?;
+    let game = games.get_mut(game_id.as_deref())?;
+    game.annotate_move(&path, comment, nags.unwrap_or_default())?;
+    save_quietly(&autosave, game_id.as_deref(), game);
+    Ok(game.move_tree().clone())
+}
+
+/// Jumps to a node of the move tree (a path of child indices; empty for the starting position).
+/// Moves made from there are added as variations instead of replacing the main line.
+#[tauri::command]
+pub fn goto_variation(
+    state: State<GameState>,
+    game_id: Option<String>,
+    autosave: State<Autosave>,
+    path: Vec<usize>,
+) -> Result<BoardState, CommandError> {
+    let mut games = state.lock().map_err(|e| e.to_string())?;
+    let game = games.get_mut(game_id.as_deref())?;
+    game.goto_variation(&path)?;
+    save_quietly(&autosave, game_id.as_deref(), game);
+    Ok(game.board_state())
+}
+
+/// Makes the variation at `path` the main line from its branch point and returns the updated tree
+#[tauri::command]
+pub fn promote_variation(
+    state: State<GameState>,
+    game_id: Option<String>,
+    autosave: State<Autosave>,
+    path: Vec<usize>,
+) -> Result<MoveTree, CommandError> {
+    let mut games = state.lock().map_err(|e| e.to_string())?;
+    let game = games.get_mut(game_id.as_deref())?;
+    game.promote_variation(&path)?;
+    save_quietly(&autosave, game_id.as_deref(), game);
+    Ok(game.move_tree().clone())
+}
+
+/// Deletes the move at `path` and every move after it, going back to the branch point if the
+/// current position was in the deleted line, and returns the updated tree
+#[tauri::command]
+pub fn delete_variation(
+    state: State<GameState>,
+    game_id: Option<String>,
+    autosave: State<Autosave>,
+    path: Vec<usize>,
+) -> Result<MoveTree, CommandError> {
+    let mut games = state.lock().map_err(|e| e.to_string())?;
+    let game = games.get_mut(game_id.as_deref())?;
+    game.delete_variation(&path)?;
+    save_quietly(&autosave, game_id.as_deref(), game);
+    Ok(game.move_tree().clone())
+}
+
+/// Loads a position from an EPD record
+#[tauri::command]
+pub fn load_epd(
+    state: State<GameState>,
+    game_id: Option<String>,
+    autosave: State<Autosave>,
+    settings: State<SettingsState>,
+    epd: String,
+) -> Result<BoardState, CommandError> {
+    let mut new_game = ChessGame::from_epd(&epd)?;
+    new_game.set_draw_rules(settings.lock().map_err(|e| e.to_string())?.draw_rules);
+    let position = new_game.board_state();
+
+    let mut games = state.lock().map_err(|e| e.to_string())?;
+    let game = games.get_mut(game_id.as_deref())?;
+    *game = new_game;
+    save_quietly(&autosave, game_id.as_deref(), game);
+    Ok(position)
+}
+
+/// Returns the current position as an EPD record with `hmvc` and `fmvn` operations
+#[tauri::command]
+pub fn get_epd(state: State<GameState>, game_id: Option<String>) -> Result<String, CommandError> {
+    let games = state.lock().map_err(|e| e.to_string())?;
+    let game = games.get(game_id.as_deref())?;
+    Ok(EpdRecord::from_position(game.get_board_state()).to_string())
+}
+
+/// Evaluates and searches every EPD record (one per line; blank lines and `#` comments
+/// are skipped), reporting whether the engine's move satisfies `bm`/`am`. Records are
+/// split into one engine pool job per engine thread.
+#[tauri::command]
+pub async fn analyze_epd(
+    settings: State<'_, SettingsState>,
+    pool: State<'_, EnginePool>,
+    records: String,
+    depth: Option<u8>,
+) -> Result<Vec<EpdAnalysis>, CommandError> {
+    if let Some(depth) = depth {
+        check_depth(depth, MAX_DEPTH)?;
+    }
+    let options = settings.lock().map_err(|e| e.to_string())?.engine.clone();
+    let limits = options.apply(depth.map(SearchLimits::depth).unwrap_or_default());
+
+    let lines: Vec<String> = records
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(str::to_string)
+        .collect();
+    let chunk_size = lines.len().div_ceil((options.threads as usize).max(1)).max(1);
+
+    // Queue every chunk before waiting, so free workers take them up side by side
+    let jobs: Vec<_> = lines
+        .chunks(chunk_size)
+        .map(|chunk| {
+            let chunk = chunk.to_vec();
+            pool.run(move || {
+                chunk
+                    .iter()
+                    .map(|line| EpdRecord::parse(line).and_then(|record| record.analyze(limits)))
+                    .collect::<Vec<_>>()
+            })
+        })
+        .collect();
+
+    let mut analyses = Vec::with_capacity(lines.len());
+    for job in jobs {
+        for analysis in job.await? {
+            analyses.push(analysis?);
+        }
+    }
+    Ok(analyses)
+}
+
+/// Looks up the current position in a Polyglot (.bin) opening book and returns
+/// the book moves, most heavily weighted first
+#[tauri::command]
+pub fn get_book_moves(state: State<GameState>, game_id: Option<String>, path: String) -> Result<Vec<BookMove>, CommandError> {
+    let mut book = File::open(&path)?;
+    let games = state.lock().map_err(|e| e.to_string())?;
+    let game = games.get(game_id.as_deref())?;
+    probe_book(&mut book, game.get_board_state()).map_err(CommandError::from)
+}
+
+/// Queues Lichess puzzles (database CSV rows or API JSON) and returns how many are waiting
+#[tauri::command]
+pub fn load_puzzles(puzzles: State<PuzzleState>, data: String) -> Result<usize, CommandError> {
+    let parsed = parse_puzzles(&data)?;
+    let mut session = puzzles.lock().map_err(|e| e.to_string())?;
+    session.load(parsed);
+    Ok(session.remaining())
+}
+
+/// Sets up the next queued puzzle on the board; returns None when the queue is empty
+#[tauri::command]
+pub fn next_puzzle(
+    state: State<GameState>,
+    game_id: Option<String>,
+    puzzles: State<PuzzleState>,
+) -> Result<Option<Puzzle>, CommandError> {
+    let mut games = state.lock().map_err(|e| e.to_string())?;
+    let game = games.get_mut(game_id.as_deref())?;
+    let mut session = puzzles.lock().map_err(|e| e.to_string())?;
+    session.next_puzzle(game).map_err(CommandError::from)
+}
+
+/// Checks a move (UCI notation) against the current puzzle's solution. Correct moves
+/// are played along with the opponent's reply.
+#[tauri::command]
+pub fn submit_puzzle_move(
+    state: State<GameState>,
+    game_id: Option<String>,
+    puzzles: State<PuzzleState>,
+    uci: String,
+) -> Result<PuzzleOutcome, CommandError> {
+    let mut games = state.lock().map_err(|e| e.to_string())?;
+    let game = games.get_mut(game_id.as_deref())?;
+    let mut session = puzzles.lock().map_err(|e| e.to_string())?;
+    session.submit_move(game, &uci).map_err(CommandError::from)
+}
+
+/// Starts a puzzle rush lasting `seconds`: the built-in and loaded puzzles that pass
+/// `filter` are served by `next_puzzle` in random order, getting harder as it goes, until
+/// time runs out or three are missed
+#[tauri::command]
+pub fn start_puzzle_rush(
+    puzzles: State<PuzzleState>,
+    filter: PuzzleFilter,
+    seconds: u64,
+) -> Result<PuzzleRushStatus, CommandError> {
+    let mut session = puzzles.lock().map_err(|e| e.to_string())?;
+    session
+        .start_rush(&filter, Duration::from_secs(seconds))
+        .map_err(CommandError::from)
+}
+
+/// Returns the puzzle rush's remaining time and score, or None when no rush was started
+#[tauri::command]
+pub fn get_puzzle_rush_status(puzzles: State<PuzzleState>) -> Result<Option<PuzzleRushStatus>, CommandError> {
+    let session = puzzles.lock().map_err(|e| e.to_string())?;
+    Ok(session.rush_status())
+}
+
+/// Returns how many puzzles were attempted, solved and failed this session
+#[tauri::command]
+pub fn get_puzzle_stats(puzzles: State<PuzzleState>) -> Result<PuzzleStats, CommandError> {
+    let session = puzzles.lock().map_err(|e| e.to_string())?;
+    Ok(session.stats())
+}
+
+/// Starts an external UCI engine (e.g. Stockfish) to use for analysis, or goes back to the
+/// built-in search when no path is given. Returns the name the engine reports.
+#[tauri::command]
+pub fn set_external_engine(engine: State<EngineState>, path: Option<String>) -> Result<Option<String>, CommandError> {
+    let external = match path {
+        Some(path) => Some(ExternalEngine::spawn(&path)?),
+        None => None,
+    };
+    let name = external.as_ref().and_then(|external| external.name().map(str::to_string));
+
+    let mut current = engine.lock().map_err(|e| e.to_string())?;
+    *current = external;
+    Ok(name)
+}
+
+/// Searches the current position with the external engine if one is set, otherwise with
+/// the built-in search under the engine options (playing from the book first when it is
+/// on). Scores are from the side to move's point of view.
+#[tauri::command]
+pub async fn search_position(
+    state: State<'_, GameState>,
+    game_id: Option<String>,
+    engine: State<'_, EngineState>,
+    settings: State<'_, SettingsState>,
+    pool: State<'_, EnginePool>,
+    depth: Option<u8>,
+    movetime_ms: Option<u64>,
+) -> Result<SearchResult, CommandError> {
+    if let Some(depth) = depth {
+        check_depth(depth, MAX_DEPTH)?;
+    }
+    let mut limits = depth.map(SearchLimits::depth).unwrap_or_default();
+    limits.movetime_ms = movetime_ms;
+
+    let position = {
+        let games = state.lock().map_err(|e| e.to_string())?;
+        let game = games.get(game_id.as_deref())?;
+        game.get_board_state().clone()
+    };
+
+    let engine = Arc::clone(&engine);
+    let options = settings.lock().map_err(|e| e.to_string())?.engine.clone();
+    pool.run(move || {
+        let mut external = engine.lock().map_err(|e| e.to_string())?;
+        if let Some(external) = external.as_mut() {
+            return external.search(&position, limits).map_err(CommandError::from);
+        }
+
+        if let Some(book) = options.book() {
+            if let Some(result) = book_move(book, &position)? {
+                return Ok(result);
+            }
+        }
+        Ok(search(&position, options.apply(limits)))
+    })
+    .await?
+}
+
+/// Suggests a move for the side to move from a short search with the built-in engine.
+/// `strength` is a skill level from 0 to 20 (the engine's own by default). With
+/// `progressive`, the first call names only the piece to move and a second call in the
+/// same position reveals the move.
+#[tauri::command]
+pub async fn get_hint(
+    state: State<'_, GameState>,
+    game_id: Option<String>,
+    settings: State<'_, SettingsState>,
+    pool: State<'_, EnginePool>,
+    strength: Option<u8>,
+    progressive: Option<bool>,
+) -> Result<Hint, CommandError> {
+    let mut options = settings.lock().map_err(|e| e.to_string())?.engine.clone();
+    if let Some(level) = strength {
+        options.set(EngineOption::SkillLevel(level))?;
+    }
+
+    // Search on a copy, then bring a half-given progressive hint back to the game
+    let mut copy = state.lock().map_err(|e| e.to_string())?.get(game_id.as_deref())?.clone();
+    let (hint, copy) = pool
+        .run(move || {
+            let hint = copy.hint(options.apply(hint_limits()), progressive.unwrap_or(false));
+            (hint, copy)
+        })
+        .await?;
+
+    let mut games = state.lock().map_err(|e| e.to_string())?;
+    games.get_mut(game_id.as_deref())?.keep_hint_from(copy);
+    hint.map_err(CommandError::from)
+}
+
+/// Sets one option of the built-in engine, saving it with the other settings, and
+/// returns all of them
+#[tauri::command]
+pub fn set_engine_option(
+    settings: State<SettingsState>,
+    store: State<SettingsStore>,
+    option: EngineOption,
+) -> Result<EngineOptions, CommandError> {
+    let mut settings = settings.lock().map_err(|e| e.to_string())?;
+    let previous = settings.engine.clone();
+    settings.engine.set(option)?;
+    settings.engine.resize_tables(Some(&previous));
+    store.save(&settings)?;
+    Ok(settings.engine.clone())
+}
+
+/// Empties the transposition table and evaluation caches, so the engine's next search
+/// starts fresh without changing their sizes
+#[tauri::command]
+pub fn clear_engine_hash() {
+    clear_transposition_table();
+    Evaluator::clear_caches();
+}
+
+/// Returns the built-in engine's name, version, the options it supports and their values
+#[tauri::command]
+pub fn get_engine_info(settings: State<SettingsState>) -> Result<EngineInfo, CommandError> {
+    let settings = settings.lock().map_err(|e| e.to_string())?;
+    Ok(EngineInfo::new(settings.engine.clone()))
+}
+
+/// Returns the saved preferences: default time control, variant, draw rules,
+/// auto-queen and engine options
+#[tauri::command]
+pub fn get_settings(settings: State<SettingsState>) -> Result<Settings, CommandError> {
+    Ok(settings.lock().map_err(|e| e.to_string())?.clone())
+}
+
+/// Replaces the preferences, saving them to disk so they survive a restart
+#[tauri::command]
+pub fn update_settings(
+    settings: State<SettingsState>,
+    store: State<SettingsStore>,
+    new_settings: Settings,
+) -> Result<Settings, CommandError> {
+    new_settings.engine.validate()?;
+    store.save(&new_settings)?;
+
+    let mut settings = settings.lock().map_err(|e| e.to_string())?;
+    new_settings.engine.resize_tables(Some(&settings.engine));
+    *settings = new_settings;
+    Ok(settings.clone())
+}
+
+/// Names the opening of the current game, if it has reached a known opening position
+#[tauri::command]
+pub fn get_opening(state: State<GameState>, game_id: Option<String>) -> Result<Option<Opening>, CommandError> {
+    let games = state.lock().map_err(|e| e.to_string())?;
+    let game = games.get(game_id.as_deref())?;
+    Ok(game.opening())
+}
+
+/// Saves the whole game, including history and repetition data, as a JSON document
+#[tauri::command]
+pub fn save_game(state: State<GameState>, game_id: Option<String>) -> Result<String, CommandError> {
+    let games = state.lock().map_err(|e| e.to_string())?;
+    let game = games.get(game_id.as_deref())?;
+    game.to_json().map_err(CommandError::from)
+}
+
+/// Restores a game saved with `save_game`
+#[tauri::command]
+pub fn load_game(
+    state: State<GameState>,
+    game_id: Option<String>,
+    autosave: State<Autosave>,
+    json: String,
+) -> Result<BoardState, CommandError> {
+    let new_game = ChessGame::from_json(&json)?;
+    let position = new_game.board_state();
+
+    let mut games = state.lock().map_err(|e| e.to_string())?;
+    let game = games.get_mut(game_id.as_deref())?;
+    *game = new_game;
+    save_quietly(&autosave, game_id.as_deref(), game);
+    Ok(position)
+}
 
-    let mut game = state.lock().map_err(|e| e.to_string())?;
+/// Stores the game in the archive and returns its archive ID; with `archive_id` the
+/// stored copy is updated instead. `tags` ("White", "Event", ...) replace the stored ones.
+#[tauri::command]
+pub fn archive_game(
+    state: State<GameState>,
+    archive: State<ArchiveState>,
+    game_id: Option<String>,
+    archive_id: Option<i64>,
+    tags: Option<Vec<(String, String)>>,
+) -> Result<i64, CommandError> {
+    let games = state.lock().map_err(|e| e.to_string())?;
+    let game = games.get(game_id.as_deref())?;
+    with_archive(&archive, |archive| archive.store(game, archive_id, tags.as_deref()))
+}
+
+/// Lists the archived games, most recently updated first
+#[tauri::command]
+pub fn list_archived_games(archive: State<ArchiveState>) -> Result<Vec<ArchivedGame>, CommandError> {
+    with_archive(&archive, GameArchive::list)
+}
+
+/// Replaces the game with one from the archive, history and variations included
+#[tauri::command]
+pub fn load_archived_game(
+    state: State<GameState>,
+    archive: State<ArchiveState>,
+    game_id: Option<String>,
+    autosave: State<Autosave>,
+    archive_id: i64,
+) -> Result<BoardState, CommandError> {
+    let new_game = with_archive(&archive, |archive| archive.load(archive_id))?;
+    let position = new_game.board_state();
+
+    let mut games = state.lock().map_err(|e| e.to_string())?;
+    let game = games.get_mut(game_id.as_deref())?;
     *game = new_game;
+    save_quietly(&autosave, game_id.as_deref(), game);
     Ok(position)
 }
 
+/// Finds the archived games that reach the position in `fen`, by any move order, with
+/// the ply it first occurs at and the move played from it. Without `variant` the FEN's
+/// own fields tell which variant it is.
+#[tauri::command]
+pub fn find_games_with_position(
+    archive: State<ArchiveState>,
+    fen: String,
+    variant: Option<Variant>,
+) -> Result<Vec<PositionMatch>, CommandError> {
+    let game = match variant {
+        Some(variant) => ChessGame::from_fen_for(&fen, variant)?,
+        None => ChessGame::from_fen(&fen)?,
+    };
+    with_archive(&archive, |archive| archive.find_position(game.get_board_state()))
+}
+
+#[tauri::command]
+pub fn delete_archived_game(archive: State<ArchiveState>, archive_id: i64) -> Result<(), CommandError> {
+    with_archive(&archive, |archive| archive.delete(archive_id))
+}
+
+/// Runs `f` on the archive, or returns the error that kept it from opening at startup
+fn with_archive<T>(
+    archive: &ArchiveState,
+    f: impl FnOnce(&GameArchive) -> Result<T, ArchiveError>,
+) -> Result<T, CommandError> {
+    let archive = archive.lock().map_err(|e| e.to_string())?;
+    let archive = archive.as_ref().map_err(CommandError::clone)?;
+    Ok(f(archive)?)
+}
+
 /// Returns the FEN string representation of the current position
 #[tauri::command]
-pub fn get_fen(state: State<GameState>) -> Result<String, String> {
-    let game = state.lock().map_err(|e| e.to_string())?;
+pub fn get_fen(state: State<GameState>, game_id: Option<String>) -> Result<String, CommandError> {
+    let games = state.lock().map_err(|e| e.to_string())?;
+    let game = games.get(game_id.as_deref())?;
     Ok(game.to_fen())
 }
 
+/// Returns the moves played so far in SAN (the default) or figurine notation
+#[tauri::command]
+pub fn get_san_history(
+    state: State<GameState>,
+    game_id: Option<String>,
+    notation: Option<Notation>,
+) -> Result<Vec<String>, CommandError> {
+    let games = state.lock().map_err(|e| e.to_string())?;
+    let game = games.get(game_id.as_deref())?;
+    Ok(game.san_history_in(notation.unwrap_or_default()))
+}
+
+/// Returns the moves played so far as numbered rows of White and Black moves, each with
+/// the FEN after it, for the move-list panel
+#[tauri::command]
+pub fn get_move_list(
+    state: State<GameState>,
+    game_id: Option<String>,
+    notation: Option<Notation>,
+) -> Result<Vec<MoveListEntry>, CommandError> {
+    let games = state.lock().map_err(|e| e.to_string())?;
+    let game = games.get(game_id.as_deref())?;
+    Ok(game.move_list(notation.unwrap_or_default()))
+}
+
+/// Returns every move of the current line with its UCI and SAN, captured piece,
+/// resulting FEN, check flags and the time it was played
+#[tauri::command]
+pub fn get_move_history(state: State<GameState>, game_id: Option<String>) -> Result<Vec<MoveRecord>, CommandError> {
+    let games = state.lock().map_err(|e| e.to_string())?;
+    let game = games.get(game_id.as_deref())?;
+    Ok(game.move_records())
+}
+
+/// Returns the pieces each side has captured, for the material trays beside the board
+#[tauri::command]
+pub fn get_captured_pieces(state: State<GameState>, game_id: Option<String>) -> Result<CapturedPieces, CommandError> {
+    let games = state.lock().map_err(|e| e.to_string())?;
+    let game = games.get(game_id.as_deref())?;
+    Ok(game.captured_pieces())
+}
+
+/// Returns the last move played with the rook squares of a castle and the square of a
+/// pawn taken en passant, so the board can highlight everything that changed
+#[tauri::command]
+pub fn get_last_move(state: State<GameState>, game_id: Option<String>) -> Result<Option<LastMove>, CommandError> {
+    let games = state.lock().map_err(|e| e.to_string())?;
+    let game = games.get(game_id.as_deref())?;
+    Ok(game.last_move())
+}
+
 /// Analyzes a specific move and returns detailed information
 #[tauri::command]
 pub fn analyze_move(
     state: State<GameState>,
+    game_id: Option<String>,
     from: String,
     to: String,
     promotion: Option<String>,
-) -> Result<MoveAnalysis, String> {
-    let from_square = Square::from_algebraic(&from).map_err(|e| e.to_string())?;
-    let to_square = Square::from_algebraic(&to).map_err(|e| e.to_string())?;
+) -> Result<MoveAnalysis, CommandError> {
+    let from_square = Square::from_algebraic(&from)?;
+    let to_square = Square::from_algebraic(&to)?;
 
     let promotion_piece = match promotion.as_deref() {
         Some(p) => Some(parse_promotion(p)?),
         None => None,
     };
 
-    let game = state.lock().map_err(|e| e.to_string())?;
+    let games = state.lock().map_err(|e| e.to_string())?;
+    let game = games.get(game_id.as_deref())?;
     let position = game.get_board_state();
 
     // Find the matching move
@@ -136,36 +1300,273 @@ pub fn analyze_move(
                 && m.to == to_square
                 && m.promotion == promotion_piece
         })
-        .ok_or_else(|| format!("Move not found: {} to {}", from, to))?;
+        .ok_or_else(|| CommandError::InvalidMove {
+            details: format!("Move not found: {} to {}", from, to),
+            mv: Some(format!("{}{}", from, to)),
+        })?;
 
     Ok(MoveAnalysis::analyze(&chess_move, position))
 }
 
-/// Analyzes all legal moves in the current position
+/// Analyzes all legal moves in the current position, biggest material gains first.
+/// The moves are analyzed on a copy of the position so the lock is not held meanwhile.
 #[tauri::command]
-pub fn analyze_all_legal_moves(state: State<GameState>) -> Result<Vec<MoveAnalysis>, String> {
-    let game = state.lock().map_err(|e| e.to_string())?;
-    let position = game.get_board_state();
-    Ok(analyze_all_moves(position))
+pub async fn analyze_all_legal_moves(
+    state: State<'_, GameState>,
+    game_id: Option<String>,
+    pool: State<'_, EnginePool>,
+) -> Result<Vec<MoveAnalysis>, CommandError> {
+    let position = state.lock().map_err(|e| e.to_string())?.get(game_id.as_deref())?.get_board_state().clone();
+    Ok(pool.run(move || analyze_all_moves(&position)).await?)
+}
+
+/// Returns the forks, pins, skewers and double checks on the board right now
+#[tauri::command]
+pub fn get_tactical_motifs(state: State<GameState>, game_id: Option<String>) -> Result<Vec<Motif>, CommandError> {
+    let games = state.lock().map_err(|e| e.to_string())?;
+    let game = games.get(game_id.as_deref())?;
+    Ok(game.tactical_motifs())
+}
+
+/// Counts each side's material by piece type, with the difference in centipawns
+/// (positive when White is ahead), for the material bar
+#[tauri::command]
+pub fn get_material_balance(state: State<GameState>, game_id: Option<String>) -> Result<MaterialBalance, CommandError> {
+    let games = state.lock().map_err(|e| e.to_string())?;
+    let game = games.get(game_id.as_deref())?;
+    Ok(material_balance(game.get_board_state()))
+}
+
+/// The squares `color` can see in fog of war, indexed like the board (0 = a1, 63 = h8),
+/// so the board can hide the rest
+#[tauri::command]
+pub fn get_visible_squares(state: State<GameState>, game_id: Option<String>, color: Color) -> Result<Vec<bool>, CommandError> {
+    let games = state.lock().map_err(|e| e.to_string())?;
+    let game = games.get(game_id.as_deref())?;
+    Ok(visibility_mask(game.get_board_state(), color))
 }
 
 /// Evaluates the current position and returns a score in centipawns
-/// Positive = White advantage, Negative = Black advantage
+/// By default Positive = White advantage, Negative = Black advantage;
+/// pass `SideToMove` as the perspective to get the score for the player to move
 #[tauri::command]
-pub fn evaluate_position(state: State<GameState>) -> Result<i32, String> {
-    let game = state.lock().map_err(|e| e.to_string())?;
+pub fn evaluate_position(
+    state: State<GameState>,
+    game_id: Option<String>,
+    perspective: Option<EvalPerspective>,
+) -> Result<i32, CommandError> {
+    let games = state.lock().map_err(|e| e.to_string())?;
+    let game = games.get(game_id.as_deref())?;
     let position = game.get_board_state();
-    Ok(Evaluator::evaluate(position))
+    Ok(Evaluator::evaluate_from(position, perspective.unwrap_or_default()))
+}
+
+/// Evaluates an arbitrary FEN without touching the current game
+#[tauri::command]
+pub fn evaluate_fen(fen: String, perspective: Option<EvalPerspective>) -> Result<i32, CommandError> {
+    let game = ChessGame::from_fen(&fen)?;
+    Ok(Evaluator::evaluate_from(game.get_board_state(), perspective.unwrap_or_default()))
+}
+
+/// Returns the pieces added, removed and moved between two positions, for animating
+/// the board. Without `to_fen` the current position is the target.
+#[tauri::command]
+pub fn get_position_diff(
+    state: State<GameState>,
+    game_id: Option<String>,
+    from_fen: String,
+    to_fen: Option<String>,
+) -> Result<PositionDiff, CommandError> {
+    let from = ChessGame::from_fen(&from_fen)?;
+    let to = match to_fen {
+        Some(fen) => ChessGame::from_fen(&fen)?.get_board_state().clone(),
+        None => state
+            .lock()
+            .map_err(|e| e.to_string())?
+            .get(game_id.as_deref())?
+            .get_board_state()
+            .clone(),
+    };
+    Ok(diff_positions(from.get_board_state(), &to))
+}
+
+/// Counts the leaf nodes of the legal move tree `depth` plies deep, for checking move
+/// generation against published perft results. Without `fen` the board's position is used.
+/// `hashed` reuses the counts of transposed subtrees, which makes deeper runs practical.
+#[tauri::command]
+pub async fn perft(
+    state: State<'_, GameState>,
+    game_id: Option<String>,
+    pool: State<'_, EnginePool>,
+    depth: u8,
+    fen: Option<String>,
+    hashed: Option<bool>,
+) -> Result<u64, CommandError> {
+    check_depth(depth, MAX_PERFT_DEPTH)?;
+    let position = position_or_fen(&state, game_id.as_deref(), fen)?;
+    let nodes = pool.run(move || {
+        if hashed.unwrap_or(false) {
+            perft_hashed(&position, depth, PERFT_HASH_MEGABYTES)
+        } else {
+            count_perft(&position, depth)
+        }
+    });
+    Ok(nodes.await?)
+}
+
+/// Perft split by root move, to find which move's subtree disagrees with a reference
+#[tauri::command]
+pub async fn perft_divide(
+    state: State<'_, GameState>,
+    game_id: Option<String>,
+    pool: State<'_, EnginePool>,
+    depth: u8,
+    fen: Option<String>,
+) -> Result<PerftDivide, CommandError> {
+    check_depth(depth, MAX_PERFT_DEPTH)?;
+    let position = position_or_fen(&state, game_id.as_deref(), fen)?;
+    Ok(pool.run(move || count_perft_divide(&position, depth)).await?)
+}
+
+/// Rejects a requested depth above `max` before any work is queued
+fn check_depth(depth: u8, max: u8) -> Result<(), CommandError> {
+    if depth > max {
+        return Err(CommandError::InvalidOption {
+            name: "depth".to_string(),
+            details: format!("{} is deeper than the maximum of {}", depth, max),
+        });
+    }
+    Ok(())
+}
+
+/// The position in `fen`, or a copy of the board's so the lock is not held while counting
+fn position_or_fen(state: &GameState, game_id: Option<&str>, fen: Option<String>) -> Result<Position, CommandError> {
+    match fen {
+        Some(fen) => Ok(ChessGame::from_fen(&fen)?.get_board_state().clone()),
+        None => Ok(state.lock().map_err(|e| e.to_string())?.get(game_id)?.get_board_state().clone()),
+    }
+}
+
+/// Converts a centipawn score (White's perspective) into win/draw/loss probabilities.
+/// When no score is given, the current position is evaluated first.
+#[tauri::command]
+pub fn get_win_probability(
+    state: State<GameState>,
+    game_id: Option<String>,
+    centipawns: Option<i32>,
+) -> Result<WinProbability, CommandError> {
+    let centipawns = match centipawns {
+        Some(score) => score,
+        None => {
+            let games = state.lock().map_err(|e| e.to_string())?;
+            let game = games.get(game_id.as_deref())?;
+            Evaluator::evaluate(game.get_board_state())
+        }
+    };
+    Ok(WinProbability::from_centipawns(centipawns))
+}
+
+/// Returns the piece-square tables currently used by the evaluator
+#[tauri::command]
+pub fn get_piece_square_tables() -> PieceSquareTables {
+    Evaluator::piece_square_tables()
+}
+
+/// Replaces the evaluator's piece-square tables with either a built-in
+/// personality or a fully custom set, returning the tables now in use
+#[tauri::command]
+pub fn set_piece_square_tables(
+    personality: Option<EvalPersonality>,
+    tables: Option<PieceSquareTables>,
+) -> Result<PieceSquareTables, CommandError> {
+    let tables = match (personality, tables) {
+        (Some(personality), None) => PieceSquareTables::for_personality(personality),
+        (None, Some(tables)) => tables,
+        _ => return Err("Provide exactly one of personality or tables".to_string().into()),
+    };
+
+    Evaluator::set_piece_square_tables(tables.clone());
+    Ok(tables)
 }
 
 /// Helper function to parse promotion string to Piece enum
 /// Accepts case-insensitive input (e.g., "queen", "Queen", "QUEEN" all work)
-fn parse_promotion(s: &str) -> Result<Piece, String> {
+fn parse_promotion(s: &str) -> Result<Piece, CommandError> {
     match s.to_ascii_lowercase().as_str() {
         "queen" => Ok(Piece::Queen),
         "rook" => Ok(Piece::Rook),
         "bishop" => Ok(Piece::Bishop),
         "knight" => Ok(Piece::Knight),
-        _ => Err(format!("Invalid promotion piece: {}. Must be Queen, Rook, Bishop, or Knight", s)),
+        _ => Err(CommandError::InvalidMove {
+            details: format!("Invalid promotion piece: {}. Must be Queen, Rook, Bishop, or Knight", s),
+            mv: None,
+        }),
+    }
+}
+
+/// The legal move from `from` to `to`, promoting to a queen when no piece was chosen
+/// and auto-queen is on
+fn resolve_move(
+    game: &ChessGame,
+    from: &str,
+    to: &str,
+    promotion: Option<String>,
+    auto_queen: bool,
+) -> Result<Move, CommandError> {
+    let from_square = Square::from_algebraic(from)?;
+    let to_square = Square::from_algebraic(to)?;
+
+    let promotion_piece = match promotion.as_deref() {
+        Some(p) => Some(parse_promotion(p)?),
+        None => None,
+    };
+
+    // Get all legal moves and find the matching one with correct flags
+    game.get_legal_moves()
+        .into_iter()
+        .find(|m| {
+            m.from == from_square
+                && m.to == to_square
+                && (m.promotion == promotion_piece
+                    || (auto_queen && promotion_piece.is_none() && m.promotion == Some(Piece::Queen)))
+        })
+        .ok_or_else(|| CommandError::InvalidMove {
+            details: format!(
+                "Illegal move: {} to {}{}",
+                from,
+                to,
+                promotion.as_ref().map(|p| format!(" (promotion: {})", p)).unwrap_or_default()
+            ),
+            mv: Some(format!("{}{}", from, to)),
+        })
+}
+
+/// The book's most heavily weighted move as a search result (scored as even), if the
+/// position is in the book
+fn book_move(path: &str, position: &Position) -> Result<Option<SearchResult>, CommandError> {
+    let moves = probe_book(&mut File::open(path)?, position)?;
+    Ok(moves.first().map(|book_move| SearchResult {
+        best_move: Some(book_move.mv),
+        score: 0,
+        depth: 0,
+        nodes: 0,
+        pv: vec![book_move.mv],
+    }))
+}
+
+/// Plays or discards the premove waiting for the side now to move and tells the
+/// frontend with a [`PREMOVE_EVENT`]
+fn run_premove(app: &AppHandle, game_id: Option<&str>, game: &mut ChessGame) {
+    if let Some(outcome) = game.play_premove() {
+        let event = PremoveEvent { game_id: game_id.unwrap_or(MAIN_GAME_ID).to_string(), outcome };
+        let _ = app.emit(PREMOVE_EVENT, event);
+    }
+}
+
+/// Autosave after a change to the game. Only the main board is autosaved, and saving
+/// is best effort: a failed write must not fail the move that triggered it.
+fn save_quietly(autosave: &Autosave, game_id: Option<&str>, game: &ChessGame) {
+    if game_id.unwrap_or(MAIN_GAME_ID) == MAIN_GAME_ID {
+        let _ = autosave.save(game);
     }
 }
@@ -0,0 +1,158 @@
+use std::future::Future;
+use std::num::NonZeroUsize;
+use std::panic::{self, AssertUnwindSafe};
+use std::pin::Pin;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Mutex, PoisonError};
+use std::task::{Context, Poll, Waker};
+use std::thread;
+
+type Job = Box<dyn FnOnce() + Send>;
+
+/// Threads that run searches and analysis, so long engine work never ties up the
+/// command threads or holds the game lock while it runs. Jobs queue up in order
+/// and go to whichever worker is free.
+pub struct EnginePool {
+    queue: Sender<Job>,
+}
+
+impl EnginePool {
+    pub fn new(workers: usize) -> Self {
+        let (queue, jobs) = mpsc::channel::<Job>();
+        let jobs = Arc::new(Mutex::new(jobs));
+        for index in 0..workers.max(1) {
+            let jobs = Arc::clone(&jobs);
+            thread::Builder::new()
+                .name(format!("engine-worker-{}", index))
+                .spawn(move || work(&jobs))
+                .expect("failed to start engine worker thread");
+        }
+        EnginePool { queue }
+    }
+
+    /// Queues `job` and returns its result once a worker has run it. A job that
+    /// panics reports an error instead of taking its worker down.
+    pub fn run<T, F>(&self, job: F) -> JobHandle<T>
+    where
+        T: Send + 'static,
+        F: FnOnce() -> T + Send + 'static,
+    {
+        let handle = JobHandle(Arc::new(Mutex::new(JobSlot { result: None, waker: None })));
+        let slot = Arc::clone(&handle.0);
+        let queued = self.queue.send(Box::new(move || {
+            let result = panic::catch_unwind(AssertUnwindSafe(job)).map_err(|_| "Engine job panicked".to_string());
+            slot.lock().unwrap_or_else(PoisonError::into_inner).finish(result);
+        }));
+
+        if queued.is_err() {
+            handle.0
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .finish(Err("Engine workers have stopped".to_string()));
+        }
+        handle
+    }
+}
+
+impl Default for EnginePool {
+    /// One worker per core
+    fn default() -> Self {
+        Self::new(thread::available_parallelism().map_or(1, NonZeroUsize::get))
+    }
+}
+
+fn work(jobs: &Mutex<Receiver<Job>>) {
+    loop {
+        // The lock is only held while waiting, so the next free worker takes the next job
+        let job = jobs.lock().unwrap_or_else(PoisonError::into_inner).recv();
+        match job {
+            Ok(job) => job(),
+            Err(_) => return,
+        }
+    }
+}
+
+struct JobSlot<T> {
+    result: Option<Result<T, String>>,
+    waker: Option<Waker>,
+}
+
+impl<T> JobSlot<T> {
+    fn finish(&mut self, result: Result<T, String>) {
+        self.result = Some(result);
+        if let Some(waker) = self.waker.take() {
+            waker.wake();
+        }
+    }
+}
+
+/// The result of a job queued on the [`EnginePool`], ready once a worker has run it
+pub struct JobHandle<T>(Arc<Mutex<JobSlot<T>>>);
+
+impl<T> Future for JobHandle<T> {
+    type Output = Result<T, String>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let mut slot = self.0.lock().unwrap_or_else(PoisonError::into_inner);
+        match slot.result.take() {
+            Some(result) => Poll::Ready(result),
+            None => {
+                slot.waker = Some(cx.waker().clone());
+                Poll::Pending
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::mpsc::channel;
+    use std::task::Wake;
+    use std::thread::Thread;
+
+    struct ThreadWaker(Thread);
+
+    impl Wake for ThreadWaker {
+        fn wake(self: Arc<Self>) {
+            self.0.unpark();
+        }
+    }
+
+    fn block_on<F: Future + Unpin>(mut future: F) -> F::Output {
+        let waker = Waker::from(Arc::new(ThreadWaker(thread::current())));
+        let mut cx = Context::from_waker(&waker);
+        loop {
+            if let Poll::Ready(output) = Pin::new(&mut future).poll(&mut cx) {
+                return output;
+            }
+            thread::park();
+        }
+    }
+
+    #[test]
+    fn test_jobs_run_on_workers() {
+        let pool = EnginePool::new(2);
+        let caller = thread::current().id();
+        let worker = block_on(pool.run(|| thread::current().id())).unwrap();
+        assert_ne!(worker, caller);
+
+        let (started, wait) = channel();
+        let slow = pool.run(move || {
+            started.send(()).unwrap();
+            thread::sleep(std::time::Duration::from_millis(50));
+            1
+        });
+        wait.recv().unwrap();
+        // The second worker takes this while the first is still busy
+        assert_eq!(block_on(pool.run(|| 2)), Ok(2));
+        assert_eq!(block_on(slow), Ok(1));
+    }
+
+    #[test]
+    fn test_panicking_job_reports_an_error() {
+        let pool = EnginePool::new(1);
+        assert!(block_on(pool.run(|| -> u8 { panic!("search failed") })).is_err());
+        assert_eq!(block_on(pool.run(|| 7)), Ok(7));
+    }
+}
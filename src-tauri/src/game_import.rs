@@ -0,0 +1,204 @@
+use once_cell::sync::Lazy;
+use serde_json::Value;
+use std::time::Duration;
+use ureq::Agent;
+
+const LICHESS_GAME_ID_LENGTH: usize = 8;
+
+/// Longest a request may take, connecting and reading the body included
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Most monthly chess.com archives searched for one game, whatever its end date claims
+const MAX_ARCHIVE_MONTHS: usize = 24;
+
+/// Shared by every download, so a stalled server cannot hold an import forever
+static AGENT: Lazy<Agent> =
+    Lazy::new(|| Agent::config_builder().timeout_global(Some(REQUEST_TIMEOUT)).build().into());
+
+/// Where a game can be downloaded from
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GameSource {
+    Lichess { id: String },
+    ChessCom { id: String, daily: bool },
+}
+
+impl GameSource {
+    /// Recognises Lichess and chess.com game URLs, or a bare Lichess game ID
+    pub fn parse(input: &str) -> Result<GameSource, String> {
+        let input = input.trim();
+        let unsupported = || format!("Not a Lichess or chess.com game: {}", input);
+
+        let without_scheme = input.split_once("://").map_or(input, |(_, rest)| rest);
+        let without_query = without_scheme.split(['?', '#']).next().unwrap_or_default();
+        let mut segments = without_query.split('/').filter(|segment| !segment.is_empty());
+
+        let host = match segments.next() {
+            Some(host) if host.contains('.') => host.trim_start_matches("www."),
+            // A bare ID (8 characters, or 12 with the player suffix)
+            Some(id) if is_lichess_id(id) => return Ok(lichess(id)),
+            _ => return Err(unsupported()),
+        };
+        let path: Vec<&str> = segments.collect();
+
+        match (host, path.as_slice()) {
+            ("lichess.org", [id, ..]) if is_lichess_id(id) => Ok(lichess(id)),
+            ("chess.com", ["game", kind, id, ..] | [kind, "game", id, ..])
+                if (*kind == "live" || *kind == "daily") && id.chars().all(|c| c.is_ascii_digit()) =>
+            {
+                Ok(GameSource::ChessCom {
+                    id: id.to_string(),
+                    daily: *kind == "daily",
+                })
+            }
+            _ => Err(unsupported()),
+        }
+    }
+
+    /// Downloads the game as PGN
+    pub fn fetch_pgn(&self) -> Result<String, String> {
+        match self {
+            GameSource::Lichess { id } => get(
+                &format!("https://lichess.org/game/export/{}?clocks=false&evals=false", id),
+                "application/x-chess-pgn",
+            ),
+            GameSource::ChessCom { id, daily } => {
+                // The public API lists games by player and month, so look those up first
+                let kind = if *daily { "daily" } else { "live" };
+                let game = get_json(&format!("https://www.chess.com/callback/{}/game/{}", kind, id))?;
+                let headers = &game["game"]["pgnHeaders"];
+                let (Some(white), Some(date)) = (headers["White"].as_str(), headers["Date"].as_str()) else {
+                    return Err(format!("chess.com game {} not found", id));
+                };
+                let start = year_month(date).ok_or_else(|| format!("Unexpected chess.com game date: {}", date))?;
+                let end = headers["EndDate"].as_str().and_then(year_month).unwrap_or(start);
+
+                // Games are filed under the month they ended, which for a daily game
+                // can be months after it started
+                for (year, month) in months_between(start, end) {
+                    let archive = get_json(&format!(
+                        "https://api.chess.com/pub/player/{}/games/{}/{:02}",
+                        white.to_lowercase(),
+                        year,
+                        month
+                    ))?;
+                    if let Some(pgn) = find_in_archive(&archive, id) {
+                        return Ok(pgn);
+                    }
+                }
+                Err(format!("chess.com game {} not found in archive", id))
+            }
+        }
+    }
+}
+
+fn lichess(id: &str) -> GameSource {
+    GameSource::Lichess {
+        id: id[..LICHESS_GAME_ID_LENGTH].to_string(),
+    }
+}
+
+fn is_lichess_id(id: &str) -> bool {
+    (id.len() == LICHESS_GAME_ID_LENGTH || id.len() == LICHESS_GAME_ID_LENGTH + 4)
+        && id.chars().all(|c| c.is_ascii_alphanumeric())
+}
+
+/// Year and month of a PGN date ("2024.05.17")
+fn year_month(date: &str) -> Option<(u32, u32)> {
+    let mut parts = date.split('.');
+    let year = parts.next()?.parse().ok()?;
+    let month = parts.next()?.parse().ok().filter(|month| (1..=12).contains(month))?;
+    Some((year, month))
+}
+
+/// Every month from `start` through `end`, or just `start` if `end` is earlier, and
+/// never more than `MAX_ARCHIVE_MONTHS` of them
+fn months_between(start: (u32, u32), end: (u32, u32)) -> Vec<(u32, u32)> {
+    let mut months = vec![start];
+    let (mut year, mut month) = start;
+    while (year, month) < end && months.len() < MAX_ARCHIVE_MONTHS {
+        (year, month) = if month == 12 { (year + 1, 1) } else { (year, month + 1) };
+        months.push((year, month));
+    }
+    months
+}
+
+/// PGN of the game with this ID in a chess.com monthly archive
+fn find_in_archive(archive: &Value, id: &str) -> Option<String> {
+    archive["games"]
+        .as_array()?
+        .iter()
+        .find(|game| {
+            game["url"]
+                .as_str()
+                .is_some_and(|url| url.rsplit('/').next() == Some(id))
+        })
+        .and_then(|game| game["pgn"].as_str().map(str::to_string))
+}
+
+fn get(url: &str, accept: &str) -> Result<String, String> {
+    AGENT
+        .get(url)
+        .header("Accept", accept)
+        .call()
+        .map_err(|e| format!("Request to {} failed: {}", url, e))?
+        .body_mut()
+        .read_to_string()
+        .map_err(|e| e.to_string())
+}
+
+fn get_json(url: &str) -> Result<Value, String> {
+    let body = get(url, "application/json")?;
+    serde_json::from_str(&body).map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_lichess_references() {
+        let expected = Ok(lichess("q7ZvsdUF"));
+        assert_eq!(GameSource::parse("https://lichess.org/q7ZvsdUF"), expected);
+        assert_eq!(GameSource::parse("lichess.org/q7ZvsdUFab12/black#32"), expected);
+        assert_eq!(GameSource::parse(" q7ZvsdUF "), expected);
+        assert!(GameSource::parse("https://lichess.org/study").is_err());
+    }
+
+    #[test]
+    fn test_parse_chess_com_references() {
+        assert_eq!(
+            GameSource::parse("https://www.chess.com/game/live/123456789?username=someone"),
+            Ok(GameSource::ChessCom { id: "123456789".to_string(), daily: false })
+        );
+        assert_eq!(
+            GameSource::parse("https://www.chess.com/daily/game/42"),
+            Ok(GameSource::ChessCom { id: "42".to_string(), daily: true })
+        );
+        assert!(GameSource::parse("https://www.chess.com/member/someone").is_err());
+    }
+
+    #[test]
+    fn test_archive_months_from_start_to_end() {
+        assert_eq!(year_month("2023.11.28"), Some((2023, 11)));
+        assert_eq!(year_month("2023.??.??"), None);
+        assert_eq!(months_between((2023, 11), (2024, 2)), vec![(2023, 11), (2023, 12), (2024, 1), (2024, 2)]);
+        assert_eq!(months_between((2024, 3), (2024, 3)), vec![(2024, 3)]);
+        assert_eq!(months_between((2024, 3), (2023, 1)), vec![(2024, 3)]);
+        let far = months_between((2024, 3), (9999, 12));
+        assert_eq!((far.len(), far.last()), (MAX_ARCHIVE_MONTHS, Some(&(2026, 2))));
+    }
+
+    #[test]
+    fn test_find_in_archive() {
+        let archive: Value = serde_json::from_str(
+            r#"{"games": [
+                {"url": "https://www.chess.com/game/live/1", "pgn": "1. d4 *"},
+                {"url": "https://www.chess.com/game/live/12", "pgn": "1. e4 *"}
+            ]}"#,
+        )
+        .unwrap();
+
+        assert_eq!(find_in_archive(&archive, "12").as_deref(), Some("1. e4 *"));
+        assert_eq!(find_in_archive(&archive, "2"), None);
+    }
+}
@@ -1,17 +1,62 @@
+mod archive;
 mod commands;
+mod command_error;
 mod chess_engine;
+mod autosave;
+mod engine_pool;
+mod game_import;
+mod sessions;
+mod settings;
 
 use std::sync::Mutex as StdMutex;
+use tauri::Manager;
+use archive::GameArchive;
+use autosave::Autosave;
+use command_error::CommandError;
+use engine_pool::EnginePool;
+use settings::SettingsStore;
 pub use chess_engine::ChessGame;
+pub use chess_engine::uci;
+pub use chess_engine::capablanca;
+pub use chess_engine::{perft, search, EvalPerspective, Evaluator, SearchLimits};
 
 #[cfg(any(target_os = "android", target_os = "ios"))]
 use tauri_plugin_haptics;
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
-    let game_state = StdMutex::new(ChessGame::new());
+    let game_state = StdMutex::new(sessions::GameSessions::new());
 
-    let mut builder = tauri::Builder::default().manage(game_state);
+    let mut builder = tauri::Builder::default()
+        .manage(game_state)
+        .manage(commands::EngineState::default())
+        .manage(EnginePool::default())
+        .manage(commands::PuzzleState::default())
+        .setup(|app| {
+            // Pick up the game that was in progress when the app last closed
+            let autosave = Autosave::in_dir(app.path().app_data_dir()?);
+            if let Some(saved) = autosave.load() {
+                let game_state = app.state::<commands::GameState>();
+                let mut games = game_state.lock().unwrap_or_else(|e| e.into_inner());
+                if let Ok(game) = games.get_mut(None) {
+                    *game = saved;
+                }
+            }
+            app.manage(autosave);
+
+            // Stored games, kept in a database next to the autosave. The app still starts
+            // without them; the archive commands then return the error that stopped it opening.
+            let archive = GameArchive::in_dir(app.path().app_data_dir()?).map_err(CommandError::from);
+            app.manage(StdMutex::new(archive));
+
+            // Saved preferences, with the engine's tables sized to match
+            let store = SettingsStore::in_dir(app.path().app_data_dir()?);
+            let settings = store.load();
+            settings.engine.resize_tables(None);
+            app.manage(StdMutex::new(settings));
+            app.manage(store);
+            Ok(())
+        });
 
     // Register shell plugin on desktop platforms only
     #[cfg(not(any(target_os = "android", target_os = "ios")))]
@@ -29,18 +74,107 @@ pub fn run() {
         .invoke_handler(tauri::generate_handler![
             // Chess commands
             commands::new_game,
+            commands::new_game_from_moves,
+            commands::new_game_with_odds,
+            commands::chess960_start_position,
+            commands::discard_autosave,
+            commands::create_game,
+            commands::close_game,
+            commands::list_games,
             commands::get_board_state,
             commands::get_legal_moves,
             commands::get_legal_moves_for_square,
+            commands::get_all_legal_destinations,
+            commands::is_move_legal,
             commands::make_move,
+            commands::make_san_move,
+            commands::make_move_uci,
+            commands::make_moves_uci,
             commands::undo_move,
+            commands::redo_move,
+            commands::set_premove,
+            commands::cancel_premove,
+            commands::get_premove,
+            commands::view_ply,
+            commands::view_first_ply,
+            commands::view_last_ply,
+            commands::get_history_view,
             commands::get_game_status,
+            commands::get_check_info,
+            commands::get_pinned_pieces,
+            commands::get_game_result,
+            commands::set_armageddon,
+            commands::resign_game,
+            commands::forfeit_on_time,
+            commands::agree_draw,
+            commands::offer_draw,
+            commands::accept_draw,
+            commands::decline_draw,
+            commands::get_draw_offer,
+            commands::claim_draw,
+            commands::set_draw_rules,
+            commands::get_draw_rules,
+            commands::validate_position,
             commands::load_fen,
+            commands::load_pgn,
+            commands::validate_pgn,
+            commands::import_game_url,
+            commands::import_pgn_database,
+            commands::get_pgn,
+            commands::export_annotated_pgn,
+            commands::export_svg,
+            commands::get_move_tree,
+            commands::goto_variation,
+            commands::promote_variation,
+            commands::delete_variation,
+            commands::annotate_move,
+            commands::load_epd,
+            commands::get_epd,
+            commands::save_game,
+            commands::load_game,
+            commands::archive_game,
+            commands::list_archived_games,
+            commands::load_archived_game,
+            commands::find_games_with_position,
+            commands::delete_archived_game,
             commands::get_fen,
+            commands::get_san_history,
+            commands::get_move_list,
+            commands::get_move_history,
+            commands::get_captured_pieces,
+            commands::get_last_move,
+            commands::get_opening,
             // Analysis commands
             commands::analyze_move,
             commands::analyze_all_legal_moves,
+            commands::get_tactical_motifs,
+            commands::get_material_balance,
+            commands::get_visible_squares,
             commands::evaluate_position,
+            commands::evaluate_fen,
+            commands::get_position_diff,
+            commands::perft,
+            commands::perft_divide,
+            commands::analyze_epd,
+            commands::get_book_moves,
+            // Puzzle commands
+            commands::load_puzzles,
+            commands::next_puzzle,
+            commands::submit_puzzle_move,
+            commands::get_puzzle_stats,
+            commands::start_puzzle_rush,
+            commands::get_puzzle_rush_status,
+            commands::set_external_engine,
+            commands::search_position,
+            commands::get_hint,
+            commands::set_engine_option,
+            commands::clear_engine_hash,
+            commands::get_engine_info,
+            commands::get_settings,
+            commands::update_settings,
+            commands::get_win_probability,
+            commands::get_piece_square_tables,
+            commands::set_piece_square_tables,
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
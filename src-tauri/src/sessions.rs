@@ -0,0 +1,96 @@
+use crate::chess_engine::ChessGame;
+use std::collections::HashMap;
+
+/// ID of the board that always exists and is used when a command gives no ID
+pub const MAIN_GAME_ID: &str = "main";
+
+/// Every open board (analysis board, live game, puzzle, ...) by game ID
+pub struct GameSessions {
+    games: HashMap<String, ChessGame>,
+    next_id: u64,
+}
+
+impl GameSessions {
+    pub fn new() -> Self {
+        GameSessions {
+            games: HashMap::from([(MAIN_GAME_ID.to_string(), ChessGame::new())]),
+            next_id: 1,
+        }
+    }
+
+    /// Opens a new board with the given game and returns its ID
+    pub fn create(&mut self, game: ChessGame) -> String {
+        let id = format!("game-{}", self.next_id);
+        self.next_id += 1;
+        self.games.insert(id.clone(), game);
+        id
+    }
+
+    /// The game with this ID, or the main game for `None`
+    pub fn get(&self, id: Option<&str>) -> Result<&ChessGame, String> {
+        let id = id.unwrap_or(MAIN_GAME_ID);
+        self.games.get(id).ok_or_else(|| unknown(id))
+    }
+
+    pub fn get_mut(&mut self, id: Option<&str>) -> Result<&mut ChessGame, String> {
+        let id = id.unwrap_or(MAIN_GAME_ID);
+        self.games.get_mut(id).ok_or_else(|| unknown(id))
+    }
+
+    /// Closes a board; the main board cannot be closed
+    pub fn close(&mut self, id: &str) -> Result<(), String> {
+        if id == MAIN_GAME_ID {
+            return Err("The main game cannot be closed".to_string());
+        }
+        self.games.remove(id).map(|_| ()).ok_or_else(|| unknown(id))
+    }
+
+    /// IDs of all open boards, the main board first
+    pub fn ids(&self) -> Vec<String> {
+        let mut ids: Vec<String> = self.games.keys().cloned().collect();
+        ids.sort_by_key(|id| (id != MAIN_GAME_ID, id.len(), id.clone()));
+        ids
+    }
+}
+
+impl Default for GameSessions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn unknown(id: &str) -> String {
+    format!("No game with ID {}", id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_boards_are_independent() {
+        let mut sessions = GameSessions::new();
+        let analysis = sessions.create(ChessGame::new());
+        let puzzle = sessions.create(ChessGame::new());
+        assert_ne!(analysis, puzzle);
+
+        sessions.get_mut(Some(&analysis)).unwrap().make_san_move("e4").unwrap();
+        sessions.get_mut(None).unwrap().make_san_move("d4").unwrap();
+
+        assert_eq!(sessions.get(Some(&analysis)).unwrap().san_history(), vec!["e4"]);
+        assert_eq!(sessions.get(Some(MAIN_GAME_ID)).unwrap().san_history(), vec!["d4"]);
+        assert!(sessions.get(Some(&puzzle)).unwrap().san_history().is_empty());
+        assert_eq!(sessions.ids(), vec![MAIN_GAME_ID.to_string(), analysis.clone(), puzzle.clone()]);
+    }
+
+    #[test]
+    fn test_close() {
+        let mut sessions = GameSessions::new();
+        let id = sessions.create(ChessGame::new());
+
+        sessions.close(&id).unwrap();
+        assert!(sessions.get(Some(&id)).is_err());
+        assert!(sessions.close(&id).is_err());
+        assert!(sessions.close(MAIN_GAME_ID).is_err());
+    }
+}
@@ -0,0 +1,147 @@
+use crate::chess_engine::{DrawRules, EngineOptions, Variant};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+
+const SETTINGS_FILE: &str = "settings.json";
+
+/// Clock for a game: starting time plus time added after every move
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TimeControl {
+    pub base_seconds: u32,
+    pub increment_seconds: u32,
+}
+
+/// Player and engine preferences that survive restarts
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    /// Clock for new games; `None` plays without one
+    pub time_control: Option<TimeControl>,
+    /// Rules variant for new games
+    pub variant: Variant,
+    /// Whether new and loaded games draw automatically by the fifty-move rule and
+    /// threefold repetition or wait for a claim
+    pub draw_rules: DrawRules,
+    /// Promote to a queen when a pawn reaches the last rank without a piece chosen
+    pub auto_queen: bool,
+    /// Built-in engine settings, strength (skill level) included
+    pub engine: EngineOptions,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            time_control: None,
+            variant: Variant::Standard,
+            draw_rules: DrawRules::default(),
+            auto_queen: false,
+            engine: EngineOptions::default(),
+        }
+    }
+}
+
+/// Keeps the settings on disk in the app data directory
+pub struct SettingsStore {
+    path: PathBuf,
+}
+
+impl SettingsStore {
+    pub fn in_dir(dir: PathBuf) -> Self {
+        SettingsStore {
+            path: dir.join(SETTINGS_FILE),
+        }
+    }
+
+    /// The saved settings, or the defaults when there are none or they cannot be read.
+    /// Settings missing from an older file take their default values, and engine options
+    /// out of range (from a file edited by hand) are all reset to theirs.
+    pub fn load(&self) -> Settings {
+        let mut settings: Settings = fs::read_to_string(&self.path)
+            .ok()
+            .and_then(|json| serde_json::from_str(&json).ok())
+            .and_then(|settings| serde_json::from_value(migrate(settings)).ok())
+            .unwrap_or_default();
+        if settings.engine.validate().is_err() {
+            settings.engine = EngineOptions::default();
+        }
+        settings
+    }
+
+    /// Writes next to the settings file and renames into place, like the autosave
+    pub fn save(&self, settings: &Settings) -> io::Result<()> {
+        let json = serde_json::to_string_pretty(settings)?;
+
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let temporary = self.path.with_extension("json.tmp");
+        fs::write(&temporary, json)?;
+        fs::rename(&temporary, &self.path)
+    }
+}
+
+/// Files written before the transposition table existed used `hash_mb` for the
+/// evaluation cache, which is now `eval_hash_mb`
+fn migrate(mut settings: Value) -> Value {
+    if let Some(engine) = settings.get_mut("engine").and_then(Value::as_object_mut) {
+        if !engine.contains_key("eval_hash_mb") {
+            if let Some(eval_hash_mb) = engine.remove("hash_mb") {
+                engine.insert("eval_hash_mb".to_string(), eval_hash_mb);
+            }
+        }
+    }
+    settings
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_save_and_load() {
+        let dir = std::env::temp_dir().join(format!("chess-settings-{}", std::process::id()));
+        let store = SettingsStore::in_dir(dir.clone());
+        assert_eq!(store.load(), Settings::default());
+
+        let mut settings = Settings {
+            time_control: Some(TimeControl { base_seconds: 300, increment_seconds: 3 }),
+            auto_queen: true,
+            engine: EngineOptions { skill_level: 5, ..Default::default() },
+            ..Default::default()
+        };
+        store.save(&settings).unwrap();
+        assert_eq!(store.load(), settings);
+
+        // A file edited to an impossible thread count keeps everything but the engine options
+        settings.engine.threads = 0;
+        store.save(&settings).unwrap();
+        let loaded = store.load();
+        assert_eq!(loaded.engine, EngineOptions::default());
+        assert!(loaded.auto_queen);
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_missing_fields_use_defaults() {
+        let settings: Settings = serde_json::from_str(r#"{ "auto_queen": true, "variant": "standard" }"#).unwrap();
+        assert!(settings.auto_queen);
+        assert_eq!(settings.variant, Variant::Standard);
+        assert_eq!(settings.engine, EngineOptions::default());
+    }
+
+    #[test]
+    fn test_hash_before_transposition_table_sizes_the_eval_cache() {
+        let old = serde_json::json!({ "engine": { "hash_mb": 32, "threads": 2 } });
+        let settings: Settings = serde_json::from_value(migrate(old)).unwrap();
+        assert_eq!(settings.engine.eval_hash_mb, 32);
+        assert_eq!(settings.engine.hash_mb, EngineOptions::default().hash_mb);
+        assert_eq!(settings.engine.threads, 2);
+
+        let current = serde_json::to_value(Settings::default()).unwrap();
+        assert_eq!(migrate(current.clone()), current);
+    }
+}
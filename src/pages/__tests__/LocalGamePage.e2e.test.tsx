@@ -42,7 +42,10 @@ vi.mock('@/components/ChessBoard', () => {
       en_passant_target: null,
       halfmove_clock: 0,
       fullmove_number: turn === 'White' ? 1 : 2,
-      position_history: [],
+      variant: 'standard',
+      pockets: { white: [0, 0, 0, 0, 0], black: [0, 0, 0, 0, 0] },
+      checks_given: [0, 0],
+      status: { type: 'InProgress' },
     };
   };
 
@@ -5,16 +5,22 @@ vi.mock('@tauri-apps/api/core', () => ({
 }));
 
 import { invoke } from '@tauri-apps/api/core';
-import type { Position } from '@/types';
+import type { Move, Position } from '@/types';
 import {
+  chess960StartPosition,
   getBoardState,
   getFen,
   getGameStatus,
   getLegalMoves,
   getLegalMovesForSquare,
+  getVisibleSquares,
   loadFen,
   makeMove,
   newGame,
+  newGameWithOdds,
+  perft,
+  perftDivide,
+  playMove,
   undoMove,
 } from '@/types/tauri';
 
@@ -33,7 +39,10 @@ function samplePosition(): Position {
     en_passant_target: null,
     halfmove_clock: 0,
     fullmove_number: 1,
-    position_history: [],
+    variant: 'standard',
+    pockets: { white: [0, 0, 0, 0, 0], black: [0, 0, 0, 0, 0] },
+    checks_given: [0, 0],
+    status: { type: 'InProgress' },
   };
 }
 
@@ -45,7 +54,35 @@ describe('tauri command wrappers', () => {
   it('creates a new game', async () => {
     mockedInvoke.mockResolvedValueOnce(undefined);
     await newGame();
-    expect(mockedInvoke).toHaveBeenCalledWith('new_game');
+    expect(mockedInvoke).toHaveBeenCalledWith('new_game', { variant: undefined, options: undefined });
+
+    mockedInvoke.mockResolvedValueOnce(undefined);
+    await newGame('standard');
+    expect(mockedInvoke).toHaveBeenLastCalledWith('new_game', { variant: 'standard', options: undefined });
+
+    mockedInvoke.mockResolvedValueOnce(undefined);
+    await newGame('chess960', { chess960_number: 518 });
+    expect(mockedInvoke).toHaveBeenLastCalledWith('new_game', {
+      variant: 'chess960',
+      options: { chess960_number: 518 },
+    });
+  });
+
+  it('starts a game at odds', async () => {
+    mockedInvoke.mockResolvedValueOnce(samplePosition());
+    await newGameWithOdds('queen', 'White');
+    expect(mockedInvoke).toHaveBeenCalledWith('new_game_with_odds', { odds: 'queen', giver: 'White' });
+  });
+
+  it('asks for a Chess960 start position', async () => {
+    const start = { fen: 'bbqnnrkr/pppppppp/8/8/8/8/PPPPPPPP/BBQNNRKR w KQkq - 0 1', white: 0, black: 0 };
+    mockedInvoke.mockResolvedValueOnce(start);
+    expect(await chess960StartPosition(0)).toEqual(start);
+    expect(mockedInvoke).toHaveBeenCalledWith('chess960_start_position', {
+      number: 0,
+      double: false,
+      blackNumber: undefined,
+    });
   });
 
   it('fetches the current board state', async () => {
@@ -55,6 +92,13 @@ describe('tauri command wrappers', () => {
     expect(mockedInvoke).toHaveBeenCalledWith('get_board_state');
   });
 
+  it('asks which squares a side can see in fog of war', async () => {
+    const mask = Array.from({ length: 64 }, (_, index) => index < 32);
+    mockedInvoke.mockResolvedValueOnce(mask);
+    expect(await getVisibleSquares('White')).toEqual(mask);
+    expect(mockedInvoke).toHaveBeenCalledWith('get_visible_squares', { color: 'White' });
+  });
+
   it('fetches all legal moves', async () => {
     mockedInvoke.mockResolvedValueOnce([]);
     const moves = await getLegalMoves();
@@ -86,6 +130,19 @@ describe('tauri command wrappers', () => {
     });
   });
 
+  it('plays a move object as returned by the engine', async () => {
+    const move: Move = {
+      from: { index: 12 },
+      to: { index: 28 },
+      promotion: null,
+      is_castling: false,
+      is_en_passant: false,
+    };
+    mockedInvoke.mockResolvedValueOnce({ type: 'InProgress' });
+    await playMove(move);
+    expect(mockedInvoke).toHaveBeenLastCalledWith('make_move', { mv: move });
+  });
+
   it('undoes the last move', async () => {
     mockedInvoke.mockResolvedValueOnce({ type: 'InProgress' });
     await undoMove();
@@ -109,4 +166,24 @@ describe('tauri command wrappers', () => {
     expect(result).toEqual(position);
     expect(mockedInvoke).toHaveBeenCalledWith('load_fen', { fen: 'startpos' });
   });
+
+  it('passes the hashed flag through to perft', async () => {
+    mockedInvoke.mockResolvedValueOnce(4865609);
+    expect(await perft(5, undefined, true)).toBe(4865609);
+    expect(mockedInvoke).toHaveBeenLastCalledWith('perft', { depth: 5, fen: undefined, hashed: true });
+  });
+
+  it('asks for perft divide from a FEN or the current position', async () => {
+    const divide = { moves: [{ move: 'e2e4', nodes: 20 }], nodes: 20 };
+    mockedInvoke.mockResolvedValueOnce(divide);
+    expect(await perftDivide(2)).toEqual(divide);
+    expect(mockedInvoke).toHaveBeenLastCalledWith('perft_divide', { depth: 2, fen: undefined });
+
+    mockedInvoke.mockResolvedValueOnce(divide);
+    await perftDivide(1, '8/8/8/8/8/8/8/K6k w - - 0 1');
+    expect(mockedInvoke).toHaveBeenLastCalledWith('perft_divide', {
+      depth: 1,
+      fen: '8/8/8/8/8/8/8/K6k w - - 0 1',
+    });
+  });
 });
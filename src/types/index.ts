@@ -15,6 +15,35 @@ export type Piece = 'Pawn' | 'Knight' | 'Bishop' | 'Rook' | 'Queen' | 'King';
 // Valid promotion pieces (excludes Pawn and King)
 export type PromotionPiece = Exclude<Piece, 'Pawn' | 'King'>;
 
+// Rule set a game is played under
+export type Variant = 'standard' | 'crazyhouse' | 'threeCheck' | 'horde' | 'chess960' | 'fogOfWar' | 'kingOfTheHill' | 'racingKings';
+
+// Handicap a stronger player gives: a piece (and the first move, for 'pawnAndMove'), or just the first move.
+// Only White can give the first move.
+export type Odds = 'pawn' | 'pawnAndMove' | 'knight' | 'rook' | 'queen' | 'extraMove';
+
+/**
+ * How to set up a new game beyond its variant: a starting FEN, or a Chess960
+ * arrangement (random when the number is left out)
+ */
+export interface VariantOptions {
+  fen?: string;
+  chess960_number?: number;
+  /** Double Fischer Random Chess: Black gets its own arrangement */
+  double?: boolean;
+  chess960_black_number?: number;
+}
+
+/**
+ * A Chess960 starting position and the numbers (0-959, 518 standard) of both sides'
+ * back-rank arrangements, which differ only in Double Fischer Random Chess
+ */
+export interface Chess960Start {
+  fen: string;
+  white: number;
+  black: number;
+}
+
 /**
  * Represents a board square (0-63)
  * 0 = a1, 7 = h1, 56 = a8, 63 = h8
@@ -38,12 +67,24 @@ export interface CastlingRights {
   white_queenside: boolean;
   black_kingside: boolean;
   black_queenside: boolean;
+  /** Files (0-7) of the castling rooks in the order above; Chess960 moves them from the corners */
+  rook_files?: [number, number, number, number];
+}
+
+/**
+ * Captured pieces each side holds in hand, counted as
+ * [pawns, knights, bishops, rooks, queens]; always empty outside Crazyhouse
+ */
+export interface Pockets {
+  white: number[];
+  black: number[];
 }
 
 /**
  * Complete game position state
  * This is returned by get_board_state and contains all information
- * needed to fully represent the current position
+ * needed to fully represent the current position. The repetition history
+ * stays in the Rust backend.
  */
 export interface Position {
   /** Array of 64 squares representing the board state */
@@ -58,13 +99,14 @@ export interface Position {
   halfmove_clock: number;
   /** Current move number (increments after Black's move) */
   fullmove_number: number;
-  /**
-   * Zobrist hashes for position repetition detection.
-   * WARNING: These are u64 values from Rust that may lose precision when serialized to JSON.
-   * This field is informational only and should NOT be used for numeric operations in JavaScript.
-   * Repetition detection is handled by the Rust backend.
-   */
-  position_history: number[];
+  /** Rules the game is played under */
+  variant: Variant;
+  /** Pieces in hand that can be dropped back onto the board */
+  pockets: Pockets;
+  /** Checks given by White and by Black; only counted in three-check */
+  checks_given: [number, number];
+  /** Status of the game in this position */
+  status: GameStatus;
 }
 
 /**
@@ -81,6 +123,28 @@ export interface Move {
   is_castling: boolean;
   /** Whether this move is an en passant capture */
   is_en_passant: boolean;
+  /** Piece dropped from the pocket onto `to` ("N@f3"); `from` is then the same square */
+  drop?: Piece | null;
+  /** Where the castling rook starts in Chess960; `to` is still the king's destination */
+  castling_rook?: Square | null;
+}
+
+/**
+ * A square a piece can move to; a promotion stands for all four promotion moves
+ */
+export interface Destination {
+  to: Square;
+  promotion: boolean;
+  castling: boolean;
+  en_passant: boolean;
+}
+
+/**
+ * Leaf nodes below each root move (UCI notation), for checking move generation
+ */
+export interface PerftDivide {
+  moves: { move: string; nodes: number }[];
+  nodes: number;
 }
 
 /**
@@ -94,7 +158,13 @@ export type GameStatus =
   | { type: 'Stalemate' }
   | { type: 'DrawByFiftyMoveRule' }
   | { type: 'DrawByInsufficientMaterial' }
-  | { type: 'DrawByRepetition' };
+  | { type: 'DrawByRepetition' }
+  | { type: 'ThreeCheckWin'; winner: Color }
+  | { type: 'HordeDestroyed'; winner: Color }
+  | { type: 'KingCaptured'; winner: Color }
+  | { type: 'KingReachedHill'; winner: Color }
+  | { type: 'RaceWon'; winner: Color }
+  | { type: 'DrawByRace' };
 
 /**
  * Type guard to check if the game status is checkmate
@@ -165,6 +235,28 @@ export function getMoveCategoryDescription(category: MoveCategory): string {
   }
 }
 
+/**
+ * Error rejected by every Tauri command
+ * Branch on `kind` rather than parsing the message
+ */
+export type CommandError =
+  | { kind: 'InvalidFen'; details: string }
+  | { kind: 'InvalidMove'; details: string; move: string | null }
+  | { kind: 'InvalidMoveInSequence'; index: number; details: string; move: string }
+  | { kind: 'InvalidSquare'; square: string }
+  | { kind: 'GameOver'; status: string }
+  | { kind: 'ParseError'; details: string }
+  | { kind: 'Io'; details: string }
+  | { kind: 'InvalidOption'; name: string; details: string }
+  | { kind: 'Other'; details: string };
+
+/**
+ * Type guard for errors thrown by `invoke`
+ */
+export function isCommandError(error: unknown): error is CommandError {
+  return typeof error === 'object' && error !== null && 'kind' in error;
+}
+
 /**
  * Helper function to format an evaluation score for display
  * @param score Evaluation in centipawns (100 = 1 pawn)
@@ -4,11 +4,31 @@
  */
 
 import { invoke } from '@tauri-apps/api/core';
-import type { GameStatus, Move, Position, PromotionPiece, MoveAnalysis } from './index';
+import type { Chess960Start, Color, Destination, GameStatus, Move, Odds, PerftDivide, Position, PromotionPiece, MoveAnalysis, Variant, VariantOptions } from './index';
 
-/** Reset the engine to the initial position. */
-export async function newGame(): Promise<void> {
-  await invoke('new_game');
+/**
+ * Reset the engine to the initial position of `variant` (the saved default when omitted),
+ * or the one `options` set up.
+ */
+export async function newGame(variant?: Variant, options?: VariantOptions): Promise<void> {
+  await invoke('new_game', { variant, options });
+}
+
+/** Start a new game in which `giver` plays at `odds`, and return its position. */
+export async function newGameWithOdds(odds: Odds, giver: Color): Promise<Position> {
+  return await invoke<Position>('new_game_with_odds', { odds, giver });
+}
+
+/**
+ * Get a Chess960 starting position: arrangement `number` or a random one, and with
+ * `double` an independent arrangement for Black (`blackNumber` or random).
+ */
+export async function chess960StartPosition(
+  number?: number,
+  double = false,
+  blackNumber?: number,
+): Promise<Chess960Start> {
+  return await invoke<Chess960Start>('chess960_start_position', { number, double, blackNumber });
 }
 
 /** Retrieve the full board state for the active game. */
@@ -16,6 +36,14 @@ export async function getBoardState(): Promise<Position> {
   return await invoke<Position>('get_board_state');
 }
 
+/**
+ * Get the squares `color` can see in fog of war, indexed 0-63 like the board
+ * (true = visible), so the rest can be hidden.
+ */
+export async function getVisibleSquares(color: Color): Promise<boolean[]> {
+  return await invoke<boolean[]>('get_visible_squares', { color });
+}
+
 /** Get all legal moves in the current position. */
 export async function getLegalMoves(): Promise<Move[]> {
   return await invoke<Move[]>('get_legal_moves');
@@ -26,6 +54,11 @@ export async function getLegalMovesForSquare(square: string): Promise<Move[]> {
   return await invoke<Move[]>('get_legal_moves_for_square', { square });
 }
 
+/** Get every legal destination of the side to move, keyed by origin square ("e2"). */
+export async function getAllLegalDestinations(): Promise<Record<string, Destination[]>> {
+  return await invoke<Record<string, Destination[]>>('get_all_legal_destinations');
+}
+
 /** Make a move and return the resulting game status. */
 export async function makeMove(
   from: string,
@@ -35,6 +68,11 @@ export async function makeMove(
   return await invoke<GameStatus>('make_move', { from, to, promotion });
 }
 
+/** Play a move exactly as `getLegalMoves` returned it and return the resulting game status. */
+export async function playMove(move: Move): Promise<GameStatus> {
+  return await invoke<GameStatus>('make_move', { mv: move });
+}
+
 /** Undo the last move and return the updated game status. */
 export async function undoMove(): Promise<GameStatus> {
   return await invoke<GameStatus>('undo_move');
@@ -64,7 +102,7 @@ export async function analyzeMove(
   return await invoke<MoveAnalysis>('analyze_move', { from, to, promotion });
 }
 
-/** Analyze all legal moves in the current position. */
+/** Analyze all legal moves in the current position, biggest material gains first. */
 export async function analyzeAllLegalMoves(): Promise<MoveAnalysis[]> {
   return await invoke<MoveAnalysis[]>('analyze_all_legal_moves');
 }
@@ -75,3 +113,16 @@ export async function analyzeAllLegalMoves(): Promise<MoveAnalysis[]> {
 export async function evaluatePosition(): Promise<number> {
   return await invoke<number>('evaluate_position');
 }
+
+/**
+ * Count leaf nodes of the legal move tree `depth` plies deep, from `fen` or the current position.
+ * `hashed` reuses the counts of transposed subtrees for faster deep runs.
+ */
+export async function perft(depth: number, fen?: string, hashed?: boolean): Promise<number> {
+  return await invoke<number>('perft', { depth, fen, hashed });
+}
+
+/** Perft split by root move, to compare against another engine's divide output. */
+export async function perftDivide(depth: number, fen?: string): Promise<PerftDivide> {
+  return await invoke<PerftDivide>('perft_divide', { depth, fen });
+}